@@ -0,0 +1,334 @@
+use crate::markdown_block::render_markdown;
+use crate::syntax_highlighting::SyntaxHighlightingSettings;
+use liquid_core::error::ResultLiquidReplaceExt;
+use liquid_core::parser;
+use liquid_core::runtime;
+use liquid_core::Expression;
+use liquid_core::Language;
+use liquid_core::Renderable;
+use liquid_core::Result;
+use liquid_core::Runtime;
+use liquid_core::Template;
+use liquid_core::ValueView;
+use liquid_core::{BlockReflection, ParseBlock, TagBlock, TagTokenIter};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::BufWriter;
+use std::io::Write;
+
+/// The kinds of admonition, with their default titles.
+pub const ADMONITION_KINDS: [(&str, &str); 5] = [
+    ("note", "Note"),
+    ("tip", "Tip"),
+    ("important", "Important"),
+    ("warning", "Warning"),
+    ("caution", "Caution"),
+];
+
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+/// Settings for rendering admonitions, defined by the `admonitions` table in `global.toml`.
+pub struct AdmonitionSettings {
+    /// The class of every admonition.
+    #[serde(default = "default_class")]
+    pub class: String,
+    /// The class of the title of every admonition.
+    #[serde(default = "default_title_class")]
+    pub title_class: String,
+    /// The class of each kind of admonition, by kind; kinds without a class here are given the admonition class followed by their kind, such as `admonition-note`.
+    #[serde(default)]
+    pub classes: BTreeMap<String, String>,
+}
+
+/// By default, admonitions have the `admonition` class.
+fn default_class() -> String {
+    "admonition".to_string()
+}
+
+/// By default, the titles of admonitions have the `admonition-title` class.
+fn default_title_class() -> String {
+    "admonition-title".to_string()
+}
+
+impl Default for AdmonitionSettings {
+    fn default() -> Self {
+        Self {
+            class: default_class(),
+            title_class: default_title_class(),
+            classes: BTreeMap::new(),
+        }
+    }
+}
+
+impl AdmonitionSettings {
+    /// Read the settings from the `global` context.
+    ///
+    /// # Arguments
+    ///
+    /// * `global` - The `global` context, if any.
+    ///
+    /// # Returns
+    ///
+    /// The settings in the `admonitions` table of the `global` context, or the default settings if there is no such table.
+    pub fn from_global(global: Option<&dyn ValueView>) -> Result<Self> {
+        let Some(settings) = global
+            .and_then(|x| x.as_object())
+            .and_then(|x| x.get("admonitions"))
+        else {
+            return Ok(Self::default());
+        };
+        serde_json::to_value(settings.to_value())
+            .and_then(serde_json::from_value)
+            .map_err(|err| liquid::Error::with_msg(format!("Invalid `admonitions` table: {}", err)))
+    }
+
+    /// Read the settings from the `global` context of a Liquid runtime.
+    ///
+    /// # Arguments
+    ///
+    /// * `runtime` - The Liquid runtime.
+    ///
+    /// # Returns
+    ///
+    /// The settings in the `admonitions` table of the `global` context, or the default settings if there is no such table.
+    pub fn from_runtime(runtime: &dyn Runtime) -> Result<Self> {
+        let global = runtime.get(&["global".into()]).ok();
+        Self::from_global(global.as_ref().map(|x| x.as_view()))
+    }
+
+    /// Create the opening tags of an admonition.
+    ///
+    /// # Arguments
+    ///
+    /// * `kind` - The kind of admonition, in lowercase.
+    ///
+    /// * `title` - The title of the admonition.
+    ///
+    /// # Returns
+    ///
+    /// The `<aside>` tag, followed by the admonition's title.
+    pub fn open(&self, kind: &str, title: &str) -> String {
+        let kind_class = self
+            .classes
+            .get(kind)
+            .cloned()
+            .unwrap_or(format!("{}-{}", self.class, kind));
+        format!(
+            "<aside class=\"{}\">\n<p class=\"{}\">{}</p>\n",
+            html_escape::encode_double_quoted_attribute(
+                format!("{} {}", self.class, kind_class).trim()
+            ),
+            html_escape::encode_double_quoted_attribute(&self.title_class),
+            title
+        )
+    }
+}
+
+/// Obtain the default title of a kind of admonition.
+///
+/// # Arguments
+///
+/// * `kind` - The kind of admonition, in lowercase.
+///
+/// # Returns
+///
+/// The default title, or `None` if there is no such kind of admonition.
+fn default_title(kind: &str) -> Option<&'static str> {
+    ADMONITION_KINDS
+        .iter()
+        .find(|(name, _)| *name == kind)
+        .map(|(_, title)| *title)
+}
+
+/// Render the alerts within rendered Markdown as admonitions.
+/// An alert is a block quote beginning with its kind, as in `> [!NOTE]`, optionally followed by a title on the same line.
+///
+/// # Arguments
+///
+/// * `html` - The rendered Markdown.
+///
+/// * `settings` - The settings for rendering admonitions.
+///
+/// # Returns
+///
+/// The rendered Markdown, with alerts as `<aside>` elements.
+pub fn render_admonitions(html: &str, settings: &AdmonitionSettings) -> String {
+    const OPENING: &str = "<blockquote>";
+    const CLOSING: &str = "</blockquote>";
+    let mut rendered = String::with_capacity(html.len());
+    // Whether or not each open block quote is an admonition, from outermost to innermost.
+    let mut open = Vec::new();
+    let mut remainder = html;
+    loop {
+        let next_opening = remainder.find(OPENING);
+        let next_closing = remainder.find(CLOSING);
+        match (next_opening, next_closing) {
+            (Some(start), closing) if closing.is_none_or(|x| start < x) => {
+                rendered.push_str(&remainder[..start]);
+                remainder = &remainder[start + OPENING.len()..];
+                match parse_alert(remainder) {
+                    Some((kind, title, paragraph, length)) => {
+                        let title = match title.is_empty() {
+                            true => default_title(&kind).unwrap_or_default(),
+                            false => title,
+                        };
+                        rendered.push_str(&settings.open(&kind, title));
+                        rendered.push_str(paragraph.unwrap_or_default());
+                        remainder = &remainder[length..];
+                        open.push(true);
+                    }
+                    None => {
+                        rendered.push_str(OPENING);
+                        open.push(false);
+                    }
+                }
+            }
+            (_, Some(start)) => {
+                rendered.push_str(&remainder[..start]);
+                remainder = &remainder[start + CLOSING.len()..];
+                match open.pop() {
+                    Some(true) => rendered.push_str("</aside>"),
+                    _ => rendered.push_str(CLOSING),
+                }
+            }
+            _ => break,
+        }
+    }
+    rendered.push_str(remainder);
+    rendered
+}
+
+/// Parse the marker at the start of an alert.
+///
+/// # Arguments
+///
+/// * `html` - The rendered Markdown following the opening tag of a block quote.
+///
+/// # Returns
+///
+/// The kind of alert in lowercase, its title, the opening tag of the paragraph the marker began (if the paragraph continues after it), and the length of the marker within the rendered Markdown; or `None` if the block quote is not an alert.
+fn parse_alert(html: &str) -> Option<(String, &str, Option<&'static str>, usize)> {
+    let marker_start = html.len() - html.trim_start().len();
+    let after_marker = html[marker_start..].strip_prefix("<p>[!")?;
+    let kind_end = after_marker.find(']')?;
+    let kind = after_marker[..kind_end].to_lowercase();
+    default_title(&kind)?;
+    let after_kind = &after_marker[kind_end + 1..];
+    let line_end = [after_kind.find('\n'), after_kind.find("</p>")]
+        .into_iter()
+        .flatten()
+        .min()
+        .unwrap_or(after_kind.len());
+    let title = after_kind[..line_end].trim();
+    let marker_end = marker_start + "<p>[!".len() + kind_end + 1 + line_end;
+    // A marker alone in its paragraph takes the paragraph with it; otherwise, the rest of the paragraph follows the title.
+    let after_title = &after_kind[line_end..];
+    if after_title.starts_with("</p>") {
+        Some((kind, title, None, marker_end + "</p>".len()))
+    } else if after_title.starts_with('\n') {
+        Some((kind, title, Some("<p>"), marker_end + 1))
+    } else {
+        None
+    }
+}
+
+#[derive(Clone, Debug)]
+/// A Liquid template block rendering Markdown as an admonition.
+/// The block begins with the kind of admonition, such as `{% note %}` or `{% warning "Custom title" %}`, and ends with its end tag, such as `{% endnote %}`.
+pub struct AdmonitionBlock {
+    /// The kind of admonition, in lowercase.
+    kind: &'static str,
+    /// The end tag of the block.
+    end_tag: String,
+}
+
+impl AdmonitionBlock {
+    /// Provides a new instance of an admonition block parser.
+    ///
+    /// # Arguments
+    ///
+    /// * `kind` - The kind of admonition, in lowercase.
+    pub fn new(kind: &'static str) -> Self {
+        Self {
+            kind,
+            end_tag: format!("end{}", kind),
+        }
+    }
+}
+
+impl BlockReflection for AdmonitionBlock {
+    fn start_tag(&self) -> &str {
+        self.kind
+    }
+
+    fn end_tag(&self) -> &str {
+        &self.end_tag
+    }
+
+    fn description(&self) -> &str {
+        ""
+    }
+}
+
+impl ParseBlock for AdmonitionBlock {
+    fn parse(
+        &self,
+        mut arguments: TagTokenIter<'_>,
+        mut tokens: TagBlock<'_, '_>,
+        options: &Language,
+    ) -> Result<Box<dyn Renderable + 'static>> {
+        let title = arguments
+            .next()
+            .map(|x| x.expect_value().into_result())
+            .transpose()?;
+        arguments.expect_nothing()?;
+
+        let raw_content = tokens.escape_liquid(false)?.to_string();
+        let content = parser::parse(&raw_content, options).map(runtime::Template::new)?;
+
+        tokens.assert_empty();
+        Ok(Box::new(Admonition {
+            kind: self.kind,
+            title,
+            content,
+        }))
+    }
+
+    fn reflection(&self) -> &dyn BlockReflection {
+        self
+    }
+}
+
+#[derive(Debug)]
+struct Admonition {
+    kind: &'static str,
+    title: Option<Expression>,
+    content: Template,
+}
+
+impl Renderable for Admonition {
+    fn render_to(&self, writer: &mut dyn Write, runtime: &dyn Runtime) -> Result<()> {
+        let settings = AdmonitionSettings::from_runtime(runtime)?;
+        let title = match &self.title {
+            Some(title) => {
+                html_escape::encode_text(&title.evaluate(runtime)?.to_kstr()).to_string()
+            }
+            None => default_title(self.kind).unwrap_or_default().to_string(),
+        };
+        let mut buf = BufWriter::new(Vec::new());
+        self.content.render_to(&mut buf, runtime)?;
+        let bytes = buf.into_inner().unwrap_or_default();
+        let liquid_rendered = String::from_utf8(bytes).unwrap_or_default();
+        let rendered = render_markdown(
+            liquid_rendered,
+            &SyntaxHighlightingSettings::from_runtime(runtime)?,
+        );
+        write!(
+            writer,
+            "{}{}</aside>",
+            settings.open(self.kind, &title),
+            rendered
+        )
+        .replace("Failed to render")?;
+        Ok(())
+    }
+}
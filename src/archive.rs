@@ -0,0 +1,165 @@
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use miette::IntoDiagnostic;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tar::{Builder, EntryType, Header};
+use tracing::debug;
+
+/// Determine if an archive is compressed with gzip.
+///
+/// # Arguments
+///
+/// * `archive` - The path of the archive.
+///
+/// # Returns
+///
+/// Whether or not the archive's name ends in `.gz` or `.tgz`.
+fn is_gzip(archive: &Path) -> bool {
+    archive
+        .extension()
+        .is_some_and(|extension| extension == "gz" || extension == "tgz")
+}
+
+/// Get the time given to every entry in an archive.
+/// This is the `SOURCE_DATE_EPOCH` environment variable, if set, and otherwise the Unix epoch.
+///
+/// # Returns
+///
+/// The time, in seconds since the Unix epoch.
+fn archive_timestamp() -> u64 {
+    std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|timestamp| timestamp.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+/// List the contents of a directory recursively, in order of path.
+///
+/// # Arguments
+///
+/// * `directory` - The directory to list.
+///
+/// # Returns
+///
+/// The paths of the directories & files within the directory.
+fn list_recursively(directory: &Path) -> miette::Result<Vec<PathBuf>> {
+    let mut entries = std::fs::read_dir(directory)
+        .into_diagnostic()?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .into_diagnostic()?;
+    entries.sort();
+    let mut paths = Vec::new();
+    for entry in entries {
+        let is_dir = entry.is_dir();
+        paths.push(entry.clone());
+        if is_dir {
+            paths.extend(list_recursively(&entry)?);
+        }
+    }
+    Ok(paths)
+}
+
+/// Write the contents of a directory to a tar archive, compressed with gzip if the archive's name ends in `.gz` or `.tgz`.
+/// Entries are ordered by path, and given the same timestamp, owner, and permissions, so that archiving the same files always produces the same archive.
+///
+/// # Arguments
+///
+/// * `directory` - The directory to archive, such as a site's output.
+///
+/// * `archive` - The path to write the archive to; if within the directory, it is not archived.
+///
+/// # Returns
+///
+/// The number of files archived.
+pub fn archive_directory(directory: &Path, archive: &Path) -> miette::Result<usize> {
+    let paths = list_recursively(directory)?;
+    let archive = std::path::absolute(archive).into_diagnostic()?;
+    let file = File::create(&archive).into_diagnostic()?;
+    let written = match is_gzip(&archive) {
+        true => {
+            let mut builder = Builder::new(GzEncoder::new(file, Compression::best()));
+            let count = append_entries(&mut builder, directory, &archive, &paths);
+            count.and_then(|count| {
+                builder
+                    .into_inner()
+                    .and_then(|encoder| encoder.finish())
+                    .and_then(|mut file| file.flush())
+                    .into_diagnostic()?;
+                Ok(count)
+            })
+        }
+        false => {
+            let mut builder = Builder::new(file);
+            let count = append_entries(&mut builder, directory, &archive, &paths);
+            count.and_then(|count| {
+                builder
+                    .into_inner()
+                    .and_then(|mut file| file.flush())
+                    .into_diagnostic()?;
+                Ok(count)
+            })
+        }
+    };
+    if written.is_err() {
+        std::fs::remove_file(&archive).ok();
+    }
+    written
+}
+
+/// Append directories & files to a tar archive.
+///
+/// # Arguments
+///
+/// * `builder` - The archive being written.
+///
+/// * `directory` - The directory the entries are within; entries are named relative to it.
+///
+/// * `archive` - The path of the archive, which is skipped if listed.
+///
+/// * `paths` - The paths of the entries, in the order they are appended.
+///
+/// # Returns
+///
+/// The number of files appended.
+fn append_entries<W: Write>(
+    builder: &mut Builder<W>,
+    directory: &Path,
+    archive: &Path,
+    paths: &[PathBuf],
+) -> miette::Result<usize> {
+    let timestamp = archive_timestamp();
+    let mut count = 0;
+    for path in paths {
+        if std::path::absolute(path).into_diagnostic()? == archive {
+            continue;
+        }
+        let name = path.strip_prefix(directory).into_diagnostic()?;
+        let mut header = Header::new_gnu();
+        header.set_mtime(timestamp);
+        header.set_uid(0);
+        header.set_gid(0);
+        if path.is_dir() {
+            header.set_entry_type(EntryType::Directory);
+            header.set_mode(0o755);
+            header.set_size(0);
+            builder
+                .append_data(&mut header, name, std::io::empty())
+                .into_diagnostic()?;
+        } else {
+            debug!("Archiving {:?} … ", path);
+            // Symbolic links are followed, so that the archive holds the files they point to.
+            let contents = std::fs::read(path).into_diagnostic()?;
+            header.set_entry_type(EntryType::Regular);
+            header.set_mode(0o644);
+            header.set_size(contents.len() as u64);
+            builder
+                .append_data(&mut header, name, contents.as_slice())
+                .into_diagnostic()?;
+            count += 1;
+        }
+    }
+    Ok(count)
+}
@@ -0,0 +1,182 @@
+use crate::builds::EdgeType;
+use crate::error::AccessibilityProblem;
+use crate::page::Page;
+use crate::paths::url_output_path;
+use crate::post_processing::{find_tags, get_attribute};
+use crate::provider::VoxProvider;
+use crate::search::html_to_text;
+use daggy::stable_dag::StableDag;
+use miette::NamedSource;
+use std::ops::Range;
+use tracing::{debug, warn};
+
+/// Link text which does not describe where a link leads, in lowercase.
+const VAGUE_LINK_TEXT: [&str; 14] = [
+    "click",
+    "click here",
+    "continue",
+    "details",
+    "go",
+    "here",
+    "learn more",
+    "link",
+    "more",
+    "more info",
+    "read more",
+    "this",
+    "this link",
+    "this page",
+];
+
+/// A problem found by auditing a rendered page.
+pub struct Finding {
+    /// The problem found.
+    pub problem: String,
+    /// How the problem may be fixed.
+    pub advice: &'static str,
+    /// The position of the problem in the rendered page.
+    pub span: Range<usize>,
+}
+
+/// Check a rendered page for common accessibility problems; images without alternative text, headings skipping levels, documents without a language, and links whose text does not describe where they lead.
+///
+/// # Arguments
+///
+/// * `html` - The rendered page.
+///
+/// # Returns
+///
+/// The problems found, in order of appearance.
+pub fn audit_accessibility(html: &str) -> Vec<Finding> {
+    let lowercase_html = html.to_ascii_lowercase();
+    let mut findings = Vec::new();
+
+    // Only whole documents are expected to declare their language.
+    let document = find_tags(html, "html").into_iter().next().map(|tag| {
+        let has_lang = get_attribute(&tag.attributes, "lang").is_some_and(|x| !x.trim().is_empty());
+        (tag.span, has_lang)
+    });
+    let document = document.or_else(|| {
+        let start = lowercase_html.find("<!doctype")?;
+        let end = lowercase_html[start..].find('>')? + start + 1;
+        Some((start..end, false))
+    });
+    if let Some((span, false)) = document {
+        findings.push(Finding {
+            problem: "Document has no language".to_string(),
+            advice: "Please declare the language of the document with a `lang` attribute on its `<html>` element.",
+            span,
+        });
+    }
+
+    // Images with an empty `alt` attribute are decorative, and are ignored by assistive technologies.
+    for tag in find_tags(html, "img") {
+        if get_attribute(&tag.attributes, "alt").is_none() {
+            findings.push(Finding {
+                problem: "Image has no alternative text".to_string(),
+                advice: "Please describe the image with an `alt` attribute, or give it an empty `alt` attribute if it is decorative.",
+                span: tag.span,
+            });
+        }
+    }
+
+    let mut headings = (1..=6)
+        .flat_map(|level| {
+            find_tags(html, &format!("h{}", level))
+                .into_iter()
+                .map(move |tag| (level, tag.span))
+        })
+        .collect::<Vec<_>>();
+    headings.sort_by_key(|(_, span)| span.start);
+    for pair in headings.windows(2) {
+        let (previous_level, _) = &pair[0];
+        let (level, span) = &pair[1];
+        if *level > previous_level + 1 {
+            findings.push(Finding {
+                problem: format!(
+                    "Heading skips from `h{}` to `h{}`",
+                    previous_level, level
+                ),
+                advice: "Please ensure that each heading is at most one level below the heading before it, so that the headings outline the page.",
+                span: span.clone(),
+            });
+        }
+    }
+
+    // Links labelled by other elements are not judged by their text.
+    for tag in find_tags(html, "a") {
+        if get_attribute(&tag.attributes, "href").is_none()
+            || get_attribute(&tag.attributes, "aria-labelledby").is_some()
+        {
+            continue;
+        }
+        let text = match get_attribute(&tag.attributes, "aria-label") {
+            Some(label) if !label.trim().is_empty() => label,
+            _ => {
+                let end = lowercase_html[tag.span.end..]
+                    .find("</a")
+                    .map(|x| x + tag.span.end)
+                    .unwrap_or(html.len());
+                html_to_text(&html[tag.span.end..end])
+            }
+        };
+        let text = text
+            .trim_matches(|x: char| x.is_whitespace() || x.is_ascii_punctuation())
+            .to_lowercase();
+        if VAGUE_LINK_TEXT.contains(&text.as_str()) {
+            findings.push(Finding {
+                problem: format!("Link text `{}` does not describe where the link leads", text),
+                advice: "Please write link text which makes sense out of context, or describe the link with an `aria-label` attribute.",
+                span: tag.span,
+            });
+        }
+    }
+
+    findings.sort_by_key(|finding| finding.span.start);
+    findings
+}
+
+/// Check each page output by a build for common accessibility problems, logging the problems found.
+///
+/// # Arguments
+///
+/// * `provider` - The provider the site was built with.
+///
+/// * `dag` - The DAG of the built site.
+///
+/// # Returns
+///
+/// The number of problems found.
+pub fn audit_site_accessibility(
+    provider: &impl VoxProvider,
+    dag: &StableDag<Page, EdgeType>,
+) -> usize {
+    let mut pages = dag
+        .graph()
+        .node_weights()
+        .filter(|page| !page.is_layout && url_output_path(&page.url).ends_with(".html"))
+        .collect::<Vec<_>>();
+    pages.sort_by_key(|page| page.to_path_string());
+    let mut problems = 0;
+    for page in pages {
+        let output_path = url_output_path(&page.url);
+        debug!("Auditing the accessibility of {:?} … ", output_path);
+        let Ok(html) = provider.read_to_string(&output_path) else {
+            continue;
+        };
+        for finding in audit_accessibility(&html) {
+            problems += 1;
+            warn!(
+                "{:?}",
+                miette::Report::new(AccessibilityProblem {
+                    src: NamedSource::new(&output_path, html.clone()),
+                    page: page.to_path_string().trim_start_matches('/').to_string(),
+                    problem: finding.problem,
+                    advice: finding.advice.to_string(),
+                    span: finding.span.into(),
+                })
+            );
+        }
+    }
+    problems
+}
@@ -1,17 +1,26 @@
+use crate::error::UrlConflict;
 use crate::page::Page;
-use ahash::AHashMap;
+use crate::paths::url_output_path;
+use crate::progress::Progress;
+use crate::url_conflicts::{suffix_url, UrlConflictStrategy};
+use crate::url_transforms::UrlTransforms;
+use ahash::{AHashMap, AHashSet};
 use chrono::Locale;
+use chrono_tz::Tz;
 use daggy::{
     petgraph::{algo::toposort, Direction},
     stable_dag::StableDag,
     NodeIndex, Walker,
 };
-use liquid::{to_object, Object, Parser};
+use liquid::{to_object, Object, Parser, Template};
 use liquid_core::to_value;
 use miette::IntoDiagnostic;
 use path_clean::PathClean;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
-use tracing::{debug, trace};
+use std::sync::Arc;
+use tracing::{debug, trace, warn};
 
 /// Information held in memory while performing a build.
 #[derive(Clone, Default)]
@@ -22,12 +31,48 @@ pub struct Build {
     pub contexts: Object,
     /// The locale information of the build, primarily used to render dates and times.
     pub locale: Locale,
+    /// The timezone of the build, used to render dates and times.
+    pub timezone: Tz,
     /// A directed acyclic graph (DAG) populated with pages and their children.
     pub dag: StableDag<Page, EdgeType>,
+    /// The layout templates compiled during the build.
+    pub templates: CompiledTemplates,
+    /// The transforms applied to the URL of every page.
+    pub url_transforms: UrlTransforms,
+}
+
+/// Liquid templates compiled during a build, keyed by the hash of their source.
+/// A layout is part of the DAG once for each page using it, but is only compiled once.
+#[derive(Clone, Default)]
+pub struct CompiledTemplates {
+    templates: AHashMap<String, Arc<Template>>,
+}
+
+impl CompiledTemplates {
+    /// Get the compiled template of a source, compiling it if it has not been compiled.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The source of the template.
+    ///
+    /// * `parser` - The Liquid parser to compile with.
+    ///
+    /// # Returns
+    ///
+    /// The compiled template.
+    pub fn get_or_parse(&mut self, source: &str, parser: &Parser) -> miette::Result<Arc<Template>> {
+        let hash = blake3::hash(source.as_bytes()).to_hex().to_string();
+        if let Some(template) = self.templates.get(&hash) {
+            return Ok(template.clone());
+        }
+        let template = Arc::new(parser.parse(source).into_diagnostic()?);
+        self.templates.insert(hash, template.clone());
+        Ok(template)
+    }
 }
 
 /// The type of edge in the DAG.
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum EdgeType {
     /// An edge between a layout and its parent page.
     Layout,
@@ -111,6 +156,31 @@ impl Build {
         Ok(ancestors)
     }
 
+    /// Get the page a layout page is rendered for; that is, the page above its layouts.
+    ///
+    /// # Arguments
+    ///
+    /// * `dag` - The DAG to search.
+    ///
+    /// * `root_index` - The index of the page in the DAG.
+    ///
+    /// # Returns
+    ///
+    /// The index of the page above the layouts, or the index of the page itself if it is not a layout.
+    pub fn get_page_above_layouts(
+        dag: &StableDag<Page, EdgeType>,
+        root_index: NodeIndex,
+    ) -> NodeIndex {
+        if !dag.graph()[root_index].is_layout {
+            return root_index;
+        }
+        dag.parents(root_index)
+            .iter(dag)
+            .find(|parent| *dag.edge_weight(parent.0).unwrap() == EdgeType::Layout)
+            .map(|parent| Build::get_page_above_layouts(dag, parent.1))
+            .unwrap_or(root_index)
+    }
+
     /// Insert the contexts of all ancestors of a layout page.
     /// Intended to be used when rendering a layout page.
     ///
@@ -147,6 +217,51 @@ impl Build {
         Ok(())
     }
 
+    /// Insert the `collections` context, mapping the name of every collection to its member pages.
+    /// Member pages are ordered by weight then date, and do not include their content.
+    pub fn insert_collections_context(&mut self) -> miette::Result<()> {
+        trace!("Adding all collections to the global contexts … ");
+        let mut collections: AHashMap<String, Vec<Page>> = AHashMap::new();
+        for page in self.dag.graph().node_weights() {
+            if page.is_layout {
+                continue;
+            }
+            let Some(page_collections) = &page.collections else {
+                continue;
+            };
+            let mut member = page.to_neighbour();
+            let mut member_contexts = self.contexts.clone();
+            member_contexts.insert(
+                "page".into(),
+                liquid_core::Value::Object(to_object(&member).into_diagnostic()?),
+            );
+            member.render_url(
+                &member_contexts,
+                &self.template_parser,
+                &self.url_transforms,
+            )?;
+            for collection in page_collections {
+                collections
+                    .entry(collection.clone())
+                    .or_default()
+                    .push(member.clone());
+            }
+        }
+        let mut collections_object = Object::new();
+        for (collection_name, mut members) in collections {
+            members.sort_by(Page::collection_order);
+            collections_object.insert(
+                collection_name.into(),
+                to_value(&members).into_diagnostic()?,
+            );
+        }
+        self.contexts.insert(
+            "collections".into(),
+            liquid_core::Value::Object(collections_object),
+        );
+        Ok(())
+    }
+
     /// Render all pages in the DAG.
     ///
     /// # Returns
@@ -154,14 +269,172 @@ impl Build {
     /// A list of all nodes that were rendered.
     pub fn render_all(&mut self) -> miette::Result<Vec<NodeIndex>> {
         trace!("Rendering all pages … ");
+        self.insert_collections_context()?;
         let mut rendered_indices = Vec::new();
         let indices = toposort(&self.dag.graph(), None).unwrap_or_default();
+        let mut progress = Progress::new("Rendering", indices.len());
         for index in indices {
             self.render_page(index, false, &mut rendered_indices)?;
+            progress.tick();
         }
+        progress.finish();
         Ok(rendered_indices)
     }
 
+    /// Render the URLs of all pages in the DAG that are not layout pages, without rendering their content.
+    pub fn render_urls(&mut self) -> miette::Result<()> {
+        trace!("Rendering URLs of all pages … ");
+        let indices = self.dag.graph().node_indices().collect::<Vec<_>>();
+        for index in indices {
+            let page = &self.dag.graph()[index];
+            if page.is_layout {
+                continue;
+            }
+            let mut page_contexts = self.contexts.clone();
+            page_contexts.insert(
+                "page".into(),
+                liquid_core::Value::Object(to_object(page).into_diagnostic()?),
+            );
+            self.dag.node_weight_mut(index).unwrap().render_url(
+                &page_contexts,
+                &self.template_parser,
+                &self.url_transforms,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Find the pages sharing a URL, and handle them per a strategy.
+    /// The URLs of all pages are rendered; pages not kept at their URLs are given new permalinks, as are the copies of them held by other pages, such as their neighbours.
+    ///
+    /// # Arguments
+    ///
+    /// * `strategy` - How pages sharing a URL are handled.
+    ///
+    /// # Returns
+    ///
+    /// The indices of the pages given new permalinks.
+    pub fn resolve_url_conflicts(
+        &mut self,
+        strategy: UrlConflictStrategy,
+    ) -> miette::Result<Vec<NodeIndex>> {
+        self.render_urls()?;
+        let mut claimants: BTreeMap<PathBuf, Vec<(String, NodeIndex)>> = BTreeMap::new();
+        for index in self.dag.graph().node_indices() {
+            let page = &self.dag.graph()[index];
+            if page.is_layout || page.url.is_empty() {
+                continue;
+            }
+            claimants
+                .entry(PathBuf::from(url_output_path(&page.url)).clean())
+                .or_default()
+                .push((
+                    page.to_path_string().trim_start_matches('/').to_string(),
+                    index,
+                ));
+        }
+        let mut taken = claimants.keys().cloned().collect::<AHashSet<_>>();
+        let mut permalinks: AHashMap<String, String> = AHashMap::new();
+        let mut resolved = Vec::new();
+        for (_, mut pages) in claimants {
+            if pages.len() < 2 {
+                continue;
+            }
+            pages.sort();
+            let url = self.dag.graph()[pages[0].1].url.clone();
+            let first_path = pages[0].0.clone();
+            if strategy == UrlConflictStrategy::Error {
+                return Err(UrlConflict {
+                    url,
+                    pages: pages
+                        .iter()
+                        .map(|(path, _)| format!("`{}`", path))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                })
+                .into_diagnostic();
+            }
+            for (path, index) in pages.into_iter().skip(1) {
+                let permalink = match strategy {
+                    UrlConflictStrategy::Suffix => {
+                        let suffixed = (2..)
+                            .map(|number| suffix_url(&url, number))
+                            .find(|suffixed| {
+                                !taken.contains(&PathBuf::from(url_output_path(suffixed)).clean())
+                            })
+                            .unwrap_or_default();
+                        taken.insert(PathBuf::from(url_output_path(&suffixed)).clean());
+                        warn!(
+                            "`{}` shares the URL `{}` with `{}`; it is output at `{}` instead … ",
+                            path, url, first_path, suffixed
+                        );
+                        suffixed
+                    }
+                    _ => {
+                        warn!(
+                            "`{}` shares the URL `{}` with `{}`; it is not output … ",
+                            path, url, first_path
+                        );
+                        String::new()
+                    }
+                };
+                permalinks.insert(path, permalink);
+                resolved.push(index);
+            }
+        }
+        if permalinks.is_empty() {
+            return Ok(resolved);
+        }
+        let indices = self.dag.graph().node_indices().collect::<Vec<_>>();
+        for index in indices {
+            let page = self.dag.node_weight_mut(index).unwrap();
+            if let Some(permalink) = permalinks.get(page.to_path_string().trim_start_matches('/')) {
+                page.permalink = permalink.clone();
+                page.url = permalink.clone();
+            }
+            for linked_page in [&mut page.next, &mut page.previous]
+                .into_iter()
+                .flatten()
+                .map(|x| x.as_mut())
+                .chain(page.related.iter_mut())
+                .chain(page.links.iter_mut())
+                .chain(page.backlinks.iter_mut())
+            {
+                if let Some(permalink) =
+                    permalinks.get(linked_page.to_path_string().trim_start_matches('/'))
+                {
+                    linked_page.permalink = permalink.clone();
+                }
+            }
+        }
+        Ok(resolved)
+    }
+
+    /// Render a page, along with every page it depends upon, in topological order.
+    ///
+    /// # Arguments
+    ///
+    /// * `root_index` - The index of the page in the DAG.
+    ///
+    /// * `rendered` - The pages already rendered, which are not rendered again; pages rendered are added to it.
+    pub fn render_with_ancestors(
+        &mut self,
+        root_index: NodeIndex,
+        rendered: &mut AHashSet<NodeIndex>,
+    ) -> miette::Result<()> {
+        let mut indices: AHashSet<NodeIndex> = Build::get_ancestors(&self.dag, root_index)
+            .into_iter()
+            .collect();
+        indices.insert(root_index);
+        let sorted_indices = toposort(&self.dag.graph(), None).unwrap_or_default();
+        for index in sorted_indices {
+            if indices.contains(&index) && rendered.insert(index) {
+                self.render_page(index, false, &mut Vec::new())?;
+            }
+        }
+        Ok(())
+    }
+
     /// Render a page.
     ///
     /// # Arguments
@@ -181,28 +454,86 @@ impl Build {
         recursive: bool,
         rendered_indices: &mut Vec<NodeIndex>,
     ) -> miette::Result<()> {
-        let root_page = self.dag.graph()[root_index].to_owned();
-        let root_path: PathBuf = PathBuf::from(root_page.to_path_string()).clean();
-        debug!("Rendering page: {:?}", root_path);
+        debug!(
+            "Rendering page: {:?}",
+            PathBuf::from(self.dag.graph()[root_index].to_path_string()).clean()
+        );
+        let root_contexts = self.get_page_contexts(root_index)?;
+        let root_page = self.dag.node_weight_mut(root_index).unwrap();
+        if root_page.render(
+            &root_contexts,
+            &self.template_parser,
+            &mut self.templates,
+            &self.url_transforms,
+        )? {
+            rendered_indices.push(root_index);
+        }
+
+        if recursive {
+            let children = self
+                .dag
+                .children(root_index)
+                .iter(&self.dag)
+                .collect::<Vec<_>>();
+            for child in children {
+                self.render_page(child.1, recursive, rendered_indices)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get the contexts a page is rendered with.
+    /// The URLs of the page's neighbours are rendered if they have not been.
+    ///
+    /// # Arguments
+    ///
+    /// * `root_index` - The index of the page in the DAG.
+    ///
+    /// # Returns
+    ///
+    /// The global contexts, along with the contexts of the page, its layouts, and the collections it depends upon.
+    /// For a layout, these include the collections that the page it wraps depends upon.
+    pub fn get_page_contexts(&mut self, root_index: NodeIndex) -> miette::Result<Object> {
+        let root_path: PathBuf =
+            PathBuf::from(self.dag.graph()[root_index].to_path_string()).clean();
         let mut root_contexts = self.contexts.clone();
         if root_path.starts_with(PathBuf::from("layouts/")) {
             debug!("Page is a layout page … ");
-            let layout_object =
-                liquid_core::Value::Object(to_object(&root_page).into_diagnostic()?);
-            root_contexts.insert("layout".into(), layout_object.clone());
+            let layout_object = liquid_core::Value::Object(
+                to_object(&self.dag.graph()[root_index]).into_diagnostic()?,
+            );
+            root_contexts.insert("layout".into(), layout_object);
             self.insert_layout_ancestor_contexts(root_index, &mut root_contexts)?;
         } else {
             debug!("Page is not a layout page … ");
-            let page_object = liquid_core::Value::Object(to_object(&root_page).into_diagnostic()?);
-            root_contexts.insert("page".into(), page_object.clone());
+            let root_page = self.dag.node_weight_mut(root_index).unwrap();
+            root_page.render_neighbour_urls(
+                &self.contexts,
+                &self.template_parser,
+                &self.url_transforms,
+            )?;
+            let page_object = liquid_core::Value::Object(to_object(&*root_page).into_diagnostic()?);
+            root_contexts.insert("page".into(), page_object);
         }
         let mut collection_pages: AHashMap<String, Vec<NodeIndex>> = AHashMap::new();
         // Find all parent pages of the root page.
-        let parents = self
+        // Layouts are also given the collections the page they wrap depends upon.
+        let mut parents = self
             .dag
             .parents(root_index)
             .iter(&self.dag)
             .collect::<Vec<_>>();
+        let page_index = Build::get_page_above_layouts(&self.dag, root_index);
+        if page_index != root_index {
+            let page_parents = self
+                .dag
+                .parents(page_index)
+                .iter(&self.dag)
+                .filter(|parent| !parents.iter().any(|x| x.1 == parent.1))
+                .collect::<Vec<_>>();
+            parents.extend(page_parents);
+        }
         for parent in parents {
             let parent_page = &self.dag.graph()[parent.1];
             let edge = self.dag.edge_weight(parent.0).unwrap();
@@ -232,10 +563,13 @@ impl Build {
         // Add the collection pages to the root page's contexts.
         trace!("Adding any collections to page's contexts … ");
         for (collection_name, collection) in collection_pages.iter_mut() {
+            collection.sort_by(|lhs, rhs| {
+                Page::collection_order(&self.dag.graph()[*lhs], &self.dag.graph()[*rhs])
+            });
             let collection_pages: Vec<liquid::Object> = collection
                 .iter()
                 .map(|page_index| {
-                    to_object(&self.dag.node_weight_mut(*page_index).unwrap().to_owned())
+                    to_object(&self.dag.graph()[*page_index])
                         .into_diagnostic()
                         .unwrap()
                 })
@@ -243,23 +577,7 @@ impl Build {
             let collection_object = to_value(&collection_pages).into_diagnostic()?;
             root_contexts.insert(collection_name.clone().into(), collection_object.clone());
         }
-        let root_page = self.dag.node_weight_mut(root_index).unwrap();
-        if root_page.render(&root_contexts, &self.template_parser)? {
-            rendered_indices.push(root_index);
-        }
-
-        if recursive {
-            let children = self
-                .dag
-                .children(root_index)
-                .iter(&self.dag)
-                .collect::<Vec<_>>();
-            for child in children {
-                self.render_page(child.1, recursive, rendered_indices)?;
-            }
-        }
-
-        Ok(())
+        Ok(root_contexts)
     }
 
     /// Find the indices of all pages in the DAG that have no parent pages.
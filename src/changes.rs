@@ -0,0 +1,130 @@
+use crate::exclusion::Exclusions;
+use crate::provider::InputProvider;
+use path_clean::PathClean;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// A stream of changes to the sources of a site, ending when the site is no longer watched.
+pub type Changes = Box<dyn Iterator<Item = Change> + Send>;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+/// A change to the sources of a site.
+pub struct Change {
+    /// The paths of the files that changed, relative to the site's directory.
+    pub paths: Vec<PathBuf>,
+}
+
+impl Change {
+    /// Determine if the global context or any snippets have changed.
+    ///
+    /// # Returns
+    ///
+    /// Whether or not `global.toml`, any file in the `snippets` folder, or any WebAssembly module has changed.
+    /// WebAssembly modules are changed along with the global context, as plugins are declared in `global.toml`.
+    pub fn global_or_snippets_changed(&self) -> bool {
+        self.global_changed() || self.snippets_changed() || self.wasm_changed()
+    }
+
+    /// Determine if the global context has changed.
+    ///
+    /// # Returns
+    ///
+    /// Whether or not `global.toml` has changed.
+    pub fn global_changed(&self) -> bool {
+        self.paths
+            .iter()
+            .any(|path| path.starts_with("global.toml"))
+    }
+
+    /// Determine if any snippets have changed.
+    ///
+    /// # Returns
+    ///
+    /// Whether or not any file in the `snippets` folder has changed.
+    pub fn snippets_changed(&self) -> bool {
+        self.paths.iter().any(|path| path.starts_with("snippets/"))
+    }
+
+    /// Determine if any WebAssembly modules, such as those of plugins, have changed.
+    ///
+    /// # Returns
+    ///
+    /// Whether or not any `.wasm` file has changed.
+    pub fn wasm_changed(&self) -> bool {
+        self.paths
+            .iter()
+            .any(|path| path.extension().is_some_and(|x| x == "wasm"))
+    }
+
+    /// Disregard changes to excluded files.
+    ///
+    /// # Arguments
+    ///
+    /// * `exclusions` - The paths excluded from the site.
+    ///
+    /// # Returns
+    ///
+    /// The change, without the paths of excluded files.
+    pub fn without_excluded(self, exclusions: &Exclusions) -> Self {
+        Self {
+            paths: exclusions.filter(self.paths),
+        }
+    }
+
+    /// Disregard changes to files whose contents are the same as they were, such as files touched by an editor without being modified.
+    /// Only the files with recorded hashes are considered; changes to other files are kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `old_hashes` - The hashes of the files before the change.
+    ///
+    /// * `new_hashes` - The hashes of the files after the change.
+    ///
+    /// # Returns
+    ///
+    /// The change, without the paths of files whose contents are unchanged.
+    pub fn without_unchanged(self, old_hashes: &SourceHashes, new_hashes: &SourceHashes) -> Self {
+        Self {
+            paths: self
+                .paths
+                .into_iter()
+                .filter(|path| {
+                    let path = path.clean();
+                    let old_hash = old_hashes.hashes.get(&path);
+                    let new_hash = new_hashes.hashes.get(&path);
+                    (old_hash.is_none() && new_hash.is_none()) || old_hash != new_hash
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+/// The hashes of the files every page of a site depends upon, being `global.toml` and the site's snippets.
+/// Pages are compared by their own hashes; see [`crate::page::Page::hash_source`].
+pub struct SourceHashes {
+    /// The BLAKE3 hash of each file's contents, keyed by its path relative to the site's directory.
+    hashes: BTreeMap<PathBuf, String>,
+}
+
+impl SourceHashes {
+    /// Hash the files every page of a site depends upon.
+    ///
+    /// # Arguments
+    ///
+    /// * `provider` - The provider the site is read from.
+    ///
+    /// # Returns
+    ///
+    /// The hashes of `global.toml` and the site's snippets; files which cannot be read are left out.
+    pub fn from_provider(provider: &(impl InputProvider + ?Sized)) -> miette::Result<Self> {
+        let mut hashes = BTreeMap::new();
+        let paths = std::iter::once(PathBuf::from("global.toml")).chain(provider.list_snippets()?);
+        for path in paths {
+            if let Ok(contents) = provider.read_bytes(&path) {
+                hashes.insert(path.clean(), blake3::hash(&contents).to_hex().to_string());
+            }
+        }
+        Ok(Self { hashes })
+    }
+}
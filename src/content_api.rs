@@ -0,0 +1,100 @@
+use crate::page::Page;
+use serde::Serialize;
+
+#[derive(PartialEq, Clone, Debug, Serialize)]
+/// A page as represented in the JSON output of a build.
+pub struct PageEntry {
+    /// The output path of the page.
+    pub url: String,
+    /// The date of the page, formatted per RFC 3339.
+    pub date: Option<String>,
+    /// The date the page was last updated, formatted per RFC 3339.
+    pub updated: Option<String>,
+    /// The collections the page belongs to.
+    pub collections: Vec<String>,
+    /// The page's frontmatter.
+    pub data: serde_json::Value,
+    /// A short summary of the page.
+    pub excerpt: String,
+    /// The rendered content of the page, not including its layouts.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+}
+
+impl PageEntry {
+    /// Represent a page in the JSON output of a build.
+    ///
+    /// # Arguments
+    ///
+    /// * `page` - The page, after rendering.
+    ///
+    /// * `include_content` - Whether or not to include the rendered content of the page.
+    ///
+    /// # Returns
+    ///
+    /// The page's JSON representation.
+    pub fn from_page(page: &Page, include_content: bool) -> Self {
+        Self {
+            url: page.url.clone(),
+            date: page.date.as_ref().map(|x| x.rfc_3339.clone()),
+            updated: page.updated.as_ref().map(|x| x.rfc_3339.clone()),
+            collections: page.collections.clone().unwrap_or_default(),
+            data: toml_to_json(&toml::Value::Table(page.data.clone())),
+            excerpt: page.excerpt.clone(),
+            content: include_content.then(|| page.rendered.to_string()),
+        }
+    }
+}
+
+/// Convert a TOML value into a JSON value.
+///
+/// # Arguments
+///
+/// * `value` - The TOML value to convert.
+///
+/// # Returns
+///
+/// The equivalent JSON value, with date-times represented as strings.
+pub fn toml_to_json(value: &toml::Value) -> serde_json::Value {
+    match value {
+        toml::Value::String(x) => serde_json::Value::String(x.clone()),
+        toml::Value::Integer(x) => serde_json::Value::from(*x),
+        toml::Value::Float(x) => serde_json::Value::from(*x),
+        toml::Value::Boolean(x) => serde_json::Value::Bool(*x),
+        toml::Value::Datetime(x) => serde_json::Value::String(x.to_string()),
+        toml::Value::Array(x) => serde_json::Value::Array(x.iter().map(toml_to_json).collect()),
+        toml::Value::Table(x) => serde_json::Value::Object(
+            x.iter()
+                .map(|(key, value)| (key.clone(), toml_to_json(value)))
+                .collect(),
+        ),
+    }
+}
+
+/// Obtain the path of the JSON output of a page.
+///
+/// # Arguments
+///
+/// * `output_path` - The path of the page's HTML output.
+///
+/// # Returns
+///
+/// The path of the JSON output beside the HTML output, or `None` if the page is not HTML.
+pub fn json_output_path(output_path: &str) -> Option<String> {
+    output_path
+        .strip_suffix(".html")
+        .map(|path| format!("{}.json", path))
+}
+
+/// Obtain the path of the JSON index of a collection.
+///
+/// # Arguments
+///
+/// * `collection` - The name of the collection.
+///
+/// # Returns
+///
+/// The path of the collection's JSON index.
+pub fn collection_index_path(collection: &str) -> String {
+    format!("output/collections/{}.json", collection)
+}
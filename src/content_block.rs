@@ -0,0 +1,150 @@
+use liquid_core::error::ResultLiquidReplaceExt;
+use liquid_core::model::ScalarCow;
+use liquid_core::Language;
+use liquid_core::Renderable;
+use liquid_core::Result;
+use liquid_core::Runtime;
+use liquid_core::Template;
+use liquid_core::ValueView;
+use liquid_core::{BlockReflection, ParseBlock, TagBlock, TagTokenIter};
+use std::collections::BTreeMap;
+use std::io::Write;
+
+/// Marks the start of a block's name in a rendered page.
+const BLOCK_START: char = '\u{F8F0}';
+
+/// Marks the end of a block's name, and the start of its contents, in a rendered page.
+const BLOCK_NAME_END: char = '\u{F8F1}';
+
+/// Marks the end of a block's contents in a rendered page.
+const BLOCK_END: char = '\u{F8F2}';
+
+#[derive(Copy, Clone, Debug, Default)]
+/// A Liquid template block defining a named region of a layout.
+/// The block begins with `{% block name %}` and ends with `{% endblock %}`.
+///
+/// In a page, the block provides the contents of the region, and is not output where it is written.
+/// In a layout, the block is replaced with the contents the page provides for the region, or otherwise rendered as written.
+pub struct ContentBlock;
+
+impl ContentBlock {
+    /// Provides a new instance of the content block parser.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl BlockReflection for ContentBlock {
+    fn start_tag(&self) -> &str {
+        "block"
+    }
+
+    fn end_tag(&self) -> &str {
+        "endblock"
+    }
+
+    fn description(&self) -> &str {
+        ""
+    }
+}
+
+impl ParseBlock for ContentBlock {
+    fn parse(
+        &self,
+        mut arguments: TagTokenIter<'_>,
+        mut tokens: TagBlock<'_, '_>,
+        options: &Language,
+    ) -> Result<Box<dyn Renderable + 'static>, liquid::Error> {
+        let name = arguments
+            .expect_next("Identifier expected")?
+            .expect_identifier()
+            .into_result()?
+            .to_string();
+        arguments.expect_nothing()?;
+
+        let content = Template::new(tokens.parse_all(options)?);
+
+        tokens.assert_empty();
+        Ok(Box::new(Content { name, content }))
+    }
+
+    fn reflection(&self) -> &dyn BlockReflection {
+        self
+    }
+}
+
+#[derive(Debug)]
+struct Content {
+    name: String,
+    content: Template,
+}
+
+impl Renderable for Content {
+    fn render_to(
+        &self,
+        writer: &mut dyn Write,
+        runtime: &dyn Runtime,
+    ) -> Result<(), liquid::Error> {
+        // Layouts are rendered with the `layout` context; pages are not.
+        let is_layout = runtime.try_get(&[ScalarCow::new("layout")]).is_some();
+        if !is_layout {
+            // The contents are marked so they can be separated from the rest of the page once rendered.
+            write!(writer, "{}{}{}", BLOCK_START, self.name, BLOCK_NAME_END)
+                .replace("Failed to render")?;
+            self.content.render_to(writer, runtime)?;
+            write!(writer, "{}", BLOCK_END).replace("Failed to render")?;
+            return Ok(());
+        }
+        let provided = runtime.try_get(&[
+            ScalarCow::new("page"),
+            ScalarCow::new("blocks"),
+            ScalarCow::new(self.name.clone()),
+        ]);
+        match provided {
+            Some(provided) => {
+                write!(writer, "{}", provided.to_kstr()).replace("Failed to render")?;
+            }
+            None => self.content.render_to(writer, runtime)?,
+        }
+        Ok(())
+    }
+}
+
+/// Separate the contents of blocks from the rest of a rendered page.
+/// Blocks with the same name are combined in the order they were rendered.
+///
+/// # Arguments
+///
+/// * `rendered` - The rendered page.
+///
+/// # Returns
+///
+/// The rendered page without its blocks, and the contents of each block by name.
+pub fn extract_blocks(rendered: &str) -> (String, BTreeMap<String, String>) {
+    let mut blocks: BTreeMap<String, String> = BTreeMap::new();
+    // The contents outside of any block, followed by the name & contents of each block being read.
+    let mut stack = vec![(String::new(), String::new())];
+    let mut characters = rendered.chars();
+    while let Some(character) = characters.next() {
+        match character {
+            BLOCK_START => {
+                let name = characters
+                    .by_ref()
+                    .take_while(|x| *x != BLOCK_NAME_END)
+                    .collect();
+                stack.push((name, String::new()));
+            }
+            BLOCK_END if stack.len() > 1 => {
+                if let Some((name, contents)) = stack.pop() {
+                    blocks.entry(name).or_default().push_str(&contents);
+                }
+            }
+            character => {
+                if let Some((_, contents)) = stack.last_mut() {
+                    contents.push(character);
+                }
+            }
+        }
+    }
+    (std::mem::take(&mut stack[0].1), blocks)
+}
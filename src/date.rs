@@ -5,11 +5,12 @@ use chrono::NaiveDateTime;
 use chrono::NaiveTime;
 use chrono::TimeZone;
 use chrono::Utc;
+use chrono_tz::Tz;
 use core::fmt;
 use serde::Deserialize;
 use serde::Serialize;
 use sys_locale::get_locale;
-use tracing::debug;
+use tracing::{debug, warn};
 
 #[derive(Eq, PartialEq, PartialOrd, Clone, Default, Debug, Serialize, Deserialize)]
 /// A page's date-time metadata
@@ -69,7 +70,13 @@ impl Date {
     /// * `value` - The `toml::Value` to convert
     ///
     /// * `locale` - The locale used to represent dates and times
-    pub fn value_to_date(value: toml::value::Datetime, locale: chrono::Locale) -> Date {
+    ///
+    /// * `timezone` - The timezone used to represent dates and times; date-times without an offset are assumed to be in this timezone
+    pub fn value_to_date(
+        value: toml::value::Datetime,
+        locale: chrono::Locale,
+        timezone: Tz,
+    ) -> Date {
         let date = match value.date {
             Some(date) => {
                 NaiveDate::from_ymd_opt(date.year.into(), date.month.into(), date.day.into())
@@ -98,9 +105,22 @@ impl Date {
                     TimeZone::from_offset(&FixedOffset::east_opt(offset_seconds.into()).unwrap());
                 naive_datetime.and_local_timezone(timezone).unwrap()
             }
-            None => naive_datetime.and_utc().into(),
+            None => naive_datetime
+                .and_local_timezone(timezone)
+                .earliest()
+                .unwrap_or_else(|| naive_datetime.and_utc().with_timezone(&timezone))
+                .fixed_offset(),
         };
-        Date::chrono_to_date(datetime.into(), locale)
+        Date::chrono_to_date(datetime.into(), locale, timezone)
+    }
+
+    /// Convert a `Date` into a `chrono::DateTime`
+    ///
+    /// # Returns
+    ///
+    /// The date-time represented by the `Date`, if it is valid
+    pub fn to_datetime(&self) -> Option<DateTime<FixedOffset>> {
+        DateTime::parse_from_rfc3339(&self.rfc_3339).ok()
     }
 
     /// Convert a `chrono::DateTime` into a `Date`
@@ -110,7 +130,14 @@ impl Date {
     /// * `datetime` - A `chrono::DateTime<chrono::Utc>`
     ///
     /// * `locale` - The locale used to represent dates and times
-    pub fn chrono_to_date(datetime: chrono::DateTime<Utc>, locale: chrono::Locale) -> Date {
+    ///
+    /// * `timezone` - The timezone used to represent dates and times
+    pub fn chrono_to_date(
+        datetime: chrono::DateTime<Utc>,
+        locale: chrono::Locale,
+        timezone: Tz,
+    ) -> Date {
+        let datetime = datetime.with_timezone(&timezone);
         Date {
             year: format!("{}", datetime.format_localized("%Y", locale)),
             short_year: format!("{}", datetime.format_localized("%y", locale)),
@@ -150,3 +177,32 @@ pub fn locale_string_to_locale(locale: String) -> chrono::Locale {
     debug!("Locale: {}", locale);
     chrono::Locale::try_from(locale.as_str()).unwrap_or(default_locale())
 }
+
+/// Gets the locale and timezone configured in a site's global settings, defaulting to the system locale and UTC
+pub fn settings_to_locale_and_timezone(settings: &toml::Table) -> (chrono::Locale, Tz) {
+    let locale = settings
+        .get("locale")
+        .and_then(|x| x.as_str())
+        .map(|x| locale_string_to_locale(x.to_string()))
+        .unwrap_or_else(default_locale);
+    let timezone = settings
+        .get("timezone")
+        .and_then(|x| x.as_str())
+        .map(|x| timezone_string_to_timezone(x.to_string()))
+        .unwrap_or(default_timezone());
+    (locale, timezone)
+}
+
+/// Gets the default timezone, being UTC
+pub fn default_timezone() -> Tz {
+    Tz::UTC
+}
+
+/// Gets a `chrono_tz::Tz` from a string, such as 'America/Toronto'
+pub fn timezone_string_to_timezone(timezone: String) -> Tz {
+    debug!("Timezone: {}", timezone);
+    timezone.parse::<Tz>().unwrap_or_else(|_| {
+        warn!("Unknown timezone `{}`; defaulting to UTC … ", timezone);
+        default_timezone()
+    })
+}
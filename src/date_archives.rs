@@ -0,0 +1,158 @@
+use crate::page::Page;
+use crate::schema::Schema;
+use chrono::Locale;
+use chrono_tz::Tz;
+use miette::IntoDiagnostic;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use toml::Table;
+
+/// The directory archive pages are named as being within, relative to the site's directory.
+/// Archive pages are not read from this directory; being hidden, no page of the site can share a path with them.
+pub const ARCHIVES_DIRECTORY: &str = ".archives";
+
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+/// Settings for the archive pages of a dated collection, defined by an entry in the `archives` list in `global.toml`.
+pub struct DateArchiveSettings {
+    /// The collection whose dated pages are archived.
+    pub collection: String,
+    /// The layout archive pages are rendered with.
+    pub layout: String,
+    /// The path archive pages are output within, relative to the output directory; defaults to the name of the collection.
+    pub path: Option<String>,
+    /// Whether or not an archive page is output for each month, as well as for each year.
+    #[serde(default = "default_monthly")]
+    pub monthly: bool,
+}
+
+/// By default, an archive page is output for each month.
+fn default_monthly() -> bool {
+    true
+}
+
+/// An archive page of a dated collection.
+pub struct DateArchive {
+    /// The archive page.
+    pub page: Page,
+    /// The positions of the pages dated within the archive's period, among the members of the collection.
+    pub members: Vec<usize>,
+}
+
+/// Synthesise the archive pages of a dated collection; one for each year, and, if enabled, each month with dated pages.
+/// An archive page's `archive` data holds the collection, year, and month it archives.
+/// As archive pages depend on the pages in their period, their hashes are derived from those of their members, so that they are rebuilt when their members change.
+///
+/// # Arguments
+///
+/// * `settings` - The settings of the collection's archives.
+///
+/// * `members` - The pages in the collection.
+///
+/// * `locale` - The locale for date formatting.
+///
+/// * `timezone` - The timezone for date formatting.
+///
+/// # Returns
+///
+/// The archive pages, in order of period.
+pub fn date_archives(
+    settings: &DateArchiveSettings,
+    members: &[&Page],
+    locale: Locale,
+    timezone: Tz,
+) -> miette::Result<Vec<DateArchive>> {
+    let mut periods: BTreeMap<(String, Option<String>), Vec<usize>> = BTreeMap::new();
+    for (position, member) in members.iter().enumerate() {
+        let Some(date) = &member.date else {
+            continue;
+        };
+        periods
+            .entry((date.year.clone(), None))
+            .or_default()
+            .push(position);
+        if settings.monthly {
+            periods
+                .entry((date.year.clone(), Some(date.month.clone())))
+                .or_default()
+                .push(position);
+        }
+    }
+    let output_directory = settings.path.clone().unwrap_or(settings.collection.clone());
+    let output_directory = output_directory.trim_matches('/');
+    let mut archives = Vec::new();
+    for ((year, month), mut positions) in periods {
+        positions.sort_by(|lhs, rhs| {
+            Page::collection_order(members[*lhs], members[*rhs]).then_with(|| {
+                members[*lhs]
+                    .to_path_string()
+                    .cmp(&members[*rhs].to_path_string())
+            })
+        });
+        let mut archive = Table::new();
+        archive.insert("collection".into(), settings.collection.clone().into());
+        archive.insert("year".into(), year.parse::<i64>().into_diagnostic()?.into());
+        let (name, title, permalink) = match &month {
+            Some(month) => {
+                archive.insert(
+                    "month".into(),
+                    month.parse::<i64>().into_diagnostic()?.into(),
+                );
+                // Every member of a monthly archive has the same month, so the first names it.
+                let long_month = members[positions[0]]
+                    .date
+                    .as_ref()
+                    .map(|date| date.long_month.clone())
+                    .unwrap_or(month.clone());
+                (
+                    format!("{}-{}", year, month),
+                    format!("{} {}", long_month, year),
+                    format!("{}/{}/{}/index.html", output_directory, year, month),
+                )
+            }
+            None => (
+                year.clone(),
+                year.clone(),
+                format!("{}/{}/index.html", output_directory, year),
+            ),
+        };
+        let mut frontmatter = Table::new();
+        frontmatter.insert("title".into(), title.into());
+        frontmatter.insert("layout".into(), settings.layout.clone().into());
+        frontmatter.insert("permalink".into(), permalink.trim_start_matches('/').into());
+        frontmatter.insert("archive".into(), archive.into());
+        let path = Path::new(ARCHIVES_DIRECTORY)
+            .join(&settings.collection)
+            .join(format!("{}.vox", name));
+        let mut page = Page::new(
+            format!(
+                "---\n{}---\n",
+                toml::to_string(&frontmatter).into_diagnostic()?
+            ),
+            path,
+            locale,
+            timezone,
+            Table::new(),
+            &Schema::default(),
+        )?;
+        // Archive pages are not in any collection, including that of the directory they are named as being within.
+        page.collections = None;
+        let member_hashes = positions
+            .iter()
+            .map(|position| {
+                format!(
+                    "{}:{}",
+                    members[*position].to_path_string(),
+                    members[*position].hash
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        page.hash = Page::hash_source(&page.data, &member_hashes);
+        archives.push(DateArchive {
+            page,
+            members: positions,
+        });
+    }
+    Ok(archives)
+}
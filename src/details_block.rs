@@ -0,0 +1,121 @@
+use crate::admonitions::{render_admonitions, AdmonitionSettings};
+use crate::markdown_block::render_markdown;
+use crate::syntax_highlighting::SyntaxHighlightingSettings;
+use liquid_core::error::ResultLiquidReplaceExt;
+use liquid_core::parser;
+use liquid_core::runtime;
+use liquid_core::Expression;
+use liquid_core::Language;
+use liquid_core::Renderable;
+use liquid_core::Result;
+use liquid_core::Runtime;
+use liquid_core::Template;
+use liquid_core::ValueView;
+use liquid_core::{BlockReflection, ParseBlock, TagBlock, TagTokenIter};
+use std::io::BufWriter;
+use std::io::Write;
+
+#[derive(Copy, Clone, Debug, Default)]
+/// A Liquid template block containing Markdown, collapsed beneath a summary.
+/// The block begins with `{% details "Summary text" %}` and ends with `{% enddetails %}`; it is expanded by default if the summary is followed by `open`.
+pub struct DetailsBlock;
+
+impl DetailsBlock {
+    /// Provides a new instance of the details tag parser.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl BlockReflection for DetailsBlock {
+    fn start_tag(&self) -> &str {
+        "details"
+    }
+
+    fn end_tag(&self) -> &str {
+        "enddetails"
+    }
+
+    fn description(&self) -> &str {
+        ""
+    }
+}
+
+impl ParseBlock for DetailsBlock {
+    fn parse(
+        &self,
+        mut arguments: TagTokenIter<'_>,
+        mut tokens: TagBlock<'_, '_>,
+        options: &Language,
+    ) -> Result<Box<dyn Renderable + 'static>, liquid::Error> {
+        let summary = arguments
+            .expect_next("Summary expected.")?
+            .expect_value()
+            .into_result()?;
+        let open = match arguments.next() {
+            Some(argument) => {
+                argument
+                    .expect_str("open")
+                    .into_result_custom_msg("\"open\" expected.")?;
+                true
+            }
+            None => false,
+        };
+        arguments.expect_nothing()?;
+
+        let raw_content = tokens.escape_liquid(false)?.to_string();
+        let content = parser::parse(&raw_content, options).map(runtime::Template::new)?;
+
+        tokens.assert_empty();
+        Ok(Box::new(Details {
+            summary,
+            open,
+            content,
+        }))
+    }
+
+    fn reflection(&self) -> &dyn BlockReflection {
+        self
+    }
+}
+
+#[derive(Debug)]
+struct Details {
+    summary: Expression,
+    open: bool,
+    content: Template,
+}
+
+impl Renderable for Details {
+    fn render_to(
+        &self,
+        writer: &mut dyn Write,
+        runtime: &dyn Runtime,
+    ) -> Result<(), liquid::Error> {
+        let summary = self.summary.evaluate(runtime)?.to_kstr().to_string();
+        let mut buf = BufWriter::new(Vec::new());
+        self.content.render_to(&mut buf, runtime)?;
+        let bytes = buf.into_inner().unwrap_or_default();
+        let liquid_rendered = String::from_utf8(bytes).unwrap_or_default();
+        let rendered = render_admonitions(
+            &render_markdown(
+                liquid_rendered,
+                &SyntaxHighlightingSettings::from_runtime(runtime)?,
+            ),
+            &AdmonitionSettings::from_runtime(runtime)?,
+        );
+        let open = match self.open {
+            true => " open",
+            false => "",
+        };
+        write!(
+            writer,
+            "<details{}>\n<summary>{}</summary>\n{}</details>",
+            open,
+            html_escape::encode_text(&summary),
+            rendered
+        )
+        .replace("Failed to render")?;
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-use miette::{Diagnostic, NamedSource};
+use miette::{Diagnostic, NamedSource, SourceSpan};
 use thiserror::Error;
 
 #[derive(Error, Debug, Diagnostic)]
@@ -44,3 +44,233 @@ pub struct InvalidDependsProperty {
     /// The page with the invalid `depends` property.
     pub src: NamedSource<String>,
 }
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("Invalid list of collections ({0}).\n{1}", src.name(), src.inner())]
+#[diagnostic(
+    code(page::invalid_collection_membership),
+    url(docsrs),
+    help("Please ensure that your `collections` property is a list of collections this page belongs to.")
+)]
+/// Invalid list of collections a page belongs to.
+pub struct InvalidCollectionsProperty {
+    #[source_code]
+    /// The page with the invalid `collections` property.
+    pub src: NamedSource<String>,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("Invalid layout ({0}).\n{1}", src.name(), src.inner())]
+#[diagnostic(
+    code(page::invalid_layout_property),
+    url(docsrs),
+    help("Please ensure that your `layout` property is the name of a layout, or a list of layout names from innermost to outermost.")
+)]
+/// Invalid layout.
+pub struct InvalidLayoutProperty {
+    #[source_code]
+    /// The page with the invalid `layout` property.
+    pub src: NamedSource<String>,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("Invalid weight ({0}).\n{1}", src.name(), src.inner())]
+#[diagnostic(
+    code(page::invalid_weight_property),
+    url(docsrs),
+    help("Please ensure that your `weight` or `order` property is an integer.")
+)]
+/// Invalid weight.
+pub struct InvalidWeightProperty {
+    #[source_code]
+    /// The page with the invalid `weight` or `order` property.
+    pub src: NamedSource<String>,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("Required frontmatter key `{key}` is missing ({0}).", src.name())]
+#[diagnostic(
+    code(page::missing_frontmatter_key),
+    url(docsrs),
+    help("Please ensure that all pages specify the keys required by the frontmatter schema.")
+)]
+/// Required frontmatter key is missing.
+pub struct MissingFrontmatterKey {
+    #[source_code]
+    /// The frontmatter of the page missing the key.
+    pub src: NamedSource<String>,
+    /// The missing key.
+    pub key: String,
+    #[label("`{key}` is required here")]
+    /// The location of the frontmatter.
+    pub span: SourceSpan,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("Frontmatter key `{key}` is not valid ({0}): {reason}", src.name())]
+#[diagnostic(
+    code(page::invalid_frontmatter_key),
+    url(docsrs),
+    help("Please ensure that all frontmatter values conform to the frontmatter schema.")
+)]
+/// Frontmatter key has a value not permitted by the schema.
+pub struct InvalidFrontmatterKey {
+    #[source_code]
+    /// The frontmatter of the page with the invalid key.
+    pub src: NamedSource<String>,
+    /// The invalid key.
+    pub key: String,
+    /// Why the key is not valid.
+    pub reason: String,
+    #[label("{reason}")]
+    /// The location of the invalid key.
+    pub span: Option<SourceSpan>,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("Frontmatter key `{key}` is unknown ({0}).", src.name())]
+#[diagnostic(
+    code(page::unknown_frontmatter_key),
+    url(docsrs),
+    help("Please ensure that all frontmatter keys are declared in the frontmatter schema, or disable `strict` schema validation.")
+)]
+/// Frontmatter key is not declared in a strict schema.
+pub struct UnknownFrontmatterKey {
+    #[source_code]
+    /// The frontmatter of the page with the unknown key.
+    pub src: NamedSource<String>,
+    /// The unknown key.
+    pub key: String,
+    #[label("unknown key")]
+    /// The location of the unknown key.
+    pub span: Option<SourceSpan>,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("Layout `{layout}` uses itself.")]
+#[diagnostic(
+    code(page::layout_cycle),
+    url(docsrs),
+    help(
+        "Please ensure that no layout uses itself, either directly or through the layouts it uses."
+    )
+)]
+/// Layout uses itself.
+pub struct LayoutCycle {
+    /// The path to the layout using itself.
+    pub layout: String,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("Multiple pages have the URL `{url}`: {pages}.")]
+#[diagnostic(
+    code(page::url_conflict),
+    url(docsrs),
+    help("Please give these pages distinct permalinks, or set `url_conflicts` in `global.toml` to \"first\" or \"suffix\" while migrating.")
+)]
+/// Multiple pages have the same URL.
+pub struct UrlConflict {
+    /// The URL the pages share.
+    pub url: String,
+    /// The paths to the pages sharing the URL.
+    pub pages: String,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("Invalid `sitemap` property of `{page}`: {reason}.")]
+#[diagnostic(
+    code(page::invalid_sitemap_property),
+    url(docsrs),
+    help("Please ensure that your `sitemap` property is either `false`, or a table with a `priority` from 0.0 to 1.0 and a `changefreq` of \"always\", \"hourly\", \"daily\", \"weekly\", \"monthly\", \"yearly\", or \"never\".")
+)]
+/// Invalid entry of a page in the sitemap.
+pub struct InvalidSitemapProperty {
+    /// The path to the page.
+    pub page: String,
+    /// Why the property is invalid.
+    pub reason: String,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("WebAssembly plugin `{plugin}` cannot be loaded.")]
+#[diagnostic(
+    code(plugin::wasm_unsupported),
+    url(docsrs),
+    help("This build of Vox was built without the `wasm` feature; please remove the plugin from the `plugins` list in `global.toml`.")
+)]
+/// WebAssembly plugin cannot be loaded.
+pub struct WasmPluginUnsupported {
+    /// The path to the plugin's module.
+    pub plugin: String,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("WebAssembly plugin `{plugin}` could not be run: {reason}.")]
+#[diagnostic(code(plugin::wasm), url(docsrs))]
+/// WebAssembly plugin could not be run.
+pub struct WasmPluginError {
+    /// The path to the plugin's module.
+    pub plugin: String,
+    /// Why the plugin could not be run.
+    pub reason: String,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("{problem} ({page}).")]
+#[diagnostic(code(page::lint), url(docsrs))]
+/// Page content breaks a lint rule.
+pub struct LintProblem {
+    #[source_code]
+    /// The source of the page.
+    pub src: NamedSource<String>,
+    /// The path to the source of the page.
+    pub page: String,
+    /// The problem found.
+    pub problem: String,
+    #[help]
+    /// How the problem may be fixed, if there is advice to give.
+    pub advice: Option<String>,
+    #[label("here")]
+    /// The location of the problem.
+    pub span: SourceSpan,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("{problem} ({page}).")]
+#[diagnostic(code(audit::accessibility), url(docsrs), help("{advice}"))]
+/// Rendered page has an accessibility problem.
+pub struct AccessibilityProblem {
+    #[source_code]
+    /// The rendered page.
+    pub src: NamedSource<String>,
+    /// The path to the source of the page.
+    pub page: String,
+    /// The problem found.
+    pub problem: String,
+    /// How the problem may be fixed.
+    pub advice: String,
+    #[label("here")]
+    /// The location of the problem.
+    pub span: SourceSpan,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("{problem} ({source_path}).")]
+#[diagnostic(
+    code(audit::html),
+    url(docsrs),
+    help("Please ensure that every element is closed, that elements are only within elements permitted to contain them, and that no two elements share an ID.")
+)]
+/// Rendered page has a structural error.
+pub struct InvalidHtml {
+    #[source_code]
+    /// The rendered page.
+    pub src: NamedSource<String>,
+    /// The path to the page or layout the erroneous markup was rendered from.
+    pub source_path: String,
+    /// The error found.
+    pub problem: String,
+    #[label("here")]
+    /// The line the error is on.
+    pub span: SourceSpan,
+}
@@ -0,0 +1,67 @@
+use miette::IntoDiagnostic;
+use path_clean::PathClean;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+/// The paths excluded from a site, defined by the `exclude` list in `global.toml`.
+/// Excluded files are neither pages nor snippets, and changes to them are not watched.
+pub struct Exclusions {
+    /// Glob patterns matching excluded paths, relative to the site's directory.
+    patterns: Vec<glob::Pattern>,
+}
+
+impl Exclusions {
+    /// Create a set of exclusions from glob patterns.
+    ///
+    /// # Arguments
+    ///
+    /// * `patterns` - Glob patterns matching excluded paths, relative to the site's directory (eg: `drafts/**`).
+    ///
+    /// # Returns
+    ///
+    /// The exclusions, or an error if a pattern is invalid.
+    pub fn new(patterns: &[String]) -> miette::Result<Self> {
+        Ok(Self {
+            patterns: patterns
+                .iter()
+                .map(|pattern| glob::Pattern::new(pattern).into_diagnostic())
+                .collect::<miette::Result<_>>()?,
+        })
+    }
+
+    /// Determine if a path is excluded.
+    /// A path is excluded if it, or any directory containing it, matches a pattern.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path, relative to the site's directory.
+    ///
+    /// # Returns
+    ///
+    /// Whether or not the path is excluded.
+    pub fn is_excluded(&self, path: impl AsRef<Path>) -> bool {
+        if self.patterns.is_empty() {
+            return false;
+        }
+        let path = path.as_ref().clean();
+        path.ancestors()
+            .filter(|x| !x.as_os_str().is_empty())
+            .any(|x| self.patterns.iter().any(|pattern| pattern.matches_path(x)))
+    }
+
+    /// Remove excluded paths from a list of paths.
+    ///
+    /// # Arguments
+    ///
+    /// * `paths` - The paths, relative to the site's directory.
+    ///
+    /// # Returns
+    ///
+    /// The paths which are not excluded.
+    pub fn filter(&self, paths: Vec<PathBuf>) -> Vec<PathBuf> {
+        paths
+            .into_iter()
+            .filter(|path| !self.is_excluded(path))
+            .collect()
+    }
+}
@@ -0,0 +1,345 @@
+use crate::builds::EdgeType;
+use crate::page::Page;
+use crate::paths::url_output_path;
+use crate::post_processing::{get_attribute, image_paths, map_tags, set_attribute};
+use crate::provider::VoxProvider;
+use ahash::{AHashMap, AHashSet};
+use base64::Engine;
+use daggy::stable_dag::StableDag;
+use path_clean::PathClean;
+use std::path::{Path, PathBuf};
+use toml::Table;
+use tracing::{debug, warn};
+
+/// The class of the element containing each page of an export.
+const PAGE_CLASS: &str = "vox-export-page";
+
+/// Styles applied to every export, so that each page begins on a new sheet when printed.
+const EXPORT_STYLE: &str =
+    "section.vox-export-page + section.vox-export-page { break-before: page; }";
+
+/// Find the pages exported from a built site.
+///
+/// # Arguments
+///
+/// * `dag` - The DAG of the built site.
+///
+/// * `collection` - The collection to export, or `None` to export every page.
+///
+/// # Returns
+///
+/// The pages output as HTML, in the order of a collection; pages with the same position are ordered by path.
+pub fn exported_pages(dag: &StableDag<Page, EdgeType>, collection: Option<&str>) -> Vec<Page> {
+    let mut pages = dag
+        .graph()
+        .node_weights()
+        .filter(|page| !page.is_layout && url_output_path(&page.url).ends_with(".html"))
+        .filter(|page| match collection {
+            Some(collection) => page
+                .collections
+                .as_ref()
+                .is_some_and(|collections| collections.iter().any(|x| x == collection)),
+            None => true,
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    pages.sort_by(|lhs, rhs| {
+        Page::collection_order(lhs, rhs)
+            .then_with(|| lhs.to_path_string().cmp(&rhs.to_path_string()))
+    });
+    pages
+}
+
+/// Combine the output of pages into a single HTML document, with their stylesheets and images inlined.
+/// Only the `<main>` element of each page is exported, if it has one, and otherwise its whole body.
+/// Links between the exported pages are rewritten to point within the document.
+///
+/// # Arguments
+///
+/// * `provider` - The provider the site was built with.
+///
+/// * `pages` - The pages to export, in order.
+///
+/// # Returns
+///
+/// The HTML document.
+pub fn export_html(provider: &impl VoxProvider, pages: &[Page]) -> miette::Result<String> {
+    let site_url = provider.get_post_processing_settings()?.url;
+    let global = provider
+        .read_to_string("global.toml")
+        .ok()
+        .and_then(|global| global.parse::<Table>().ok())
+        .unwrap_or_default();
+    let title = global
+        .get("title")
+        .and_then(|x| x.as_str())
+        .unwrap_or("Export");
+    let anchors = pages
+        .iter()
+        .map(|page| (output_path(&page.url), anchor(&page.url)))
+        .collect::<AHashMap<_, _>>();
+
+    // Files which cannot be inlined, such as those on other sites, are only reported once.
+    let mut uninlined = AHashSet::new();
+    let mut stylesheets: Vec<PathBuf> = Vec::new();
+    let mut styles: Vec<String> = Vec::new();
+    let mut sections = Vec::new();
+    for page in pages {
+        let page_output_path = output_path(&page.url);
+        debug!("Exporting {:?} … ", page_output_path);
+        let html = provider.read_to_string(&page_output_path)?;
+        let (head, body) = split_document(&html);
+        let main = inner_elements(body, "main").into_iter().next();
+        let body = main.as_deref().unwrap_or(body);
+        let page_output = page_output_path.to_string_lossy();
+        map_tags(head, "link", |attributes| {
+            let is_stylesheet = get_attribute(attributes, "rel").is_some_and(|rel| {
+                rel.split_whitespace()
+                    .any(|x| x.eq_ignore_ascii_case("stylesheet"))
+            });
+            if let Some(href) = get_attribute(attributes, "href").filter(|_| is_stylesheet) {
+                match image_paths(&href, &page_output, site_url.as_deref()).first() {
+                    Some(path) if !stylesheets.contains(path) => stylesheets.push(path.clone()),
+                    Some(_) => {}
+                    None => {
+                        if uninlined.insert(href.clone()) {
+                            warn!("Unable to inline the stylesheet `{}`.", href);
+                        }
+                    }
+                }
+            }
+            false
+        });
+        for style in inner_elements(head, "style") {
+            if !styles.contains(&style) {
+                styles.push(style);
+            }
+        }
+        let body = map_tags(body, "img", |attributes| {
+            let Some(src) = get_attribute(attributes, "src") else {
+                return false;
+            };
+            match data_url(
+                provider,
+                &image_paths(&src, &page_output, site_url.as_deref()),
+            ) {
+                Some(data_url) => {
+                    set_attribute(attributes, "src", data_url);
+                    true
+                }
+                None => {
+                    if uninlined.insert(src.clone()) {
+                        warn!("Unable to inline the image `{}`.", src);
+                    }
+                    false
+                }
+            }
+        });
+        let body = map_tags(&body, "a", |attributes| {
+            let Some(href) = get_attribute(attributes, "href") else {
+                return false;
+            };
+            if href.starts_with('#') {
+                return false;
+            }
+            // Links to directories lead to their index pages.
+            let Some(anchor) = image_paths(&href, &page_output, site_url.as_deref())
+                .first()
+                .and_then(|path| {
+                    anchors
+                        .get(path)
+                        .or_else(|| anchors.get(&path.join("index.html")))
+                })
+            else {
+                return false;
+            };
+            let fragment = href
+                .split_once('#')
+                .map(|(_, fragment)| fragment)
+                .filter(|fragment| !fragment.is_empty());
+            let target = fragment.unwrap_or(anchor);
+            set_attribute(attributes, "href", format!("#{}", target));
+            true
+        });
+        sections.push(format!(
+            "<section id=\"{}\" class=\"{}\">\n{}\n</section>",
+            anchor(&page.url),
+            PAGE_CLASS,
+            body.trim()
+        ));
+    }
+
+    let mut css = String::new();
+    for stylesheet in stylesheets {
+        match provider.read_to_string(&stylesheet) {
+            Ok(contents) => {
+                css.push_str(&inline_css_urls(provider, &contents, &stylesheet));
+                css.push('\n');
+            }
+            Err(_) => warn!("Unable to inline the stylesheet {:?}.", stylesheet),
+        }
+    }
+    for style in styles {
+        css.push_str(&style);
+        css.push('\n');
+    }
+    css.push_str(EXPORT_STYLE);
+    Ok(format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}\n</style>\n</head>\n<body>\n{}\n</body>\n</html>\n",
+        html_escape::encode_text(title),
+        css,
+        sections.join("\n")
+    ))
+}
+
+/// Obtain the output path of a page.
+///
+/// # Arguments
+///
+/// * `url` - The URL of the page.
+///
+/// # Returns
+///
+/// The path the page is output at.
+fn output_path(url: &str) -> PathBuf {
+    PathBuf::from(url_output_path(url)).clean()
+}
+
+/// Name the element containing a page of an export.
+///
+/// # Arguments
+///
+/// * `url` - The URL of the page.
+///
+/// # Returns
+///
+/// An identifier derived from the URL.
+fn anchor(url: &str) -> String {
+    let anchor = url
+        .trim_start_matches('/')
+        .trim_end_matches('/')
+        .trim_end_matches(".html")
+        .chars()
+        .map(|x| match x.is_ascii_alphanumeric() {
+            true => x.to_ascii_lowercase(),
+            false => '-',
+        })
+        .collect::<String>();
+    format!("vox-page-{}", anchor)
+}
+
+/// Split an HTML document into its head and the contents of its body.
+///
+/// # Arguments
+///
+/// * `html` - The HTML document.
+///
+/// # Returns
+///
+/// The source preceding the `<body>` tag, and the source between it and the `</body>` tag; if there is no `<body>` tag, the whole document is the body.
+fn split_document(html: &str) -> (&str, &str) {
+    let lowercase_html = html.to_ascii_lowercase();
+    let Some(body_start) = lowercase_html.find("<body") else {
+        return ("", html);
+    };
+    let Some(content_start) = lowercase_html[body_start..]
+        .find('>')
+        .map(|x| x + body_start + 1)
+    else {
+        return ("", html);
+    };
+    let content_end = lowercase_html
+        .rfind("</body")
+        .filter(|x| *x >= content_start)
+        .unwrap_or(html.len());
+    (&html[..body_start], &html[content_start..content_end])
+}
+
+/// Obtain the contents of each occurrence of an HTML element.
+///
+/// # Arguments
+///
+/// * `html` - The HTML.
+///
+/// * `name` - The name of the element, in lowercase.
+///
+/// # Returns
+///
+/// The contents of the elements, in order of appearance.
+fn inner_elements(html: &str, name: &str) -> Vec<String> {
+    let lowercase_html = html.to_ascii_lowercase();
+    let opening = format!("<{}", name);
+    let closing = format!("</{}", name);
+    let mut contents = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = lowercase_html[offset..].find(&opening).map(|x| x + offset) {
+        let Some(content_start) = lowercase_html[start..].find('>').map(|x| x + start + 1) else {
+            break;
+        };
+        let Some(content_end) = lowercase_html[content_start..]
+            .find(&closing)
+            .map(|x| x + content_start)
+        else {
+            break;
+        };
+        contents.push(html[content_start..content_end].to_string());
+        offset = content_end;
+    }
+    contents
+}
+
+/// Encode a file as a `data:` URL.
+///
+/// # Arguments
+///
+/// * `provider` - The provider the file is read from.
+///
+/// * `paths` - The paths the file may be found at, in order of preference.
+///
+/// # Returns
+///
+/// The file as a `data:` URL, or `None` if it could not be read.
+fn data_url(provider: &impl VoxProvider, paths: &[PathBuf]) -> Option<String> {
+    paths.iter().find_map(|path| {
+        let bytes = provider.read_bytes(path).ok()?;
+        let mime = mime_guess::from_path(path).first_or_octet_stream();
+        Some(format!(
+            "data:{};base64,{}",
+            mime,
+            base64::engine::general_purpose::STANDARD.encode(bytes)
+        ))
+    })
+}
+
+/// Inline the files a stylesheet refers to with `url()`, such as fonts and images.
+///
+/// # Arguments
+///
+/// * `provider` - The provider the files are read from.
+///
+/// * `css` - The stylesheet.
+///
+/// * `stylesheet` - The path of the stylesheet, which relative URLs are resolved against.
+///
+/// # Returns
+///
+/// The stylesheet, with the files it refers to as `data:` URLs where they could be read.
+fn inline_css_urls(provider: &impl VoxProvider, css: &str, stylesheet: &Path) -> String {
+    let mut inlined = String::with_capacity(css.len());
+    let mut offset = 0;
+    while let Some(start) = css[offset..].find("url(").map(|x| x + offset + 4) {
+        let Some(end) = css[start..].find(')').map(|x| x + start) else {
+            break;
+        };
+        inlined.push_str(&css[offset..start]);
+        let url = css[start..end].trim().trim_matches(['"', '\'']);
+        let paths = image_paths(url, &stylesheet.to_string_lossy(), None);
+        match data_url(provider, &paths) {
+            Some(data_url) => inlined.push_str(&format!("\"{}\"", data_url)),
+            None => inlined.push_str(&css[start..end]),
+        }
+        offset = end;
+    }
+    inlined.push_str(&css[offset..]);
+    inlined
+}
@@ -0,0 +1,318 @@
+use crate::page::Page;
+use crate::paths::{canonical_url, to_slash_string};
+use crate::post_processing::{get_attribute, map_tags, set_attribute};
+use path_clean::PathClean;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// The attributes of rendered content holding URLs, by element.
+const URL_ATTRIBUTES: [(&str, &str); 9] = [
+    ("a", "href"),
+    ("img", "src"),
+    ("img", "srcset"),
+    ("source", "src"),
+    ("source", "srcset"),
+    ("video", "src"),
+    ("video", "poster"),
+    ("audio", "src"),
+    ("iframe", "src"),
+];
+
+#[derive(PartialEq, Eq, Clone, Copy, Default, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// What the entries of a feed contain.
+pub enum FeedContent {
+    /// The rendered content of each page, not including its layouts.
+    #[default]
+    Full,
+    /// The excerpt of each page.
+    Excerpt,
+    /// The `summary` defined in each page's frontmatter, or otherwise its excerpt.
+    Summary,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+/// Settings for the Atom feed of a collection, defined by an entry in the `feeds` list in `global.toml`.
+pub struct FeedSettings {
+    /// The collection whose pages are the entries of the feed.
+    pub collection: String,
+    /// The path the feed is output at, relative to the output directory; defaults to `atom.xml` within the directory named after the collection.
+    pub path: Option<String>,
+    /// The title of the feed; defaults to the title of the site.
+    pub title: Option<String>,
+    /// What the entries of the feed contain.
+    #[serde(default)]
+    pub content: FeedContent,
+    /// Whether or not relative URLs within the content of entries are made absolute with the site's URL.
+    #[serde(default = "default_absolute_urls")]
+    pub absolute_urls: bool,
+    /// The maximum number of entries in the feed, being the most recent pages.
+    pub limit: Option<usize>,
+}
+
+/// By default, relative URLs within feeds are made absolute, as feed readers often resolve them against the feed instead of the page.
+fn default_absolute_urls() -> bool {
+    true
+}
+
+impl FeedSettings {
+    /// Get the URL of the feed.
+    ///
+    /// # Returns
+    ///
+    /// The path the feed is output at, relative to the output directory.
+    pub fn url(&self) -> String {
+        match &self.path {
+            Some(path) => path.trim_start_matches('/').to_string(),
+            None => format!("{}/atom.xml", self.collection.trim_matches('/')),
+        }
+    }
+
+    /// Get the path the feed is output at.
+    ///
+    /// # Returns
+    ///
+    /// The path the feed is output at, relative to the site's directory.
+    pub fn output_path(&self) -> String {
+        format!("output/{}", self.url())
+    }
+}
+
+/// Make a URL within a page absolute.
+///
+/// # Arguments
+///
+/// * `url` - The URL, as written in the page.
+///
+/// * `page_url` - The URL of the page, relative to the site.
+///
+/// * `site_url` - The URL of the site.
+///
+/// # Returns
+///
+/// The absolute URL, or `None` if the URL is already absolute or is empty.
+pub fn absolute_url(url: &str, page_url: &str, site_url: &str) -> Option<String> {
+    let url = url.trim();
+    let site_url = site_url.trim_end_matches('/');
+    let page_url = page_url.trim_start_matches('/');
+    // URLs with a scheme, such as `https:` or `mailto:`, are already absolute.
+    let scheme = url
+        .split_once(':')
+        .map(|(scheme, _)| scheme)
+        .filter(|scheme| {
+            scheme.starts_with(|x: char| x.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|x| x.is_ascii_alphanumeric() || "+-.".contains(x))
+        });
+    if url.is_empty() || url.starts_with("//") || scheme.is_some() {
+        return None;
+    }
+    if let Some(path) = url.strip_prefix('/') {
+        return Some(format!("{}/{}", site_url, path));
+    }
+    if url.starts_with(['#', '?']) {
+        return Some(format!("{}/{}{}", site_url, page_url, url));
+    }
+    let suffix_start = url.find(['?', '#']).unwrap_or(url.len());
+    let (path, suffix) = url.split_at(suffix_start);
+    let resolved = Path::new(page_url)
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join(path)
+        .clean();
+    let resolved = to_slash_string(resolved);
+    // Paths climbing above the site are kept at its root.
+    let resolved = match resolved.as_str() {
+        "." => "",
+        resolved => resolved.trim_start_matches("../").trim_start_matches('/'),
+    };
+    let trailing_slash = match path.ends_with('/') && !resolved.is_empty() {
+        true => "/",
+        false => "",
+    };
+    Some(format!(
+        "{}/{}{}{}",
+        site_url, resolved, trailing_slash, suffix
+    ))
+}
+
+/// Make the relative URLs within rendered content absolute, so that they work outside of the page, such as in feed readers.
+///
+/// # Arguments
+///
+/// * `html` - The rendered content.
+///
+/// * `page_url` - The URL of the page, relative to the site.
+///
+/// * `site_url` - The URL of the site.
+///
+/// # Returns
+///
+/// The rendered content, with its links, images, and media referred to by absolute URLs.
+pub fn absolutise_urls(html: &str, page_url: &str, site_url: &str) -> String {
+    let mut html = html.to_string();
+    for (element, attribute) in URL_ATTRIBUTES {
+        html = map_tags(&html, element, |attributes| {
+            let Some(value) = get_attribute(attributes, attribute) else {
+                return false;
+            };
+            let absolute = match attribute {
+                // Each candidate of a `srcset` is a URL, optionally followed by a descriptor.
+                "srcset" => value
+                    .split(',')
+                    .map(|candidate| {
+                        let candidate = candidate.trim();
+                        let (url, descriptor) =
+                            candidate.split_once(' ').unwrap_or((candidate, ""));
+                        let url = absolute_url(url, page_url, site_url).unwrap_or(url.to_string());
+                        format!("{} {}", url, descriptor.trim()).trim().to_string()
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                _ => match absolute_url(&value, page_url, site_url) {
+                    Some(absolute) => absolute,
+                    None => return false,
+                },
+            };
+            if absolute == value {
+                return false;
+            }
+            set_attribute(attributes, attribute, absolute);
+            true
+        });
+    }
+    html
+}
+
+/// Create an Atom feed of a collection's pages.
+///
+/// # Arguments
+///
+/// * `settings` - The settings of the feed.
+///
+/// * `pages` - The pages in the collection.
+///
+/// * `title` - The title of the site, if any.
+///
+/// * `author` - The author of the site, if any.
+///
+/// * `site_url` - The URL of the site, if any; without it, links are relative to the site's root.
+///
+/// * `now` - The current date-time, formatted per RFC 3339; this is when the feed was updated if none of its pages are dated.
+///
+/// # Returns
+///
+/// The feed as XML, with its entries ordered from most to least recent.
+pub fn atom_feed(
+    settings: &FeedSettings,
+    pages: &[&Page],
+    title: Option<&str>,
+    author: Option<&str>,
+    site_url: Option<&str>,
+    now: &str,
+) -> String {
+    let mut pages = pages.to_vec();
+    pages.sort_by(|lhs, rhs| {
+        let lhs_date = lhs.date.as_ref().and_then(|x| x.to_datetime());
+        let rhs_date = rhs.date.as_ref().and_then(|x| x.to_datetime());
+        (rhs_date.is_some(), rhs_date)
+            .cmp(&(lhs_date.is_some(), lhs_date))
+            .then_with(|| lhs.to_path_string().cmp(&rhs.to_path_string()))
+    });
+    if let Some(limit) = settings.limit {
+        pages.truncate(limit);
+    }
+    let root = site_url.unwrap_or_default().trim_end_matches('/');
+    let page_link = |page: &Page| match page.canonical_url.is_empty() {
+        true => canonical_url(&page.url, site_url),
+        false => page.canonical_url.clone(),
+    };
+    let last_updated = |page: &Page| {
+        page.updated
+            .as_ref()
+            .or(page.date.as_ref())
+            .map(|x| x.rfc_3339.clone())
+    };
+    let updated = pages
+        .iter()
+        .filter_map(|page| {
+            let date = page.updated.as_ref().or(page.date.as_ref())?;
+            Some((date.to_datetime()?, date.rfc_3339.clone()))
+        })
+        .max()
+        .map(|(_, rfc_3339)| rfc_3339)
+        .unwrap_or(now.to_string());
+    let title = settings.title.as_deref().or(title).unwrap_or("Feed");
+    let feed_link = format!("{}/{}", root, settings.url());
+
+    let mut feed = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    feed.push_str(&format!(
+        "  <title>{}</title>\n  <updated>{}</updated>\n  <link href=\"{}\" rel=\"self\"/>\n  <link href=\"{}/\"/>\n  <id>{}</id>\n",
+        html_escape::encode_text(title),
+        updated,
+        html_escape::encode_double_quoted_attribute(&feed_link),
+        html_escape::encode_double_quoted_attribute(root),
+        html_escape::encode_text(&feed_link)
+    ));
+    if let Some(author) = author {
+        feed.push_str(&format!(
+            "  <author>\n    <name>{}</name>\n  </author>\n",
+            html_escape::encode_text(author)
+        ));
+    }
+    for page in pages {
+        let link = page_link(page);
+        let page_title = page
+            .data
+            .get("title")
+            .and_then(|x| x.as_str())
+            .unwrap_or(&page.name);
+        feed.push_str("  <entry>\n");
+        feed.push_str(&format!(
+            "    <title>{}</title>\n    <link href=\"{}\"/>\n    <id>{}</id>\n",
+            html_escape::encode_text(page_title),
+            html_escape::encode_double_quoted_attribute(&link),
+            html_escape::encode_text(&link)
+        ));
+        feed.push_str(&format!(
+            "    <updated>{}</updated>\n",
+            last_updated(page).unwrap_or(updated.clone())
+        ));
+        if let Some(date) = &page.date {
+            feed.push_str(&format!("    <published>{}</published>\n", date.rfc_3339));
+        }
+        match settings.content {
+            FeedContent::Full => {
+                let content = match site_url.filter(|_| settings.absolute_urls) {
+                    Some(site_url) => absolutise_urls(&page.rendered, &page.url, site_url),
+                    None => page.rendered.to_string(),
+                };
+                feed.push_str(&format!(
+                    "    <content type=\"html\">{}</content>\n",
+                    html_escape::encode_text(&content)
+                ));
+            }
+            FeedContent::Excerpt => {
+                feed.push_str(&format!(
+                    "    <summary>{}</summary>\n",
+                    html_escape::encode_text(&page.excerpt)
+                ));
+            }
+            FeedContent::Summary => {
+                let summary = page
+                    .data
+                    .get("summary")
+                    .and_then(|x| x.as_str())
+                    .unwrap_or(&page.excerpt);
+                feed.push_str(&format!(
+                    "    <summary>{}</summary>\n",
+                    html_escape::encode_text(summary)
+                ));
+            }
+        }
+        feed.push_str("  </entry>\n");
+    }
+    feed.push_str("</feed>\n");
+    feed
+}
@@ -0,0 +1,102 @@
+use chrono::DateTime;
+use chrono::FixedOffset;
+use chrono::Locale;
+use liquid_core::parser::FilterArguments;
+use liquid_core::Expression;
+use liquid_core::Result;
+use liquid_core::Runtime;
+use liquid_core::{
+    Display_filter, Filter, FilterParameters, FilterReflection, FromFilterParameters, ParseFilter,
+};
+use liquid_core::{Value, ValueView};
+use std::fmt::Write;
+
+#[derive(Debug, FilterParameters)]
+struct FormatDateArgs {
+    #[parameter(
+        description = "The strftime-style pattern to format the date with.",
+        arg_type = "str"
+    )]
+    format: Expression,
+}
+
+#[derive(Clone, Debug, FilterReflection)]
+#[filter(
+    name = "format_date",
+    description = "Formats a date with a strftime-style pattern, using the locale of the build.",
+    parameters(FormatDateArgs),
+    parsed(FormatDateFilter)
+)]
+/// A Liquid filter formatting dates with a strftime-style pattern.
+/// The filter accepts either a page's `date` or a date-time formatted per RFC 3339 or RFC 2822, eg: `{{ page.date | format_date: "%A, %-d %B %Y" }}`.
+pub struct FormatDate {
+    locale: Locale,
+}
+
+impl FormatDate {
+    /// Provides a new instance of the date formatting filter parser.
+    ///
+    /// # Arguments
+    ///
+    /// * `locale` - The locale used to format dates.
+    pub fn new(locale: Locale) -> Self {
+        Self { locale }
+    }
+}
+
+impl ParseFilter for FormatDate {
+    fn parse(&self, arguments: FilterArguments) -> Result<Box<dyn Filter>> {
+        let args = FormatDateArgs::from_args(arguments)?;
+        Ok(Box::new(FormatDateFilter {
+            args,
+            locale: self.locale,
+        }))
+    }
+
+    fn reflection(&self) -> &dyn FilterReflection {
+        self
+    }
+}
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "format_date"]
+struct FormatDateFilter {
+    #[parameters]
+    args: FormatDateArgs,
+    locale: Locale,
+}
+
+/// Obtain a date-time from a `Date` object or a string representing a date-time.
+///
+/// # Arguments
+///
+/// * `input` - A Liquid value.
+///
+/// # Returns
+///
+/// The date-time represented by the value, if any.
+fn input_to_datetime(input: &dyn ValueView) -> Option<DateTime<FixedOffset>> {
+    let date_string = match input.as_object() {
+        Some(date) => date.get("rfc_3339")?.to_kstr().to_string(),
+        None => input.as_scalar()?.to_kstr().to_string(),
+    };
+    DateTime::parse_from_rfc3339(&date_string)
+        .or_else(|_| DateTime::parse_from_rfc2822(&date_string))
+        .ok()
+}
+
+impl Filter for FormatDateFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let datetime = input_to_datetime(input)
+            .ok_or_else(|| liquid_core::Error::with_msg("Invalid date format"))?;
+        let mut formatted = String::new();
+        write!(
+            formatted,
+            "{}",
+            datetime.format_localized(args.format.as_str(), self.locale)
+        )
+        .map_err(|_| liquid_core::Error::with_msg("Invalid format string"))?;
+        Ok(Value::scalar(formatted))
+    }
+}
@@ -1,42 +1,766 @@
-use crate::provider::VoxProvider;
+use crate::manifest::BuildManifest;
+use crate::math_block::MathCache;
+use crate::page::Page;
+use crate::paths::to_slash_string;
+use crate::post_processing::PostProcessor;
+use crate::provider::{
+    FileReader, InputProvider, OutputProvider, PageGenerator, ParserCustomiser, VoxProvider,
+};
+use crate::url_transforms::UrlTransformer;
 use miette::IntoDiagnostic;
+use path_clean::PathClean;
+use std::{
+    collections::BTreeSet,
+    fmt,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+};
 
-#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// The directory output is staged in during a full build, relative to the site's directory.
+const STAGING_DIRECTORY: &str = ".vox/staging";
+
+/// The directory the previous output is moved to while replacing it with the staged output, relative to the site's directory.
+const PREVIOUS_OUTPUT_DIRECTORY: &str = ".vox/previous-output";
+
+/// The manifest of the files output by full builds, relative to the site's directory.
+const MANIFEST_PATH: &str = ".vox/manifest.json";
+
+/// The directory the site is output to, unless another is given with [`FsProvider::with_output_directory`].
+const DEFAULT_OUTPUT_DIRECTORY: &str = "output";
+
+#[derive(Clone)]
 /// A provider of the Vox build system that reads & writes from the file system.
-pub struct FsProvider;
-impl VoxProvider for FsProvider {
+pub struct FsProvider {
+    root: PathBuf,
+    output_directory: PathBuf,
+    environment: Option<String>,
+    post_processors: Vec<PostProcessor>,
+    parser_customisers: Vec<ParserCustomiser>,
+    page_generators: Vec<PageGenerator>,
+    url_transformers: Vec<UrlTransformer>,
+    staging: Arc<AtomicBool>,
+    staged_outputs: Arc<Mutex<BTreeSet<PathBuf>>>,
+    math_cache: MathCache,
+    #[cfg(feature = "git")]
+    git_history: crate::git::GitHistory,
+}
+impl fmt::Debug for FsProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FsProvider")
+            .field("root", &self.root)
+            .field("output_directory", &self.output_directory)
+            .field("environment", &self.environment)
+            .field("post_processors", &self.post_processors.len())
+            .field("parser_customisers", &self.parser_customisers.len())
+            .field("page_generators", &self.page_generators.len())
+            .field("url_transformers", &self.url_transformers.len())
+            .field("staging", &self.staging.load(Ordering::SeqCst))
+            .finish()
+    }
+}
+impl Default for FsProvider {
+    fn default() -> Self {
+        Self {
+            root: PathBuf::new(),
+            output_directory: PathBuf::from(DEFAULT_OUTPUT_DIRECTORY),
+            environment: None,
+            post_processors: Vec::new(),
+            parser_customisers: Vec::new(),
+            page_generators: Vec::new(),
+            url_transformers: Vec::new(),
+            staging: Arc::new(AtomicBool::new(false)),
+            staged_outputs: Arc::new(Mutex::new(BTreeSet::new())),
+            math_cache: MathCache::default(),
+            #[cfg(feature = "git")]
+            git_history: crate::git::GitHistory::default(),
+        }
+    }
+}
+impl InputProvider for FsProvider {
     fn read_to_string(&self, path: impl AsRef<std::path::Path>) -> miette::Result<String> {
-        std::fs::read_to_string(path).into_diagnostic()
+        std::fs::read_to_string(self.site_path(path.as_ref())).into_diagnostic()
+    }
+    fn read_bytes(&self, path: impl AsRef<std::path::Path>) -> miette::Result<Vec<u8>> {
+        std::fs::read(self.site_path(path.as_ref())).into_diagnostic()
+    }
+    fn list_vox_files(&self) -> miette::Result<Vec<std::path::PathBuf>> {
+        self.list_files("", "**/*.vox")
+    }
+    fn list_markdown_files(&self) -> miette::Result<Vec<std::path::PathBuf>> {
+        self.list_files("", "**/*.md")
+    }
+    fn list_html_files(&self) -> miette::Result<Vec<std::path::PathBuf>> {
+        Ok(self
+            .list_files("", "**/*.html")?
+            .into_iter()
+            .filter(|path| {
+                !path.starts_with(&self.output_directory) && !path.starts_with("snippets")
+            })
+            .collect())
+    }
+    fn list_snippets(&self) -> miette::Result<Vec<std::path::PathBuf>> {
+        self.list_files("snippets", "snippets/**/*")
+    }
+    fn list_directory(
+        &self,
+        directory: &std::path::Path,
+    ) -> miette::Result<Vec<std::path::PathBuf>> {
+        self.list_files(directory, &format!("{}/**/*", to_slash_string(directory)))
+    }
+    fn file_reader(&self) -> Option<FileReader> {
+        let provider = self.clone();
+        Some(Arc::new(move |path| {
+            std::fs::read(provider.site_path(path)).into_diagnostic()
+        }))
+    }
+    fn math_cache(&self) -> Option<MathCache> {
+        Some(self.math_cache.clone())
+    }
+    #[cfg(feature = "git")]
+    fn refresh_git_history(&self) {
+        self.git_history.refresh(&self.root)
+    }
+    #[cfg(feature = "git")]
+    fn get_git_metadata(
+        &self,
+        path: &std::path::Path,
+        locale: chrono::Locale,
+        timezone: chrono_tz::Tz,
+    ) -> Option<crate::git::GitMetadata> {
+        self.git_history.metadata(path, locale, timezone)
+    }
+    #[cfg(feature = "git")]
+    fn get_repository_metadata(&self) -> Option<crate::git::RepositoryMetadata> {
+        self.git_history.repository()
+    }
+    fn working_directory(&self) -> Option<std::path::PathBuf> {
+        Some(std::env::current_dir().ok()?.join(&self.root))
     }
+    fn environment(&self) -> Option<String> {
+        self.environment.clone()
+    }
+    #[cfg(feature = "watch")]
+    fn watch(&self) -> miette::Result<Option<crate::changes::Changes>> {
+        use notify_debouncer_full::{new_debouncer, notify::RecursiveMode};
+        let directory = std::env::current_dir().into_diagnostic()?.join(&self.root);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let mut debouncer =
+            new_debouncer(std::time::Duration::from_secs(1), None, sender).into_diagnostic()?;
+        tracing::info!("Watching {:?} … ", directory);
+        debouncer
+            .watch(&directory, RecursiveMode::Recursive)
+            .into_diagnostic()?;
+        let output_directory = self.output_directory.clone();
+        let provider = self.clone();
+        let changes = receiver
+            .into_iter()
+            .map_while(Result::ok)
+            .filter_map(move |events| {
+                // Files are only watched while the debouncer exists.
+                let _ = &debouncer;
+                let paths = events
+                    .into_iter()
+                    // Files being read (eg: by building the site) are not changes.
+                    .filter(|event| !event.kind.is_access())
+                    // Changes made by the site's hooks would otherwise trigger another build, and so on forever.
+                    .filter(|event| !crate::hooks::changed_by_hooks(&directory, event.time))
+                    .flat_map(|event| event.event.paths)
+                    .map(|path| {
+                        path.strip_prefix(&directory)
+                            .map(|x| x.to_path_buf())
+                            .unwrap_or(path)
+                    })
+                    // Changes to the output directory, staged output, or version control are irrelevant.
+                    .filter(|path| {
+                        !path.starts_with(&output_directory)
+                            && !path.starts_with(".vox")
+                            && !path.starts_with(".git")
+                    })
+                    .collect::<Vec<_>>();
+                // Changes behind symbolic links are irrelevant if they are not followed.
+                let paths = if provider.follows_symlinks().unwrap_or(true) {
+                    paths
+                } else {
+                    paths
+                        .into_iter()
+                        .filter(|path| !provider.is_behind_symlink(path))
+                        .collect()
+                };
+                if paths.is_empty() {
+                    return None;
+                }
+                tracing::trace!("Changes detected: {:#?} … ", paths);
+                Some(crate::changes::Change { paths })
+            });
+        Ok(Some(Box::new(changes)))
+    }
+}
+impl OutputProvider for FsProvider {
     fn write_file(
         &self,
         path: impl AsRef<std::path::Path> + Clone,
         contents: impl AsRef<[u8]>,
     ) -> miette::Result<()> {
-        if let Some(parent_path) = path.as_ref().parent() {
+        if let Some(staged_output) = self.staged_output(path.as_ref()) {
+            self.lock_staged_outputs()?.insert(staged_output);
+        }
+        let path = self.output_path(path.as_ref());
+        if let Some(parent_path) = path.parent() {
             std::fs::create_dir_all(parent_path).into_diagnostic()?;
         }
+        // Staged files may be linked to the current output, which must not be modified.
+        if self.staging.load(Ordering::SeqCst) {
+            std::fs::remove_file(&path).ok();
+        }
         std::fs::write(path, contents).into_diagnostic()
     }
     fn remove_file(&self, path: impl AsRef<std::path::Path>) -> miette::Result<()> {
-        std::fs::remove_file(path).into_diagnostic()
+        if let Some(staged_output) = self.staged_output(path.as_ref()) {
+            self.lock_staged_outputs()?.remove(&staged_output);
+        }
+        std::fs::remove_file(self.output_path(path.as_ref())).into_diagnostic()
     }
-    fn list_vox_files(&self) -> miette::Result<Vec<std::path::PathBuf>> {
-        Ok(glob::glob("**/*.vox")
-            .into_diagnostic()?
-            .filter_map(Result::ok)
-            .collect())
+    fn stage_output(&self) -> miette::Result<bool> {
+        let staging_directory = self.root.join(STAGING_DIRECTORY);
+        // Output staged by an interrupted build is discarded.
+        if staging_directory.exists() {
+            std::fs::remove_dir_all(&staging_directory).into_diagnostic()?;
+        }
+        std::fs::create_dir_all(&staging_directory).into_diagnostic()?;
+        let output_directory = self.root.join(&self.output_directory);
+        if output_directory.is_dir() {
+            link_directory(
+                &output_directory,
+                &staging_directory,
+                self.follows_symlinks()?,
+                &mut Vec::new(),
+            )?;
+        }
+        tracing::debug!("Staging output in {:?} … ", staging_directory);
+        self.lock_staged_outputs()?.clear();
+        self.staging.store(true, Ordering::SeqCst);
+        Ok(true)
     }
-    fn list_snippets(&self) -> miette::Result<Vec<std::path::PathBuf>> {
-        Ok(glob::glob("snippets/**/*")
-            .into_diagnostic()?
-            .filter_map(Result::ok)
-            .collect())
+    fn commit_staged_output(&self, remove_orphans: bool) -> miette::Result<usize> {
+        if !self.staging.swap(false, Ordering::SeqCst) {
+            return Ok(0);
+        }
+        let manifest = BuildManifest::new(std::mem::take(&mut *self.lock_staged_outputs()?));
+        let manifest_path = self.root.join(MANIFEST_PATH);
+        let previous_manifest = if manifest_path.is_file() {
+            BuildManifest::from_reader(std::fs::File::open(&manifest_path).into_diagnostic()?)?
+        } else {
+            BuildManifest::default()
+        };
+        // Unless orphans are removed, they are remembered so that they can be removed by a later build.
+        let mut orphans_removed = 0;
+        let manifest = if remove_orphans {
+            let staging_directory = self.root.join(STAGING_DIRECTORY);
+            for orphan in previous_manifest.orphans(&manifest) {
+                orphans_removed += 1;
+                tracing::info!("Removing orphaned output `{}` … ", orphan.to_string_lossy());
+                let orphan_path = staging_directory.join(&orphan);
+                std::fs::remove_file(&orphan_path).ok();
+                // Directories left empty are removed as well.
+                for directory in orphan_path.ancestors().skip(1) {
+                    if directory == staging_directory || std::fs::remove_dir(directory).is_err() {
+                        break;
+                    }
+                }
+            }
+            manifest
+        } else {
+            previous_manifest.merge(manifest)
+        };
+        let output_directory = self.root.join(&self.output_directory);
+        let previous_output_directory = self.root.join(PREVIOUS_OUTPUT_DIRECTORY);
+        if previous_output_directory.exists() {
+            std::fs::remove_dir_all(&previous_output_directory).into_diagnostic()?;
+        }
+        if output_directory.exists() {
+            std::fs::rename(&output_directory, &previous_output_directory).into_diagnostic()?;
+        }
+        // The output directory may be nested within directories which do not yet exist.
+        if let Some(parent) = output_directory.parent() {
+            std::fs::create_dir_all(parent).into_diagnostic()?;
+        }
+        std::fs::rename(self.root.join(STAGING_DIRECTORY), &output_directory).into_diagnostic()?;
+        tracing::debug!("Replaced {:?} with staged output … ", output_directory);
+        if previous_output_directory.exists() {
+            std::fs::remove_dir_all(previous_output_directory).into_diagnostic()?;
+        }
+        manifest.to_writer(std::fs::File::create(manifest_path).into_diagnostic()?)?;
+        Ok(orphans_removed)
+    }
+    fn discard_staged_output(&self) -> miette::Result<()> {
+        if !self.staging.swap(false, Ordering::SeqCst) {
+            return Ok(());
+        }
+        tracing::debug!("Discarding staged output … ");
+        std::fs::remove_dir_all(self.root.join(STAGING_DIRECTORY)).into_diagnostic()
+    }
+}
+impl VoxProvider for FsProvider {
+    fn get_post_processors(&self) -> Vec<PostProcessor> {
+        self.post_processors.clone()
+    }
+    fn get_parser_customisers(&self) -> Vec<ParserCustomiser> {
+        self.parser_customisers.clone()
+    }
+    fn get_page_generators(&self) -> Vec<PageGenerator> {
+        self.page_generators.clone()
+    }
+    fn get_url_transformers(&self) -> Vec<UrlTransformer> {
+        self.url_transformers.clone()
     }
 }
 impl FsProvider {
     /// Create a new Vox provider that reads & writes from the file system.
+    /// Paths are relative to the current working directory, unless a root directory is given with [`FsProvider::with_root`].
     pub fn new() -> Self {
-        Self {}
+        Self::default()
     }
+
+    /// Set the directory of the site, which paths are relative to.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The path to the site's directory.
+    ///
+    /// # Returns
+    ///
+    /// The provider, reading & writing within the given directory.
+    pub fn with_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = root.into();
+        self
+    }
+
+    /// Get the directory of the site, which paths are relative to.
+    ///
+    /// # Returns
+    ///
+    /// The path to the site's directory; if empty, paths are relative to the current working directory.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Set the directory the site is output to, in place of `output`.
+    /// Vox refers to output by paths within `output`, which are written to this directory instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `output_directory` - The path to the output directory, relative to the site's directory.
+    ///
+    /// # Returns
+    ///
+    /// The provider, writing output to the given directory.
+    pub fn with_output_directory(mut self, output_directory: impl Into<PathBuf>) -> Self {
+        self.output_directory = output_directory.into();
+        self
+    }
+
+    /// Get the directory the site is output to.
+    ///
+    /// # Returns
+    ///
+    /// The path to the output directory, relative to the site's directory.
+    pub fn output_directory(&self) -> &Path {
+        &self.output_directory
+    }
+
+    /// Set the environment the site is built for, such as `production`, which is given to templates as `meta.environment`.
+    ///
+    /// # Arguments
+    ///
+    /// * `environment` - The name of the environment.
+    ///
+    /// # Returns
+    ///
+    /// The provider, building the site for the given environment.
+    pub fn with_environment(mut self, environment: impl Into<String>) -> Self {
+        self.environment = Some(environment.into());
+        self
+    }
+
+    /// Obtain the location of a file within the site's directory, accounting for the output directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the file, relative to the site's directory.
+    ///
+    /// # Returns
+    ///
+    /// The path to the file, within the output directory if it is in `output`.
+    fn site_path(&self, path: &Path) -> PathBuf {
+        match path.clean().strip_prefix("output") {
+            Ok(output_path) => self.root.join(&self.output_directory).join(output_path),
+            Err(_) => self.root.join(path),
+        }
+    }
+
+    /// Obtain the location a file is written to, accounting for the output directory and staged output.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the file, relative to the site's directory.
+    ///
+    /// # Returns
+    ///
+    /// The path to the file, within the staging directory if it is in the output directory and output is being staged.
+    fn output_path(&self, path: &Path) -> PathBuf {
+        if self.staging.load(Ordering::SeqCst) {
+            if let Ok(output_path) = path.clean().strip_prefix("output") {
+                return self.root.join(STAGING_DIRECTORY).join(output_path);
+            }
+        }
+        self.site_path(path)
+    }
+
+    /// Obtain the path of a file within the output directory, if it is being staged.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the file, relative to the site's directory.
+    ///
+    /// # Returns
+    ///
+    /// The path to the file relative to the output directory, or `None` if the file is not in the output directory or output is not being staged.
+    fn staged_output(&self, path: &Path) -> Option<PathBuf> {
+        if !self.staging.load(Ordering::SeqCst) {
+            return None;
+        }
+        path.clean()
+            .strip_prefix("output")
+            .ok()
+            .map(|x| x.to_path_buf())
+    }
+
+    /// Lock the set of files written to the output directory while staging output.
+    ///
+    /// # Returns
+    ///
+    /// The paths of the files written, relative to the output directory.
+    fn lock_staged_outputs(&self) -> miette::Result<MutexGuard<'_, BTreeSet<PathBuf>>> {
+        self.staged_outputs
+            .lock()
+            .map_err(|e| miette::miette!("{}", e))
+    }
+
+    /// Determine whether symbolic links within the site are followed.
+    /// This is defined by the `follow_symlinks` option in `global.toml`, and is `true` by default.
+    ///
+    /// # Returns
+    ///
+    /// Whether or not symbolic links are followed.
+    fn follows_symlinks(&self) -> miette::Result<bool> {
+        let Ok(global_file) = self.read_to_string("global.toml") else {
+            return Ok(true);
+        };
+        match global_file
+            .parse::<toml::Table>()
+            .into_diagnostic()?
+            .get("follow_symlinks")
+        {
+            Some(follow_symlinks) => follow_symlinks.as_bool().ok_or(miette::miette!(
+                "`follow_symlinks` must be `true` or `false`."
+            )),
+            None => Ok(true),
+        }
+    }
+
+    /// Determine whether a path is, or is within, a symbolic link.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path, relative to the site's directory.
+    ///
+    /// # Returns
+    ///
+    /// Whether or not the path is reached through a symbolic link.
+    #[cfg(feature = "watch")]
+    fn is_behind_symlink(&self, path: &Path) -> bool {
+        path.ancestors()
+            .filter(|x| !x.as_os_str().is_empty())
+            .any(|x| {
+                std::fs::symlink_metadata(self.root.join(x))
+                    .is_ok_and(|metadata| metadata.file_type().is_symlink())
+            })
+    }
+
+    /// List the files within a directory of the site matching a pattern.
+    ///
+    /// # Arguments
+    ///
+    /// * `directory` - The directory to search, relative to the site's directory.
+    ///
+    /// * `pattern` - A glob pattern, relative to the site's directory.
+    ///
+    /// # Returns
+    ///
+    /// The paths of the matching files, relative to the site's directory.
+    fn list_files(
+        &self,
+        directory: impl AsRef<Path>,
+        pattern: &str,
+    ) -> miette::Result<Vec<PathBuf>> {
+        let pattern = glob::Pattern::new(pattern).into_diagnostic()?;
+        let mut files = Vec::new();
+        self.walk_directory(
+            directory.as_ref(),
+            self.follows_symlinks()?,
+            &mut Vec::new(),
+            &mut files,
+        );
+        Ok(files
+            .into_iter()
+            .filter(|path| pattern.matches_path(path))
+            .collect())
+    }
+
+    /// Find the files within a directory of the site & its subdirectories.
+    /// Directories which cannot be read are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `directory` - The directory to search, relative to the site's directory.
+    ///
+    /// * `follow_symlinks` - Whether or not to follow symbolic links.
+    ///
+    /// * `ancestors` - The canonical paths of the directories being searched, used to detect symbolic link loops.
+    ///
+    /// * `files` - Where to record the paths of the files found, relative to the site's directory.
+    fn walk_directory(
+        &self,
+        directory: &Path,
+        follow_symlinks: bool,
+        ancestors: &mut Vec<PathBuf>,
+        files: &mut Vec<PathBuf>,
+    ) {
+        let absolute_directory = match self.root.join(directory) {
+            // An empty path refers to the current working directory.
+            x if x.as_os_str().is_empty() => PathBuf::from("."),
+            x => x,
+        };
+        let Ok(entries) = std::fs::read_dir(&absolute_directory) else {
+            return;
+        };
+        ancestors.push(
+            absolute_directory
+                .canonicalize()
+                .unwrap_or(absolute_directory),
+        );
+        for entry in entries.filter_map(Result::ok) {
+            let path = directory.join(entry.file_name());
+            // Vox's own files, such as staged output, are not part of the site.
+            if path.starts_with(".vox") {
+                continue;
+            }
+            let Some(is_directory) = entry_is_directory(&entry, follow_symlinks, ancestors) else {
+                continue;
+            };
+            if is_directory {
+                self.walk_directory(&path, follow_symlinks, ancestors, files);
+            } else {
+                files.push(path);
+            }
+        }
+        ancestors.pop();
+    }
+
+    /// Add a function transforming each page's rendered HTML before it is written.
+    ///
+    /// # Arguments
+    ///
+    /// * `post_processor` - A function given a page and its rendered HTML, returning the HTML to be written.
+    ///
+    /// # Returns
+    ///
+    /// The provider, with the function added after any previously added.
+    pub fn with_post_processor(
+        mut self,
+        post_processor: impl Fn(&Page, String) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.post_processors.push(Arc::new(post_processor));
+        self
+    }
+
+    /// Add a function customising the Liquid parser, such as by registering additional tags, filters, or blocks.
+    ///
+    /// # Arguments
+    ///
+    /// * `parser_customiser` - A function given the parser's builder, returning the builder to use.
+    ///
+    /// # Returns
+    ///
+    /// The provider, with the function added after any previously added.
+    pub fn with_parser_customiser(
+        mut self,
+        parser_customiser: impl Fn(liquid::ParserBuilder) -> liquid::ParserBuilder
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        self.parser_customisers.push(Arc::new(parser_customiser));
+        self
+    }
+
+    /// Add a function generating pages which are not read from the site's files, such as index pages or pages of content from elsewhere.
+    ///
+    /// # Arguments
+    ///
+    /// * `page_generator` - A function given the pages read from the site's files, with the site's locale and timezone, returning the pages to add.
+    ///
+    /// # Returns
+    ///
+    /// The provider, with the function added after any previously added.
+    pub fn with_page_generator(
+        mut self,
+        page_generator: impl Fn(&[&Page], chrono::Locale, chrono_tz::Tz) -> miette::Result<Vec<Page>>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        self.page_generators.push(Arc::new(page_generator));
+        self
+    }
+
+    /// Add a function transforming the URL of every page, such as to enforce a convention for URLs.
+    ///
+    /// # Arguments
+    ///
+    /// * `url_transformer` - A function given a page and its URL, returning the URL to use; it may be given a URL it has already transformed, which it should leave as it is.
+    ///
+    /// # Returns
+    ///
+    /// The provider, with the function added after any previously added.
+    pub fn with_url_transformer(
+        mut self,
+        url_transformer: impl Fn(&Page, String) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.url_transformers.push(Arc::new(url_transformer));
+        self
+    }
+}
+
+/// Recreate a directory's files in another directory, linking to the original files where possible.
+/// Symbolic links which are not followed are themselves recreated.
+///
+/// # Arguments
+///
+/// * `source` - The directory to recreate.
+///
+/// * `destination` - Where to recreate the directory.
+///
+/// * `follow_symlinks` - Whether or not to follow symbolic links.
+///
+/// * `ancestors` - The canonical paths of the directories being recreated, used to detect symbolic link loops.
+fn link_directory(
+    source: &Path,
+    destination: &Path,
+    follow_symlinks: bool,
+    ancestors: &mut Vec<PathBuf>,
+) -> miette::Result<()> {
+    ancestors.push(source.canonicalize().into_diagnostic()?);
+    for entry in std::fs::read_dir(source).into_diagnostic()? {
+        let entry = entry.into_diagnostic()?;
+        let source_path = entry.path();
+        let destination_path = destination.join(entry.file_name());
+        match entry_is_directory(&entry, follow_symlinks, ancestors) {
+            Some(true) => {
+                std::fs::create_dir_all(&destination_path).into_diagnostic()?;
+                link_directory(&source_path, &destination_path, follow_symlinks, ancestors)?;
+            }
+            Some(false) => std::fs::hard_link(&source_path, &destination_path)
+                .or_else(|_| std::fs::copy(&source_path, &destination_path).map(|_| ()))
+                .into_diagnostic()?,
+            // Hard links to a symbolic link are themselves symbolic links.
+            None if entry.file_type().into_diagnostic()?.is_symlink() => {
+                std::fs::hard_link(&source_path, &destination_path)
+                    .or_else(|_| {
+                        std::fs::read_link(&source_path)
+                            .and_then(|target| symlink(&target, &destination_path))
+                    })
+                    .into_diagnostic()?
+            }
+            None => (),
+        }
+    }
+    ancestors.pop();
+    Ok(())
+}
+
+/// Determine how a directory entry is to be treated when searching a directory.
+///
+/// # Arguments
+///
+/// * `entry` - The directory entry.
+///
+/// * `follow_symlinks` - Whether or not to follow symbolic links.
+///
+/// * `ancestors` - The canonical paths of the directories being searched.
+///
+/// # Returns
+///
+/// Whether or not the entry is a directory to search, or `None` if it is to be skipped.
+/// Symbolic links are skipped if they are not followed, are broken, or lead to a directory being searched.
+fn entry_is_directory(
+    entry: &std::fs::DirEntry,
+    follow_symlinks: bool,
+    ancestors: &[PathBuf],
+) -> Option<bool> {
+    let file_type = entry.file_type().ok()?;
+    if !file_type.is_symlink() {
+        return Some(file_type.is_dir());
+    }
+    if !follow_symlinks {
+        return None;
+    }
+    let path = entry.path();
+    let Ok(metadata) = std::fs::metadata(&path) else {
+        tracing::warn!("Skipping broken symbolic link {:?} … ", path);
+        return None;
+    };
+    if !metadata.is_dir() {
+        return Some(false);
+    }
+    let target = path.canonicalize().ok()?;
+    if ancestors.contains(&target) {
+        tracing::warn!(
+            "Skipping symbolic link {:?}, as it leads to {:?}, which contains it … ",
+            path,
+            target
+        );
+        return None;
+    }
+    Some(true)
+}
+
+/// Create a symbolic link.
+///
+/// # Arguments
+///
+/// * `target` - The path the link leads to.
+///
+/// * `link` - Where to create the link.
+fn symlink(target: &Path, link: &Path) -> std::io::Result<()> {
+    #[cfg(unix)]
+    return std::os::unix::fs::symlink(target, link);
+    #[cfg(windows)]
+    return if link
+        .parent()
+        .map(|x| x.join(target))
+        .is_some_and(|x| x.is_dir())
+    {
+        std::os::windows::fs::symlink_dir(target, link)
+    } else {
+        std::os::windows::fs::symlink_file(target, link)
+    };
+    #[cfg(not(any(unix, windows)))]
+    return Err(std::io::Error::other(format!(
+        "Unable to recreate symbolic link {:?} on this platform.",
+        link
+    )));
 }
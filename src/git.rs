@@ -0,0 +1,195 @@
+use crate::date::Date;
+use serde::{Deserialize, Serialize};
+
+#[derive(Eq, PartialEq, Clone, Default, Debug, Serialize, Deserialize)]
+/// A page's metadata, derived from its version control history.
+pub struct GitMetadata {
+    /// The date of the first commit containing the page.
+    pub created: Option<Date>,
+    /// The date of the most recent commit modifying the page.
+    pub modified: Option<Date>,
+    /// The names of the authors of commits modifying the page, in order of their first contribution.
+    pub authors: Vec<String>,
+}
+
+#[derive(Eq, PartialEq, Clone, Default, Debug, Serialize, Deserialize)]
+/// Metadata of the Git repository containing a site, at the time of building.
+pub struct RepositoryMetadata {
+    /// The hash of the current commit.
+    pub commit: String,
+    /// The abbreviated hash of the current commit.
+    pub short_commit: String,
+    /// Whether or not there are uncommitted changes.
+    pub dirty: bool,
+}
+
+/// The commits modifying a file, from newest to oldest, as their dates and authors.
+#[cfg(feature = "git")]
+type Commits = Vec<(chrono::DateTime<chrono::Utc>, String)>;
+
+/// The repository's metadata when its history was read, and the commits modifying each file, by path.
+#[cfg(feature = "git")]
+type History = (RepositoryMetadata, ahash::AHashMap<String, Commits>);
+
+#[cfg(feature = "git")]
+#[derive(Clone, Debug, Default)]
+/// The history of the Git repository containing a site.
+/// The history is read in one pass, and only read again once another commit is checked out.
+pub struct GitHistory {
+    /// The repository's metadata when the history was read, and the commits modifying each file, by path relative to the site's directory.
+    state: std::sync::Arc<std::sync::Mutex<Option<History>>>,
+}
+
+#[cfg(feature = "git")]
+impl GitHistory {
+    /// Read the history of the Git repository containing a site, unless it was read at the commit checked out.
+    /// Whether or not there are uncommitted changes is checked each time.
+    ///
+    /// # Arguments
+    ///
+    /// * `directory` - The directory of the site; if empty, the current working directory.
+    pub fn refresh(&self, directory: impl AsRef<std::path::Path>) {
+        let git = |args: &[&str]| {
+            std::process::Command::new("git")
+                .arg("-C")
+                .arg(directory.as_ref())
+                .args(args)
+                .output()
+                .ok()
+                .filter(|output| output.status.success())
+                .map(|output| String::from_utf8_lossy(&output.stdout).to_string())
+        };
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        let Some(head) = git(&["rev-parse", "HEAD"]).map(|x| x.trim().to_string()) else {
+            *state = None;
+            return;
+        };
+        let Some(dirty) = git(&["status", "--porcelain"]).map(|x| !x.trim().is_empty()) else {
+            *state = None;
+            return;
+        };
+        if let Some((repository, _)) = state.as_mut().filter(|(read_at, _)| read_at.commit == head)
+        {
+            repository.dirty = dirty;
+            return;
+        }
+        let Some(short_commit) =
+            git(&["rev-parse", "--short", "HEAD"]).map(|x| x.trim().to_string())
+        else {
+            *state = None;
+            return;
+        };
+        let Some(log) = git(&[
+            "-c",
+            "core.quotePath=false",
+            "log",
+            "-M",
+            "--name-status",
+            "--relative",
+            "--format=%x1e%aI%x1f%an",
+            "--",
+            ".",
+        ]) else {
+            *state = None;
+            return;
+        };
+        // Commits are listed from newest to oldest, so files are renamed from their current paths to their former ones.
+        let mut commits: ahash::AHashMap<String, Commits> = ahash::AHashMap::new();
+        let mut current_paths: ahash::AHashMap<String, String> = ahash::AHashMap::new();
+        for entry in log.split('\u{1e}').skip(1) {
+            let mut lines = entry.lines();
+            let Some((date, author)) =
+                lines
+                    .next()
+                    .and_then(|x| x.split_once('\u{1f}'))
+                    .and_then(|(date, author)| {
+                        chrono::DateTime::parse_from_rfc3339(date)
+                            .ok()
+                            .map(|date| (date.to_utc(), author.to_string()))
+                    })
+            else {
+                continue;
+            };
+            for fields in lines.map(|x| x.split('\t').collect::<Vec<_>>()) {
+                let path = match fields.as_slice() {
+                    [status, former_path, path] if status.starts_with('R') => {
+                        let current_path = current_paths
+                            .get(*path)
+                            .cloned()
+                            .unwrap_or(path.to_string());
+                        current_paths.insert(former_path.to_string(), current_path.clone());
+                        current_path
+                    }
+                    [_, _, path] | [_, path] => current_paths
+                        .get(*path)
+                        .cloned()
+                        .unwrap_or(path.to_string()),
+                    _ => continue,
+                };
+                commits
+                    .entry(path)
+                    .or_default()
+                    .push((date, author.clone()));
+            }
+        }
+        *state = Some((
+            RepositoryMetadata {
+                commit: head,
+                short_commit,
+                dirty,
+            },
+            commits,
+        ));
+    }
+
+    /// Obtain the metadata of the Git repository containing a site, as of when its history was last read.
+    ///
+    /// # Returns
+    ///
+    /// The repository's metadata, or `None` if the site is not in a Git repository.
+    pub fn repository(&self) -> Option<RepositoryMetadata> {
+        let state = self.state.lock().ok()?;
+        state.as_ref().map(|(repository, _)| repository.clone())
+    }
+
+    /// Obtain a page's metadata from the history of the Git repository containing it.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the page, relative to the site's directory.
+    ///
+    /// * `locale` - The locale used to represent dates and times.
+    ///
+    /// * `timezone` - The timezone used to represent dates and times.
+    ///
+    /// # Returns
+    ///
+    /// The page's metadata, or `None` if the page is not tracked by Git.
+    pub fn metadata(
+        &self,
+        path: impl AsRef<std::path::Path>,
+        locale: chrono::Locale,
+        timezone: chrono_tz::Tz,
+    ) -> Option<GitMetadata> {
+        let state = self.state.lock().ok()?;
+        let (_, history) = state.as_ref()?;
+        let commits = history.get(&crate::paths::to_slash_string(path))?;
+        let mut authors: Vec<String> = Vec::new();
+        for (_, author) in commits.iter().rev() {
+            if !authors.contains(author) {
+                authors.push(author.clone());
+            }
+        }
+        Some(GitMetadata {
+            created: commits
+                .last()
+                .map(|(date, _)| Date::chrono_to_date(*date, locale, timezone)),
+            modified: commits
+                .first()
+                .map(|(date, _)| Date::chrono_to_date(*date, locale, timezone)),
+            authors,
+        })
+    }
+}
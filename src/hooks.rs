@@ -0,0 +1,227 @@
+use miette::IntoDiagnostic;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// How long after hooks finish that changes to a site are still taken to have been made by them, allowing for changes to be noticed late.
+const HOOK_GRACE_PERIOD: Duration = Duration::from_millis(500);
+
+/// How long the times hooks ran are remembered for.
+const HOOK_MEMORY: Duration = Duration::from_secs(60);
+
+/// The times hooks were running, by the directory they were run in.
+static HOOK_RUNS: Mutex<Vec<(PathBuf, Instant, Instant)>> = Mutex::new(Vec::new());
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+/// When hooks are run during a build.
+pub enum HookStage {
+    /// Before pages are read, so that hooks can prepare the site's files.
+    PreBuild,
+    /// After output is written, so that hooks can process the site's output.
+    PostBuild,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+/// A command run while building a site, written either as the command alone or as a table.
+pub enum Hook {
+    /// A command, run with every build.
+    Command(String),
+    /// A command, with settings controlling when it is run.
+    Table {
+        /// The command.
+        command: String,
+        /// Whether or not the command is run when a watched site is rebuilt; defaults to `true`.
+        #[serde(default = "default_watch")]
+        watch: bool,
+    },
+}
+
+/// By default, hooks are run when a watched site is rebuilt.
+fn default_watch() -> bool {
+    true
+}
+
+impl Hook {
+    /// Get the command run by the hook.
+    ///
+    /// # Returns
+    ///
+    /// The command line.
+    pub fn command(&self) -> &str {
+        match self {
+            Hook::Command(command) => command,
+            Hook::Table { command, .. } => command,
+        }
+    }
+
+    /// Determine if the hook is run when a watched site is rebuilt.
+    ///
+    /// # Returns
+    ///
+    /// Whether or not the hook is run when rebuilding.
+    pub fn watch(&self) -> bool {
+        match self {
+            Hook::Command(_) => true,
+            Hook::Table { watch, .. } => *watch,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Debug, Default, Serialize, Deserialize)]
+/// Commands run while building a site, defined by the `hooks` table in `global.toml`.
+pub struct HookSettings {
+    /// Commands run before pages are read.
+    #[serde(default)]
+    pub pre_build: Vec<Hook>,
+    /// Commands run after output is written.
+    #[serde(default)]
+    pub post_build: Vec<Hook>,
+}
+
+impl HookSettings {
+    /// Get the commands run at a stage of a build.
+    ///
+    /// # Arguments
+    ///
+    /// * `stage` - The stage of the build.
+    ///
+    /// * `rebuilding` - Whether or not a watched site is being rebuilt, in which case hooks with `watch = false` are not run.
+    ///
+    /// # Returns
+    ///
+    /// The commands, in the order they are run.
+    pub fn commands(&self, stage: HookStage, rebuilding: bool) -> Vec<&str> {
+        match stage {
+            HookStage::PreBuild => &self.pre_build,
+            HookStage::PostBuild => &self.post_build,
+        }
+        .iter()
+        .filter(|hook| !rebuilding || hook.watch())
+        .map(|hook| hook.command())
+        .collect()
+    }
+}
+
+/// Run hooks with the system's shell, logging their output.
+/// Standard output is logged as information, and standard error as warnings.
+/// The time the hooks run for is remembered, so that the changes they make are not mistaken for changes made by hand; see [`changed_by_hooks`].
+///
+/// # Arguments
+///
+/// * `commands` - The commands to run, in order.
+///
+/// * `directory` - The directory to run the commands in.
+///
+/// # Returns
+///
+/// An error if a command could not be run or did not succeed; later commands are not run.
+pub fn run_hooks(commands: &[&str], directory: &Path) -> miette::Result<()> {
+    if commands.is_empty() {
+        return Ok(());
+    }
+    let start = Instant::now();
+    let ran = run_commands(commands, directory);
+    if let Ok(mut runs) = HOOK_RUNS.lock() {
+        let end = Instant::now();
+        runs.retain(|(_, _, ended)| end.duration_since(*ended) < HOOK_MEMORY);
+        runs.push((canonical_directory(directory), start, end));
+    }
+    ran
+}
+
+/// Determine if a change to a site was made while its hooks were running, and so is taken to have been made by them.
+///
+/// # Arguments
+///
+/// * `directory` - The directory of the site.
+///
+/// * `time` - When the change was noticed.
+///
+/// # Returns
+///
+/// Whether or not hooks were running in the site's directory when the change was noticed.
+pub fn changed_by_hooks(directory: &Path, time: Instant) -> bool {
+    let directory = canonical_directory(directory);
+    HOOK_RUNS.lock().is_ok_and(|runs| {
+        runs.iter().any(|(hooked, start, end)| {
+            *hooked == directory && time >= *start && time <= *end + HOOK_GRACE_PERIOD
+        })
+    })
+}
+
+/// Get the canonical form of a directory, so that the same directory is recognised however it is written.
+///
+/// # Arguments
+///
+/// * `directory` - The directory.
+///
+/// # Returns
+///
+/// The canonical path of the directory, or the path as given if it cannot be made canonical.
+fn canonical_directory(directory: &Path) -> PathBuf {
+    directory
+        .canonicalize()
+        .unwrap_or_else(|_| directory.to_path_buf())
+}
+
+/// Run commands with the system's shell, logging their output.
+///
+/// # Arguments
+///
+/// * `commands` - The commands to run, in order.
+///
+/// * `directory` - The directory to run the commands in.
+///
+/// # Returns
+///
+/// An error if a command could not be run or did not succeed; later commands are not run.
+fn run_commands(commands: &[&str], directory: &Path) -> miette::Result<()> {
+    for command in commands {
+        info!("Running `{}` … ", command);
+        let output = shell(command)
+            .current_dir(directory)
+            .output()
+            .into_diagnostic()?;
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            info!("{}", line);
+        }
+        for line in String::from_utf8_lossy(&output.stderr).lines() {
+            warn!("{}", line);
+        }
+        if !output.status.success() {
+            return Err(miette::miette!(
+                "The hook `{}` exited with {}.",
+                command,
+                output.status
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Create a command run by the system's shell.
+///
+/// # Arguments
+///
+/// * `command` - The command line.
+///
+/// # Returns
+///
+/// The shell, given the command line.
+pub(crate) fn shell(command: &str) -> Command {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    shell.arg(command);
+    shell
+}
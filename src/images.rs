@@ -0,0 +1,150 @@
+/// Read a big-endian 16-bit integer.
+///
+/// # Arguments
+///
+/// * `bytes` - The bytes to read from.
+///
+/// * `position` - The position of the integer.
+///
+/// # Returns
+///
+/// The integer, if the bytes are long enough to contain it.
+fn read_u16_be(bytes: &[u8], position: usize) -> Option<u32> {
+    let integer = bytes.get(position..position + 2)?;
+    Some(u16::from_be_bytes([integer[0], integer[1]]) as u32)
+}
+
+/// Read a little-endian 16-bit integer.
+///
+/// # Arguments
+///
+/// * `bytes` - The bytes to read from.
+///
+/// * `position` - The position of the integer.
+///
+/// # Returns
+///
+/// The integer, if the bytes are long enough to contain it.
+fn read_u16_le(bytes: &[u8], position: usize) -> Option<u32> {
+    let integer = bytes.get(position..position + 2)?;
+    Some(u16::from_le_bytes([integer[0], integer[1]]) as u32)
+}
+
+/// Read a little-endian 24-bit integer.
+///
+/// # Arguments
+///
+/// * `bytes` - The bytes to read from.
+///
+/// * `position` - The position of the integer.
+///
+/// # Returns
+///
+/// The integer, if the bytes are long enough to contain it.
+fn read_u24_le(bytes: &[u8], position: usize) -> Option<u32> {
+    let integer = bytes.get(position..position + 3)?;
+    Some(u32::from_le_bytes([integer[0], integer[1], integer[2], 0]))
+}
+
+/// Read a big-endian 32-bit integer.
+///
+/// # Arguments
+///
+/// * `bytes` - The bytes to read from.
+///
+/// * `position` - The position of the integer.
+///
+/// # Returns
+///
+/// The integer, if the bytes are long enough to contain it.
+fn read_u32_be(bytes: &[u8], position: usize) -> Option<u32> {
+    let integer = bytes.get(position..position + 4)?;
+    Some(u32::from_be_bytes([
+        integer[0], integer[1], integer[2], integer[3],
+    ]))
+}
+
+/// Obtain the dimensions of a JPEG image from its start-of-frame segment.
+///
+/// # Arguments
+///
+/// * `bytes` - The contents of the image file.
+///
+/// # Returns
+///
+/// The width and height of the image, if they can be determined.
+fn jpeg_dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    let mut position = 2;
+    loop {
+        // Markers may be preceded by any number of fill bytes.
+        while *bytes.get(position)? == 0xFF && *bytes.get(position + 1)? == 0xFF {
+            position += 1;
+        }
+        if *bytes.get(position)? != 0xFF {
+            return None;
+        }
+        let marker = *bytes.get(position + 1)?;
+        let is_start_of_frame =
+            (0xC0..=0xCF).contains(&marker) && ![0xC4, 0xC8, 0xCC].contains(&marker);
+        if is_start_of_frame {
+            let height = read_u16_be(bytes, position + 5)?;
+            let width = read_u16_be(bytes, position + 7)?;
+            return Some((width, height));
+        }
+        position += 2 + read_u16_be(bytes, position + 2)? as usize;
+    }
+}
+
+/// Obtain the dimensions of a WebP image.
+///
+/// # Arguments
+///
+/// * `bytes` - The contents of the image file.
+///
+/// # Returns
+///
+/// The width and height of the image, if they can be determined.
+fn webp_dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    match bytes.get(12..16)? {
+        b"VP8 " => Some((
+            read_u16_le(bytes, 26)? & 0x3FFF,
+            read_u16_le(bytes, 28)? & 0x3FFF,
+        )),
+        b"VP8L" => {
+            let header = bytes.get(21..25)?;
+            let width = 1 + (header[0] as u32 | ((header[1] as u32 & 0x3F) << 8));
+            let height = 1
+                + ((header[1] as u32 >> 6)
+                    | ((header[2] as u32) << 2)
+                    | ((header[3] as u32 & 0x0F) << 10));
+            Some((width, height))
+        }
+        b"VP8X" => Some((1 + read_u24_le(bytes, 24)?, 1 + read_u24_le(bytes, 27)?)),
+        _ => None,
+    }
+}
+
+/// Obtain the dimensions of an image.
+/// PNG, JPEG, GIF, and WebP images are supported.
+///
+/// # Arguments
+///
+/// * `bytes` - The contents of the image file.
+///
+/// # Returns
+///
+/// The width and height of the image in pixels, or `None` if they cannot be determined.
+pub fn image_dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some((read_u32_be(bytes, 16)?, read_u32_be(bytes, 20)?))
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some((read_u16_le(bytes, 6)?, read_u16_le(bytes, 8)?))
+    } else if bytes.starts_with(&[0xFF, 0xD8]) {
+        jpeg_dimensions(bytes)
+    } else if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP") {
+        webp_dimensions(bytes)
+    } else {
+        None
+    }
+    .filter(|(width, height)| *width > 0 && *height > 0)
+}
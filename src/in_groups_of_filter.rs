@@ -0,0 +1,65 @@
+use liquid_core::Expression;
+use liquid_core::Result;
+use liquid_core::Runtime;
+use liquid_core::{
+    Display_filter, Filter, FilterParameters, FilterReflection, FromFilterParameters, ParseFilter,
+};
+use liquid_core::{Value, ValueView};
+
+#[derive(Debug, FilterParameters)]
+struct InGroupsOfArgs {
+    #[parameter(
+        description = "The number of items in each group.",
+        arg_type = "integer"
+    )]
+    size: Expression,
+    #[parameter(
+        description = "The value the last group is filled with, so that it has as many items as the others.",
+        arg_type = "any"
+    )]
+    fill: Option<Expression>,
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "in_groups_of",
+    description = "Splits an array into groups of a given size, optionally filling the last group.",
+    parameters(InGroupsOfArgs),
+    parsed(InGroupsOfFilter)
+)]
+/// A Liquid filter splitting an array into groups of a given size, such as the rows of a grid.
+/// The last group has fewer items than the others unless a value to fill it with is given, eg: `{% assign rows = blog | in_groups_of: 3, nil %}`.
+pub struct InGroupsOf;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "in_groups_of"]
+struct InGroupsOfFilter {
+    #[parameters]
+    args: InGroupsOfArgs,
+}
+
+impl Filter for InGroupsOfFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let size = usize::try_from(args.size)
+            .ok()
+            .filter(|x| *x > 0)
+            .ok_or_else(|| liquid_core::Error::with_msg("Group size must be positive"))?;
+        let items = match input.as_array() {
+            Some(array) => array.values().map(|x| x.to_value()).collect::<Vec<_>>(),
+            None if input.is_nil() => Vec::new(),
+            None => return Err(liquid_core::Error::with_msg("Array expected")),
+        };
+        let groups = items
+            .chunks(size)
+            .map(|group| {
+                let mut group = group.to_vec();
+                if let Some(fill) = &args.fill {
+                    group.resize(size, fill.to_value());
+                }
+                Value::array(group)
+            })
+            .collect::<Vec<_>>();
+        Ok(Value::array(groups))
+    }
+}
@@ -3,21 +3,144 @@
 // #![feature(doc_auto_cfg)]
 #![warn(missing_docs)]
 
+/// Admonitions, such as notes and warnings, within Markdown.
+pub mod admonitions;
+
+/// Audits of the pages output by builds, such as for accessibility.
+pub mod audit;
+
 /// Operations relevant to the build process.
 pub mod builds;
 
+/// Changes to the sources of a site, used to rebuild it when watching.
+pub mod changes;
+
+/// A template block for named regions of layouts which pages can provide.
+pub mod content_block;
+
+/// JSON representations of pages, for using builds as a content API.
+pub mod content_api;
+
 /// Date and time representations.
 pub mod date;
 
+/// Index pages of dated collections, for each year & month.
+pub mod date_archives;
+
+/// A template block for collapsible details.
+pub mod details_block;
+
+/// Exclusion of paths from a site.
+pub mod exclusion;
+
+/// Atom feeds of collections.
+pub mod feeds;
+
+/// A template filter for formatting dates.
+pub mod format_date_filter;
+
+/// Page metadata derived from version control.
+pub mod git;
+
+/// Commands run before & after building a site.
+pub mod hooks;
+
+/// Detection of image dimensions.
+pub mod images;
+
+/// A template filter splitting arrays into groups of a given size.
+pub mod in_groups_of_filter;
+
+/// A template tag reading CSV, JSON, or TOML data into a variable.
+pub mod load_data_tag;
+
+/// Linting of page content before rendering.
+pub mod lint;
+
+/// Records of the files output by builds.
+pub mod manifest;
+
 /// A template block for Markdown.
 pub mod markdown_block;
 
 /// A template block for math.
 pub mod math_block;
 
+/// A provider layering the source of a site above another, such as a theme.
+pub mod overlay_provider;
+
 /// Logic pertaining to individual pages.
 pub mod page;
 
+/// Representations of paths that are the same on every platform.
+pub mod paths;
+
+/// Plugins extending how sites are built.
+pub mod plugins;
+
+/// Processing of rendered pages before they are written.
+pub mod post_processing;
+
+/// Reporting the progress of builds.
+pub mod progress;
+
+/// Template filters shuffling arrays & picking random items from them, optionally seeded.
+pub mod random_filters;
+
+/// Computation of related pages.
+pub mod related;
+
+/// Data fetched from remote sources for use in the global context.
+pub mod remote_data;
+
+/// Validation of page frontmatter.
+pub mod schema;
+
+/// A template tag for search engine and social media metadata.
+pub mod seo_tag;
+
+/// Generation of search indices.
+pub mod search;
+
+/// Representations of a site's DAG that can be written to & read from storage.
+pub mod serialised_dag;
+
+/// Sites built by a Vox provider.
+pub mod site;
+
+/// Sitemaps listing the pages of a site for search engines.
+pub mod sitemap;
+
+/// A template filter sorting arrays of objects by values nested within them.
+pub mod sort_by_filter;
+
+/// A provider of the Vox build system that reads from one provider & writes through another.
+pub mod split_provider;
+
+/// Summaries of builds, for use by tools wrapping Vox.
+pub mod summary;
+
+/// Highlighting of code within Markdown.
+pub mod syntax_highlighting;
+
+/// The Liquid parser of a site, kept between builds.
+pub mod template_parser;
+
+/// Themes, layered beneath the sites using them.
+pub mod theme;
+
+/// Handling of pages sharing a URL.
+pub mod url_conflicts;
+
+/// How the URLs of pages are written, such as with trailing slashes.
+pub mod url_style;
+
+/// Transforms applied to the URLs of pages, such as making them lowercase.
+pub mod url_transforms;
+
+/// Links between pages written as `[[Other Page]]`.
+pub mod wikilinks;
+
 /// Errors originating during the build process.
 pub mod error;
 
@@ -31,3 +154,27 @@ pub mod fs_provider;
 /// A provider of the Vox build system that reads & writes from memory.
 #[cfg(feature = "ram_provider")]
 pub mod ram_provider;
+
+/// Utilities for testing site builds, using sites declared in memory.
+#[cfg(feature = "testing")]
+pub mod testing;
+
+/// A service rendering pages on request, for embedding sites in other web applications.
+#[cfg(feature = "service")]
+pub mod service;
+
+/// Archives of a site's output, for distribution as a single file.
+#[cfg(feature = "archive")]
+pub mod archive;
+/// Export of a site's pages as a single, self-contained HTML document.
+#[cfg(feature = "export")]
+pub mod export;
+/// Migration of sites from other static site generators and blogging platforms.
+#[cfg(feature = "migrate")]
+pub mod migrate;
+/// Validation of the HTML output by builds.
+#[cfg(feature = "validate")]
+pub mod validation;
+/// Plugins compiled to WebAssembly, run in a sandbox.
+#[cfg(feature = "wasm")]
+pub mod wasm_plugins;
@@ -0,0 +1,432 @@
+use crate::error::LintProblem;
+use crate::page::Page;
+use crate::provider::InputProvider;
+use ahash::AHashSet;
+use miette::{IntoDiagnostic, NamedSource};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use tracing::{debug, error, warn};
+
+/// Markers of unfinished writing.
+const TODO_MARKERS: &str = r"\b(TODO|FIXME|XXX|TBD)\b";
+
+#[derive(PartialEq, Eq, Clone, Copy, Default, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// How severe a lint problem is.
+pub enum LintLevel {
+    /// The problem is logged, and the build continues.
+    #[default]
+    Warning,
+    /// The problem is logged, and the build fails.
+    Error,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+/// A style rule, defined by an entry in the `rules` list of the `lint` table in `global.toml`.
+pub struct StyleRule {
+    /// A regular expression matching text which breaks the rule.
+    pub pattern: String,
+    /// The problem reported for each match.
+    pub message: String,
+    /// How severe breaking the rule is; defaults to the level of the other rules.
+    pub level: Option<LintLevel>,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+/// Settings for linting the content of pages, defined by the `lint` table in `global.toml`.
+pub struct LintSettings {
+    /// How severe problems found by the built-in rules are.
+    #[serde(default)]
+    pub level: LintLevel,
+    /// Phrases which may not be used, matched regardless of case.
+    #[serde(default)]
+    pub banned: Vec<String>,
+    /// Whether or not to report markers of unfinished writing, such as `TODO`.
+    #[serde(default = "default_true")]
+    pub todo: bool,
+    /// Whether or not to report references to footnotes which are not defined.
+    #[serde(default = "default_true")]
+    pub footnotes: bool,
+    /// Whether or not to report words repeated one after the other, such as 'the the'.
+    #[serde(default = "default_true")]
+    pub repeated_words: bool,
+    /// Style rules of the site's own.
+    #[serde(default)]
+    pub rules: Vec<StyleRule>,
+}
+
+/// By default, each built-in rule is enabled.
+fn default_true() -> bool {
+    true
+}
+
+/// A problem found by linting a page's content.
+pub struct LintFinding {
+    /// The problem found.
+    pub problem: String,
+    /// How the problem may be fixed, if there is advice to give.
+    pub advice: Option<String>,
+    /// How severe the problem is.
+    pub level: LintLevel,
+    /// The position of the problem in the page's content.
+    pub span: Range<usize>,
+}
+
+/// Lints the content of pages, with its rules compiled.
+pub struct Linter {
+    /// The settings the linter was created from.
+    settings: LintSettings,
+    /// The banned phrases, with the expressions matching them.
+    banned: Vec<(String, Regex)>,
+    /// The site's style rules, with the expressions matching them.
+    rules: Vec<(StyleRule, Regex)>,
+}
+
+impl Linter {
+    /// Create a linter.
+    ///
+    /// # Arguments
+    ///
+    /// * `settings` - The settings for linting.
+    ///
+    /// # Returns
+    ///
+    /// A linter, or an error if a style rule's pattern is not a valid regular expression.
+    pub fn new(settings: LintSettings) -> miette::Result<Self> {
+        let banned = settings
+            .banned
+            .iter()
+            .map(|phrase| {
+                let pattern = format!(r"(?i)\b{}\b", regex::escape(phrase));
+                Ok((phrase.clone(), Regex::new(&pattern).into_diagnostic()?))
+            })
+            .collect::<miette::Result<Vec<_>>>()?;
+        let rules = settings
+            .rules
+            .iter()
+            .map(|rule| match Regex::new(&rule.pattern) {
+                Ok(pattern) => Ok((rule.clone(), pattern)),
+                Err(err) => Err(miette::miette!(
+                    "The lint rule pattern `{}` is not valid: {}",
+                    rule.pattern,
+                    err
+                )),
+            })
+            .collect::<miette::Result<Vec<_>>>()?;
+        Ok(Self {
+            settings,
+            banned,
+            rules,
+        })
+    }
+
+    /// Lint the content of a page.
+    /// Code, Liquid tags, and HTML tags are not linted, though HTML comments are.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - The content of the page, before rendering.
+    ///
+    /// # Returns
+    ///
+    /// The problems found, in order of appearance.
+    pub fn lint(&self, content: &str) -> Vec<LintFinding> {
+        let prose = mask_non_prose(content);
+        let level = self.settings.level;
+        let mut findings = Vec::new();
+        if self.settings.todo {
+            let markers = Regex::new(TODO_MARKERS).unwrap();
+            for marker in markers.find_iter(&prose) {
+                findings.push(LintFinding {
+                    problem: format!("`{}` marks unfinished writing", marker.as_str()),
+                    advice: Some("Please finish the writing, and remove the marker.".to_string()),
+                    level,
+                    span: marker.range(),
+                });
+            }
+        }
+        for (phrase, pattern) in &self.banned {
+            for banned in pattern.find_iter(&prose) {
+                findings.push(LintFinding {
+                    problem: format!("`{}` is a banned phrase", phrase),
+                    advice: Some("Please rephrase the text without it.".to_string()),
+                    level,
+                    span: banned.range(),
+                });
+            }
+        }
+        if self.settings.footnotes {
+            findings.extend(
+                undefined_footnotes(&prose)
+                    .into_iter()
+                    .map(|(label, span)| LintFinding {
+                        problem: format!("Footnote `[^{}]` has no definition", label),
+                        advice: Some(format!(
+                            "Please define the footnote on a line beginning with `[^{}]:`.",
+                            label
+                        )),
+                        level,
+                        span,
+                    }),
+            );
+        }
+        if self.settings.repeated_words {
+            findings.extend(
+                repeated_words(&prose)
+                    .into_iter()
+                    .map(|(word, span)| LintFinding {
+                        problem: format!("`{}` is repeated", word),
+                        advice: Some("Please remove the repeated word.".to_string()),
+                        level,
+                        span,
+                    }),
+            );
+        }
+        for (rule, pattern) in &self.rules {
+            for matched in pattern.find_iter(&prose) {
+                findings.push(LintFinding {
+                    problem: rule.message.clone(),
+                    advice: None,
+                    level: rule.level.unwrap_or(level),
+                    span: matched.range(),
+                });
+            }
+        }
+        findings.sort_by_key(|finding| finding.span.start);
+        findings
+    }
+}
+
+/// Replace the parts of a page's content which are not prose with spaces; code, Liquid tags, and HTML tags.
+/// Line breaks are kept, and every character is replaced by as many spaces as it has bytes, so that positions in the result are positions in the content.
+///
+/// # Arguments
+///
+/// * `content` - The content of a page.
+///
+/// # Returns
+///
+/// The content with only its prose remaining.
+fn mask_non_prose(content: &str) -> String {
+    let mut masked = String::with_capacity(content.len());
+    let mut fence: Option<String> = None;
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+        let fence_marker = ["```", "~~~"]
+            .into_iter()
+            .find(|marker| indent < 4 && trimmed.starts_with(marker));
+        match (&fence, fence_marker) {
+            (Some(open), Some(_)) if trimmed.trim_end().starts_with(open.as_str()) => {
+                fence = None;
+                masked.push_str(&blank(line));
+            }
+            (Some(_), _) => masked.push_str(&blank(line)),
+            (None, Some(marker)) => {
+                fence = Some(marker.to_string());
+                masked.push_str(&blank(line));
+            }
+            (None, None) => masked.push_str(&mask_inline(line)),
+        }
+    }
+    masked
+}
+
+/// Replace the code spans, Liquid tags, and HTML tags within a line of content with spaces.
+///
+/// # Arguments
+///
+/// * `line` - The line of content.
+///
+/// # Returns
+///
+/// The line, with only its prose remaining.
+fn mask_inline(line: &str) -> String {
+    let mut masked = String::with_capacity(line.len());
+    let mut offset = 0;
+    while offset < line.len() {
+        let rest = &line[offset..];
+        let end = if rest.starts_with('`') {
+            let ticks = rest.len() - rest.trim_start_matches('`').len();
+            rest[ticks..].find(&rest[..ticks]).map(|x| x + 2 * ticks)
+        } else if rest.starts_with("{%") || rest.starts_with("{{") {
+            let closing = if rest.starts_with("{%") { "%}" } else { "}}" };
+            rest.find(closing).map(|x| x + 2)
+        } else if rest.starts_with("<!--") {
+            // Comments are prose, and may hold markers of unfinished writing.
+            None
+        } else if rest.starts_with('<')
+            && rest[1..]
+                .chars()
+                .next()
+                .is_some_and(|x| x.is_ascii_alphabetic() || x == '/')
+        {
+            rest.find('>').map(|x| x + 1)
+        } else {
+            None
+        };
+        match end {
+            Some(end) => {
+                masked.push_str(&blank(&rest[..end]));
+                offset += end;
+            }
+            None => {
+                let character = rest.chars().next().unwrap();
+                masked.push(character);
+                offset += character.len_utf8();
+            }
+        }
+    }
+    masked
+}
+
+/// Replace text with spaces, keeping its line breaks and length in bytes.
+///
+/// # Arguments
+///
+/// * `text` - The text.
+///
+/// # Returns
+///
+/// The blanked text.
+fn blank(text: &str) -> String {
+    text.chars()
+        .map(|x| match x {
+            '\n' | '\r' => x.to_string(),
+            _ => " ".repeat(x.len_utf8()),
+        })
+        .collect()
+}
+
+/// Find references to footnotes which are not defined.
+///
+/// # Arguments
+///
+/// * `prose` - The prose of a page.
+///
+/// # Returns
+///
+/// The label & position of each reference to an undefined footnote.
+fn undefined_footnotes(prose: &str) -> Vec<(String, Range<usize>)> {
+    let footnote = Regex::new(r"\[\^([^\]\s]+)\]").unwrap();
+    let mut definitions = AHashSet::new();
+    let mut references = Vec::new();
+    for captures in footnote.captures_iter(prose) {
+        let whole = captures.get(0).unwrap();
+        let label = captures[1].to_string();
+        let line_start = prose[..whole.start()].rfind('\n').map_or(0, |x| x + 1);
+        let is_definition = prose[line_start..whole.start()].trim().is_empty()
+            && prose[whole.end()..].starts_with(':');
+        if is_definition {
+            definitions.insert(label);
+        } else {
+            references.push((label, whole.range()));
+        }
+    }
+    references
+        .into_iter()
+        .filter(|(label, _)| !definitions.contains(label))
+        .collect()
+}
+
+/// Find words repeated one after the other, separated only by whitespace.
+///
+/// # Arguments
+///
+/// * `prose` - The prose of a page.
+///
+/// # Returns
+///
+/// Each repeated word, with the position of its repetition.
+fn repeated_words(prose: &str) -> Vec<(String, Range<usize>)> {
+    let word = Regex::new(r"\b[\p{L}']+\b").unwrap();
+    let mut repeated = Vec::new();
+    let mut previous: Option<(String, usize)> = None;
+    for matched in word.find_iter(prose) {
+        let lowercase = matched.as_str().to_lowercase();
+        if let Some((previous_word, previous_end)) = &previous {
+            let between = &prose[*previous_end..matched.start()];
+            // Words separated by a blank line are in different paragraphs.
+            if *previous_word == lowercase
+                && between.chars().all(char::is_whitespace)
+                && between.matches('\n').count() < 2
+            {
+                repeated.push((matched.as_str().to_string(), matched.range()));
+            }
+        }
+        previous = Some((lowercase, matched.end()));
+    }
+    repeated
+}
+
+/// Lint the content of pages, logging the problems found.
+/// Layouts are not linted, nor are pages with `lint = false` in their frontmatter.
+///
+/// # Arguments
+///
+/// * `provider` - The provider the pages are read from.
+///
+/// * `linter` - The linter.
+///
+/// * `pages` - The pages to lint.
+///
+/// # Returns
+///
+/// An error if any problem found is an error.
+pub fn lint_pages<'a>(
+    provider: &(impl InputProvider + ?Sized),
+    linter: &Linter,
+    pages: impl IntoIterator<Item = &'a Page>,
+) -> miette::Result<()> {
+    let mut errors = 0;
+    for page in pages {
+        if page.is_layout
+            || page
+                .data
+                .get("lint")
+                .and_then(|x| x.as_bool())
+                .is_some_and(|lint| !lint)
+        {
+            continue;
+        }
+        let path = page.to_path_string().trim_start_matches('/').to_string();
+        debug!("Linting {:?} … ", path);
+        let findings = linter.lint(&page.content);
+        if findings.is_empty() {
+            continue;
+        }
+        // Problems are shown within the page's source, so that their lines are those of the file.
+        let (source, offset) = match provider.read_to_string(&path) {
+            Ok(source) => match source.find(&*page.content) {
+                Some(offset) => (source, offset),
+                None => (page.content.to_string(), 0),
+            },
+            Err(_) => (page.content.to_string(), 0),
+        };
+        for finding in findings {
+            let level = finding.level;
+            let report = miette::Report::new(LintProblem {
+                src: NamedSource::new(&path, source.clone()),
+                page: path.clone(),
+                problem: finding.problem,
+                advice: finding.advice,
+                span: (finding.span.start + offset..finding.span.end + offset).into(),
+            });
+            match level {
+                LintLevel::Warning => warn!("{:?}", report),
+                LintLevel::Error => {
+                    errors += 1;
+                    error!("{:?}", report);
+                }
+            }
+        }
+    }
+    match errors {
+        0 => Ok(()),
+        errors => Err(miette::miette!(
+            "Linting found errors in the content of pages ({}).",
+            errors
+        )),
+    }
+}
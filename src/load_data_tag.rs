@@ -0,0 +1,228 @@
+use crate::provider::FileReader;
+use crate::remote_data::DataFormat;
+use liquid_core::error::ResultLiquidExt;
+use liquid_core::model::KString;
+use liquid_core::Expression;
+use liquid_core::Language;
+use liquid_core::Renderable;
+use liquid_core::Result;
+use liquid_core::Runtime;
+use liquid_core::ValueView;
+use liquid_core::{ParseTag, TagReflection, TagTokenIter};
+use miette::IntoDiagnostic;
+use path_clean::PathClean;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Write;
+use std::path::Path;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+};
+
+#[derive(Clone, Default)]
+/// Data fetched from URLs while rendering, shared by the data-loading tags of a parser.
+/// Data is kept for the duration of a build, so that each URL is fetched at most once per build.
+pub struct FetchedData {
+    /// Whether to refuse to fetch data from URLs.
+    offline: Arc<AtomicBool>,
+    /// Data fetched during the build, by URL.
+    fetched: Arc<Mutex<HashMap<String, String>>>,
+}
+
+impl FetchedData {
+    /// Discard the data fetched during the previous build.
+    ///
+    /// # Arguments
+    ///
+    /// * `offline` - Whether to refuse to fetch data from URLs during the build.
+    pub fn begin_build(&self, offline: bool) -> miette::Result<()> {
+        self.offline.store(offline, Ordering::SeqCst);
+        self.fetched
+            .lock()
+            .map_err(|e| miette::miette!("{}", e))?
+            .clear();
+        Ok(())
+    }
+}
+
+#[derive(Clone, Default)]
+/// A Liquid template tag reading CSV, JSON, or TOML data into a variable.
+/// The tag is written as `{% load_data "data/projects.csv" as projects %}`, given a path relative to the site's directory or a URL.
+pub struct LoadDataTag {
+    /// Reads files from the site's provider.
+    reader: Option<FileReader>,
+    /// Data already fetched from URLs during the build.
+    fetched: FetchedData,
+}
+
+impl fmt::Debug for LoadDataTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoadDataTag")
+            .field("reader", &self.reader.is_some())
+            .finish()
+    }
+}
+
+impl LoadDataTag {
+    /// Provides a new instance of the data-loading tag parser.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - Reads files from the site's provider, if it can do so while pages are being rendered.
+    ///
+    /// * `fetched` - Data already fetched from URLs during the build.
+    pub fn new(reader: Option<FileReader>, fetched: FetchedData) -> Self {
+        Self { reader, fetched }
+    }
+}
+
+impl TagReflection for LoadDataTag {
+    fn tag(&self) -> &'static str {
+        "load_data"
+    }
+
+    fn description(&self) -> &'static str {
+        ""
+    }
+}
+
+impl ParseTag for LoadDataTag {
+    fn parse(
+        &self,
+        mut arguments: TagTokenIter<'_>,
+        _options: &Language,
+    ) -> Result<Box<dyn Renderable>> {
+        let source = arguments
+            .expect_next("Path or URL expected.")?
+            .expect_value()
+            .into_result()?;
+        arguments
+            .expect_next("\"as\" expected.")?
+            .expect_str("as")
+            .into_result_custom_msg("\"as\" expected.")?;
+        let name = arguments
+            .expect_next("Identifier expected.")?
+            .expect_identifier()
+            .into_result()?
+            .to_owned()
+            .into();
+        arguments.expect_nothing()?;
+        Ok(Box::new(LoadData {
+            source,
+            name,
+            reader: self.reader.clone(),
+            fetched: self.fetched.clone(),
+        }))
+    }
+
+    fn reflection(&self) -> &dyn TagReflection {
+        self
+    }
+}
+
+struct LoadData {
+    source: Expression,
+    name: KString,
+    reader: Option<FileReader>,
+    #[cfg_attr(not(feature = "remote_data"), allow(dead_code))]
+    fetched: FetchedData,
+}
+
+impl fmt::Debug for LoadData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoadData")
+            .field("source", &self.source)
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+impl LoadData {
+    fn trace(&self) -> String {
+        format!("{{% load_data {} as {} %}}", self.source, self.name)
+    }
+
+    /// Read data as a string.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - A path relative to the site's directory, or a URL.
+    ///
+    /// # Returns
+    ///
+    /// The data.
+    fn read(&self, source: &str) -> miette::Result<String> {
+        if source.starts_with("http://") || source.starts_with("https://") {
+            return self.fetch(source);
+        }
+        let reader = self.reader.as_ref().ok_or(miette::miette!(
+            "The site's provider cannot read files while pages are being rendered."
+        ))?;
+        let path = Path::new(source.trim_start_matches('/')).clean();
+        String::from_utf8(reader(&path)?).into_diagnostic()
+    }
+
+    /// Fetch data from a URL, unless it has already been fetched during the build.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of the data.
+    ///
+    /// # Returns
+    ///
+    /// The data.
+    #[cfg(feature = "remote_data")]
+    fn fetch(&self, url: &str) -> miette::Result<String> {
+        let mut fetched = self
+            .fetched
+            .fetched
+            .lock()
+            .map_err(|e| miette::miette!("{}", e))?;
+        if let Some(body) = fetched.get(url) {
+            return Ok(body.clone());
+        }
+        if self.fetched.offline.load(Ordering::SeqCst) {
+            return Err(miette::miette!(
+                "Working offline, so data cannot be fetched from URLs."
+            ));
+        }
+        let body = ureq::get(url)
+            .call()
+            .into_diagnostic()?
+            .into_string()
+            .into_diagnostic()?;
+        fetched.insert(url.to_string(), body.clone());
+        Ok(body)
+    }
+
+    #[cfg(not(feature = "remote_data"))]
+    fn fetch(&self, _url: &str) -> miette::Result<String> {
+        Err(miette::miette!(
+            "Loading data from URLs requires the `remote_data` feature."
+        ))
+    }
+}
+
+impl Renderable for LoadData {
+    fn render_to(&self, _writer: &mut dyn Write, runtime: &dyn Runtime) -> Result<()> {
+        let source = self
+            .source
+            .evaluate(runtime)
+            .trace_with(|| self.trace().into())?
+            .to_kstr()
+            .to_string();
+        let value = DataFormat::from_path(&source)
+            .ok_or(miette::miette!(
+                "The format of `{}` cannot be determined from its extension.",
+                source
+            ))
+            .and_then(|format| format.parse(&self.read(&source)?))
+            .map_err(|err| {
+                liquid_core::Error::with_msg(format!("Unable to load `{}`: {}", source, err))
+            })
+            .trace_with(|| self.trace().into())?;
+        runtime.set_global(self.name.clone(), value);
+        Ok(())
+    }
+}
@@ -1,32 +1,60 @@
 use actix_files::NamedFile;
 use actix_web::dev::{ServiceRequest, ServiceResponse};
-use actix_web::{App, HttpServer};
-use clap::{Parser, Subcommand};
+use actix_web::{web, App, HttpResponse, HttpServer};
+use clap::{Parser, Subcommand, ValueEnum};
 use miette::IntoDiagnostic;
 use mimalloc::MiMalloc;
-use notify_debouncer_full::{new_debouncer, notify::RecursiveMode};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::net::Ipv4Addr;
-use std::sync::mpsc::channel;
-use std::sync::LazyLock;
-use std::{path::PathBuf, time::Duration};
+use std::num::NonZeroUsize;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
 use tokio::time::sleep;
-use tracing::{error, info, trace, Level};
+use tracing::{debug, error, info, warn, Level};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+use vox::changes::Change;
+use vox::export::{export_html, exported_pages};
 use vox::fs_provider::FsProvider;
-use vox::provider::{VoxProvider, VERSION};
+use vox::page::Page;
+use vox::provider::{InputProvider, OutputProvider, VoxProvider, VERSION};
+use vox::remote_data::refresh_remote_data;
+use vox::search::{query_index, SearchDocument};
+use vox::service::VoxService;
+use vox::site::{BuildOptions, Site};
+use vox::summary::BuildSummary;
+use vox::theme::{get_theme, install_theme, theme_name, themed_provider, ThemeLock, LOCKFILE};
 
 #[global_allocator]
 static GLOBAL: MiMalloc = MiMalloc;
 
-static FS_PROVIDER: LazyLock<FsProvider> = LazyLock::new(|| FsProvider::new());
-
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
-    /// The level of log output; warnings, information, debugging messages, and trace logs.
-    #[arg(short, long, action = clap::ArgAction::Count, default_value_t = 2, global = true)]
+    /// The level of log output; warnings, information, debugging messages, and trace logs. By default, information is logged.
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
     verbosity: u8,
+    /// A file to append all logs to, including trace logs, regardless of the level of log output.
+    #[arg(long, default_value = None, global = true)]
+    log_file: Option<PathBuf>,
+    /// The maximum number of worker threads; by default, one per CPU core.
+    #[arg(long, env = "VOX_JOBS", default_value = None, global = true)]
+    jobs: Option<NonZeroUsize>,
+    /// The directory to output the site to, relative to the site directory; by default, `output`.
+    #[arg(long, default_value = None, global = true)]
+    output_directory: Option<PathBuf>,
+    /// The environment the site is built for, such as `production`, given to templates as `meta.environment`.
+    #[arg(long, env = "VOX_ENV", default_value = None, global = true)]
+    environment: Option<String>,
 }
 #[derive(Subcommand)]
 enum Commands {
@@ -35,86 +63,633 @@ enum Commands {
         /// An optional path to the site directory.
         #[arg(default_value = None)]
         path: Option<PathBuf>,
-        /// Watch for changes.
-        #[arg(short, long, default_value_t = false)]
-        watch: bool,
+        /// Watch for changes; `--watch=false` does not watch, even if `vox.toml` sets `watch`.
+        #[arg(short, long, num_args = 0..=1, require_equals = true, default_missing_value = "true", default_value = None)]
+        watch: Option<bool>,
         /// Visualise the DAG.
         #[arg(short = 'd', long, default_value_t = false)]
         visualise_dag: bool,
         /// Generate stylesheet for syntax highlighting.
         #[arg(short = 's', long, default_value_t = false)]
         generate_syntax_css: bool,
+        /// Output JSON representations of pages and collections.
+        #[arg(short = 'j', long, default_value_t = false)]
+        json: bool,
+        /// Remove files output by previous builds which were not output by this build.
+        #[arg(long, default_value_t = false)]
+        remove_orphans: bool,
+        /// Only use cached remote data, rather than fetching any.
+        #[arg(long, default_value_t = false)]
+        offline: bool,
+        /// Print a summary of each build in the given format.
+        #[arg(long, value_enum, default_value = None)]
+        summary: Option<SummaryFormat>,
+        /// Write the output to a tar archive once built, compressed with gzip if the archive's name ends in `.gz` or `.tgz`.
+        #[arg(long, default_value = None, conflicts_with = "watch")]
+        archive: Option<PathBuf>,
+        /// The number of times to retry a failed build; by default, failed builds are retried indefinitely when watching for changes, and not retried otherwise.
+        #[arg(long, default_value = None)]
+        retry: Option<usize>,
+        /// The number of seconds to wait before retrying a failed build.
+        #[arg(long, default_value_t = 5)]
+        retry_delay: u64,
+        /// Double the time waited before retrying after each consecutive failed build.
+        #[arg(long, default_value_t = false)]
+        retry_backoff: bool,
+        /// Check the pages output for problems once built, logging any found; may be given more than once.
+        #[arg(long, value_enum)]
+        audit: Vec<Audit>,
     },
     /// Serve the site.
     Serve {
         /// An optional path to the site directory.
         #[arg(default_value = None)]
         path: Option<PathBuf>,
-        /// Watch for changes.
-        #[arg(short, long, default_value_t = false)]
-        watch: bool,
-        /// The port to serve the site on.
-        #[arg(short, long, default_value_t = 80)]
-        port: u16,
+        /// Watch for changes; `--watch=false` does not watch, even if `vox.toml` sets `watch`.
+        #[arg(short, long, num_args = 0..=1, require_equals = true, default_missing_value = "true", default_value = None)]
+        watch: Option<bool>,
+        /// The port to serve the site on; by default, 80.
+        #[arg(short, long, default_value = None)]
+        port: Option<u16>,
+        /// Render pages when they are first requested, rather than building the site before serving it.
+        #[arg(short, long, default_value_t = false, conflicts_with_all = ["visualise_dag", "generate_syntax_css", "json", "remove_orphans", "summary", "retry", "retry_delay", "retry_backoff"])]
+        on_demand: bool,
         /// Visualise the DAG.
         #[arg(short = 'd', long, default_value_t = false)]
         visualise_dag: bool,
         /// Generate stylesheet for syntax highlighting.
         #[arg(short = 's', long, default_value_t = false)]
         generate_syntax_css: bool,
+        /// Output JSON representations of pages and collections.
+        #[arg(short = 'j', long, default_value_t = false)]
+        json: bool,
+        /// Remove files output by previous builds which were not output by this build.
+        #[arg(long, default_value_t = false)]
+        remove_orphans: bool,
+        /// Only use cached remote data, rather than fetching any.
+        #[arg(long, default_value_t = false)]
+        offline: bool,
+        /// Print a summary of each build in the given format.
+        #[arg(long, value_enum, default_value = None)]
+        summary: Option<SummaryFormat>,
+        /// The number of times to retry a failed build; by default, failed builds are retried indefinitely when watching for changes, and not retried otherwise.
+        #[arg(long, default_value = None)]
+        retry: Option<usize>,
+        /// The number of seconds to wait before retrying a failed build.
+        #[arg(long, default_value_t = 5)]
+        retry_delay: u64,
+        /// Double the time waited before retrying after each consecutive failed build.
+        #[arg(long, default_value_t = false)]
+        retry_backoff: bool,
+    },
+    /// Remove the site's output, either the files orphaned by previous builds or all of it.
+    #[command(group(clap::ArgGroup::new("target").required(true).args(["orphans", "all"])))]
+    Clean {
+        /// An optional path to the site directory.
+        #[arg(default_value = None)]
+        path: Option<PathBuf>,
+        /// Build the site and remove only the files output by previous builds which were not output by this build.
+        #[arg(short, long, default_value_t = false)]
+        orphans: bool,
+        /// Remove the output and `.vox` folders entirely, including files placed in the output by other means and cached data.
+        #[arg(short, long, default_value_t = false)]
+        all: bool,
+        /// When removing everything, do not ask for confirmation.
+        #[arg(short, long, default_value_t = false, requires = "all")]
+        yes: bool,
+        /// When removing orphans, build the site with a visualisation of the DAG.
+        #[arg(short = 'd', long, default_value_t = false, requires = "orphans")]
+        visualise_dag: bool,
+        /// When removing orphans, build the site with a stylesheet for syntax highlighting.
+        #[arg(short = 's', long, default_value_t = false, requires = "orphans")]
+        generate_syntax_css: bool,
+        /// When removing orphans, build the site with JSON representations of pages and collections.
+        #[arg(short = 'j', long, default_value_t = false, requires = "orphans")]
+        json: bool,
+    },
+    /// Deploy the built site, using the `deploy` settings in `global.toml`.
+    Deploy {
+        /// An optional path to the site directory.
+        #[arg(default_value = None)]
+        path: Option<PathBuf>,
+        /// Deploy to GitHub Pages, rather than to a remote host over SSH.
+        #[arg(short, long, default_value_t = false, conflicts_with_all = ["s3", "rsync"])]
+        github_pages: bool,
+        /// Deploy to an Amazon S3 bucket, rather than to a remote host over SSH.
+        #[arg(long, default_value_t = false, conflicts_with = "rsync")]
+        s3: bool,
+        /// Deploy to a remote host over SSH, even if `vox.toml` sets another target.
+        #[arg(long, default_value_t = false)]
+        rsync: bool,
+    },
+    /// Migrate a site from another static site generator.
+    Migrate {
+        #[command(subcommand)]
+        source: MigrationSource,
+    },
+    /// Import posts from a blogging platform.
+    Import {
+        #[command(subcommand)]
+        source: ImportSource,
+    },
+    /// Measure how long the site takes to build, from scratch and incrementally.
+    Bench {
+        /// An optional path to the site directory.
+        #[arg(default_value = None)]
+        path: Option<PathBuf>,
+        /// The number of builds from scratch.
+        #[arg(short, long, default_value_t = 5)]
+        cold: usize,
+        /// The number of incremental builds, each after changing a random page.
+        #[arg(short, long, default_value_t = 10)]
+        incremental: usize,
+        /// Only use cached remote data, rather than fetching any.
+        #[arg(long, default_value_t = false)]
+        offline: bool,
+    },
+    /// Build the site, then combine its pages into a single, self-contained HTML file.
+    Export {
+        /// An optional path to the site directory.
+        #[arg(default_value = None)]
+        path: Option<PathBuf>,
+        /// The file to write the export to.
+        #[arg(short, long, default_value = "export.html")]
+        output: PathBuf,
+        /// Only export the pages in a collection, rather than every page.
+        #[arg(short, long, default_value = None)]
+        collection: Option<String>,
+        /// Only use cached remote data, rather than fetching any.
+        #[arg(long, default_value_t = false)]
+        offline: bool,
     },
+    /// Render a Liquid template with the contexts of the site, printing the result.
+    Eval {
+        /// The template to render; if omitted or `-`, the template is read from standard input.
+        #[arg(default_value = None)]
+        template: Option<String>,
+        /// An optional path to the site directory.
+        #[arg(short, long, default_value = None)]
+        path: Option<PathBuf>,
+        /// The path of a page, relative to the site directory, whose contexts the template is rendered with.
+        #[arg(long, default_value = None)]
+        page: Option<PathBuf>,
+    },
+    /// Manage the themes installed into the site.
+    Theme {
+        /// An optional path to the site directory.
+        #[arg(short, long, default_value = None, global = true)]
+        path: Option<PathBuf>,
+        #[command(subcommand)]
+        action: ThemeAction,
+    },
+}
+#[derive(Subcommand)]
+enum MigrationSource {
+    /// Migrate a Hugo site.
+    Hugo {
+        /// The path to the Hugo site.
+        source: PathBuf,
+        /// An optional path to write the migrated site to.
+        #[arg(default_value = None)]
+        destination: Option<PathBuf>,
+    },
+}
+#[derive(Subcommand)]
+enum ImportSource {
+    /// Import a WordPress export (WXR) file.
+    Wxr {
+        /// The path to the export file.
+        export: PathBuf,
+        /// An optional path to the site directory.
+        #[arg(default_value = None)]
+        destination: Option<PathBuf>,
+        /// Do not download referenced images.
+        #[arg(short, long, default_value_t = false)]
+        no_images: bool,
+    },
+    /// Import an RSS feed.
+    Rss {
+        /// The path or URL of the feed.
+        feed: String,
+        /// An optional path to the site directory.
+        #[arg(default_value = None)]
+        destination: Option<PathBuf>,
+        /// Do not download referenced images.
+        #[arg(short, long, default_value_t = false)]
+        no_images: bool,
+    },
+}
+#[derive(Clone, Copy, ValueEnum)]
+enum SummaryFormat {
+    /// A single line of JSON, printed to standard output.
+    Json,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Audit {
+    /// Common accessibility problems, such as images without alternative text.
+    #[value(name = "a11y")]
+    Accessibility,
+    /// Structural errors in the HTML, such as unclosed elements and duplicate IDs.
+    Html,
+}
+
+#[derive(Serialize)]
+/// A summary of a build, along with the warnings logged during it.
+struct SummaryReport<'a> {
+    #[serde(flatten)]
+    summary: &'a BuildSummary,
+    /// The warnings logged during the build.
+    warnings: Vec<String>,
+}
+
+/// Whether or not warnings are being collected for build summaries.
+static COLLECTING_WARNINGS: AtomicBool = AtomicBool::new(false);
+
+/// The warnings logged since the last build summary was printed.
+static WARNINGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// A layer of the logger collecting warnings for build summaries.
+struct WarningCollector;
+
+impl<S: tracing::Subscriber> Layer<S> for WarningCollector {
+    fn on_event(
+        &self,
+        event: &tracing::Event<'_>,
+        _ctx: tracing_subscriber::layer::Context<'_, S>,
+    ) {
+        if *event.metadata().level() > Level::WARN || !COLLECTING_WARNINGS.load(Ordering::Relaxed) {
+            return;
+        }
+        let mut message = MessageVisitor(String::new());
+        event.record(&mut message);
+        if let Ok(mut warnings) = WARNINGS.lock() {
+            warnings.push(message.0);
+        }
+    }
+}
+
+/// Reads the message of a log event.
+struct MessageVisitor(String);
+
+impl tracing::field::Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.0 = format!("{:?}", value)
+                .trim_end_matches([' ', '…'])
+                .to_string();
+        }
+    }
+}
+
+/// Print the summary of a build, along with the warnings logged since the last summary was printed.
+///
+/// # Arguments
+///
+/// * `summary` - The summary of the build.
+///
+/// * `format` - The format to print the summary in.
+fn print_summary(summary: &BuildSummary, format: SummaryFormat) {
+    let warnings = WARNINGS
+        .lock()
+        .map(|mut warnings| std::mem::take(&mut *warnings))
+        .unwrap_or_default();
+    let report = SummaryReport { summary, warnings };
+    match format {
+        SummaryFormat::Json => match serde_json::to_string(&report) {
+            Ok(report) => println!("{}", report),
+            Err(err) => error!("Unable to summarise the build: {}", err),
+        },
+    }
+}
+
+#[derive(Subcommand)]
+enum ThemeAction {
+    /// Install a theme from a Git repository.
+    Install {
+        /// The URL of the theme's Git repository.
+        url: String,
+        /// An optional name for the theme; by default, the theme is named after its repository.
+        #[arg(short, long, default_value = None)]
+        name: Option<String>,
+    },
+    /// Update installed themes to the latest commit of their repositories.
+    Update {
+        /// An optional name of the theme to update; by default, all installed themes are updated.
+        #[arg(default_value = None)]
+        name: Option<String>,
+    },
+    /// List the installed themes.
+    List,
+}
+
+/// The file setting defaults for command-line options, relative to the site's directory.
+const PROJECT_CONFIG: &str = "vox.toml";
+
+/// The port a site is served on, unless another is given.
+const DEFAULT_PORT: u16 = 80;
+
+/// The level of log output, unless another is given; information.
+const DEFAULT_VERBOSITY: u8 = 2;
+
+/// The directory the site is output to, unless another is given.
+const DEFAULT_OUTPUT_DIRECTORY: &str = "output";
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+/// Defaults for command-line options, defined in `vox.toml`; options given on the command line take precedence.
+struct ProjectConfig {
+    /// The level of log output, as the number of times `-v` would be given.
+    verbosity: Option<u8>,
+    /// The directory to output the site to, relative to the site's directory.
+    output: Option<PathBuf>,
+    /// The environment the site is built for, such as `production`.
+    environment: Option<String>,
+    /// Defaults for `vox build`.
+    build: BuildDefaults,
+    /// Defaults for `vox serve`.
+    serve: ServeDefaults,
+    /// Defaults for `vox deploy`.
+    deploy: DeployDefaults,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+/// Defaults for `vox build`, defined by the `build` table in `vox.toml`.
+struct BuildDefaults {
+    /// Whether or not to watch for changes.
+    watch: Option<bool>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+/// Defaults for `vox serve`, defined by the `serve` table in `vox.toml`.
+struct ServeDefaults {
+    /// Whether or not to watch for changes.
+    watch: Option<bool>,
+    /// The port to serve the site on.
+    port: Option<u16>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+/// Defaults for `vox deploy`, defined by the `deploy` table in `vox.toml`.
+struct DeployDefaults {
+    /// Where to deploy the site.
+    target: Option<DeployTarget>,
+}
+
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+/// Where a site is deployed.
+enum DeployTarget {
+    /// A remote host, over SSH.
+    #[default]
+    Rsync,
+    /// GitHub Pages.
+    GithubPages,
+    /// An Amazon S3 bucket.
+    S3,
+}
+
+impl ProjectConfig {
+    /// Read the defaults for command-line options of the site in the current directory.
+    ///
+    /// # Returns
+    ///
+    /// The defaults in `vox.toml`, or no defaults if the site has no `vox.toml`.
+    fn read() -> miette::Result<Self> {
+        let path = Path::new(PROJECT_CONFIG);
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+        let config = std::fs::read_to_string(path).into_diagnostic()?;
+        toml::from_str(&config)
+            .map_err(|err| miette::miette!("Unable to read `{}`: {}", PROJECT_CONFIG, err))
+    }
+
+    /// Get the directory the site is output to.
+    ///
+    /// # Returns
+    ///
+    /// The path to the output directory, relative to the site's directory.
+    fn output_directory(&self) -> PathBuf {
+        self.output
+            .clone()
+            .unwrap_or(PathBuf::from(DEFAULT_OUTPUT_DIRECTORY))
+    }
+
+    /// Create a provider reading & writing the site in the current directory.
+    ///
+    /// # Returns
+    ///
+    /// A provider outputting to the site's output directory, for the site's environment.
+    fn provider(&self) -> FsProvider {
+        let provider = FsProvider::new().with_output_directory(self.output_directory());
+        match &self.environment {
+            Some(environment) => provider.with_environment(environment),
+            None => provider,
+        }
+    }
+}
+
+/// Read the defaults for command-line options of the site in the current directory, then begin logging.
+///
+/// # Arguments
+///
+/// * `verbosity` - The number of times `-v` was given; if none, the level of log output is taken from `vox.toml`.
+///
+/// * `log_file` - A file to append all logs to, if given.
+///
+/// * `overrides` - The options given on the command line which take precedence over `vox.toml`.
+///
+/// # Returns
+///
+/// The defaults in `vox.toml`, with the output directory & environment given on the command line in place of their own.
+fn init_site(
+    verbosity: u8,
+    log_file: Option<&Path>,
+    overrides: &SiteOverrides,
+) -> miette::Result<ProjectConfig> {
+    let mut config = ProjectConfig::read()?;
+    if let Some(output) = &overrides.output_directory {
+        config.output = Some(output.clone());
+    }
+    if let Some(environment) = &overrides.environment {
+        config.environment = Some(environment.clone());
+    }
+    let verbosity = match verbosity {
+        0 => config.verbosity.unwrap_or(DEFAULT_VERBOSITY),
+        verbosity => verbosity,
+    };
+    init_logging(verbosity, log_file)?;
+    Ok(config)
+}
+
+/// Options given on the command line which take precedence over those in `vox.toml` for every command.
+struct SiteOverrides {
+    /// The directory to output the site to.
+    output_directory: Option<PathBuf>,
+    /// The environment the site is built for.
+    environment: Option<String>,
 }
 
-#[tokio::main(flavor = "multi_thread")]
-async fn main() -> miette::Result<()> {
+#[derive(Deserialize, Default)]
+/// Settings for deploying a site, defined by the `deploy` table in `global.toml`.
+struct DeploySettings {
+    /// The remote host to deploy to.
+    host: Option<String>,
+    /// The user to connect to the remote host as.
+    user: Option<String>,
+    /// The port to connect to the remote host on.
+    port: Option<u16>,
+    /// The path on the remote host to deploy to.
+    path: Option<String>,
+    /// Whether or not to delete remote files no longer present in the output.
+    #[serde(default)]
+    delete_orphans: bool,
+    /// The Git repository to push to when deploying to GitHub Pages; either a remote name or URL.
+    repository: Option<String>,
+    /// The branch to push to when deploying to GitHub Pages.
+    branch: Option<String>,
+    /// The custom domain of the site when deploying to GitHub Pages.
+    cname: Option<String>,
+    /// The S3 bucket to deploy to.
+    bucket: Option<String>,
+    /// The prefix of keys in the S3 bucket to deploy to.
+    prefix: Option<String>,
+    /// The AWS region of the S3 bucket.
+    region: Option<String>,
+    /// The CloudFront distribution to invalidate changed paths in after deploying to S3.
+    cloudfront_distribution: Option<String>,
+    /// The `Cache-Control` header of HTML, XML, and JSON files deployed to S3.
+    document_cache_control: Option<String>,
+    /// The `Cache-Control` header of all other files deployed to S3.
+    cache_control: Option<String>,
+}
+
+fn main() -> miette::Result<()> {
     miette::set_panic_hook();
     let cli = Cli::parse();
+    let mut runtime = tokio::runtime::Builder::new_multi_thread();
+    runtime.worker_threads(worker_count(cli.jobs));
+    runtime
+        .enable_all()
+        .build()
+        .into_diagnostic()?
+        .block_on(run(cli))
+}
+
+/// Get the number of worker threads to use.
+///
+/// # Arguments
+///
+/// * `jobs` - The maximum number of worker threads, if limited.
+///
+/// # Returns
+///
+/// The given number of worker threads, or the number of CPU cores if not limited.
+fn worker_count(jobs: Option<NonZeroUsize>) -> usize {
+    jobs.or(std::thread::available_parallelism().ok())
+        .map_or(1, NonZeroUsize::get)
+}
+
+async fn run(cli: Cli) -> miette::Result<()> {
+    let workers = worker_count(cli.jobs);
+    // The log file is relative to where Vox is invoked, not to the site directory.
+    let log_file = cli
+        .log_file
+        .map(std::path::absolute)
+        .transpose()
+        .into_diagnostic()?;
+    let overrides = SiteOverrides {
+        output_directory: cli.output_directory,
+        environment: cli.environment,
+    };
     match cli.command {
         Some(Commands::Build {
             path,
             watch,
             visualise_dag,
             generate_syntax_css,
+            json,
+            remove_orphans,
+            offline,
+            summary,
+            retry,
+            retry_delay,
+            retry_backoff,
+            archive,
+            audit,
         }) => {
+            // The archive is relative to where Vox is invoked, not to the site directory.
+            let archive = archive
+                .map(std::path::absolute)
+                .transpose()
+                .into_diagnostic()?;
             if let Some(path) = path {
                 std::env::set_current_dir(path).into_diagnostic()?;
             }
-            let verbosity_level = match cli.verbosity {
-                0 => Level::ERROR,
-                1 => Level::WARN,
-                2 => Level::INFO,
-                3 => Level::DEBUG,
-                4 => Level::TRACE,
-                _ => Level::TRACE,
-            };
-            let mut subscriber_builder = tracing_subscriber::fmt()
-                .with_env_filter(format!("vox={}", verbosity_level))
-                .pretty()
-                .with_file(false)
-                .with_line_number(false);
-            if cli.verbosity >= 3 {
-                subscriber_builder = subscriber_builder
-                    .with_thread_ids(true)
-                    .with_thread_names(true)
-                    .with_file(true)
-                    .with_line_number(true);
-            }
-            subscriber_builder.init();
+            let config = init_site(cli.verbosity, log_file.as_deref(), &overrides)?;
+            // A site being archived is not watched, as the archive is only written once the build finishes.
+            let watch = archive.is_none() && watch.or(config.build.watch).unwrap_or(false);
+            COLLECTING_WARNINGS.store(summary.is_some(), Ordering::Relaxed);
             info!("Building … ");
+            let mut retries = Retries::new(retry, retry_delay, retry_backoff, watch);
+            let provider = config.provider();
             loop {
-                let building = build(watch, visualise_dag, generate_syntax_css);
+                let building = build(
+                    &provider,
+                    watch,
+                    visualise_dag,
+                    generate_syntax_css,
+                    json,
+                    remove_orphans,
+                    offline,
+                    &audit,
+                    summary,
+                    Some(&mut retries),
+                );
                 match building {
                     Ok(_) => {
                         if !watch {
                             break;
                         }
                     }
-                    Err(err) => {
-                        error!("Building failed: {:#?}", err);
-                        info!("Retrying in 5 seconds … ");
-                        sleep(Duration::from_secs(5)).await;
-                        continue;
-                    }
+                    Err(err) => retries.retry(err)?,
+                }
+            }
+            if let Some(archive) = archive {
+                info!("Archiving the output to `{}` … ", archive.to_string_lossy());
+                let files = vox::archive::archive_directory(&config.output_directory(), &archive)?;
+                info!("Archived {} files … ", files);
+            }
+        }
+        Some(Commands::Serve {
+            path,
+            watch,
+            port,
+            on_demand: true,
+            offline,
+            ..
+        }) => {
+            if let Some(path) = path {
+                std::env::set_current_dir(path).into_diagnostic()?;
+            }
+            let config = init_site(cli.verbosity, log_file.as_deref(), &overrides)?;
+            let watch = watch.or(config.serve.watch).unwrap_or(false);
+            let port = port.or(config.serve.port).unwrap_or(DEFAULT_PORT);
+            // Pages rendered on demand use the remote data fetched before serving begins.
+            let provider = config.provider();
+            let output_directory = config.output_directory();
+            refresh_remote_data(&provider, offline)?;
+            match themed_provider(provider.clone())? {
+                Some(themed) => {
+                    serve_on_demand(themed, output_directory, watch, port, workers, offline).await?
+                }
+                None => {
+                    serve_on_demand(provider, output_directory, watch, port, workers, offline)
+                        .await?
                 }
             }
         }
@@ -124,54 +699,60 @@ async fn main() -> miette::Result<()> {
             port,
             visualise_dag,
             generate_syntax_css,
+            json,
+            remove_orphans,
+            offline,
+            summary,
+            retry,
+            retry_delay,
+            retry_backoff,
+            ..
         }) => {
             if let Some(path) = path {
                 std::env::set_current_dir(path).into_diagnostic()?;
             }
-            let verbosity_level = match cli.verbosity {
-                0 => Level::ERROR,
-                1 => Level::WARN,
-                2 => Level::INFO,
-                3 => Level::DEBUG,
-                4 => Level::TRACE,
-                _ => Level::TRACE,
-            };
-            let mut subscriber_builder = tracing_subscriber::fmt()
-                .with_env_filter(format!("vox={}", verbosity_level))
-                .pretty()
-                .with_file(false)
-                .with_line_number(false);
-            if cli.verbosity >= 3 {
-                subscriber_builder = subscriber_builder
-                    .with_thread_ids(true)
-                    .with_thread_names(true)
-                    .with_file(true)
-                    .with_line_number(true);
-            }
-            subscriber_builder.init();
-            let build_loop = tokio::spawn(async move {
+            let config = init_site(cli.verbosity, log_file.as_deref(), &overrides)?;
+            let watch = watch.or(config.serve.watch).unwrap_or(false);
+            let port = port.or(config.serve.port).unwrap_or(DEFAULT_PORT);
+            COLLECTING_WARNINGS.store(summary.is_some(), Ordering::Relaxed);
+            let provider = config.provider();
+            let output_directory = config.output_directory();
+            let built_provider = provider.clone();
+            // Builds block, so they are run on their own thread, leaving the worker threads free to serve the site.
+            let build_loop = tokio::task::spawn_blocking(move || {
+                let mut retries = Retries::new(retry, retry_delay, retry_backoff, watch);
                 loop {
-                    let building = build(watch, visualise_dag, generate_syntax_css);
+                    let building = build(
+                        &built_provider,
+                        watch,
+                        visualise_dag,
+                        generate_syntax_css,
+                        json,
+                        remove_orphans,
+                        offline,
+                        &[],
+                        summary,
+                        Some(&mut retries),
+                    );
                     match building {
                         Ok(_) => {
                             if !watch {
                                 break;
                             }
                         }
-                        Err(err) => {
-                            error!("Building failed: {:#?}", err);
-                            info!("Retrying in 5 seconds … ");
-                            sleep(Duration::from_secs(5)).await;
-                            continue;
-                        }
+                        Err(err) => retries.retry(err)?,
                     }
                 }
+                Ok::<(), miette::Report>(())
             });
             let serve_loop = tokio::spawn(async move {
                 loop {
+                    let provider = provider.clone();
+                    let output_directory = output_directory.clone();
                     let serving = tokio::spawn(
-                        HttpServer::new(|| {
-                            let mut service = actix_files::Files::new("/", "output")
+                        HttpServer::new(move || {
+                            let not_found = output_directory.join("404.html");
+                            let mut service = actix_files::Files::new("/", &output_directory)
                                 .prefer_utf8(true)
                                 .use_hidden_files()
                                 .use_etag(true)
@@ -179,16 +760,21 @@ async fn main() -> miette::Result<()> {
                                 .show_files_listing()
                                 .redirect_to_slash_directory();
                             service = service.index_file("index.html");
-                            service = service.default_handler(|req: ServiceRequest| {
+                            service = service.default_handler(move |req: ServiceRequest| {
                                 let (http_req, _payload) = req.into_parts();
-                                async {
-                                    let response = NamedFile::open("output/404.html")?
-                                        .into_response(&http_req);
+                                let not_found = not_found.clone();
+                                async move {
+                                    let response =
+                                        NamedFile::open(not_found)?.into_response(&http_req);
                                     Ok(ServiceResponse::new(http_req, response))
                                 }
                             });
-                            App::new().service(service)
+                            App::new()
+                                .app_data(web::Data::new(provider.clone()))
+                                .route("/__vox/search", web::get().to(search))
+                                .service(service)
                         })
+                        .workers(workers)
                         .bind((Ipv4Addr::UNSPECIFIED, port))
                         .unwrap()
                         .run(),
@@ -217,92 +803,1400 @@ async fn main() -> miette::Result<()> {
                     }
                 }
             });
-            build_loop.await.into_diagnostic()?;
+            build_loop.await.into_diagnostic()??;
             serve_loop.await.into_diagnostic()?;
         }
+        Some(Commands::Clean {
+            path,
+            orphans,
+            all: _,
+            yes,
+            visualise_dag,
+            generate_syntax_css,
+            json,
+        }) => {
+            if let Some(path) = path {
+                std::env::set_current_dir(path).into_diagnostic()?;
+            }
+            let config = init_site(cli.verbosity, log_file.as_deref(), &overrides)?;
+            if orphans {
+                info!("Building to find orphaned output … ");
+                build(
+                    &config.provider(),
+                    false,
+                    visualise_dag,
+                    generate_syntax_css,
+                    json,
+                    true,
+                    false,
+                    &[],
+                    None,
+                    None,
+                )?;
+            } else {
+                let directories = [config.output_directory(), PathBuf::from(".vox")]
+                    .into_iter()
+                    .filter(|x| x.is_dir())
+                    .collect::<Vec<_>>();
+                if directories.is_empty() {
+                    return Ok(());
+                }
+                let names = directories
+                    .iter()
+                    .map(|x| x.to_string_lossy())
+                    .collect::<Vec<_>>();
+                if !yes && !confirm(&format!("Remove {}?", names.join(" & ")))? {
+                    info!("Nothing removed … ");
+                    return Ok(());
+                }
+                for directory in directories.iter() {
+                    info!("Removing `{}` … ", directory.to_string_lossy());
+                    std::fs::remove_dir_all(directory).into_diagnostic()?;
+                }
+            }
+        }
+        Some(Commands::Deploy {
+            path,
+            github_pages,
+            s3,
+            rsync,
+        }) => {
+            if let Some(path) = path {
+                std::env::set_current_dir(path).into_diagnostic()?;
+            }
+            let config = init_site(cli.verbosity, log_file.as_deref(), &overrides)?;
+            let output_directory = config.output_directory();
+            if !output_directory.is_dir() {
+                return Err(miette::miette!(
+                    "No output found; please build the site before deploying."
+                ));
+            }
+            let settings = deploy_settings()?;
+            let target = if github_pages {
+                DeployTarget::GithubPages
+            } else if s3 {
+                DeployTarget::S3
+            } else if rsync {
+                DeployTarget::Rsync
+            } else {
+                config.deploy.target.unwrap_or_default()
+            };
+            match target {
+                DeployTarget::GithubPages => deploy_github_pages(&settings, &config.provider())?,
+                DeployTarget::S3 => deploy_s3(&settings, &output_directory)?,
+                DeployTarget::Rsync => deploy_rsync(&settings, &output_directory)?,
+            }
+        }
+        Some(Commands::Migrate { source }) => {
+            init_site(cli.verbosity, log_file.as_deref(), &overrides)?;
+            let report = match source {
+                MigrationSource::Hugo {
+                    source,
+                    destination,
+                } => {
+                    vox::migrate::migrate_hugo(&source, &destination.unwrap_or(PathBuf::from(".")))?
+                }
+            };
+            print_migration_report(report);
+        }
+        Some(Commands::Import { source }) => {
+            init_site(cli.verbosity, log_file.as_deref(), &overrides)?;
+            let report = match source {
+                ImportSource::Wxr {
+                    export,
+                    destination,
+                    no_images,
+                } => vox::migrate::import_wxr(
+                    &export,
+                    &destination.unwrap_or(PathBuf::from(".")),
+                    !no_images,
+                )?,
+                ImportSource::Rss {
+                    feed,
+                    destination,
+                    no_images,
+                } => vox::migrate::import_rss(
+                    &feed,
+                    &destination.unwrap_or(PathBuf::from(".")),
+                    !no_images,
+                )?,
+            };
+            print_migration_report(report);
+        }
+        Some(Commands::Bench {
+            path,
+            cold,
+            incremental,
+            offline,
+        }) => {
+            if let Some(path) = path {
+                std::env::set_current_dir(path).into_diagnostic()?;
+            }
+            let config = init_site(cli.verbosity, log_file.as_deref(), &overrides)?;
+            // Pages are changed while benchmarking, so the site is benchmarked on a copy of it.
+            let site_directory = std::env::current_dir().into_diagnostic()?;
+            let bench_directory =
+                std::env::temp_dir().join(format!("vox-bench-{}", std::process::id()));
+            info!(
+                "Copying the site to `{}` … ",
+                bench_directory.to_string_lossy()
+            );
+            let benched = copy_site(
+                &site_directory,
+                &bench_directory,
+                &config.output_directory(),
+            )
+            .and_then(|_| std::env::set_current_dir(&bench_directory).into_diagnostic())
+            .and_then(|_| {
+                // Hooks may publish or otherwise act upon the site, and are not run when benchmarking.
+                let options = BuildOptions {
+                    offline,
+                    skip_hooks: true,
+                    ..Default::default()
+                };
+                let provider = config.provider();
+                match themed_provider(provider.clone())? {
+                    Some(themed) => bench(themed, options, cold, incremental),
+                    None => bench(provider, options, cold, incremental),
+                }
+            });
+            std::env::set_current_dir(&site_directory).into_diagnostic()?;
+            if let Err(err) = std::fs::remove_dir_all(&bench_directory) {
+                warn!(
+                    "Unable to remove `{}`: {}",
+                    bench_directory.to_string_lossy(),
+                    err
+                );
+            }
+            benched?;
+        }
+        Some(Commands::Export {
+            path,
+            output,
+            collection,
+            offline,
+        }) => {
+            // The export is relative to where Vox is invoked, not to the site directory.
+            let output = std::path::absolute(output).into_diagnostic()?;
+            if let Some(path) = path {
+                std::env::set_current_dir(path).into_diagnostic()?;
+            }
+            let config = init_site(cli.verbosity, log_file.as_deref(), &overrides)?;
+            let options = BuildOptions {
+                offline,
+                ..Default::default()
+            };
+            let provider = config.provider();
+            let exported = match themed_provider(provider.clone())? {
+                Some(themed) => export(themed, options, collection.as_deref())?,
+                None => export(provider, options, collection.as_deref())?,
+            };
+            std::fs::write(&output, exported).into_diagnostic()?;
+            info!("Exported to `{}` … ", output.to_string_lossy());
+        }
+        Some(Commands::Eval {
+            template,
+            path,
+            page,
+        }) => {
+            let template = match template.filter(|x| x != "-") {
+                Some(template) => template,
+                None => std::io::read_to_string(std::io::stdin()).into_diagnostic()?,
+            };
+            if let Some(path) = path {
+                std::env::set_current_dir(path).into_diagnostic()?;
+            }
+            let config = init_site(cli.verbosity, log_file.as_deref(), &overrides)?;
+            let provider = config.provider();
+            let rendered = match themed_provider(provider.clone())? {
+                Some(themed) => themed.evaluate(&template, page.as_deref())?,
+                None => provider.evaluate(&template, page.as_deref())?,
+            };
+            println!("{}", rendered.trim_end_matches('\n'));
+        }
+        Some(Commands::Theme { path, action }) => {
+            if let Some(path) = path {
+                std::env::set_current_dir(path).into_diagnostic()?;
+            }
+            init_site(cli.verbosity, log_file.as_deref(), &overrides)?;
+            manage_themes(action)?;
+        }
         None => println!("Vox {}", VERSION),
     };
     Ok(())
 }
 
-fn build(watch: bool, visualise_dag: bool, generate_syntax_css: bool) -> miette::Result<()> {
-    let parser = FS_PROVIDER.create_liquid_parser()?;
-    let global = FS_PROVIDER.get_global_context()?;
-    let (mut dag, mut pages, mut layouts) = FS_PROVIDER.generate_dag()?;
+/// Install, update, or list the themes installed into the site in the current directory.
+///
+/// # Arguments
+///
+/// * `action` - What to do with the site's themes.
+fn manage_themes(action: ThemeAction) -> miette::Result<()> {
+    let provider = FsProvider::new();
+    let mut lock = ThemeLock::read(&provider)?;
+    match action {
+        ThemeAction::Install { url, name } => {
+            let name = name.unwrap_or_else(|| theme_name(&url));
+            if lock.get(&name).is_some() {
+                return Err(miette::miette!(
+                    "A theme named `{}` is already installed; run `vox theme update {}` to update it.",
+                    name,
+                    name
+                ));
+            }
+            let theme = install_theme(&provider, &name, &url)?;
+            info!(
+                "Installed `{}` at {}.",
+                theme.name,
+                short_revision(&theme.revision)
+            );
+            lock.insert(theme);
+            provider.write_file(LOCKFILE, lock.to_toml()?)?;
+            match get_theme(&provider)? {
+                None => {
+                    let global = provider.read_to_string("global.toml").unwrap_or_default();
+                    provider.write_file(
+                        "global.toml",
+                        format!("theme = \"{}\"\n{}", name, global),
+                    )?;
+                    info!("The site now uses `{}` as its theme.", name);
+                }
+                Some(current) if current != name => info!(
+                    "The site uses `{}` as its theme; set `theme = \"{}\"` in `global.toml` to use `{}` instead.",
+                    current, name, name
+                ),
+                Some(_) => (),
+            }
+        }
+        ThemeAction::Update { name } => {
+            let themes = match name {
+                Some(name) => vec![lock
+                    .get(&name)
+                    .cloned()
+                    .ok_or(miette::miette!("No theme named `{}` is installed.", name))?],
+                None => lock.themes.clone(),
+            };
+            if themes.is_empty() {
+                info!("No themes are installed.");
+            }
+            for theme in themes {
+                let updated = install_theme(&provider, &theme.name, &theme.url)?;
+                if updated.revision == theme.revision {
+                    info!("`{}` is up to date.", theme.name);
+                } else {
+                    info!(
+                        "Updated `{}` from {} to {}.",
+                        theme.name,
+                        short_revision(&theme.revision),
+                        short_revision(&updated.revision)
+                    );
+                }
+                lock.insert(updated);
+            }
+            provider.write_file(LOCKFILE, lock.to_toml()?)?;
+        }
+        ThemeAction::List => {
+            let current = get_theme(&provider)?;
+            if lock.themes.is_empty() {
+                info!("No themes are installed.");
+            }
+            for theme in lock.themes {
+                let marker = match current.as_deref() == Some(theme.name.as_str()) {
+                    true => "*",
+                    false => " ",
+                };
+                println!(
+                    "{} {} {} {}",
+                    marker,
+                    theme.name,
+                    short_revision(&theme.revision),
+                    theme.url
+                );
+            }
+        }
+    }
+    Ok(())
+}
 
-    // Write the initial site to the output directory.
-    info!("Performing initial build … ");
-    let (_updated_pages, updated_dag) = FS_PROVIDER.generate_site(
-        parser.clone(),
-        global.0.clone(),
-        global.1,
-        dag,
+/// Abbreviate the hash of a commit.
+///
+/// # Arguments
+///
+/// * `revision` - The hash of the commit.
+///
+/// # Returns
+///
+/// The first seven characters of the hash.
+fn short_revision(revision: &str) -> &str {
+    &revision[..revision.len().min(7)]
+}
+
+fn print_migration_report(report: vox::migrate::MigrationReport) {
+    info!(
+        "Wrote {} pages and {} layouts.",
+        report.pages, report.layouts
+    );
+    for (shortcode, pages) in report.unsupported_shortcodes {
+        warn!(
+            "Unsupported shortcode `{}` used in: {}",
+            shortcode,
+            pages
+                .iter()
+                .map(|x| format!("`{}`", x.to_string_lossy()))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+    for hint in report.hints {
+        info!("{}", hint);
+    }
+}
+
+#[derive(Deserialize)]
+/// The parameters of a request to the search endpoint.
+struct SearchParameters {
+    /// The search query.
+    q: String,
+    /// The maximum number of results.
+    limit: Option<usize>,
+}
+
+async fn search(
+    provider: web::Data<FsProvider>,
+    parameters: web::Query<SearchParameters>,
+) -> HttpResponse {
+    let Ok(Some(settings)) = provider.get_search_settings() else {
+        return HttpResponse::NotFound()
+            .body("No `search` settings found in `global.toml`; no search index was generated.");
+    };
+    let documents = match provider
+        .read_to_string(settings.output_path())
+        .and_then(|index| serde_json::from_str::<Vec<SearchDocument>>(&index).into_diagnostic())
+    {
+        Ok(documents) => documents,
+        Err(err) => {
+            error!("Unable to read the search index: {:#?}", err);
+            return HttpResponse::InternalServerError().body("Unable to read the search index.");
+        }
+    };
+    HttpResponse::Ok().json(query_index(
+        &documents,
+        &parameters.q,
+        parameters.limit.unwrap_or(10),
+    ))
+}
+
+fn init_logging(verbosity: u8, log_file: Option<&Path>) -> miette::Result<()> {
+    let verbosity_level = match verbosity {
+        0 => Level::ERROR,
+        1 => Level::WARN,
+        2 => Level::INFO,
+        3 => Level::DEBUG,
+        4 => Level::TRACE,
+        _ => Level::TRACE,
+    };
+    let mut console_layer = tracing_subscriber::fmt::layer()
+        .pretty()
+        .with_writer(std::io::stderr)
+        .with_file(false)
+        .with_line_number(false);
+    if verbosity >= 3 {
+        console_layer = console_layer
+            .with_thread_ids(true)
+            .with_thread_names(true)
+            .with_file(true)
+            .with_line_number(true);
+    }
+    let file_layer = match log_file {
+        Some(log_file) => {
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(log_file)
+                .map_err(|err| {
+                    miette::miette!(
+                        "Unable to open the log file `{}`: {}",
+                        log_file.display(),
+                        err
+                    )
+                })?;
+            Some(
+                tracing_subscriber::fmt::layer()
+                    .with_ansi(false)
+                    .with_writer(Mutex::new(file))
+                    .with_thread_ids(true)
+                    .with_thread_names(true)
+                    .with_file(true)
+                    .with_line_number(true)
+                    .with_filter(EnvFilter::new(format!("vox={}", Level::TRACE))),
+            )
+        }
+        None => None,
+    };
+    tracing_subscriber::registry()
+        .with(WarningCollector)
+        .with(console_layer.with_filter(EnvFilter::new(format!("vox={}", verbosity_level))))
+        .with(file_layer)
+        .init();
+    Ok(())
+}
+
+/// Read the settings for deploying the site in the current directory.
+///
+/// # Returns
+///
+/// The settings in the `deploy` table of `global.toml`, or the default settings if there is no such table.
+fn deploy_settings() -> miette::Result<DeploySettings> {
+    let global = FsProvider::new()
+        .read_to_string("global.toml")?
+        .parse::<toml::Table>()
+        .into_diagnostic()?;
+    match global.get("deploy") {
+        Some(settings) => settings.clone().try_into().into_diagnostic(),
+        None => Ok(DeploySettings::default()),
+    }
+}
+
+/// Hash the contents of each file in the output directory, so that deployments can find the files which changed.
+///
+/// # Arguments
+///
+/// * `output_directory` - The directory the site is output to.
+///
+/// # Returns
+///
+/// A hash of each file's contents, by its path relative to the output directory.
+fn hash_output(output_directory: &Path) -> miette::Result<BTreeMap<String, String>> {
+    let mut hashes = BTreeMap::new();
+    let pattern = output_directory.join("**").join("*");
+    for file in glob::glob(&pattern.to_string_lossy())
+        .into_diagnostic()?
+        .filter_map(Result::ok)
+        .filter(|x| x.is_file())
+    {
+        let key = file
+            .strip_prefix(output_directory)
+            .into_diagnostic()?
+            .to_string_lossy()
+            .replace('\\', "/");
+        let contents = std::fs::read(&file).into_diagnostic()?;
+        hashes.insert(key, blake3::hash(&contents).to_hex().to_string());
+    }
+    Ok(hashes)
+}
+
+/// The manifests of deployments to remote hosts, relative to the site's directory.
+const RSYNC_MANIFEST_PATH: &str = ".vox/rsync-manifest.toml";
+
+/// Deploy the built site to a remote host with rsync over SSH.
+/// Only files whose contents changed since the last deployment to the same destination are transferred.
+///
+/// # Arguments
+///
+/// * `settings` - The settings for deploying the site.
+///
+/// * `output_directory` - The directory the site is output to.
+fn deploy_rsync(settings: &DeploySettings, output_directory: &Path) -> miette::Result<()> {
+    let host = settings.host.as_ref().ok_or(miette::miette!(
+        "No `deploy.host` setting found in `global.toml`."
+    ))?;
+    let path = settings.path.as_ref().ok_or(miette::miette!(
+        "No `deploy.path` setting found in `global.toml`."
+    ))?;
+    let destination = match &settings.user {
+        Some(user) => format!("{}@{}:{}", user, host, path),
+        None => format!("{}:{}", host, path),
+    };
+    // Each destination has a manifest of the contents of the files last deployed there.
+    let mut manifests: BTreeMap<String, BTreeMap<String, String>> =
+        match std::fs::read_to_string(RSYNC_MANIFEST_PATH) {
+            Ok(manifests) => toml::from_str(&manifests).unwrap_or_default(),
+            Err(_) => BTreeMap::new(),
+        };
+    let old_manifest = manifests.remove(&destination).unwrap_or_default();
+    let new_manifest = hash_output(output_directory)?;
+    let changed = new_manifest
+        .iter()
+        .filter(|(key, hash)| old_manifest.get(*key) != Some(hash))
+        .map(|(key, _)| key.clone())
+        .collect::<Vec<_>>();
+    let removed = if settings.delete_orphans {
+        old_manifest
+            .keys()
+            .filter(|key| !new_manifest.contains_key(*key))
+            .cloned()
+            .collect::<Vec<_>>()
+    } else {
+        Vec::new()
+    };
+    if changed.is_empty() && removed.is_empty() {
+        info!(
+            "Nothing has changed since the last deployment to `{}` … ",
+            destination
+        );
+        return Ok(());
+    }
+    let mut rsync = Command::new("rsync");
+    // Only the files listed are transferred; listed files missing from the output are deleted from the remote host.
+    rsync.args([
+        "--compress",
+        "--human-readable",
+        "--from0",
+        "--files-from=-",
+    ]);
+    if !removed.is_empty() {
+        rsync.arg("--delete-missing-args");
+    }
+    if let Some(port) = settings.port {
+        rsync.args(["--rsh", &format!("ssh -p {}", port)]);
+    }
+    rsync.arg(output_directory);
+    rsync.arg(&destination);
+    rsync.stdin(std::process::Stdio::piped());
+    info!(
+        "Deploying {} changed files to `{}` … ",
+        changed.len(),
+        destination
+    );
+    let mut child = rsync.spawn().into_diagnostic()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        use std::io::Write;
+        for key in changed.iter().chain(removed.iter()) {
+            stdin.write_all(key.as_bytes()).into_diagnostic()?;
+            stdin.write_all(b"\0").into_diagnostic()?;
+        }
+    }
+    let status = child.wait().into_diagnostic()?;
+    if !status.success() {
+        return Err(miette::miette!(
+            "Deploying failed: rsync exited with {}.",
+            status
+        ));
+    }
+    // Removed files remain in the manifest if they were not deleted.
+    let mut manifest = new_manifest;
+    if !settings.delete_orphans {
+        for (key, hash) in old_manifest {
+            manifest.entry(key).or_insert(hash);
+        }
+    }
+    manifests.insert(destination.clone(), manifest);
+    std::fs::create_dir_all(".vox").into_diagnostic()?;
+    std::fs::write(
+        RSYNC_MANIFEST_PATH,
+        toml::to_string(&manifests).into_diagnostic()?,
+    )
+    .into_diagnostic()?;
+    info!("Deployed to `{}` … ", destination);
+    Ok(())
+}
+
+async fn serve_on_demand<P: VoxProvider + Send + Sync + 'static>(
+    provider: P,
+    output_directory: PathBuf,
+    watch: bool,
+    port: u16,
+    workers: usize,
+    offline: bool,
+) -> miette::Result<()> {
+    // A theme's assets are copied into the output directory, so that they are served along with it.
+    provider.copy_assets()?;
+    // Files not output by pages, such as assets, are served from the output directory.
+    let mut service = VoxService::new(provider)
+        .with_caching()
+        .with_static_files(output_directory);
+    if offline {
+        service = service.offline();
+    }
+    if watch {
+        let watched_service = service.clone();
+        std::thread::spawn(move || {
+            let changes = match watched_service.provider().watch() {
+                Ok(Some(changes)) => changes,
+                Ok(None) => return,
+                Err(err) => {
+                    error!("Watching failed: {:#?}", err);
+                    return;
+                }
+            };
+            for change in changes {
+                let exclusions = match watched_service.provider().get_exclusions() {
+                    Ok(exclusions) => exclusions,
+                    Err(err) => {
+                        error!("Unable to read excluded paths: {:#?}", err);
+                        continue;
+                    }
+                };
+                let change = change.without_excluded(&exclusions);
+                if change.paths.is_empty() {
+                    continue;
+                }
+                if let Err(err) = watched_service.invalidate(&change) {
+                    error!("Unable to discard changed pages: {:#?}", err);
+                }
+            }
+        });
+    }
+    println!("Serving on {}:{} … ", Ipv4Addr::UNSPECIFIED, port);
+    HttpServer::new(move || {
+        App::new()
+            .route("/__vox/search", web::get().to(search))
+            .service(service.clone().scope(""))
+    })
+    .workers(workers)
+    .bind((Ipv4Addr::UNSPECIFIED, port))
+    .into_diagnostic()?
+    .run()
+    .await
+    .into_diagnostic()?;
+    Ok(())
+}
+
+/// The longest time waited before retrying a failed build when the time is doubled after each failure, in seconds.
+const MAX_RETRY_BACKOFF: u64 = 600;
+
+/// Decides whether, and when, failed builds are retried.
+struct Retries {
+    /// The number of times a failed build is retried, or `None` if failed builds are always retried.
+    limit: Option<usize>,
+    /// The number of seconds waited before retrying a failed build.
+    delay: u64,
+    /// Whether or not the time waited is doubled after each consecutive failed build.
+    backoff: bool,
+    /// The number of consecutive failed builds.
+    failures: usize,
+}
+
+impl Retries {
+    /// Create a policy for retrying failed builds.
+    ///
+    /// # Arguments
+    ///
+    /// * `retry` - The number of times to retry a failed build, if given.
+    ///
+    /// * `delay` - The number of seconds to wait before retrying a failed build.
+    ///
+    /// * `backoff` - Whether or not to double the time waited after each consecutive failed build.
+    ///
+    /// * `watch` - Whether or not the site is being watched for changes.
+    ///
+    /// # Returns
+    ///
+    /// A policy retrying failed builds the given number of times, or, by default, indefinitely when watching for changes and never otherwise.
+    fn new(retry: Option<usize>, delay: u64, backoff: bool, watch: bool) -> Self {
+        Self {
+            limit: retry.or(if watch { None } else { Some(0) }),
+            delay,
+            backoff,
+            failures: 0,
+        }
+    }
+
+    /// Record that the site was built, so that later failures are retried as if none came before.
+    fn succeeded(&mut self) {
+        self.failures = 0;
+    }
+
+    /// Wait before retrying a failed build.
+    ///
+    /// # Arguments
+    ///
+    /// * `err` - Why the build failed.
+    ///
+    /// # Returns
+    ///
+    /// The error, if the build is not to be retried.
+    fn retry(&mut self, err: miette::Report) -> miette::Result<()> {
+        if self.limit.is_some_and(|limit| self.failures >= limit) {
+            return Err(err);
+        }
+        error!("Building failed: {:#?}", err);
+        let delay = match self.backoff {
+            true => self
+                .delay
+                .saturating_mul(1 << self.failures.min(32))
+                .min(MAX_RETRY_BACKOFF.max(self.delay)),
+            false => self.delay,
+        };
+        self.failures += 1;
+        match self.limit {
+            Some(limit) => info!(
+                "Retrying in {} seconds ({} of {}) … ",
+                delay, self.failures, limit
+            ),
+            None => info!("Retrying in {} seconds … ", delay),
+        }
+        std::thread::sleep(Duration::from_secs(delay));
+        Ok(())
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn build(
+    provider: &FsProvider,
+    watch: bool,
+    visualise_dag: bool,
+    generate_syntax_css: bool,
+    json: bool,
+    remove_orphans: bool,
+    offline: bool,
+    audits: &[Audit],
+    summary: Option<SummaryFormat>,
+    retries: Option<&mut Retries>,
+) -> miette::Result<()> {
+    let options = BuildOptions {
         visualise_dag,
         generate_syntax_css,
-    )?;
-    dag = updated_dag;
+        generate_json: json,
+        remove_orphans,
+        offline,
+        audit_accessibility: audits.contains(&Audit::Accessibility),
+        validate_html: audits.contains(&Audit::Html),
+        ..Default::default()
+    };
+    match themed_provider(provider.clone())? {
+        Some(themed) => build_site(
+            Site::new(themed).with_options(options),
+            watch,
+            summary,
+            retries,
+        ),
+        None => build_site(
+            Site::new(provider.clone()).with_options(options),
+            watch,
+            summary,
+            retries,
+        ),
+    }
+}
+
+fn build_site<P: VoxProvider>(
+    mut site: Site<P>,
+    watch: bool,
+    summary_format: Option<SummaryFormat>,
+    retries: Option<&mut Retries>,
+) -> miette::Result<()> {
+    let print = |summary: &BuildSummary| {
+        if let Some(format) = summary_format {
+            print_summary(summary, format);
+        }
+    };
+    // Write the initial site to the output directory.
+    info!("Performing initial build … ");
+    site.build()?;
+    if let Some(summary) = site.summary() {
+        print(summary);
+    }
+    if let Some(retries) = retries {
+        retries.succeeded();
+    }
 
     // Watch for changes to the site.
     if watch {
-        let current_path = std::env::current_dir().into_diagnostic()?;
-        let output_path = current_path.join("output");
-        let git_path = current_path.join(".git");
-        let (sender, receiver) = channel();
-        let mut debouncer =
-            new_debouncer(Duration::from_secs(1), None, sender).into_diagnostic()?;
-        info!("Watching {:?} … ", current_path);
-        debouncer
-            .watch(&current_path, RecursiveMode::Recursive)
-            .into_diagnostic()?;
-
-        while let Ok(events) = receiver.recv().into_diagnostic()? {
-            // Changes to the output directory or version control are irrelevant.
-            if !events.iter().any(|event| {
-                event
-                    .paths
-                    .iter()
-                    .any(|path| !path.starts_with(&output_path) && !path.starts_with(&git_path))
-            }) {
-                continue;
-            }
-            let global_or_snippets_changed = events.iter().any(|event| {
-                event.paths.iter().any(|path| {
-                    path.strip_prefix(current_path.clone())
-                        .unwrap_or(path)
-                        .starts_with("global.toml")
-                        || path
-                            .strip_prefix(current_path.clone())
-                            .unwrap_or(path)
-                            .starts_with("snippets/")
-                })
-            });
-            trace!(
-                "Changes detected: {:#?} … ",
-                events
-                    .into_iter()
-                    .map(|event| event
-                        .paths
-                        .clone()
-                        .into_iter()
-                        .map(|path| {
-                            path.strip_prefix(current_path.clone())
-                                .unwrap_or(&path)
-                                .to_path_buf()
-                        })
-                        .collect::<Vec<_>>())
-                    .collect::<Vec<_>>()
+        site.watch_with_summaries(print)?;
+    }
+    Ok(())
+}
+
+/// Build a site, then combine its pages into a single HTML document.
+///
+/// # Arguments
+///
+/// * `provider` - The provider the site is built with.
+///
+/// * `options` - The options the site is built with.
+///
+/// * `collection` - The collection to export, or `None` to export every page.
+///
+/// # Returns
+///
+/// The HTML document.
+fn export<P: VoxProvider>(
+    provider: P,
+    options: BuildOptions,
+    collection: Option<&str>,
+) -> miette::Result<String> {
+    let mut site = Site::new(provider).with_options(options);
+    site.build()?;
+    let pages = site
+        .dag()
+        .map(|dag| exported_pages(dag, collection))
+        .unwrap_or_default();
+    if pages.is_empty() {
+        return Err(match collection {
+            Some(collection) => miette::miette!(
+                "No pages in the collection `{}` are output as HTML.",
+                collection
+            ),
+            None => miette::miette!("No pages are output as HTML."),
+        });
+    }
+    info!("Exporting {} pages … ", pages.len());
+    export_html(site.provider(), &pages)
+}
+
+/// Build a site repeatedly, printing how long each stage of the builds took.
+///
+/// # Arguments
+///
+/// * `provider` - The provider the site is built with.
+///
+/// * `options` - The options the site is built with.
+///
+/// * `cold` - The number of builds from scratch.
+///
+/// * `incremental` - The number of incremental builds, each after changing a random page.
+fn bench<P: VoxProvider + Clone>(
+    provider: P,
+    options: BuildOptions,
+    cold: usize,
+    incremental: usize,
+) -> miette::Result<()> {
+    let mut cold_summaries = Vec::new();
+    let mut site = Site::new(provider.clone()).with_options(options);
+    for run in 1..=cold.max(1) {
+        info!(
+            "Performing build {} of {} from scratch … ",
+            run,
+            cold.max(1)
+        );
+        site = Site::new(provider.clone()).with_options(options);
+        site.build()?;
+        cold_summaries.extend(site.summary().cloned());
+    }
+    // Only pages with source files are changed, rather than pages generated when building.
+    let pages = [
+        provider.list_vox_files()?,
+        provider.list_markdown_files()?,
+        provider.list_html_files()?,
+    ]
+    .concat()
+    .into_iter()
+    .filter(|path| !Page::is_layout_path(path))
+    .collect::<Vec<_>>();
+    let mut incremental_summaries = Vec::new();
+    if !pages.is_empty() {
+        for run in 1..=incremental {
+            // The page is changed by appending a line, and restored once the site has been rebuilt.
+            let page = &pages[fastrand::usize(..pages.len())];
+            info!(
+                "Performing incremental build {} of {} after changing `{}` … ",
+                run,
+                incremental,
+                page.to_string_lossy()
             );
+            let original = provider.read_to_string(page)?;
+            let change = Change {
+                paths: vec![page.clone()],
+            };
+            provider.write_file(page, format!("{}\n", original))?;
+            let rebuilt = site.rebuild(&change);
+            provider.write_file(page, &original)?;
+            rebuilt?;
+            incremental_summaries.extend(site.summary().cloned());
+            site.rebuild(&change)?;
+        }
+    }
+    print_bench_report("Builds from scratch", &cold_summaries);
+    print_bench_report("Incremental builds", &incremental_summaries);
+    Ok(())
+}
+
+/// Copy a site to another directory, without its output.
+///
+/// # Arguments
+///
+/// * `source` - The directory of the site.
+///
+/// * `destination` - The directory to copy the site to.
+///
+/// * `output_directory` - The directory the site is output to, relative to the site's directory.
+fn copy_site(source: &Path, destination: &Path, output_directory: &Path) -> miette::Result<()> {
+    copy_directory(source, destination, &[source.join(output_directory)])
+}
+
+/// Copy a directory and its contents.
+///
+/// # Arguments
+///
+/// * `source` - The directory to copy.
+///
+/// * `destination` - The directory to copy to.
+///
+/// * `skipped` - Paths within the source directory which are not copied.
+fn copy_directory(source: &Path, destination: &Path, skipped: &[PathBuf]) -> miette::Result<()> {
+    std::fs::create_dir_all(destination).into_diagnostic()?;
+    for entry in std::fs::read_dir(source).into_diagnostic()? {
+        let path = entry.into_diagnostic()?.path();
+        if skipped.contains(&path) {
+            continue;
+        }
+        let Some(name) = path.file_name() else {
+            continue;
+        };
+        match path.is_dir() {
+            true => copy_directory(&path, &destination.join(name), skipped)?,
+            false => {
+                std::fs::copy(&path, destination.join(name)).into_diagnostic()?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Print the time taken by each stage of a set of builds.
+///
+/// # Arguments
+///
+/// * `title` - The kind of builds.
+///
+/// * `summaries` - The summaries of the builds.
+fn print_bench_report(title: &str, summaries: &[BuildSummary]) {
+    if summaries.is_empty() {
+        return;
+    }
+    // Stages are listed in the order they were first performed.
+    let mut stages: Vec<(String, Vec<f64>)> = Vec::new();
+    for summary in summaries {
+        for stage in summary.stages.iter() {
+            match stages.iter_mut().find(|(name, _)| *name == stage.name) {
+                Some((_, times)) => times.push(stage.seconds),
+                None => stages.push((stage.name.clone(), vec![stage.seconds])),
+            }
+        }
+    }
+    stages.push((
+        "total".to_string(),
+        summaries.iter().map(|summary| summary.seconds).collect(),
+    ));
+    println!("{} ({}):", title, summaries.len());
+    println!(
+        "  {:<20} {:>10} {:>10} {:>10} {:>10}",
+        "stage", "mean", "p50", "p90", "max"
+    );
+    for (name, mut times) in stages {
+        times.sort_by(f64::total_cmp);
+        println!(
+            "  {:<20} {:>10} {:>10} {:>10} {:>10}",
+            name,
+            format_seconds(times.iter().sum::<f64>() / times.len() as f64),
+            format_seconds(percentile(&times, 50.0)),
+            format_seconds(percentile(&times, 90.0)),
+            format_seconds(percentile(&times, 100.0))
+        );
+    }
+}
 
-            (dag, pages, layouts) = FS_PROVIDER.incremental_regeneration(
-                global_or_snippets_changed,
-                parser.clone(),
-                visualise_dag,
-                generate_syntax_css,
-                dag,
-                pages,
-                layouts,
-            )?;
+/// Find a percentile of a set of times, using the nearest-rank method.
+///
+/// # Arguments
+///
+/// * `sorted` - The times, in ascending order.
+///
+/// * `percentile` - The percentile to find, from 0 to 100.
+///
+/// # Returns
+///
+/// The smallest time at least as great as the given percentage of the times.
+fn percentile(sorted: &[f64], percentile: f64) -> f64 {
+    let rank = (percentile / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Format a duration for display.
+///
+/// # Arguments
+///
+/// * `seconds` - The duration, in seconds.
+///
+/// # Returns
+///
+/// The duration in milliseconds if less than a second, and in seconds otherwise.
+fn format_seconds(seconds: f64) -> String {
+    if seconds < 1.0 {
+        format!("{:.2} ms", seconds * 1000.0)
+    } else {
+        format!("{:.2} s", seconds)
+    }
+}
+
+/// Deploy the built site to GitHub Pages, by committing it to a branch of the site's repository and pushing the branch.
+///
+/// # Arguments
+///
+/// * `settings` - The settings for deploying the site.
+///
+/// * `provider` - The provider the site was built with.
+fn deploy_github_pages(settings: &DeploySettings, provider: &FsProvider) -> miette::Result<()> {
+    let repository = settings.repository.as_deref().unwrap_or("origin");
+    let branch = settings.branch.as_deref().unwrap_or("gh-pages");
+    // A remote name is resolved to its URL, as the output is committed outside of the site's repository.
+    let repository_url = match Command::new("git")
+        .args(["remote", "get-url", repository])
+        .output()
+    {
+        Ok(output) if output.status.success() => {
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        }
+        _ => repository.to_string(),
+    };
+    // GitHub Pages should serve the output as-is, rather than processing it with Jekyll.
+    provider.write_file("output/.nojekyll", "")?;
+    if let Some(cname) = &settings.cname {
+        provider.write_file("output/CNAME", cname)?;
+    }
+    let git_directory =
+        std::env::temp_dir().join(format!("vox-github-pages-{}", std::process::id()));
+    let output_directory = std::env::current_dir()
+        .into_diagnostic()?
+        .join(provider.output_directory());
+    // The site's repository may configure the identity used to commit.
+    let identity = ["user.name", "user.email"]
+        .into_iter()
+        .filter_map(|key| {
+            Command::new("git")
+                .args(["config", key])
+                .output()
+                .ok()
+                .filter(|output| output.status.success())
+                .map(|output| format!("{}={}", key, String::from_utf8_lossy(&output.stdout).trim()))
+        })
+        .flat_map(|config| ["-c".to_string(), config])
+        .collect::<Vec<_>>();
+    let run_git = |args: &[&str]| {
+        Command::new("git")
+            .args(&identity)
+            .arg("--git-dir")
+            .arg(&git_directory)
+            .arg("--work-tree")
+            .arg(&output_directory)
+            .args(args)
+            .status()
+            .into_diagnostic()
+    };
+    let git = |args: &[&str]| -> miette::Result<()> {
+        let status = run_git(args)?;
+        if !status.success() {
+            return Err(miette::miette!(
+                "Deploying failed: `git {}` exited with {}.",
+                args.join(" "),
+                status
+            ));
+        }
+        Ok(())
+    };
+    info!("Deploying to `{}` on `{}` … ", branch, repository_url);
+    let deploying = git(&["init", "--quiet"]).and_then(|_| {
+        // The output is committed on top of the branch, if it exists, so that the branch's history is kept.
+        match run_git(&[
+            "ls-remote",
+            "--quiet",
+            "--exit-code",
+            "--heads",
+            &repository_url,
+            branch,
+        ])?
+        .code()
+        {
+            Some(0) => {
+                git(&["fetch", "--quiet", "--depth", "1", &repository_url, branch])?;
+                git(&["reset", "--quiet", "--soft", "FETCH_HEAD"])?;
+            }
+            // No branch matches.
+            Some(2) => info!("Creating `{}` … ", branch),
+            _ => {
+                return Err(miette::miette!(
+                    "Deploying failed: unable to read the branches of `{}`.",
+                    repository_url
+                ))
+            }
+        }
+        git(&["add", "--all"])?;
+        if run_git(&["diff", "--cached", "--quiet", "HEAD"])?.success() {
+            info!("Nothing has changed since the last deployment … ");
+            return Ok(());
+        }
+        git(&[
+            "commit",
+            "--quiet",
+            "--message",
+            &format!("Deploy site built by Vox {}", VERSION),
+        ])?;
+        git(&[
+            "push",
+            "--quiet",
+            &repository_url,
+            &format!("HEAD:{}", branch),
+        ])
+    });
+    // Failing to clean up must not hide whether or not the site was deployed.
+    if let Err(err) = std::fs::remove_dir_all(&git_directory) {
+        warn!(
+            "Unable to remove `{}`: {}",
+            git_directory.to_string_lossy(),
+            err
+        );
+    }
+    deploying?;
+    info!("Deployed to `{}` on `{}` … ", branch, repository_url);
+    Ok(())
+}
+
+fn aws(args: &[&str], region: Option<&str>) -> miette::Result<std::process::Output> {
+    let mut aws = Command::new("aws");
+    aws.args(args);
+    if let Some(region) = region {
+        aws.args(["--region", region]);
+    }
+    aws.output().into_diagnostic()
+}
+
+/// Ask the user to confirm an action.
+///
+/// # Arguments
+///
+/// * `question` - The question asked of the user.
+///
+/// # Returns
+///
+/// Whether or not the user answered yes, or an error if there is no terminal to ask them with.
+fn confirm(question: &str) -> miette::Result<bool> {
+    use std::io::{IsTerminal, Write};
+    if !std::io::stdin().is_terminal() {
+        return Err(miette::miette!(
+            "Unable to ask for confirmation without a terminal; pass `--yes` to confirm."
+        ));
+    }
+    print!("{} [y/N] ", question);
+    std::io::stdout().flush().into_diagnostic()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).into_diagnostic()?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// The most paths invalidated in CloudFront by a single request.
+const CLOUDFRONT_BATCH_SIZE: usize = 1000;
+
+/// The most paths invalidated in CloudFront individually; CloudFront limits how many paths can be invalidated at once, so beyond this, every path is invalidated.
+const CLOUDFRONT_PATH_LIMIT: usize = 3000;
+
+/// Get the CloudFront paths to invalidate for files deployed to S3.
+/// Pages output as `index.html` are also invalidated by the URL of their directory.
+///
+/// # Arguments
+///
+/// * `prefix` - The prefix of keys in the S3 bucket, without leading or trailing slashes.
+///
+/// * `keys` - The keys of the files, relative to the prefix.
+///
+/// # Returns
+///
+/// The percent-encoded paths to invalidate, or a single path invalidating everything under the prefix if there are too many.
+fn cloudfront_paths<'a>(prefix: &str, keys: impl Iterator<Item = &'a String>) -> Vec<String> {
+    let root = match prefix.is_empty() {
+        true => "/".to_string(),
+        false => format!("/{}/", encode_cloudfront_path(prefix)),
+    };
+    let mut paths = Vec::new();
+    for key in keys {
+        let path = format!("{}{}", root, encode_cloudfront_path(key));
+        if let Some(directory) = path.strip_suffix("index.html").filter(|x| x.ends_with('/')) {
+            paths.push(directory.to_string());
+        }
+        paths.push(path);
+    }
+    if paths.len() > CLOUDFRONT_PATH_LIMIT {
+        return vec![format!("{}*", root)];
+    }
+    paths
+}
+
+/// Percent-encode a path for CloudFront, leaving only unreserved characters & slashes as they are.
+/// Characters such as `*` have meaning in invalidation paths, so are encoded.
+///
+/// # Arguments
+///
+/// * `path` - The path to encode.
+///
+/// # Returns
+///
+/// The encoded path.
+fn encode_cloudfront_path(path: &str) -> String {
+    path.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// Deploy the built site to an Amazon S3 bucket with the AWS CLI.
+/// Only files whose contents changed since the last deployment to the bucket are uploaded.
+///
+/// # Arguments
+///
+/// * `settings` - The settings for deploying the site.
+///
+/// * `output_directory` - The directory the site is output to.
+fn deploy_s3(settings: &DeploySettings, output_directory: &Path) -> miette::Result<()> {
+    let bucket = settings.bucket.as_ref().ok_or(miette::miette!(
+        "No `deploy.bucket` setting found in `global.toml`."
+    ))?;
+    let region = settings.region.as_deref();
+    let prefix = settings
+        .prefix
+        .as_deref()
+        .unwrap_or_default()
+        .trim_matches('/');
+    let s3_uri = |key: &str| {
+        if prefix.is_empty() {
+            format!("s3://{}/{}", bucket, key)
+        } else {
+            format!("s3://{}/{}/{}", bucket, prefix, key)
+        }
+    };
+    // The manifest of the previous deployment maps each file to a hash of its contents.
+    let manifest_uri = s3_uri(".vox-manifest.toml");
+    let old_manifest: BTreeMap<String, String> =
+        match aws(&["s3", "cp", &manifest_uri, "-"], region) {
+            Ok(output) if output.status.success() => {
+                toml::from_str(&String::from_utf8_lossy(&output.stdout)).unwrap_or_default()
+            }
+            _ => BTreeMap::new(),
+        };
+    let new_manifest = hash_output(output_directory)?;
+    let changed = new_manifest
+        .iter()
+        .filter(|(key, hash)| old_manifest.get(*key) != Some(hash))
+        .map(|(key, _)| key.clone())
+        .collect::<Vec<_>>();
+    let removed = if settings.delete_orphans {
+        old_manifest
+            .keys()
+            .filter(|key| !new_manifest.contains_key(*key))
+            .cloned()
+            .collect::<Vec<_>>()
+    } else {
+        Vec::new()
+    };
+    info!(
+        "Deploying {} changed files to `{}` … ",
+        changed.len(),
+        s3_uri("")
+    );
+    for key in changed.iter() {
+        let file = output_directory.join(key);
+        let content_type = mime_guess::from_path(&file).first_or_octet_stream();
+        let cache_control = match (content_type.type_(), content_type.subtype()) {
+            (mime_guess::mime::TEXT, mime_guess::mime::HTML)
+            | (_, mime_guess::mime::XML)
+            | (_, mime_guess::mime::JSON) => settings
+                .document_cache_control
+                .as_deref()
+                .unwrap_or("public, max-age=0, must-revalidate"),
+            _ => settings
+                .cache_control
+                .as_deref()
+                .unwrap_or("public, max-age=86400"),
+        };
+        debug!("Uploading `{}` … ", key);
+        let output = aws(
+            &[
+                "s3",
+                "cp",
+                &file.to_string_lossy(),
+                &s3_uri(key),
+                "--content-type",
+                content_type.essence_str(),
+                "--cache-control",
+                cache_control,
+            ],
+            region,
+        )?;
+        if !output.status.success() {
+            return Err(miette::miette!(
+                "Deploying failed: unable to upload `{}`.\n{}",
+                key,
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+    }
+    for key in removed.iter() {
+        debug!("Removing `{}` … ", key);
+        let output = aws(&["s3", "rm", &s3_uri(key)], region)?;
+        if !output.status.success() {
+            return Err(miette::miette!(
+                "Deploying failed: unable to remove `{}`.\n{}",
+                key,
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+    }
+    // Removed files remain in the manifest if they were not deleted.
+    let mut manifest = new_manifest;
+    if !settings.delete_orphans {
+        for (key, hash) in old_manifest {
+            manifest.entry(key).or_insert(hash);
+        }
+    }
+    let manifest_path =
+        std::env::temp_dir().join(format!("vox-manifest-{}.toml", std::process::id()));
+    std::fs::write(
+        &manifest_path,
+        toml::to_string(&manifest).into_diagnostic()?,
+    )
+    .into_diagnostic()?;
+    // The manifest lists every deployed file, so it is not made public along with them.
+    let mut output = aws(
+        &[
+            "s3",
+            "cp",
+            &manifest_path.to_string_lossy(),
+            &manifest_uri,
+            "--acl",
+            "private",
+        ],
+        region,
+    )?;
+    if String::from_utf8_lossy(&output.stderr).contains("AccessControlListNotSupported") {
+        warn!("The bucket does not support ACLs, so the manifest is only private if the bucket's policy keeps `{}` private … ", manifest_uri);
+        output = aws(
+            &["s3", "cp", &manifest_path.to_string_lossy(), &manifest_uri],
+            region,
+        )?;
+    }
+    std::fs::remove_file(&manifest_path).into_diagnostic()?;
+    if !output.status.success() {
+        return Err(miette::miette!(
+            "Deploying failed: unable to upload the manifest.\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    if let Some(distribution) = &settings.cloudfront_distribution {
+        let paths = cloudfront_paths(prefix, changed.iter().chain(removed.iter()));
+        if !paths.is_empty() {
+            info!("Invalidating {} paths in CloudFront … ", paths.len());
+        }
+        for batch in paths.chunks(CLOUDFRONT_BATCH_SIZE) {
+            let mut args = vec![
+                "cloudfront",
+                "create-invalidation",
+                "--distribution-id",
+                distribution,
+                "--paths",
+            ];
+            args.extend(batch.iter().map(|x| x.as_str()));
+            let output = aws(&args, None)?;
+            if !output.status.success() {
+                return Err(miette::miette!(
+                    "Deploying failed: unable to invalidate CloudFront paths.\n{}",
+                    String::from_utf8_lossy(&output.stderr)
+                ));
+            }
         }
     }
+    info!("Deployed to `{}` … ", s3_uri(""));
     Ok(())
 }
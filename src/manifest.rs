@@ -0,0 +1,80 @@
+use miette::IntoDiagnostic;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeSet,
+    io::{Read, Write},
+    path::PathBuf,
+};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+/// A record of the files output by building a site.
+pub struct BuildManifest {
+    /// The paths of the files output, relative to the output directory.
+    pub outputs: BTreeSet<PathBuf>,
+}
+
+impl BuildManifest {
+    /// Create a manifest of the files output by a build.
+    ///
+    /// # Arguments
+    ///
+    /// * `outputs` - The paths of the files output, relative to the output directory.
+    ///
+    /// # Returns
+    ///
+    /// A manifest listing the given files.
+    pub fn new(outputs: impl IntoIterator<Item = PathBuf>) -> Self {
+        Self {
+            outputs: outputs.into_iter().collect(),
+        }
+    }
+
+    /// Find the files listed in this manifest which were not output by a later build.
+    ///
+    /// # Arguments
+    ///
+    /// * `later` - The manifest of the later build.
+    ///
+    /// # Returns
+    ///
+    /// The paths of the orphaned files, relative to the output directory, in order.
+    pub fn orphans(&self, later: &BuildManifest) -> Vec<PathBuf> {
+        self.outputs.difference(&later.outputs).cloned().collect()
+    }
+
+    /// Combine this manifest with the manifest of a later build.
+    ///
+    /// # Arguments
+    ///
+    /// * `later` - The manifest of the later build.
+    ///
+    /// # Returns
+    ///
+    /// A manifest listing the files output by either build.
+    pub fn merge(mut self, later: BuildManifest) -> Self {
+        self.outputs.extend(later.outputs);
+        self
+    }
+
+    /// Write the manifest as JSON.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Where to write the manifest.
+    pub fn to_writer(&self, writer: impl Write) -> miette::Result<()> {
+        serde_json::to_writer(writer, self).into_diagnostic()
+    }
+
+    /// Read a manifest previously written as JSON.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - Where to read the manifest from.
+    ///
+    /// # Returns
+    ///
+    /// The manifest read.
+    pub fn from_reader(reader: impl Read) -> miette::Result<Self> {
+        serde_json::from_reader(reader).into_diagnostic()
+    }
+}
@@ -1,25 +1,104 @@
+use crate::admonitions::{render_admonitions, AdmonitionSettings};
+use crate::provider::FileReader;
+use crate::syntax_highlighting::SyntaxHighlightingSettings;
 use comrak::markdown_to_html_with_plugins;
-use comrak::plugins::syntect::SyntectAdapter;
 use comrak::ComrakPlugins;
 use comrak::ListStyleType;
-use liquid_core::error::ResultLiquidReplaceExt;
+use liquid_core::error::{ResultLiquidExt, ResultLiquidReplaceExt};
 use liquid_core::parser;
 use liquid_core::runtime;
+use liquid_core::Expression;
 use liquid_core::Language;
 use liquid_core::Renderable;
 use liquid_core::Result;
 use liquid_core::Runtime;
 use liquid_core::Template;
+use liquid_core::ValueView;
 use liquid_core::{BlockReflection, ParseBlock, TagBlock, TagTokenIter};
+use miette::IntoDiagnostic;
+use path_clean::PathClean;
+use std::fmt;
 use std::io::BufWriter;
 use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Replace the custom containers within Markdown with `<div>` elements.
+/// A container begins with a line of at least three colons followed by its classes, as in `::: warning`, and ends with a line of colons alone; containers can be nested.
+/// The contents of each container remain Markdown, being separated from the `<div>` tags by blank lines.
+///
+/// # Arguments
+///
+/// * `markdown` - The Markdown text.
+///
+/// # Returns
+///
+/// The Markdown text, with its containers as HTML.
+pub fn render_containers(markdown: &str) -> String {
+    let mut rendered = String::with_capacity(markdown.len());
+    let mut open_containers = 0;
+    let mut fence: Option<&str> = None;
+    for line in markdown.split_inclusive('\n') {
+        let trimmed = line.trim();
+        let is_indented = line.len() - line.trim_start().len() >= 4;
+        // Containers are not recognised within fenced code.
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) && !is_indented {
+                fence = None;
+            }
+            rendered.push_str(line);
+            continue;
+        }
+        if !is_indented {
+            if let Some(marker) = ["```", "~~~"].into_iter().find(|x| trimmed.starts_with(x)) {
+                fence = Some(marker);
+                rendered.push_str(line);
+                continue;
+            }
+        }
+        let classes = trimmed
+            .strip_prefix(":::")
+            .filter(|_| !is_indented)
+            .map(|x| x.trim_start_matches(':').trim());
+        match classes {
+            Some("") if open_containers > 0 => {
+                open_containers -= 1;
+                rendered.push_str("\n</div>\n\n");
+            }
+            Some(classes)
+                if !classes.is_empty()
+                    && classes
+                        .chars()
+                        .all(|x| x.is_alphanumeric() || "-_ ".contains(x)) =>
+            {
+                open_containers += 1;
+                rendered.push_str(&format!(
+                    "\n<div class=\"{}\">\n\n",
+                    classes.split_whitespace().collect::<Vec<_>>().join(" ")
+                ));
+            }
+            _ => rendered.push_str(line),
+        }
+    }
+    // Containers left open are closed at the end of the text.
+    for _ in 0..open_containers {
+        rendered.push_str("\n</div>\n");
+    }
+    rendered
+}
 
 /// Render Markdown as HTML
 ///
 /// # Arguments
 ///
 /// * `text_to_render` - The Markdown text to render into HTML
-pub fn render_markdown(text_to_render: String) -> String {
+///
+/// * `syntax_highlighting` - The settings for highlighting code
+pub fn render_markdown(
+    text_to_render: String,
+    syntax_highlighting: &SyntaxHighlightingSettings,
+) -> String {
+    let text_to_render = render_containers(&text_to_render);
     let mut options = comrak::Options::default();
     options.extension.strikethrough = true;
     options.extension.tagfilter = false;
@@ -59,20 +138,88 @@ pub fn render_markdown(text_to_render: String) -> String {
     options.render.prefer_fenced = false;
     options.render.figure_with_caption = false;
     let mut plugins = ComrakPlugins::default();
-    let syntax_highlighting_adapter = SyntectAdapter::new(None);
+    let syntax_highlighting_adapter = syntax_highlighting.adapter();
     plugins.render.codefence_syntax_highlighter = Some(&syntax_highlighting_adapter);
     markdown_to_html_with_plugins(&text_to_render, &options, &plugins)
 }
 
-#[derive(Copy, Clone, Debug, Default)]
+/// Shift the levels of the headings in rendered HTML, so that they fit within the outline of the surrounding document.
+/// Levels are kept between `h1` and `h6`.
+///
+/// # Arguments
+///
+/// * `html` - The rendered HTML.
+///
+/// * `shift` - The number of levels to demote headings by; negative numbers promote headings.
+///
+/// # Returns
+///
+/// The HTML with its headings shifted.
+pub fn shift_headings(html: &str, shift: i64) -> String {
+    if shift == 0 {
+        return html.to_string();
+    }
+    let mut shifted = String::with_capacity(html.len());
+    let mut remainder = html;
+    while let Some(start) = remainder.find('<') {
+        shifted.push_str(&remainder[..start]);
+        let tag = &remainder[start..];
+        let name_start = match tag[1..].starts_with('/') {
+            true => 2,
+            false => 1,
+        };
+        let name = tag.as_bytes().get(name_start..name_start + 3);
+        let level = match name {
+            Some([b'h' | b'H', level @ b'1'..=b'6', end])
+                if end.is_ascii_whitespace() || *end == b'>' || *end == b'/' =>
+            {
+                Some((level - b'0') as i64)
+            }
+            _ => None,
+        };
+        match level {
+            Some(level) => {
+                let level = (level + shift).clamp(1, 6);
+                shifted.push_str(&tag[..name_start + 1]);
+                shifted.push_str(&level.to_string());
+                remainder = &tag[name_start + 2..];
+            }
+            None => {
+                shifted.push('<');
+                remainder = &tag[1..];
+            }
+        }
+    }
+    shifted.push_str(remainder);
+    shifted
+}
+
+#[derive(Clone, Default)]
 /// A Liquid template block containing Markdown.
 /// The block begins with `{% markdown %}` and ends with `{% endmarkdown %}`.
-pub struct MarkdownBlock;
+/// Headings can be demoted with `shift`, as in `{% markdown shift=1 %}`.
+/// The Markdown can instead be read from a file with `file`, as in `{% markdown file="notes/chapter1.md" %}{% endmarkdown %}`.
+pub struct MarkdownBlock {
+    /// Reads files from the site's provider.
+    reader: Option<FileReader>,
+}
+
+impl fmt::Debug for MarkdownBlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MarkdownBlock")
+            .field("reader", &self.reader.is_some())
+            .finish()
+    }
+}
 
 impl MarkdownBlock {
     /// Provides a new instance of the Markdown tag parser.
-    pub fn new() -> Self {
-        Self
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - Reads files from the site's provider, if it can do so while pages are being rendered.
+    pub fn new(reader: Option<FileReader>) -> Self {
+        Self { reader }
     }
 }
 
@@ -96,16 +243,54 @@ impl ParseBlock for MarkdownBlock {
         mut arguments: TagTokenIter<'_>,
         mut tokens: TagBlock<'_, '_>,
         options: &Language,
-    ) -> Result<Box<(dyn Renderable + 'static)>, liquid::Error> {
+    ) -> Result<Box<dyn Renderable + 'static>, liquid::Error> {
+        let mut shift = None;
+        let mut file = None;
+        while let Ok(next) = arguments.expect_next("") {
+            let argument = next.expect_identifier().into_result()?.to_owned();
+            arguments
+                .expect_next("\"=\" expected.")?
+                .expect_str("=")
+                .into_result_custom_msg("\"=\" expected.")?;
+            let value = arguments
+                .expect_next("Value expected.")?
+                .expect_value()
+                .into_result()?;
+            match argument.as_str() {
+                "shift" => shift = Some(value),
+                "file" => file = Some(value),
+                _ => {
+                    return Err(liquid::Error::with_msg(format!(
+                        "Unknown argument `{}`.",
+                        argument
+                    )))
+                }
+            }
+        }
         arguments.expect_nothing()?;
 
         let raw_content = tokens.escape_liquid(false)?.to_string();
+        // Markdown read from a file takes the place of the block's contents.
+        if file.is_some() && !raw_content.trim().is_empty() {
+            return Err(liquid::Error::with_msg(
+                "A Markdown block reading a file must be empty.",
+            ));
+        }
         let content = parser::parse(&raw_content, options)
             .map(runtime::Template::new)
             .unwrap();
 
         tokens.assert_empty();
-        Ok(Box::new(Markdown { content }))
+        let file = file.map(|path| MarkdownFile {
+            path,
+            reader: self.reader.clone(),
+            language: Arc::new(options.clone()),
+        });
+        Ok(Box::new(Markdown {
+            content,
+            shift,
+            file,
+        }))
     }
 
     fn reflection(&self) -> &dyn BlockReflection {
@@ -113,9 +298,60 @@ impl ParseBlock for MarkdownBlock {
     }
 }
 
+/// A Markdown file read by a Markdown block.
+struct MarkdownFile {
+    /// The path to the file, relative to the site's directory.
+    path: Expression,
+    /// Reads files from the site's provider.
+    reader: Option<FileReader>,
+    /// The language the file is parsed as a Liquid template with.
+    language: Arc<Language>,
+}
+
+impl fmt::Debug for MarkdownFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MarkdownFile")
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+impl MarkdownFile {
+    /// Read the file, and render it as a Liquid template.
+    ///
+    /// # Arguments
+    ///
+    /// * `runtime` - The Liquid runtime of the page containing the block.
+    ///
+    /// # Returns
+    ///
+    /// The file's contents, rendered as a Liquid template with the page's context.
+    fn render(&self, runtime: &dyn Runtime) -> Result<String> {
+        let path = self.path.evaluate(runtime)?.to_kstr().to_string();
+        let trace = || format!("{{% markdown file=\"{}\" %}}", path);
+        let contents = self
+            .reader
+            .as_ref()
+            .ok_or(miette::miette!(
+                "The site's provider cannot read files while pages are being rendered."
+            ))
+            .and_then(|reader| reader(&Path::new(path.trim_start_matches('/')).clean()))
+            .and_then(|bytes| String::from_utf8(bytes).into_diagnostic())
+            .map_err(|err| liquid::Error::with_msg(format!("Unable to read `{}`: {}", path, err)))
+            .trace_with(|| trace().into())?;
+        parser::parse(&contents, &self.language)
+            .map(runtime::Template::new)
+            .trace_with(|| trace().into())?
+            .render(runtime)
+            .trace_with(|| trace().into())
+    }
+}
+
 #[derive(Debug)]
 struct Markdown {
     content: Template,
+    shift: Option<Expression>,
+    file: Option<MarkdownFile>,
 }
 
 impl Renderable for Markdown {
@@ -124,11 +360,32 @@ impl Renderable for Markdown {
         writer: &mut dyn Write,
         runtime: &dyn Runtime,
     ) -> Result<(), liquid::Error> {
-        let mut buf = BufWriter::new(Vec::new());
-        self.content.render_to(&mut buf, runtime)?;
-        let bytes = buf.into_inner().unwrap_or_default();
-        let liquid_rendered = String::from_utf8(bytes).unwrap_or_default();
-        let rendered = render_markdown(liquid_rendered);
+        let liquid_rendered = match &self.file {
+            Some(file) => file.render(runtime)?,
+            None => {
+                let mut buf = BufWriter::new(Vec::new());
+                self.content.render_to(&mut buf, runtime)?;
+                let bytes = buf.into_inner().unwrap_or_default();
+                String::from_utf8(bytes).unwrap_or_default()
+            }
+        };
+        let shift = match &self.shift {
+            Some(shift) => shift
+                .evaluate(runtime)?
+                .as_scalar()
+                .and_then(|x| x.to_integer())
+                .ok_or(liquid::Error::with_msg("`shift` must be an integer."))?,
+            None => 0,
+        };
+        let admonition_settings = AdmonitionSettings::from_runtime(runtime)?;
+        let syntax_highlighting = SyntaxHighlightingSettings::from_runtime(runtime)?;
+        let rendered = shift_headings(
+            &render_admonitions(
+                &render_markdown(liquid_rendered, &syntax_highlighting),
+                &admonition_settings,
+            ),
+            shift,
+        );
         write!(writer, "{}", rendered).replace("Failed to render")?;
         Ok(())
     }
@@ -1,3 +1,4 @@
+use crate::provider::{InputProvider, OutputProvider};
 use latex2mathml::latex_to_mathml;
 use latex2mathml::DisplayStyle;
 use liquid_core::error::ResultLiquidReplaceExt;
@@ -6,27 +7,180 @@ use liquid_core::Renderable;
 use liquid_core::Result;
 use liquid_core::Runtime;
 use liquid_core::{BlockReflection, ParseBlock, TagBlock, TagTokenIter};
+use miette::IntoDiagnostic;
+use std::collections::{BTreeMap, BTreeSet};
 use std::io::Write;
+use std::sync::{Arc, Mutex};
 
-#[derive(Copy, Clone, Debug, Default)]
+/// The path math rendered by previous builds is cached at, relative to the site's directory.
+pub const MATH_CACHE_PATH: &str = ".vox/math.json";
+
+#[derive(Debug, Default)]
+struct MathCacheState {
+    /// Whether or not the cache has been read from storage.
+    loaded: bool,
+    /// Whether or not math has been rendered since the cache was last saved.
+    changed: bool,
+    /// Rendered math, keyed by its LaTeX source.
+    entries: BTreeMap<String, String>,
+    /// The LaTeX sources rendered or read from the cache since it was last saved.
+    used: BTreeSet<String>,
+}
+
+#[derive(Clone, Debug, Default)]
+/// A cache of math rendered as MathML, keyed by its LaTeX source.
+/// Clones of the cache share its contents, so that it can be kept by the Liquid parser and saved once pages have been rendered.
+pub struct MathCache {
+    state: Arc<Mutex<MathCacheState>>,
+}
+
+impl MathCache {
+    /// Create an empty cache of rendered math.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read math rendered by previous builds, unless it has already been read.
+    /// A cache which cannot be read is treated as empty.
+    ///
+    /// # Arguments
+    ///
+    /// * `provider` - The provider the site is read from.
+    pub fn load(&self, provider: &(impl InputProvider + ?Sized)) -> miette::Result<()> {
+        let mut state = self.state.lock().map_err(|e| miette::miette!("{}", e))?;
+        if state.loaded {
+            return Ok(());
+        }
+        state.loaded = true;
+        if let Some(entries) = provider
+            .read_to_string(MATH_CACHE_PATH)
+            .ok()
+            .and_then(|x| serde_json::from_str::<BTreeMap<String, String>>(&x).ok())
+        {
+            state.entries.extend(entries);
+        }
+        Ok(())
+    }
+
+    /// Write the cache to storage, if math has been rendered since it was last saved.
+    ///
+    /// # Arguments
+    ///
+    /// * `provider` - The provider the site is written through.
+    ///
+    /// * `prune` - Whether or not to forget math not rendered or read from the cache since it was last saved, such as after a full build.
+    pub fn save(
+        &self,
+        provider: &(impl OutputProvider + ?Sized),
+        prune: bool,
+    ) -> miette::Result<()> {
+        let mut state = self.state.lock().map_err(|e| miette::miette!("{}", e))?;
+        if prune {
+            let used = std::mem::take(&mut state.used);
+            let count = state.entries.len();
+            state.entries.retain(|latex, _| used.contains(latex));
+            state.changed |= state.entries.len() != count;
+        }
+        if !state.changed {
+            return Ok(());
+        }
+        provider.write_file(
+            MATH_CACHE_PATH,
+            serde_json::to_string(&state.entries).into_diagnostic()?,
+        )?;
+        state.changed = false;
+        Ok(())
+    }
+
+    /// Render LaTeX as MathML, reusing the result of an earlier rendering of the same LaTeX.
+    ///
+    /// # Arguments
+    ///
+    /// * `latex` - The LaTeX source of the math.
+    ///
+    /// # Returns
+    ///
+    /// The rendered MathML.
+    pub fn render(&self, latex: &str) -> Result<String> {
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|e| liquid::Error::with_msg(e.to_string()))?;
+        state.used.insert(latex.to_string());
+        if let Some(rendered) = state.entries.get(latex) {
+            return Ok(rendered.clone());
+        }
+        let rendered = render_math(latex)?;
+        state.entries.insert(latex.to_string(), rendered.clone());
+        state.changed = true;
+        Ok(rendered)
+    }
+}
+
+/// Render LaTeX as MathML.
+///
+/// # Arguments
+///
+/// * `latex` - The LaTeX source of the math.
+///
+/// # Returns
+///
+/// The rendered MathML.
+pub fn render_math(latex: &str) -> Result<String> {
+    latex_to_mathml(latex, DisplayStyle::Inline)
+        .map_err(|e| liquid::Error::with_msg(format!("Unable to render math: {}", e)))
+}
+
+#[derive(Clone, Debug)]
 /// A Liquid template block containing math.
-/// The block begins with `{% math %}` and ends with `{% endmath %}`.
-pub struct MathBlock;
+/// The block begins with `{% math %}` and ends with `{% endmath %}`; for math within a sentence, the compact `{% m %}` block ends with `{% endm %}`.
+pub struct MathBlock {
+    start_tag: &'static str,
+    end_tag: &'static str,
+    cache: Option<MathCache>,
+}
+
+impl Default for MathBlock {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
 
 impl MathBlock {
     /// Provides a new instance of the math tag parser.
-    pub fn new() -> Self {
-        Self
+    ///
+    /// # Arguments
+    ///
+    /// * `cache` - A cache of rendered math, if math is to be rendered only once for each LaTeX source.
+    pub fn new(cache: Option<MathCache>) -> Self {
+        Self {
+            start_tag: "math",
+            end_tag: "endmath",
+            cache,
+        }
+    }
+
+    /// Provides a new instance of the inline math tag parser, whose block begins with `{% m %}` and ends with `{% endm %}`.
+    ///
+    /// # Arguments
+    ///
+    /// * `cache` - A cache of rendered math, if math is to be rendered only once for each LaTeX source.
+    pub fn inline(cache: Option<MathCache>) -> Self {
+        Self {
+            start_tag: "m",
+            end_tag: "endm",
+            cache,
+        }
     }
 }
 
 impl BlockReflection for MathBlock {
     fn start_tag(&self) -> &str {
-        "math"
+        self.start_tag
     }
 
     fn end_tag(&self) -> &str {
-        "endmath"
+        self.end_tag
     }
 
     fn description(&self) -> &str {
@@ -40,11 +194,14 @@ impl ParseBlock for MathBlock {
         mut arguments: TagTokenIter<'_>,
         mut tokens: TagBlock<'_, '_>,
         _options: &Language,
-    ) -> Result<Box<(dyn Renderable + 'static)>, liquid::Error> {
+    ) -> Result<Box<dyn Renderable + 'static>, liquid::Error> {
         arguments.expect_nothing()?;
 
         let raw_content = tokens.escape_liquid(false)?.to_string();
-        let content = latex_to_mathml(&raw_content, DisplayStyle::Inline).unwrap();
+        let content = match &self.cache {
+            Some(cache) => cache.render(&raw_content)?,
+            None => render_math(&raw_content)?,
+        };
 
         tokens.assert_empty();
         Ok(Box::new(Math { content }))
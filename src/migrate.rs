@@ -0,0 +1,1127 @@
+use crate::fs_provider::FsProvider;
+use crate::paths::slugify;
+use crate::provider::OutputProvider;
+use ahash::{AHashMap, AHashSet};
+use miette::IntoDiagnostic;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use toml::Table;
+use tracing::{debug, info, warn};
+
+/// Hugo template expressions with direct equivalents in Vox, and their equivalents.
+const HUGO_VARIABLES: [(&str, &str); 11] = [
+    (".Title", "page.data.title"),
+    (".Content", "page.rendered"),
+    (".Date", "page.date"),
+    (".Lastmod", "page.updated"),
+    (".Permalink", "page.url"),
+    (".RelPermalink", "page.url"),
+    (".Site.Title", "global.title"),
+    (".Site.BaseURL", "global.url"),
+    (".Site.Params.", "global.params."),
+    (".Params.", "page.data."),
+    (".Summary", "page.rendered | strip_html | truncatewords: 70"),
+];
+
+/// Hugo permalink tokens, and their equivalents in Vox.
+const HUGO_PERMALINK_TOKENS: [(&str, &str); 9] = [
+    (":year", "{{ page.date.year }}"),
+    (":month", "{{ page.date.month }}"),
+    (":day", "{{ page.date.day }}"),
+    (":yearday", "{{ page.date.y_day }}"),
+    (":section", "{{ page.collections.last }}"),
+    (":title", "{{ page.data.title | slugify }}"),
+    (":slug", "{{ page.data.slug | default: page.name }}"),
+    (":contentbasename", "{{ page.name }}"),
+    (":filename", "{{ page.name }}"),
+];
+
+#[derive(Clone, Debug, Default)]
+/// A summary of a migration from another static site generator or blogging platform.
+pub struct MigrationReport {
+    /// The number of pages written.
+    pub pages: usize,
+    /// The number of layouts written.
+    pub layouts: usize,
+    /// The names of unsupported shortcodes, and the pages using them.
+    pub unsupported_shortcodes: BTreeMap<String, BTreeSet<PathBuf>>,
+    /// Suggestions for completing the migration by hand.
+    pub hints: Vec<String>,
+}
+
+/// Convert a YAML value into a TOML value.
+///
+/// # Arguments
+///
+/// * `value` - The YAML value to convert.
+///
+/// # Returns
+///
+/// The equivalent TOML value, or `None` if the value is null.
+fn yaml_to_toml(value: serde_yaml::Value) -> Option<toml::Value> {
+    match value {
+        serde_yaml::Value::Null => None,
+        serde_yaml::Value::Bool(x) => Some(toml::Value::Boolean(x)),
+        serde_yaml::Value::Number(x) => match x.as_i64() {
+            Some(x) => Some(toml::Value::Integer(x)),
+            None => x.as_f64().map(toml::Value::Float),
+        },
+        serde_yaml::Value::String(x) => Some(toml::Value::String(x)),
+        serde_yaml::Value::Sequence(x) => Some(toml::Value::Array(
+            x.into_iter().filter_map(yaml_to_toml).collect(),
+        )),
+        serde_yaml::Value::Mapping(x) => Some(toml::Value::Table(
+            x.into_iter()
+                .filter_map(|(key, value)| {
+                    let key = match key {
+                        serde_yaml::Value::String(key) => key,
+                        key => serde_yaml::to_string(&key).ok()?.trim().to_string(),
+                    };
+                    yaml_to_toml(value).map(|value| (key, value))
+                })
+                .collect(),
+        )),
+        serde_yaml::Value::Tagged(x) => yaml_to_toml(x.value),
+    }
+}
+
+/// Separate a Hugo page into its frontmatter and body.
+/// Frontmatter may be TOML (delimited by `+++`), YAML (delimited by `---`), or JSON.
+///
+/// # Arguments
+///
+/// * `contents` - The contents of the page.
+///
+/// # Returns
+///
+/// A tuple where the first element is the frontmatter, and where the second element is the body.
+pub fn parse_hugo_frontmatter(contents: &str) -> miette::Result<(Table, String)> {
+    let trimmed = contents.trim_start();
+    for (delimiter, is_toml) in [("+++", true), ("---", false)] {
+        if let Some(rest) = trimmed.strip_prefix(delimiter) {
+            let Some((frontmatter, body)) = rest.split_once(&format!("\n{}", delimiter)) else {
+                continue;
+            };
+            let body = body.split_once('\n').map(|x| x.1).unwrap_or_default();
+            let frontmatter = if is_toml {
+                frontmatter.parse::<Table>().into_diagnostic()?
+            } else {
+                match yaml_to_toml(serde_yaml::from_str(frontmatter).into_diagnostic()?) {
+                    Some(toml::Value::Table(table)) => table,
+                    _ => Table::new(),
+                }
+            };
+            return Ok((frontmatter, body.to_string()));
+        }
+    }
+    if trimmed.starts_with('{') {
+        // JSON frontmatter is a JSON object at the start of the file, which is also valid YAML.
+        // Braces inside strings do not open or close objects.
+        let mut depth = 0;
+        let mut in_string = false;
+        let mut escaped = false;
+        for (index, character) in trimmed.char_indices() {
+            match (in_string, character) {
+                (true, _) if escaped => escaped = false,
+                (true, '\\') => escaped = true,
+                (true, '"') => in_string = false,
+                (true, _) => {}
+                (false, '"') => in_string = true,
+                (false, '{') => depth += 1,
+                (false, '}') => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                let frontmatter =
+                    match yaml_to_toml(serde_yaml::from_str(&trimmed[..=index]).into_diagnostic()?)
+                    {
+                        Some(toml::Value::Table(table)) => table,
+                        _ => Table::new(),
+                    };
+                return Ok((frontmatter, trimmed[index + 1..].to_string()));
+            }
+        }
+    }
+    Ok((Table::new(), contents.to_string()))
+}
+
+/// Normalise Hugo frontmatter into Vox frontmatter.
+///
+/// # Arguments
+///
+/// * `frontmatter` - The frontmatter of a Hugo page.
+///
+/// # Returns
+///
+/// The equivalent Vox frontmatter.
+pub fn normalise_hugo_frontmatter(mut frontmatter: Table) -> Table {
+    if let Some(lastmod) = frontmatter.remove("lastmod") {
+        frontmatter.insert("updated".to_string(), lastmod);
+    }
+    for key in ["date", "updated"] {
+        if let Some(toml::Value::String(date)) = frontmatter.get(key) {
+            if let Ok(date) = date.parse::<toml::value::Datetime>() {
+                frontmatter.insert(key.to_string(), toml::Value::Datetime(date));
+            }
+        }
+    }
+    // Hugo allows dates to be used as aliases for one another; Vox only recognises `date`.
+    for alias in ["publishDate", "pubdate", "published"] {
+        if let Some(date) = frontmatter.remove(alias) {
+            frontmatter.entry("date").or_insert(date);
+        }
+    }
+    if let Some(toml::Value::String(url)) = frontmatter.remove("url") {
+        frontmatter.insert(
+            "permalink".to_string(),
+            toml::Value::String(hugo_path_to_permalink(&url)),
+        );
+    }
+    frontmatter
+}
+
+/// Convert an output path as written in Hugo into a Vox permalink.
+///
+/// # Arguments
+///
+/// * `path` - The Hugo output path, possibly containing permalink tokens.
+///
+/// # Returns
+///
+/// The equivalent Vox permalink.
+pub fn hugo_path_to_permalink(path: &str) -> String {
+    let mut permalink = path.trim_start_matches('/').to_string();
+    // Longer tokens are replaced first, so that `:yearday` is not mistaken for `:year`.
+    let mut tokens = HUGO_PERMALINK_TOKENS.to_vec();
+    tokens.sort_by_key(|(token, _)| std::cmp::Reverse(token.len()));
+    for (token, replacement) in tokens {
+        permalink = permalink.replace(token, replacement);
+    }
+    if permalink.is_empty() || permalink.ends_with('/') {
+        permalink.push_str("index.html");
+    } else if Path::new(&permalink).extension().is_none() {
+        permalink.push_str("/index.html");
+    }
+    permalink
+}
+
+/// Convert the shortcodes in a Hugo page's body.
+/// The `figure` and `highlight` shortcodes are translated, and all other shortcodes are left as-is.
+///
+/// # Arguments
+///
+/// * `body` - The body of the Hugo page.
+///
+/// # Returns
+///
+/// The converted body, and the names of any shortcodes that could not be converted.
+pub fn convert_hugo_shortcodes(body: &str) -> (String, BTreeSet<String>) {
+    let mut result = String::new();
+    let mut unsupported = BTreeSet::new();
+    let mut remainder = body;
+    while let Some(start) = [remainder.find("{{<"), remainder.find("{{%")]
+        .into_iter()
+        .flatten()
+        .min()
+    {
+        let closing = if remainder[start..].starts_with("{{<") {
+            ">}}"
+        } else {
+            "%}}"
+        };
+        let Some(length) = remainder[start..].find(closing) else {
+            break;
+        };
+        let end = start + length + closing.len();
+        let shortcode = &remainder[start + 3..end - 3].trim();
+        result.push_str(&remainder[..start]);
+        let name = shortcode
+            .split_whitespace()
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        match name.as_str() {
+            "figure" => {
+                let attribute = |key: &str| {
+                    shortcode
+                        .split_once(&format!("{}=\"", key))
+                        .and_then(|x| x.1.split_once('"'))
+                        .map(|x| x.0.to_string())
+                };
+                result.push_str(&format!(
+                    "<figure><img src=\"{}\" alt=\"{}\">",
+                    attribute("src").unwrap_or_default(),
+                    attribute("alt").unwrap_or_default()
+                ));
+                if let Some(caption) = attribute("caption") {
+                    result.push_str(&format!("<figcaption>{}</figcaption>", caption));
+                }
+                result.push_str("</figure>");
+            }
+            "highlight" => {
+                let language = shortcode.split_whitespace().nth(1).unwrap_or_default();
+                result.push_str(&format!("```{}", language.trim_matches('"')));
+            }
+            "/highlight" => result.push_str("```"),
+            _ => {
+                unsupported.insert(name.trim_start_matches('/').to_string());
+                result.push_str(&format!(
+                    "{{% raw %}}{}{{% endraw %}}",
+                    &remainder[start..end]
+                ));
+            }
+        }
+        remainder = &remainder[end..];
+    }
+    result.push_str(remainder);
+    (result, unsupported)
+}
+
+/// Translate a Hugo template into a Vox layout.
+/// Template actions with direct equivalents in Vox are translated; all others are commented out.
+///
+/// # Arguments
+///
+/// * `template` - The Hugo template.
+///
+/// # Returns
+///
+/// The translated template, and the template actions that could not be translated.
+pub fn translate_hugo_template(template: &str) -> (String, Vec<String>) {
+    let mut result = String::new();
+    let mut untranslated = Vec::new();
+    let mut remainder = template;
+    while let Some(start) = remainder.find("{{") {
+        let Some(length) = remainder[start..].find("}}") else {
+            break;
+        };
+        let end = start + length + 2;
+        let action = remainder[start + 2..end - 2]
+            .trim_matches('-')
+            .trim()
+            .to_string();
+        result.push_str(&remainder[..start]);
+        let translation = HUGO_VARIABLES
+            .iter()
+            .find(|(variable, _)| {
+                if variable.ends_with('.') {
+                    action.starts_with(variable)
+                        && action[variable.len()..]
+                            .chars()
+                            .all(|x| x.is_alphanumeric() || x == '_')
+                } else {
+                    action == *variable
+                }
+            })
+            .map(|(variable, replacement)| {
+                // Parameters keep the case they are written with in frontmatter.
+                let key = &action[variable.len()..];
+                format!("{{{{ {}{} }}}}", replacement, key)
+            });
+        match translation {
+            Some(translation) => result.push_str(&translation),
+            None => {
+                result.push_str(&format!(
+                    "{{% comment %}}TODO: translate `{}`{{% endcomment %}}",
+                    action
+                ));
+                untranslated.push(action);
+            }
+        }
+        remainder = &remainder[end..];
+    }
+    result.push_str(remainder);
+    (result, untranslated)
+}
+
+/// Convert a Hugo language code into a locale.
+///
+/// # Arguments
+///
+/// * `language_code` - The Hugo language code, eg: `en-us`.
+///
+/// # Returns
+///
+/// The equivalent locale, eg: `en_US`.
+fn language_code_to_locale(language_code: &str) -> String {
+    match language_code.split_once(['-', '_']) {
+        Some((language, region)) => {
+            format!("{}_{}", language.to_lowercase(), region.to_uppercase())
+        }
+        None => language_code.to_lowercase(),
+    }
+}
+
+/// Read a Hugo site's configuration.
+///
+/// # Arguments
+///
+/// * `source` - The path to the Hugo site.
+///
+/// # Returns
+///
+/// The site's configuration, or an empty table if none is found.
+fn read_hugo_config(source: &Path) -> miette::Result<Table> {
+    for name in ["hugo", "config"] {
+        if let Ok(config) = std::fs::read_to_string(source.join(format!("{}.toml", name))) {
+            return config.parse::<Table>().into_diagnostic();
+        }
+        for extension in ["yaml", "yml", "json"] {
+            if let Ok(config) =
+                std::fs::read_to_string(source.join(format!("{}.{}", name, extension)))
+            {
+                return match yaml_to_toml(serde_yaml::from_str(&config).into_diagnostic()?) {
+                    Some(toml::Value::Table(table)) => Ok(table),
+                    _ => Ok(Table::new()),
+                };
+            }
+        }
+    }
+    Ok(Table::new())
+}
+
+/// Migrate a Hugo site to Vox.
+///
+/// # Arguments
+///
+/// * `source` - The path to the Hugo site.
+///
+/// * `destination` - The path to write the Vox site to.
+///
+/// # Returns
+///
+/// A summary of the migration.
+pub fn migrate_hugo(source: &Path, destination: &Path) -> miette::Result<MigrationReport> {
+    let provider = FsProvider::new();
+    let mut report = MigrationReport::default();
+    let config = read_hugo_config(source)?;
+
+    // The site configuration becomes the global context.
+    info!("Migrating site configuration … ");
+    let mut global = Table::new();
+    for (hugo_key, vox_key) in [
+        ("title", "title"),
+        ("baseURL", "url"),
+        ("params", "params"),
+        ("timeZone", "timezone"),
+    ] {
+        if let Some(value) = config.get(hugo_key) {
+            global.insert(vox_key.to_string(), value.clone());
+        }
+    }
+    if let Some(toml::Value::String(url)) = global.get_mut("url") {
+        *url = url.trim_end_matches('/').to_string();
+    }
+    if let Some(language_code) = config.get("languageCode").and_then(|x| x.as_str()) {
+        global.insert(
+            "locale".to_string(),
+            toml::Value::String(language_code_to_locale(language_code)),
+        );
+    }
+    provider.write_file(
+        destination.join("global.toml"),
+        toml::to_string(&global).into_diagnostic()?,
+    )?;
+
+    // Permalink configuration becomes the frontmatter defaults of each collection.
+    let mut permalink_sections = BTreeSet::new();
+    if let Some(permalinks) = config.get("permalinks").and_then(|x| x.as_table()) {
+        for (section, permalink) in permalinks {
+            // Permalinks may be configured per page kind, in which case only regular pages are relevant.
+            let Some(permalink) = permalink.as_str() else {
+                continue;
+            };
+            let mut defaults = Table::new();
+            defaults.insert(
+                "permalink".to_string(),
+                toml::Value::String(hugo_path_to_permalink(permalink)),
+            );
+            provider.write_file(
+                destination.join(section).join("_defaults.toml"),
+                toml::to_string(&defaults).into_diagnostic()?,
+            )?;
+            permalink_sections.insert(section.clone());
+        }
+        if let Some(toml::Value::Table(page_permalinks)) = permalinks.get("page") {
+            for (section, permalink) in page_permalinks {
+                if let Some(permalink) = permalink.as_str() {
+                    let mut defaults = Table::new();
+                    defaults.insert(
+                        "permalink".to_string(),
+                        toml::Value::String(hugo_path_to_permalink(permalink)),
+                    );
+                    provider.write_file(
+                        destination.join(section).join("_defaults.toml"),
+                        toml::to_string(&defaults).into_diagnostic()?,
+                    )?;
+                    permalink_sections.insert(section.clone());
+                }
+            }
+        }
+    }
+
+    // Templates become layouts.
+    let layouts_path = source.join("layouts");
+    let mut layout_names = BTreeSet::new();
+    for template_path in glob::glob(&format!("{}/**/*.html", layouts_path.to_string_lossy()))
+        .into_diagnostic()?
+        .filter_map(Result::ok)
+    {
+        let relative_path = template_path
+            .strip_prefix(&layouts_path)
+            .into_diagnostic()?
+            .with_extension("");
+        // Templates in `_default` are the most general, and keep their names; others are named after their directory.
+        let layout_name = relative_path
+            .components()
+            .map(|x| x.as_os_str().to_string_lossy().to_string())
+            .filter(|x| x != "_default")
+            .collect::<Vec<_>>()
+            .join("_");
+        debug!("Migrating layout: {:?} … ", template_path);
+        let template = std::fs::read_to_string(&template_path).into_diagnostic()?;
+        let (layout, untranslated) = translate_hugo_template(&template);
+        provider.write_file(
+            destination
+                .join("layouts")
+                .join(format!("{}.vox", layout_name)),
+            format!("---\n---\n{}", layout),
+        )?;
+        if !untranslated.is_empty() {
+            report.hints.push(format!(
+                "`layouts/{}.vox` has {} template actions needing translation by hand, marked with `TODO`.",
+                layout_name,
+                untranslated.len()
+            ));
+        }
+        layout_names.insert(layout_name);
+        report.layouts += 1;
+    }
+    if layout_names.contains("baseof") {
+        report.hints.push("Hugo's `baseof` template defines blocks that other templates fill; in Vox, instead give layouts the `baseof` layout and use `{{ layouts | map: \"rendered\" | first }}` where the block was.".to_string());
+    }
+
+    // Content becomes pages.
+    let content_path = source.join("content");
+    for content_file in glob::glob(&format!("{}/**/*", content_path.to_string_lossy()))
+        .into_diagnostic()?
+        .filter_map(Result::ok)
+        .filter(|x| x.is_file())
+    {
+        let relative_path = content_file
+            .strip_prefix(&content_path)
+            .into_diagnostic()?
+            .to_path_buf();
+        let extension = relative_path
+            .extension()
+            .map(|x| x.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let is_markdown = ["md", "markdown"].contains(&extension.as_str());
+        if !is_markdown && extension != "html" {
+            // Page resources are copied alongside the other assets of the site.
+            let resource_path = destination.join("assets").join(&relative_path);
+            provider.write_file(
+                resource_path,
+                std::fs::read(&content_file).into_diagnostic()?,
+            )?;
+            continue;
+        }
+        let parent = relative_path.parent().unwrap_or(Path::new(""));
+        let stem = relative_path
+            .file_stem()
+            .map(|x| x.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let section = parent
+            .components()
+            .next()
+            .map(|x| x.as_os_str().to_string_lossy().to_string());
+        // Leaf bundles are named after their directory, and list pages are pages depending on their section.
+        let (page_path, is_list) = match stem.as_str() {
+            "_index" => match parent.to_string_lossy().as_ref() {
+                "" => (PathBuf::from("index.vox"), true),
+                parent => (
+                    PathBuf::from("pages").join(format!("{}_index.vox", parent.replace('/', "_"))),
+                    true,
+                ),
+            },
+            "index" if !parent.as_os_str().is_empty() => (parent.with_extension("vox"), false),
+            _ => (parent.join(format!("{}.vox", stem)), false),
+        };
+        debug!("Migrating page: {:?} … ", content_file);
+        let contents = std::fs::read_to_string(&content_file).into_diagnostic()?;
+        let (frontmatter, body) = parse_hugo_frontmatter(&contents)?;
+        let mut frontmatter = normalise_hugo_frontmatter(frontmatter);
+        if is_list && !parent.as_os_str().is_empty() {
+            frontmatter.insert(
+                "depends".to_string(),
+                toml::Value::Array(vec![toml::Value::String(
+                    parent.to_string_lossy().replace('/', "_"),
+                )]),
+            );
+        }
+        let default_layout = if is_list { "list" } else { "single" };
+        if !frontmatter.contains_key("layout") && layout_names.contains(default_layout) {
+            frontmatter.insert(
+                "layout".to_string(),
+                toml::Value::String(default_layout.to_string()),
+            );
+        }
+        let has_section_permalink = !is_list
+            && section
+                .as_ref()
+                .is_some_and(|x| permalink_sections.contains(x));
+        if !frontmatter.contains_key("permalink") && !has_section_permalink {
+            let permalink = if is_list {
+                format!("{}/index.html", parent.to_string_lossy())
+            } else {
+                let slug = frontmatter
+                    .get("slug")
+                    .and_then(|x| x.as_str())
+                    .map(|x| x.to_string())
+                    .unwrap_or(page_path.with_extension("").to_string_lossy().to_string());
+                match parent.to_string_lossy().as_ref() {
+                    "" => format!("{}/index.html", slug),
+                    _ => format!(
+                        "{}/{}/index.html",
+                        parent.to_string_lossy(),
+                        slug.rsplit('/').next().unwrap_or_default()
+                    ),
+                }
+            };
+            frontmatter.insert(
+                "permalink".to_string(),
+                toml::Value::String(permalink.trim_start_matches('/').to_string()),
+            );
+        }
+        let (body, unsupported) = convert_hugo_shortcodes(&body);
+        for shortcode in unsupported {
+            report
+                .unsupported_shortcodes
+                .entry(shortcode)
+                .or_default()
+                .insert(page_path.clone());
+        }
+        let body = if is_markdown {
+            format!("{{% markdown %}}\n{}\n{{% endmarkdown %}}", body.trim())
+        } else {
+            body
+        };
+        provider.write_file(
+            destination.join(&page_path),
+            format!(
+                "---\n{}---\n{}\n",
+                toml::to_string(&frontmatter).into_diagnostic()?,
+                body
+            ),
+        )?;
+        report.pages += 1;
+    }
+    if source.join("static").is_dir() {
+        for static_file in glob::glob(&format!("{}/static/**/*", source.to_string_lossy()))
+            .into_diagnostic()?
+            .filter_map(Result::ok)
+            .filter(|x| x.is_file())
+        {
+            let relative_path = static_file
+                .strip_prefix(source.join("static"))
+                .into_diagnostic()?;
+            provider.write_file(
+                destination.join("assets").join(relative_path),
+                std::fs::read(&static_file).into_diagnostic()?,
+            )?;
+        }
+        report.hints.push("Static files and page resources were copied to `assets/`; Vox does not copy them to the output, so copy them before or after building.".to_string());
+    }
+    Ok(report)
+}
+
+/// The namespace of the `content:encoded` element of RSS feeds.
+const CONTENT_NAMESPACE: &str = "http://purl.org/rss/1.0/modules/content/";
+
+/// The namespace of the `dc:creator` element of RSS feeds.
+const DUBLIN_CORE_NAMESPACE: &str = "http://purl.org/dc/elements/1.1/";
+
+/// The prefix of the namespaces of elements specific to WordPress exports.
+const WORDPRESS_NAMESPACE: &str = "http://wordpress.org/export/";
+
+/// File extensions of images downloaded when importing.
+const IMAGE_EXTENSIONS: [&str; 8] = ["png", "jpg", "jpeg", "gif", "webp", "svg", "avif", "bmp"];
+
+/// Find the text of a child element.
+///
+/// # Arguments
+///
+/// * `node` - The parent element.
+///
+/// * `name` - The name of the child element.
+///
+/// * `namespace` - The namespace (or prefix thereof) of the child element, if any.
+///
+/// # Returns
+///
+/// The text of the first matching child element, if it exists and is not empty.
+fn child_text(node: roxmltree::Node, name: &str, namespace: Option<&str>) -> Option<String> {
+    node.children()
+        .find(|child| {
+            child.tag_name().name() == name
+                && match (namespace, child.tag_name().namespace()) {
+                    (None, None) => true,
+                    (Some(namespace), Some(child_namespace)) => {
+                        child_namespace.starts_with(namespace)
+                    }
+                    _ => false,
+                }
+        })
+        .and_then(|child| child.text())
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+}
+
+/// Obtain the path of a URL.
+///
+/// # Arguments
+///
+/// * `url` - An absolute URL.
+///
+/// # Returns
+///
+/// The path of the URL, without a leading slash, query, or fragment.
+fn url_path(url: &str) -> String {
+    let url = url.split_once("://").map(|x| x.1).unwrap_or(url);
+    let path = url.split_once('/').map(|x| x.1).unwrap_or_default();
+    path.split(['?', '#'])
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Obtain the host of a URL.
+///
+/// # Arguments
+///
+/// * `url` - An absolute URL.
+///
+/// # Returns
+///
+/// The host of the URL.
+fn url_host(url: &str) -> String {
+    let url = url.split_once("://").map(|x| x.1).unwrap_or(url);
+    url.split(['/', '?', '#'])
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Convert a date-time as written in a feed into a TOML date-time.
+///
+/// # Arguments
+///
+/// * `date` - A date-time formatted per RFC 2822, or as in WordPress exports (eg: `2024-01-02 10:00:00`, in UTC).
+///
+/// # Returns
+///
+/// The equivalent TOML date-time, if the date-time is valid.
+fn feed_date_to_toml(date: &str) -> Option<toml::Value> {
+    let datetime = chrono::DateTime::parse_from_rfc2822(date)
+        .map(|x| x.to_utc())
+        .or_else(|_| {
+            chrono::NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S").map(|x| x.and_utc())
+        })
+        .ok()?;
+    datetime
+        .to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+        .parse::<toml::value::Datetime>()
+        .ok()
+        .map(toml::Value::Datetime)
+}
+
+/// Rewrite the URLs in the `src` and `href` attributes of HTML.
+///
+/// # Arguments
+///
+/// * `html` - The HTML to rewrite.
+///
+/// * `rewrite` - A function returning the replacement of a URL, if it should be replaced.
+///
+/// # Returns
+///
+/// The HTML with its URLs rewritten.
+fn rewrite_urls(html: &str, mut rewrite: impl FnMut(&str) -> Option<String>) -> String {
+    let mut result = String::new();
+    let mut remainder = html;
+    while let Some((start, attribute)) = ["src=", "href="]
+        .iter()
+        .filter_map(|attribute| remainder.find(attribute).map(|x| (x, attribute)))
+        .min()
+    {
+        let value_start = start + attribute.len();
+        let Some(quote) = remainder[value_start..]
+            .chars()
+            .next()
+            .filter(|x| *x == '"' || *x == '\'')
+        else {
+            result.push_str(&remainder[..value_start]);
+            remainder = &remainder[value_start..];
+            continue;
+        };
+        let Some(length) = remainder[value_start + 1..].find(quote) else {
+            break;
+        };
+        let url = &remainder[value_start + 1..value_start + 1 + length];
+        result.push_str(&remainder[..value_start + 1]);
+        result.push_str(&rewrite(url).unwrap_or(url.to_string()));
+        remainder = &remainder[value_start + 1 + length..];
+    }
+    result.push_str(remainder);
+    result
+}
+
+/// Find WordPress shortcodes in a post.
+///
+/// # Arguments
+///
+/// * `content` - The content of the post.
+///
+/// # Returns
+///
+/// The names of the shortcodes used in the post.
+fn find_wordpress_shortcodes(content: &str) -> BTreeSet<String> {
+    let mut shortcodes = BTreeSet::new();
+    for (start, _) in content.match_indices('[') {
+        let remainder = &content[start + 1..];
+        let name: String = remainder
+            .chars()
+            .take_while(|x| x.is_ascii_lowercase() || *x == '_' || *x == '-')
+            .collect();
+        let Some(end) = remainder.find(']') else {
+            continue;
+        };
+        // Markdown links are not shortcodes.
+        let is_link = remainder[end + 1..].starts_with('(');
+        let is_shortcode = remainder[name.len()..].starts_with([' ', ']']);
+        if !name.is_empty() && is_shortcode && !is_link {
+            shortcodes.insert(name);
+        }
+    }
+    shortcodes
+}
+
+/// Download a file.
+///
+/// # Arguments
+///
+/// * `url` - The URL of the file.
+///
+/// # Returns
+///
+/// The contents of the file.
+fn download(url: &str) -> miette::Result<Vec<u8>> {
+    let response = ureq::get(url).call().into_diagnostic()?;
+    let mut contents = Vec::new();
+    std::io::Read::read_to_end(&mut response.into_reader(), &mut contents).into_diagnostic()?;
+    Ok(contents)
+}
+
+/// Import the posts of a WordPress export (WXR) file.
+///
+/// # Arguments
+///
+/// * `export` - The path to the export file.
+///
+/// * `destination` - The path to write the Vox site to.
+///
+/// * `download_images` - Whether or not to download referenced images into the site.
+///
+/// # Returns
+///
+/// A summary of the import.
+pub fn import_wxr(
+    export: &Path,
+    destination: &Path,
+    download_images: bool,
+) -> miette::Result<MigrationReport> {
+    let feed = std::fs::read_to_string(export).into_diagnostic()?;
+    import_feed(&feed, destination, true, download_images)
+}
+
+/// Import the posts of an RSS feed.
+///
+/// # Arguments
+///
+/// * `feed` - The path or URL of the feed.
+///
+/// * `destination` - The path to write the Vox site to.
+///
+/// * `download_images` - Whether or not to download referenced images into the site.
+///
+/// # Returns
+///
+/// A summary of the import.
+pub fn import_rss(
+    feed: &str,
+    destination: &Path,
+    download_images: bool,
+) -> miette::Result<MigrationReport> {
+    let feed = if feed.starts_with("http://") || feed.starts_with("https://") {
+        String::from_utf8_lossy(&download(feed)?).to_string()
+    } else {
+        std::fs::read_to_string(feed).into_diagnostic()?
+    };
+    import_feed(&feed, destination, false, download_images)
+}
+
+/// Import the items of an RSS feed, which may be a WordPress export.
+///
+/// # Arguments
+///
+/// * `feed` - The contents of the feed.
+///
+/// * `destination` - The path to write the Vox site to.
+///
+/// * `is_wordpress` - Whether or not the feed is a WordPress export.
+///
+/// * `download_images` - Whether or not to download referenced images into the site.
+///
+/// # Returns
+///
+/// A summary of the import.
+fn import_feed(
+    feed: &str,
+    destination: &Path,
+    is_wordpress: bool,
+    download_images: bool,
+) -> miette::Result<MigrationReport> {
+    let provider = FsProvider::new();
+    let mut report = MigrationReport::default();
+    let document = roxmltree::Document::parse(feed).into_diagnostic()?;
+    let channel = document
+        .descendants()
+        .find(|x| x.has_tag_name("channel"))
+        .ok_or(miette::miette!("No `channel` element found in the feed."))?;
+    let site_url = child_text(channel, "link", None)
+        .map(|x| x.trim_end_matches('/').to_string())
+        .unwrap_or_default();
+    let site_host = url_host(&site_url);
+
+    // The feed's metadata becomes the global context, unless importing into an existing site.
+    let global_path = destination.join("global.toml");
+    if !global_path.exists() {
+        info!("Importing site metadata … ");
+        let mut global = Table::new();
+        if let Some(title) = child_text(channel, "title", None) {
+            global.insert("title".to_string(), toml::Value::String(title));
+        }
+        if let Some(description) = child_text(channel, "description", None) {
+            global.insert("description".to_string(), toml::Value::String(description));
+        }
+        if !site_url.is_empty() {
+            global.insert("url".to_string(), toml::Value::String(site_url.clone()));
+        }
+        if let Some(language) = child_text(channel, "language", None) {
+            global.insert(
+                "locale".to_string(),
+                toml::Value::String(language_code_to_locale(&language)),
+            );
+        }
+        provider.write_file(&global_path, toml::to_string(&global).into_diagnostic()?)?;
+    }
+
+    let mut downloaded_images: AHashMap<String, Option<String>> = AHashMap::new();
+    let mut imported_paths: AHashSet<PathBuf> = AHashSet::new();
+    let mut unpublished = 0;
+    for item in channel.children().filter(|x| x.has_tag_name("item")) {
+        let post_type =
+            child_text(item, "post_type", Some(WORDPRESS_NAMESPACE)).unwrap_or("post".to_string());
+        if !["post", "page"].contains(&post_type.as_str()) {
+            continue;
+        }
+        if child_text(item, "status", Some(WORDPRESS_NAMESPACE))
+            .is_some_and(|status| status != "publish")
+        {
+            unpublished += 1;
+            continue;
+        }
+        let title = child_text(item, "title", None).unwrap_or_default();
+        let link = child_text(item, "link", None).unwrap_or_default();
+        let slug = child_text(item, "post_name", Some(WORDPRESS_NAMESPACE))
+            .or_else(|| {
+                url_path(&link)
+                    .trim_end_matches('/')
+                    .rsplit('/')
+                    .next()
+                    .map(|x| {
+                        Path::new(x)
+                            .with_extension("")
+                            .to_string_lossy()
+                            .to_string()
+                    })
+                    .filter(|x| !x.is_empty())
+            })
+            .unwrap_or(slugify(&title));
+        let collection = if post_type == "page" {
+            "pages"
+        } else {
+            "posts"
+        };
+        // Pages sharing a slug are numbered, rather than overwriting one another.
+        let page_path_of = |slug: &str| PathBuf::from(collection).join(format!("{}.vox", slug));
+        let slug = match imported_paths.contains(&page_path_of(&slug)) {
+            true => {
+                let numbered_slug = (2..)
+                    .map(|number| format!("{}-{}", slug, number))
+                    .find(|x| !imported_paths.contains(&page_path_of(x)))
+                    .unwrap_or_default();
+                warn!(
+                    "More than one page has the slug `{}`; importing `{}` as `{}` … ",
+                    slug, title, numbered_slug
+                );
+                numbered_slug
+            }
+            false => slug,
+        };
+        let page_path = page_path_of(&slug);
+        imported_paths.insert(page_path.clone());
+        debug!("Importing page: {:?} … ", page_path);
+
+        let mut frontmatter = Table::new();
+        frontmatter.insert("title".to_string(), toml::Value::String(title));
+        let date = child_text(item, "pubDate", None)
+            .and_then(|x| feed_date_to_toml(&x))
+            .or_else(|| {
+                child_text(item, "post_date_gmt", Some(WORDPRESS_NAMESPACE))
+                    .and_then(|x| feed_date_to_toml(&x))
+            });
+        if let Some(date) = date {
+            frontmatter.insert("date".to_string(), date);
+        }
+        if let Some(updated) = child_text(item, "post_modified_gmt", Some(WORDPRESS_NAMESPACE))
+            .and_then(|x| feed_date_to_toml(&x))
+        {
+            frontmatter.insert("updated".to_string(), updated);
+        }
+        if let Some(author) = child_text(item, "creator", Some(DUBLIN_CORE_NAMESPACE))
+            .or_else(|| child_text(item, "author", None))
+        {
+            frontmatter.insert("author".to_string(), toml::Value::String(author));
+        }
+        // WordPress distinguishes categories and tags by their domain.
+        let mut categories = Vec::new();
+        let mut tags = Vec::new();
+        for category in item.children().filter(|x| x.has_tag_name("category")) {
+            let Some(name) = category.text().map(|x| x.trim().to_string()) else {
+                continue;
+            };
+            match category.attribute("domain") {
+                Some("category") => categories.push(toml::Value::String(name)),
+                _ => tags.push(toml::Value::String(name)),
+            }
+        }
+        if !categories.is_empty() {
+            frontmatter.insert("categories".to_string(), toml::Value::Array(categories));
+        }
+        if !tags.is_empty() {
+            frontmatter.insert("tags".to_string(), toml::Value::Array(tags));
+        }
+        let encoded_content = child_text(item, "encoded", Some(CONTENT_NAMESPACE));
+        // WordPress excerpts are in a namespace beneath that of the export.
+        let excerpt = if is_wordpress {
+            child_text(item, "encoded", Some(WORDPRESS_NAMESPACE))
+        } else {
+            encoded_content
+                .as_ref()
+                .and_then(|_| child_text(item, "description", None))
+        };
+        if let Some(excerpt) = excerpt {
+            frontmatter.insert("excerpt".to_string(), toml::Value::String(excerpt));
+        }
+        // Existing URLs are kept, so that links to the site continue to work.
+        let path = url_path(&link);
+        let permalink = if path.is_empty() || link.contains('?') {
+            format!("{}/{}/index.html", collection, slug)
+        } else if path.ends_with('/') {
+            format!("{}index.html", path)
+        } else if Path::new(&path).extension().is_none() {
+            format!("{}/index.html", path)
+        } else {
+            path
+        };
+        frontmatter.insert("permalink".to_string(), toml::Value::String(permalink));
+
+        let content = encoded_content
+            .or_else(|| child_text(item, "description", None))
+            .unwrap_or_default();
+        let content = rewrite_urls(&content, |url| {
+            let is_image = Path::new(&url_path(url)).extension().is_some_and(|x| {
+                IMAGE_EXTENSIONS.contains(&x.to_string_lossy().to_lowercase().as_str())
+            });
+            if download_images && is_image && url.starts_with("http") {
+                let local_url = downloaded_images.entry(url.to_string()).or_insert_with(|| {
+                    let path = url_path(url);
+                    let path = path.strip_prefix("wp-content/uploads/").unwrap_or(&path);
+                    let local_path = if url_host(url) == site_host {
+                        PathBuf::from("images").join(path)
+                    } else {
+                        PathBuf::from("images").join(url_host(url)).join(path)
+                    };
+                    debug!("Downloading image: {} … ", url);
+                    match download(url).and_then(|image| {
+                        // The site's own assets are not copied to the output, so images are written where they are linked.
+                        provider.write_file(destination.join("output").join(&local_path), image)
+                    }) {
+                        Ok(_) => Some(format!("/{}", local_path.to_string_lossy())),
+                        Err(e) => {
+                            warn!("Unable to download image `{}`: {}", url, e);
+                            None
+                        }
+                    }
+                });
+                if local_url.is_some() {
+                    return local_url.clone();
+                }
+            }
+            // Links within the site become relative to its root.
+            if !site_host.is_empty() && url_host(url) == site_host {
+                return Some(format!("/{}", url_path(url)));
+            }
+            None
+        });
+        for shortcode in find_wordpress_shortcodes(&content) {
+            report
+                .unsupported_shortcodes
+                .entry(shortcode)
+                .or_default()
+                .insert(page_path.clone());
+        }
+        // Content is protected from being interpreted as Liquid.
+        let content = if content.contains("{{") || content.contains("{%") {
+            format!("{{% raw %}}{}{{% endraw %}}", content)
+        } else {
+            content
+        };
+        // WordPress separates paragraphs with blank lines rather than HTML, as Markdown does.
+        let content = if is_wordpress {
+            format!("{{% markdown %}}\n{}\n{{% endmarkdown %}}", content)
+        } else {
+            content
+        };
+        provider.write_file(
+            destination.join(&page_path),
+            format!(
+                "---\n{}---\n{}\n",
+                toml::to_string(&frontmatter).into_diagnostic()?,
+                content
+            ),
+        )?;
+        report.pages += 1;
+    }
+    if unpublished > 0 {
+        report.hints.push(format!(
+            "{} unpublished posts and pages were not imported.",
+            unpublished
+        ));
+    }
+    if downloaded_images.values().any(Option::is_some) {
+        report.hints.push("Images were downloaded to `output/images/`; removing all of the output, such as with `vox clean --all`, also removes them, so keep a copy.".to_string());
+    }
+    Ok(report)
+}
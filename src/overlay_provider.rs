@@ -0,0 +1,183 @@
+use crate::changes::Changes;
+use crate::git::{GitMetadata, RepositoryMetadata};
+use crate::math_block::MathCache;
+use crate::provider::{FileReader, InputProvider};
+use chrono::Locale;
+use chrono_tz::Tz;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// The folders the lower layer of an overlay contributes to the site.
+pub const LAYERED_DIRECTORIES: [&str; 3] = ["layouts", "snippets", "assets"];
+
+#[derive(Clone, Debug)]
+/// A source of a site layered above another, such as a site above its theme.
+/// The lower layer contributes only its layouts, snippets, and assets, and files in the upper layer take precedence over files at the same path in the lower layer.
+///
+/// An overlay is only a source of a site; to build a site with it, pair it with an output provider using a [`crate::split_provider::SplitProvider`].
+pub struct OverlayProvider<U: InputProvider, L: InputProvider> {
+    upper: U,
+    lower: L,
+}
+
+/// Determine if a path is within a folder the lower layer of an overlay contributes to the site.
+///
+/// # Arguments
+///
+/// * `path` - The path, relative to the site's directory.
+///
+/// # Returns
+///
+/// Whether or not the path is within a layered folder.
+fn is_layered(path: &Path) -> bool {
+    LAYERED_DIRECTORIES
+        .iter()
+        .any(|directory| path.starts_with(directory))
+}
+
+/// Combine the paths listed by the layers of an overlay.
+///
+/// # Arguments
+///
+/// * `upper` - The paths listed by the upper layer.
+///
+/// * `lower` - The paths listed by the lower layer.
+///
+/// # Returns
+///
+/// The paths listed by the upper layer, followed by those listed only by the lower layer.
+fn combine(mut upper: Vec<PathBuf>, lower: Vec<PathBuf>) -> Vec<PathBuf> {
+    let lower = lower
+        .into_iter()
+        .filter(|path| !upper.contains(path))
+        .collect::<Vec<_>>();
+    upper.extend(lower);
+    upper
+}
+
+impl<U: InputProvider, L: InputProvider> InputProvider for OverlayProvider<U, L> {
+    fn read_to_string(&self, path: impl AsRef<Path>) -> miette::Result<String> {
+        self.upper
+            .read_to_string(path.as_ref())
+            .or_else(|err| match is_layered(path.as_ref()) {
+                true => self.lower.read_to_string(path.as_ref()),
+                false => Err(err),
+            })
+    }
+    fn read_bytes(&self, path: impl AsRef<Path>) -> miette::Result<Vec<u8>> {
+        self.upper
+            .read_bytes(path.as_ref())
+            .or_else(|err| match is_layered(path.as_ref()) {
+                true => self.lower.read_bytes(path.as_ref()),
+                false => Err(err),
+            })
+    }
+    fn list_vox_files(&self) -> miette::Result<Vec<PathBuf>> {
+        Ok(combine(
+            self.upper.list_vox_files()?,
+            self.lower
+                .list_vox_files()?
+                .into_iter()
+                .filter(|path| path.starts_with("layouts"))
+                .collect(),
+        ))
+    }
+    fn list_markdown_files(&self) -> miette::Result<Vec<PathBuf>> {
+        self.upper.list_markdown_files()
+    }
+    fn list_html_files(&self) -> miette::Result<Vec<PathBuf>> {
+        self.upper.list_html_files()
+    }
+    fn list_snippets(&self) -> miette::Result<Vec<PathBuf>> {
+        Ok(combine(
+            self.upper.list_snippets()?,
+            self.lower.list_snippets()?,
+        ))
+    }
+    fn list_directory(&self, directory: &Path) -> miette::Result<Vec<PathBuf>> {
+        let upper = self.upper.list_directory(directory)?;
+        Ok(match is_layered(directory) {
+            true => combine(upper, self.lower.list_directory(directory)?),
+            false => upper,
+        })
+    }
+    fn list_assets(&self) -> miette::Result<Vec<PathBuf>> {
+        Ok(combine(
+            self.upper.list_assets()?,
+            self.lower.list_directory(Path::new("assets"))?,
+        ))
+    }
+    fn file_reader(&self) -> Option<FileReader> {
+        let Some(lower) = self.lower.file_reader() else {
+            return self.upper.file_reader();
+        };
+        let upper = self.upper.file_reader()?;
+        Some(Arc::new(move |path| {
+            upper(path).or_else(|err| match is_layered(path) {
+                true => lower(path),
+                false => Err(err),
+            })
+        }))
+    }
+    fn math_cache(&self) -> Option<MathCache> {
+        self.upper.math_cache()
+    }
+    fn refresh_git_history(&self) {
+        self.upper.refresh_git_history();
+        self.lower.refresh_git_history();
+    }
+    fn get_git_metadata(&self, path: &Path, locale: Locale, timezone: Tz) -> Option<GitMetadata> {
+        self.upper
+            .get_git_metadata(path, locale, timezone)
+            .or_else(|| self.lower.get_git_metadata(path, locale, timezone))
+    }
+    fn get_repository_metadata(&self) -> Option<RepositoryMetadata> {
+        self.upper.get_repository_metadata()
+    }
+    fn working_directory(&self) -> Option<PathBuf> {
+        self.upper.working_directory()
+    }
+    fn environment(&self) -> Option<String> {
+        self.upper.environment()
+    }
+    fn watch(&self) -> miette::Result<Option<Changes>> {
+        self.upper.watch()
+    }
+}
+
+impl<U: InputProvider, L: InputProvider> OverlayProvider<U, L> {
+    /// Create a source of a site layered above another.
+    ///
+    /// # Arguments
+    ///
+    /// * `upper` - The source whose files take precedence, such as a site.
+    ///
+    /// * `lower` - The source contributing layouts, snippets, and assets, such as a theme.
+    ///
+    /// # Returns
+    ///
+    /// A provider reading from `upper`, then from `lower`.
+    pub fn new(upper: U, lower: L) -> Self {
+        Self { upper, lower }
+    }
+
+    /// Get the source whose files take precedence.
+    ///
+    /// # Returns
+    ///
+    /// The upper layer.
+    pub fn upper(&self) -> &U {
+        &self.upper
+    }
+
+    /// Get the source contributing layouts, snippets, and assets.
+    ///
+    /// # Returns
+    ///
+    /// The lower layer.
+    pub fn lower(&self) -> &L {
+        &self.lower
+    }
+}
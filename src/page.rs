@@ -1,36 +1,79 @@
 use crate::{
-    date::Date,
-    error::{DateNotValid, FrontmatterNotFound, InvalidDependsProperty},
+    admonitions::{render_admonitions, AdmonitionSettings},
+    builds::CompiledTemplates,
+    content_block::extract_blocks,
+    date::{self, Date},
+    error::{
+        DateNotValid, FrontmatterNotFound, InvalidCollectionsProperty, InvalidDependsProperty,
+        InvalidLayoutProperty, InvalidWeightProperty,
+    },
+    git::GitMetadata,
+    markdown_block::render_markdown,
+    paths::{canonical_url, normalise_separators, slugify, to_slash_string},
+    schema::Schema,
+    search::html_to_text,
+    syntax_highlighting::SyntaxHighlightingSettings,
+    url_style::UrlStyle,
+    url_transforms::UrlTransforms,
+    wikilinks::resolve_wikilinks,
 };
 use chrono::Locale;
+use chrono_tz::Tz;
 use core::fmt;
-use liquid::{Object, Parser};
+use liquid::{
+    model::{DisplayCow, KStringCow, ObjectView, State, Value},
+    Object, Parser, ValueView,
+};
 use miette::IntoDiagnostic;
 use miette::NamedSource;
 use path_clean::PathClean;
 use serde::{Deserialize, Serialize};
 use std::{
+    cell::Cell,
+    cmp::Ordering,
+    collections::BTreeMap,
     ffi::OsString,
-    path::{Component, PathBuf},
+    path::{Component, Path, PathBuf},
+    sync::Arc,
 };
 use toml::Table;
 
+/// The number of words read per minute, used to estimate how long a page takes to read.
+pub const WORDS_PER_MINUTE: usize = 200;
+
 #[derive(PartialEq, Clone, Default, Debug, Serialize, Deserialize)]
 /// Internal representation of a page.
 pub struct Page {
     /// A page's contextual data, represented as TOML at the head of the file.
     pub data: Table,
     /// A page's contents following the frontmatter.
-    pub content: String,
+    /// This is shared between copies of the page.
+    pub content: Arc<str>,
+    /// A hash of a page's frontmatter and contents, computed when the page is created.
+    #[serde(default)]
+    pub hash: String,
+    /// The number of words in a page's contents, not counting template tags or HTML markup.
+    #[serde(default)]
+    pub word_count: usize,
+    /// The estimated number of minutes needed to read a page's contents, rounded up.
+    #[serde(default)]
+    pub reading_time: usize,
     /// Data representing the output path of a page.
     /// This is defined in a page's frontmatter.
     pub permalink: String,
     /// A page's date-time metadata, formatted per the RFC 3339 standard.
     /// This is defined in a page's frontmatter.
     pub date: Option<Date>,
+    /// A page's date-time metadata indicating when it was last revised, formatted per the RFC 3339 standard.
+    /// This is defined in a page's frontmatter.
+    pub updated: Option<Date>,
+    /// The position of a page within its collections, with lower weights appearing first.
+    /// This is defined in a page's frontmatter, as either `weight` or `order`.
+    pub weight: Option<i64>,
     /// The collections a page belongs to.
     /// This is defined by a page's path, with one collection per path component, and collections including each successive path component as well.
     /// Example: `books/fantasy/page.vox` is in `books`, `fantasy`, and `books_fantasy`.
+    /// Pages other than layouts may also be added to collections in their frontmatter, regardless of their path.
     pub collections: Option<Vec<String>>,
     /// The collections a page depends on.
     /// This is defined in a page's frontmatter.
@@ -38,16 +81,105 @@ pub struct Page {
     /// The layout a page uses.
     /// This is defined in a page's frontmatter.
     pub layout: Option<String>,
+    /// The layouts wrapping a page's layout, from innermost to outermost.
+    /// This is defined in a page's frontmatter when `layout` is a list, and takes the place of the layouts those layouts would otherwise use.
+    #[serde(default)]
+    pub outer_layouts: Vec<String>,
     /// Path to the page, not including the page itself.
     pub directory: String,
     /// The page's base filename.
     pub name: String,
+    /// The format the page is written in, determined by its file extension.
+    #[serde(default)]
+    pub format: PageFormat,
     /// Whether or not a page is a layout.
     pub is_layout: bool,
     /// The output path of a file; a processed `permalink` value.
     pub url: String,
+    /// The absolute URL of a page; the site's `url` followed by the page's `url`.
+    /// Without a site URL, this is the page's URL relative to the site's root.
+    /// This is empty for pages which are not output.
+    #[serde(default)]
+    pub canonical_url: String,
     /// The rendered content of a page.
-    pub rendered: String,
+    /// This is shared between copies of the page.
+    pub rendered: Arc<str>,
+    /// A short summary of a page, computed when the page is rendered.
+    /// This is the `excerpt` defined in a page's frontmatter, the text of its rendered content preceding its excerpt separator, or otherwise the text of the first paragraph of its rendered content.
+    #[serde(default)]
+    pub excerpt: String,
+    /// The rendered contents of the named blocks a page provides to its layouts.
+    /// These are defined with `{% block name %}` … `{% endblock %}` in a page's contents.
+    #[serde(default)]
+    pub blocks: BTreeMap<String, String>,
+    /// Whether or not the `collections` context was read when the page was last rendered.
+    #[serde(skip)]
+    pub reads_collections: bool,
+    /// A page's metadata derived from its version control history.
+    /// This is only available when the `git` feature is enabled.
+    pub git: Option<GitMetadata>,
+    /// The page following this page in its collection, without its content.
+    /// A page's collection is the last of the collections it belongs to, ordered by weight then date.
+    pub next: Option<Box<Page>>,
+    /// The page preceding this page in its collection, without its content.
+    /// A page's collection is the last of the collections it belongs to, ordered by weight then date.
+    pub previous: Option<Box<Page>>,
+    /// The pages most related to this page, without their content.
+    /// This is only computed when the `related` table is present in `global.toml`.
+    #[serde(default)]
+    pub related: Vec<Page>,
+    /// The pages this page links to with wikilinks, without their content.
+    #[serde(default)]
+    pub links: Vec<Page>,
+    /// The pages linking to this page with wikilinks, without their content.
+    #[serde(default)]
+    pub backlinks: Vec<Page>,
+}
+
+#[derive(Eq, PartialEq, Clone, Copy, Default, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// The format a page is written in.
+pub enum PageFormat {
+    #[default]
+    /// A Vox page (`.vox`), whose body is a Liquid template.
+    Vox,
+    /// A Markdown page (`.md`), whose body is rendered as Markdown after being rendered as a Liquid template.
+    Markdown,
+    /// An HTML page (`.html`), whose body is output verbatim, or inserted into its layout if it has one.
+    Html,
+}
+
+impl PageFormat {
+    /// Determine the format of a page from its path.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the page.
+    ///
+    /// # Returns
+    ///
+    /// The format of the page, or `None` if the path does not have the extension of a page.
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        match path.as_ref().extension()?.to_str()? {
+            "vox" => Some(Self::Vox),
+            "md" => Some(Self::Markdown),
+            "html" => Some(Self::Html),
+            _ => None,
+        }
+    }
+
+    /// Get the file extension of pages in this format.
+    ///
+    /// # Returns
+    ///
+    /// The file extension, without a leading period.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Vox => "vox",
+            Self::Markdown => "md",
+            Self::Html => "html",
+        }
+    }
 }
 
 impl fmt::Display for Page {
@@ -58,10 +190,11 @@ impl fmt::Display for Page {
 
 impl Page {
     /// Determine if a page is a layout based on its path.
+    /// The path is only interpreted lexically; the file system is not accessed.
     ///
     /// # Arguments
     ///
-    /// * `path` - The path to the page.
+    /// * `path` - The path to the page, relative to the site's directory.
     ///
     /// # Returns
     ///
@@ -71,10 +204,11 @@ impl Page {
     }
 
     /// Get the names of the collections a page belongs to based on its path.
+    /// The path is only interpreted lexically; the file system is not accessed.
     ///
     /// # Arguments
     ///
-    /// * `path` - The path to the page.
+    /// * `path` - The path to the page, relative to the site's directory.
     ///
     /// # Returns
     ///
@@ -97,7 +231,7 @@ impl Page {
             .collect();
         if let Some(first_path_component) = path_components.first() {
             if first_path_component.extension().is_some()
-                || *first_path_component == PathBuf::from("layouts")
+                || *first_path_component == Path::new("layouts")
             {
                 return Ok(None);
             }
@@ -115,12 +249,27 @@ impl Page {
                 })
                 .collect();
             results.push(path_to_this_component.join("_"));
-            results.push(path_component.to_string_lossy().to_string())
+            results.push(to_slash_string(&path_component))
         }
         results.dedup();
         Ok(Some(results))
     }
 
+    /// Determine if a file begins with frontmatter.
+    /// Markdown files are only pages if they begin with frontmatter, so that other Markdown files (eg: a `README.md`) are left alone.
+    /// HTML files without frontmatter are pages output verbatim.
+    ///
+    /// # Arguments
+    ///
+    /// * `contents` - The contents of the file.
+    ///
+    /// # Returns
+    ///
+    /// Whether or not the file's first non-empty line begins its frontmatter.
+    pub fn begins_with_frontmatter(contents: &str) -> bool {
+        contents.lines().find(|x| !x.trim().is_empty()) == Some("---")
+    }
+
     /// Determine if two pages are equivalent despite their rendered content.
     ///
     /// # Arguments
@@ -133,16 +282,135 @@ impl Page {
     ///
     /// Whether or not the two pages are equivalent.
     pub fn are_equivalent(lhs: &Page, rhs: &Page) -> bool {
-        lhs.data == rhs.data
-            && lhs.content == rhs.content
+        lhs.hash == rhs.hash
             && lhs.permalink == rhs.permalink
             && lhs.date == rhs.date
+            && lhs.updated == rhs.updated
+            && lhs.weight == rhs.weight
             && lhs.collections == rhs.collections
             && lhs.depends == rhs.depends
             && lhs.layout == rhs.layout
+            && lhs.outer_layouts == rhs.outer_layouts
             && lhs.directory == rhs.directory
             && lhs.name == rhs.name
             && lhs.is_layout == rhs.is_layout
+            && lhs.git == rhs.git
+            && Self::are_neighbours_equivalent(&lhs.next, &rhs.next)
+            && Self::are_neighbours_equivalent(&lhs.previous, &rhs.previous)
+            && Self::are_linked_pages_equivalent(&lhs.related, &rhs.related)
+            && Self::are_linked_pages_equivalent(&lhs.links, &rhs.links)
+            && Self::are_linked_pages_equivalent(&lhs.backlinks, &rhs.backlinks)
+    }
+
+    /// Determine if two lists of linked pages are equivalent despite their rendered URLs.
+    ///
+    /// # Arguments
+    ///
+    /// * `lhs` - The first list of linked pages to compare.
+    ///
+    /// * `rhs` - The second list of linked pages to compare.
+    ///
+    /// # Returns
+    ///
+    /// Whether or not the two lists of linked pages are equivalent.
+    fn are_linked_pages_equivalent(lhs: &[Page], rhs: &[Page]) -> bool {
+        lhs.len() == rhs.len()
+            && lhs
+                .iter()
+                .zip(rhs.iter())
+                .all(|(lhs, rhs)| lhs.is_equivalent(rhs))
+    }
+
+    /// Determine if two neighbouring pages are equivalent despite their rendered URLs.
+    ///
+    /// # Arguments
+    ///
+    /// * `lhs` - The first neighbouring page to compare, if any.
+    ///
+    /// * `rhs` - The second neighbouring page to compare, if any.
+    ///
+    /// # Returns
+    ///
+    /// Whether or not the two neighbouring pages are equivalent.
+    fn are_neighbours_equivalent(lhs: &Option<Box<Page>>, rhs: &Option<Box<Page>>) -> bool {
+        match (lhs, rhs) {
+            (Some(lhs), Some(rhs)) => lhs.is_equivalent(rhs),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    /// Obtain a copy of a page suitable for linking to it from a neighbouring page.
+    ///
+    /// # Returns
+    ///
+    /// A copy of the page without its content, neighbours, related pages, or links.
+    pub fn to_neighbour(&self) -> Page {
+        Page {
+            content: Arc::default(),
+            hash: Self::hash_source(&self.data, ""),
+            rendered: Arc::default(),
+            blocks: BTreeMap::new(),
+            next: None,
+            previous: None,
+            related: Vec::new(),
+            links: Vec::new(),
+            backlinks: Vec::new(),
+            ..self.clone()
+        }
+    }
+
+    /// Render the URLs of a page's neighbours, related pages, and linked pages.
+    ///
+    /// # Arguments
+    ///
+    /// * `contexts` - The Liquid contexts to render with.
+    ///
+    /// * `parser` - The Liquid parser to render with.
+    ///
+    /// * `url_transforms` - The transforms applied to every page's URL.
+    pub fn render_neighbour_urls(
+        &mut self,
+        contexts: &Object,
+        parser: &Parser,
+        url_transforms: &UrlTransforms,
+    ) -> miette::Result<()> {
+        for neighbour in [&mut self.next, &mut self.previous]
+            .into_iter()
+            .flatten()
+            .map(|x| x.as_mut())
+            .chain(self.related.iter_mut())
+            .chain(self.links.iter_mut())
+            .chain(self.backlinks.iter_mut())
+        {
+            let mut neighbour_contexts = contexts.clone();
+            neighbour_contexts.insert(
+                "page".into(),
+                liquid_core::Value::Object(liquid::to_object(&neighbour).into_diagnostic()?),
+            );
+            neighbour.render_url(&neighbour_contexts, parser, url_transforms)?;
+        }
+        Ok(())
+    }
+
+    /// Compare two pages by their order within a collection.
+    /// Pages are ordered by weight, then by date; pages without a weight or date appear last.
+    ///
+    /// # Arguments
+    ///
+    /// * `lhs` - The first page to compare.
+    ///
+    /// * `rhs` - The second page to compare.
+    ///
+    /// # Returns
+    ///
+    /// The ordering of the two pages.
+    pub fn collection_order(lhs: &Page, rhs: &Page) -> Ordering {
+        let lhs_date = lhs.date.as_ref().and_then(|x| x.to_datetime());
+        let rhs_date = rhs.date.as_ref().and_then(|x| x.to_datetime());
+        (lhs.weight.is_none(), lhs.weight)
+            .cmp(&(rhs.weight.is_none(), rhs.weight))
+            .then((lhs_date.is_none(), lhs_date).cmp(&(rhs_date.is_none(), rhs_date)))
     }
 
     /// Determine if a page is equivalent to another page aside from rendered content.
@@ -158,22 +426,44 @@ impl Page {
         Self::are_equivalent(self, other)
     }
 
+    /// Compute a hash of a page's source.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The page's frontmatter.
+    ///
+    /// * `content` - The page's contents following the frontmatter.
+    ///
+    /// # Returns
+    ///
+    /// The BLAKE3 hash of the page's source, as a hexadecimal string.
+    pub fn hash_source(data: &Table, content: &str) -> String {
+        let data = data.to_string();
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&(data.len() as u64).to_le_bytes());
+        hasher.update(data.as_bytes());
+        hasher.update(content.as_bytes());
+        hasher.finalize().to_hex().to_string()
+    }
+
     /// Determine if a page is a layout.
+    /// This is determined from the page's path when the page is created.
     ///
     /// # Returns
     ///
     /// Whether or not the page is a layout.
     pub fn is_layout(&self) -> bool {
-        Page::is_layout_path(self.to_path_string())
+        self.is_layout
     }
 
     /// Get the names of the collections a page belongs to.
+    /// These are determined from the page's path when the page is created.
     ///
     /// # Returns
     ///
     /// The names of the collections a page belongs to, or `None` if the page does not belong to a collection.
     pub fn get_collections(&self) -> miette::Result<Option<Vec<String>>> {
-        Page::get_collections_from_path(self.to_path_string())
+        Ok(self.collections.clone())
     }
 
     /// Renders a page's content and URL.
@@ -184,20 +474,75 @@ impl Page {
     ///
     /// * `parser` - The Liquid parser to render with.
     ///
+    /// * `templates` - The templates compiled during the build, which layouts are compiled into once.
+    ///
+    /// * `url_transforms` - The transforms applied to every page's URL.
+    ///
     /// # Returns
     ///
     /// Whether or not the page changed when rendering.
-    pub fn render(&mut self, contexts: &Object, parser: &Parser) -> miette::Result<bool> {
-        let permalink_changed = self.render_url(contexts, parser)?;
-        let rendered_content = parser
-            .parse(&self.content)
-            .into_diagnostic()?
-            .render(contexts)
+    pub fn render(
+        &mut self,
+        contexts: &Object,
+        parser: &Parser,
+        templates: &mut CompiledTemplates,
+        url_transforms: &UrlTransforms,
+    ) -> miette::Result<bool> {
+        let permalink_changed = self.render_url(contexts, parser, url_transforms)?;
+        let (rendered_content, blocks) = if self.format == PageFormat::Html {
+            // HTML pages are not templates, and are output as written.
+            (self.content.to_string(), BTreeMap::new())
+        } else {
+            // Layouts are shared by many pages, so their templates are reused.
+            let tracked_contexts = CollectionsReadTracker::new(contexts);
+            let rendered_content = match self.is_layout {
+                true => templates
+                    .get_or_parse(&self.content, parser)?
+                    .render(&tracked_contexts),
+                false => parser
+                    .parse(&self.content)
+                    .into_diagnostic()?
+                    .render(&tracked_contexts),
+            }
             .into_diagnostic()?;
-        if !permalink_changed && rendered_content == self.rendered {
+            self.reads_collections = tracked_contexts.read.get();
+            // Blocks provided to the page's layouts are not part of its rendered content.
+            let (rendered_content, blocks) = extract_blocks(&rendered_content);
+            let rendered_content = match self.format {
+                PageFormat::Markdown => {
+                    let global = contexts.get("global").map(|x| x.as_view());
+                    render_admonitions(
+                        &render_markdown(
+                            rendered_content,
+                            &SyntaxHighlightingSettings::from_global(global).into_diagnostic()?,
+                        ),
+                        &AdmonitionSettings::from_global(global).into_diagnostic()?,
+                    )
+                }
+                _ => rendered_content,
+            };
+            (resolve_wikilinks(&rendered_content, &self.links), blocks)
+        };
+        // A page's excerpt separator takes precedence over that of the site.
+        let excerpt_separator = self
+            .data
+            .get("excerpt_separator")
+            .and_then(|x| x.as_str())
+            .map(|x| x.to_string())
+            .or_else(|| {
+                contexts
+                    .get("global")?
+                    .as_object()?
+                    .get("excerpt_separator")?
+                    .as_scalar()
+                    .map(|x| x.to_kstr().to_string())
+            });
+        self.excerpt = self.summarise(&rendered_content, excerpt_separator.as_deref());
+        if !permalink_changed && *rendered_content == *self.rendered && blocks == self.blocks {
             return Ok(false);
         }
-        self.rendered = rendered_content;
+        self.rendered = rendered_content.into();
+        self.blocks = blocks;
         Ok(true)
     }
 
@@ -209,11 +554,29 @@ impl Page {
     ///
     /// * `parser` - The Liquid parser to render with.
     ///
+    /// * `url_transforms` - The transforms applied to every page's URL.
+    ///
     /// # Returns
     ///
     /// Whether or not the page's URL changed when rendering.
-    pub fn render_url(&mut self, contexts: &Object, parser: &Parser) -> miette::Result<bool> {
-        let expanded_permalink = match self.permalink.as_str() {
+    pub fn render_url(
+        &mut self,
+        contexts: &Object,
+        parser: &Parser,
+        url_transforms: &UrlTransforms,
+    ) -> miette::Result<bool> {
+        // Pages without a permalink of their own use that of their collection, if it has one.
+        let (permalink, collection) = match self.permalink.is_empty() {
+            true => self
+                .collection_permalink(contexts)
+                .map(|(collection, template)| (template, Some(collection)))
+                .unwrap_or_default(),
+            false => (self.permalink.clone(), None),
+        };
+        let collection = collection
+            .or_else(|| self.collections.as_ref().and_then(|x| x.last().cloned()))
+            .unwrap_or_default();
+        let expanded_permalink = match permalink.as_str() {
             "date" => {
                 "{{ page.collections.last }}/{{ page.date.year }}/{{ page.date.month }}/{{ page.date.day }}/{{ page.data.title }}.html".to_owned()
             }
@@ -231,21 +594,171 @@ impl Page {
                 "{{ page.collections.last }}/{{ page.data.title }}.html".to_owned()
             }
             _ => {
-                self.permalink.to_owned()
+                self.expand_permalink_tokens(&permalink, &collection)
             }
         };
-        let rendered_permalink = parser
-            .parse(&expanded_permalink)
+        // URLs are separated by forward slashes, even if rendered from paths on platforms that use backslashes.
+        let rendered_permalink = normalise_separators(
+            &parser
+                .parse(&expanded_permalink)
+                .into_diagnostic()?
+                .render(contexts)
+                .into_diagnostic()?,
+        );
+        let rendered_permalink = UrlStyle::from_global(contexts.get("global").map(|x| x.as_view()))
             .into_diagnostic()?
-            .render(contexts)
-            .into_diagnostic()?;
-        if rendered_permalink == self.url {
+            .apply(&rendered_permalink);
+        // Pages without URLs are not output, so there is nothing to transform.
+        let rendered_permalink = match rendered_permalink.is_empty() {
+            true => rendered_permalink,
+            false => url_transforms.apply(self, rendered_permalink),
+        };
+        let site_url = contexts
+            .get("global")
+            .and_then(|x| x.as_object())
+            .and_then(|x| x.get("url"))
+            .filter(|x| x.is_scalar())
+            .map(|x| x.to_kstr().to_string());
+        let canonical_url = match rendered_permalink.is_empty() {
+            true => String::new(),
+            false => canonical_url(&rendered_permalink, site_url.as_deref()),
+        };
+        if rendered_permalink == self.url && canonical_url == self.canonical_url {
             return Ok(false);
         }
         self.url = rendered_permalink;
+        self.canonical_url = canonical_url;
         Ok(true)
     }
 
+    /// Get the permalink template of the collections a page is in.
+    /// Templates are defined by the `permalink` of a collection's table within the `collections` table of `global.toml`.
+    /// Of the collections with templates, the last the page is in is used.
+    ///
+    /// # Arguments
+    ///
+    /// * `contexts` - The Liquid contexts to render with.
+    ///
+    /// # Returns
+    ///
+    /// The name of the collection with its template, or `None` if none of the page's collections have a template.
+    pub fn collection_permalink(&self, contexts: &Object) -> Option<(String, String)> {
+        let collection_settings = contexts
+            .get("global")?
+            .as_object()?
+            .get("collections")?
+            .as_object()?;
+        self.collections
+            .iter()
+            .flatten()
+            .rev()
+            .find_map(|collection| {
+                let template = collection_settings
+                    .get(collection.as_str())?
+                    .as_object()?
+                    .get("permalink")?
+                    .as_scalar()?
+                    .to_kstr()
+                    .to_string();
+                Some((collection.clone(), template))
+            })
+    }
+
+    /// Get the value a permalink token expands into for a page.
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - The name of the token, without its braces.
+    ///
+    /// * `collection` - The collection `{collection}` expands into.
+    ///
+    /// # Returns
+    ///
+    /// The value of the token, which is empty if the page has no value for it, or `None` if there is no such token.
+    fn permalink_token_value(&self, token: &str, collection: &str) -> Option<String> {
+        let data_string = |key: &str| {
+            self.data
+                .get(key)
+                .and_then(|x| x.as_str())
+                .map(|x| x.to_string())
+        };
+        let date_part = |part: fn(&Date) -> &String| {
+            self.date
+                .as_ref()
+                .map(|x| part(x).clone())
+                .unwrap_or_default()
+        };
+        let value = match token {
+            "collection" => collection.to_string(),
+            "parent" => self
+                .directory
+                .rsplit('/')
+                .next()
+                .unwrap_or_default()
+                .to_string(),
+            "name" => self.name.clone(),
+            "title" => slugify(&data_string("title").unwrap_or(self.name.clone())),
+            "slug" => data_string("slug")
+                .unwrap_or(slugify(&data_string("title").unwrap_or(self.name.clone()))),
+            "year" => date_part(|x| &x.year),
+            "short_year" => date_part(|x| &x.short_year),
+            "month" => date_part(|x| &x.month),
+            "day" => date_part(|x| &x.day),
+            "hour" => date_part(|x| &x.hour),
+            "minute" => date_part(|x| &x.minute),
+            "second" => date_part(|x| &x.second),
+            "y_day" => date_part(|x| &x.y_day),
+            "week" => date_part(|x| &x.week),
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    /// Expand the tokens within a permalink, such as `{year}` and `{slug}`, before it is rendered with Liquid.
+    /// Braces belonging to Liquid, such as those of `{{ page.url }}`, and unknown tokens are left as they are.
+    /// Tokens for which the page has no value, such as `{year}` for an undated page, are removed along with the slash following them.
+    ///
+    /// # Arguments
+    ///
+    /// * `permalink` - The permalink.
+    ///
+    /// * `collection` - The collection `{collection}` expands into.
+    ///
+    /// # Returns
+    ///
+    /// The permalink with its tokens replaced by the page's values.
+    pub fn expand_permalink_tokens(&self, permalink: &str, collection: &str) -> String {
+        let mut expanded = String::with_capacity(permalink.len());
+        let mut rest = permalink;
+        while let Some(start) = rest.find('{') {
+            let (before, from_brace) = rest.split_at(start);
+            expanded.push_str(before);
+            let token = from_brace[1..]
+                .split_once('}')
+                .map(|(token, _)| token)
+                .filter(|x| {
+                    !x.is_empty() && x.chars().all(|x| x.is_ascii_alphanumeric() || x == '_')
+                })
+                .filter(|_| !expanded.ends_with('{'));
+            let value = token.and_then(|x| self.permalink_token_value(x, collection));
+            match (token, value) {
+                (Some(token), Some(value)) => {
+                    rest = &from_brace[token.len() + 2..];
+                    if value.is_empty() {
+                        rest = rest.strip_prefix('/').unwrap_or(rest);
+                    }
+                    expanded.push_str(&value);
+                }
+                _ => {
+                    expanded.push('{');
+                    rest = &from_brace[1..];
+                }
+            }
+        }
+        expanded.push_str(rest);
+        expanded
+    }
+
     /// Separate a page's contents into the frontmatter and body.
     ///
     /// # Arguments
@@ -284,24 +797,109 @@ impl Page {
         Ok((frontmatter, body))
     }
 
+    /// Merge frontmatter over a set of defaults.
+    /// Tables present in both are merged recursively; otherwise, values in the frontmatter take precedence.
+    ///
+    /// # Arguments
+    ///
+    /// * `defaults` - The default frontmatter values.
+    ///
+    /// * `frontmatter` - The frontmatter values taking precedence.
+    ///
+    /// # Returns
+    ///
+    /// The merged frontmatter.
+    pub fn merge_frontmatter(mut defaults: Table, frontmatter: Table) -> Table {
+        for (key, value) in frontmatter {
+            let merged_value = match (defaults.remove(&key), value) {
+                (Some(toml::Value::Table(default_table)), toml::Value::Table(table)) => {
+                    toml::Value::Table(Self::merge_frontmatter(default_table, table))
+                }
+                (_, value) => value,
+            };
+            defaults.insert(key, merged_value);
+        }
+        defaults
+    }
+
     /// Create a representation of a page in memory.
     ///
     /// # Arguments
     ///
     /// * `contents` - The contents of the page.
     ///
-    /// * `path` - The path to the page.
+    /// * `path` - The path to the page, relative to the site's directory, as given by the provider.
     ///
     /// * `locale` - The locale used to render dates and times.
     ///
+    /// * `timezone` - The timezone used to render dates and times, unless the page specifies its own.
+    ///
+    /// * `defaults` - Frontmatter values used when the page does not specify its own.
+    ///
+    /// * `schema` - The schema the page's frontmatter must conform to.
+    ///
     /// # Returns
     ///
     /// An instance of a page.
-    pub fn new(contents: String, path: impl Into<PathBuf>, locale: Locale) -> miette::Result<Page> {
+    pub fn new(
+        contents: String,
+        path: impl Into<PathBuf>,
+        locale: Locale,
+        timezone: Tz,
+        defaults: Table,
+        schema: &Schema,
+    ) -> miette::Result<Page> {
         let path = path.into().clean();
-        let (frontmatter, body) = Self::get_frontmatter_and_body(contents.clone(), path.clone())?;
-        let frontmatter_data = frontmatter.parse::<Table>().into_diagnostic()?;
+        let format = PageFormat::from_path(&path).unwrap_or_default();
+        // HTML pages without frontmatter are output verbatim, so frontmatter defaults do not apply to them.
+        let verbatim = format == PageFormat::Html && !Self::begins_with_frontmatter(&contents);
+        let (frontmatter, body, defaults) = if verbatim {
+            (String::new(), contents.clone(), Table::new())
+        } else {
+            let (frontmatter, body) =
+                Self::get_frontmatter_and_body(contents.clone(), path.clone())?;
+            (frontmatter, body, defaults)
+        };
+        let frontmatter_data =
+            Self::merge_frontmatter(defaults, frontmatter.parse::<Table>().into_diagnostic()?);
+        let mut collections = Page::get_collections_from_path(path.clone())?;
+        // Collections listed in the frontmatter are added before those derived from the page's path, so that the last collection of a page, used in permalinks and to find neighbouring pages, remains derived from its path.
+        if !Page::is_layout_path(&path) {
+            if let Some(declared) = frontmatter_data.get("collections") {
+                let declared = declared
+                    .as_array()
+                    .and_then(|x| {
+                        x.iter()
+                            .map(|y| y.as_str().map(|z| z.to_string()))
+                            .collect::<Option<Vec<_>>>()
+                    })
+                    .ok_or(InvalidCollectionsProperty {
+                        src: NamedSource::new(path.to_string_lossy(), frontmatter.clone()),
+                    })
+                    .into_diagnostic()?;
+                let derived = collections.unwrap_or_default();
+                let mut merged = Vec::new();
+                for collection in declared {
+                    if !derived.contains(&collection) && !merged.contains(&collection) {
+                        merged.push(collection);
+                    }
+                }
+                merged.extend(derived);
+                collections = Some(merged);
+            }
+        }
+        // Layouts are templates for other pages, and are not subject to the schema, nor are pages output verbatim.
+        if !Page::is_layout_path(&path) && !verbatim {
+            schema
+                .for_collections(&collections.clone().unwrap_or_default())
+                .validate(&frontmatter_data, &frontmatter, &path)?;
+        }
         let frontmatter_data_clone = frontmatter_data.clone();
+        let timezone = frontmatter_data
+            .get("timezone")
+            .and_then(|x| x.as_str())
+            .map(|x| date::timezone_string_to_timezone(x.to_string()))
+            .unwrap_or(timezone);
         let date = if let Some(date) = frontmatter_data.get("date") {
             let date_value = date
                 .as_datetime()
@@ -309,16 +907,60 @@ impl Page {
                     src: NamedSource::new(path.to_string_lossy(), frontmatter.clone()),
                 })
                 .into_diagnostic()?;
-            Some(Date::value_to_date(*date_value, locale))
+            Some(Date::value_to_date(*date_value, locale, timezone))
+        } else {
+            None
+        };
+        let updated = if let Some(updated) = frontmatter_data.get("updated") {
+            let updated_value = updated
+                .as_datetime()
+                .ok_or(DateNotValid {
+                    src: NamedSource::new(path.to_string_lossy(), frontmatter.clone()),
+                })
+                .into_diagnostic()?;
+            Some(Date::value_to_date(*updated_value, locale, timezone))
         } else {
             None
         };
-        let layout = frontmatter_data_clone
-            .get("layout")
-            .map(|p| p.as_str().unwrap().to_string());
+        let weight = match frontmatter_data_clone
+            .get("weight")
+            .or(frontmatter_data_clone.get("order"))
+        {
+            Some(weight) => Some(
+                weight
+                    .as_integer()
+                    .ok_or(InvalidWeightProperty {
+                        src: NamedSource::new(path.to_string_lossy(), frontmatter.clone()),
+                    })
+                    .into_diagnostic()?,
+            ),
+            None => None,
+        };
+        // A list of layouts is a chain, with each layout wrapped by the next.
+        let mut layouts = match frontmatter_data_clone.get("layout") {
+            Some(toml::Value::String(layout)) => vec![layout.clone()],
+            Some(toml::Value::Array(layouts)) => layouts
+                .iter()
+                .map(|x| x.as_str().map(|y| y.to_string()))
+                .collect::<Option<Vec<_>>>()
+                .ok_or(InvalidLayoutProperty {
+                    src: NamedSource::new(path.to_string_lossy(), frontmatter.clone()),
+                })
+                .into_diagnostic()?,
+            Some(_) => {
+                return Err(InvalidLayoutProperty {
+                    src: NamedSource::new(path.to_string_lossy(), frontmatter.clone()),
+                })
+                .into_diagnostic()
+            }
+            None => Vec::new(),
+        };
+        let layout = (!layouts.is_empty()).then(|| layouts.remove(0));
+        // HTML pages are output at their own paths unless given a permalink.
         let permalink = frontmatter_data_clone
             .get("permalink")
-            .map(|p| p.as_str().unwrap().to_string());
+            .map(|p| p.as_str().unwrap().to_string())
+            .or((format == PageFormat::Html).then(|| to_slash_string(&path)));
         let depends = match frontmatter_data_clone.get("depends") {
             Some(depends) => Some(
                 depends
@@ -340,36 +982,228 @@ impl Page {
             ),
             None => None,
         };
+        let word_count = Self::count_words(&body);
         Ok(Page {
+            hash: Self::hash_source(&frontmatter_data, &body),
+            word_count,
+            reading_time: word_count.div_ceil(WORDS_PER_MINUTE),
             data: frontmatter_data,
-            content: body,
+            content: body.into(),
             permalink: permalink.unwrap_or_default(),
             date,
+            updated,
+            weight,
             layout,
+            outer_layouts: layouts,
             depends,
-            directory: path
-                .parent()
-                .unwrap_or(&PathBuf::new())
-                .to_string_lossy()
-                .to_string(),
+            directory: to_slash_string(path.parent().unwrap_or(&PathBuf::new())),
             name: path
                 .file_stem()
                 .unwrap_or(&OsString::new())
                 .to_string_lossy()
                 .to_string(),
-            collections: Page::get_collections_from_path(path.clone())?,
+            format,
+            collections,
             is_layout: Page::is_layout_path(path),
             url: String::new(),
-            rendered: String::new(),
+            canonical_url: String::new(),
+            rendered: Arc::default(),
+            excerpt: String::new(),
+            blocks: BTreeMap::new(),
+            reads_collections: false,
+            git: None,
+            next: None,
+            previous: None,
+            related: Vec::new(),
+            links: Vec::new(),
+            backlinks: Vec::new(),
         })
     }
 
+    /// Count the words in a page's contents.
+    /// Template tags and HTML markup are not words, and neither is punctuation on its own, such as Markdown syntax.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - The page's contents following the frontmatter.
+    ///
+    /// # Returns
+    ///
+    /// The number of words in the page's contents.
+    pub fn count_words(content: &str) -> usize {
+        let mut text = String::with_capacity(content.len());
+        let mut remainder = content;
+        while let Some(start) = remainder.find('{') {
+            text.push_str(&remainder[..start]);
+            let tag = &remainder[start..];
+            let closing = match tag.get(..2) {
+                Some("{%") => "%}",
+                Some("{{") => "}}",
+                _ => {
+                    text.push('{');
+                    remainder = &tag[1..];
+                    continue;
+                }
+            };
+            remainder = tag.find(closing).map_or("", |end| &tag[end + 2..]);
+            text.push(' ');
+        }
+        text.push_str(remainder);
+        html_to_text(&text)
+            .split_whitespace()
+            .filter(|word| word.chars().any(char::is_alphanumeric))
+            .count()
+    }
+
+    /// Obtain a short summary of a page.
+    ///
+    /// # Arguments
+    ///
+    /// * `rendered` - The page's rendered content.
+    ///
+    /// * `excerpt_separator` - The text marking where the page's excerpt ends, such as `<!--more-->`, if any.
+    ///
+    /// # Returns
+    ///
+    /// The `excerpt` defined in the page's frontmatter, the text of the rendered content preceding the excerpt separator, or otherwise the text of the first paragraph of the rendered content.
+    pub fn summarise(&self, rendered: &str, excerpt_separator: Option<&str>) -> String {
+        if let Some(excerpt) = self.data.get("excerpt").and_then(|x| x.as_str()) {
+            return excerpt.to_string();
+        }
+        if let Some(separator_start) = excerpt_separator
+            .filter(|x| !x.is_empty())
+            .and_then(|x| rendered.find(x))
+        {
+            return html_to_text(&rendered[..separator_start]);
+        }
+        let paragraph_start = ["<p>", "<p "]
+            .iter()
+            .filter_map(|x| rendered.find(x))
+            .min()
+            .unwrap_or_default();
+        let first_paragraph = rendered[paragraph_start..]
+            .split("</p>")
+            .next()
+            .unwrap_or_default();
+        html_to_text(first_paragraph)
+    }
+
     /// Return the path to a page.
     ///
     /// # Returns
     ///
     /// The path to a page as a string.
     pub fn to_path_string(&self) -> String {
-        format!("{}/{}.vox", self.directory, self.name)
+        format!(
+            "{}/{}.{}",
+            self.directory,
+            self.name,
+            self.format.extension()
+        )
+    }
+}
+
+#[derive(Debug)]
+/// A view of a page's contexts that notes whether the `collections` context is read while rendering.
+/// This includes reads made by the snippets a page includes.
+struct CollectionsReadTracker<'a> {
+    /// The contexts being rendered with.
+    contexts: &'a Object,
+    /// Whether or not the `collections` context has been read.
+    read: Cell<bool>,
+}
+
+impl<'a> CollectionsReadTracker<'a> {
+    /// Track reads of the `collections` context.
+    ///
+    /// # Arguments
+    ///
+    /// * `contexts` - The contexts being rendered with.
+    ///
+    /// # Returns
+    ///
+    /// A view of the contexts that notes reads of the `collections` context.
+    fn new(contexts: &'a Object) -> Self {
+        Self {
+            contexts,
+            read: Cell::new(false),
+        }
+    }
+
+    /// Note a lookup of a context.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The name of the context being looked up.
+    fn note(&self, index: &str) {
+        if index == "collections" {
+            self.read.set(true);
+        }
+    }
+}
+
+impl ValueView for CollectionsReadTracker<'_> {
+    fn as_debug(&self) -> &dyn fmt::Debug {
+        self
+    }
+
+    fn render(&self) -> DisplayCow<'_> {
+        self.contexts.render()
+    }
+
+    fn source(&self) -> DisplayCow<'_> {
+        self.contexts.source()
+    }
+
+    fn type_name(&self) -> &'static str {
+        self.contexts.type_name()
+    }
+
+    fn query_state(&self, state: State) -> bool {
+        self.contexts.query_state(state)
+    }
+
+    fn to_kstr(&self) -> KStringCow<'_> {
+        self.contexts.to_kstr()
+    }
+
+    fn to_value(&self) -> Value {
+        self.contexts.to_value()
+    }
+
+    fn as_object(&self) -> Option<&dyn ObjectView> {
+        Some(self)
+    }
+}
+
+impl ObjectView for CollectionsReadTracker<'_> {
+    fn as_value(&self) -> &dyn ValueView {
+        self
+    }
+
+    fn size(&self) -> i64 {
+        ObjectView::size(self.contexts)
+    }
+
+    fn keys<'k>(&'k self) -> Box<dyn Iterator<Item = KStringCow<'k>> + 'k> {
+        ObjectView::keys(self.contexts)
+    }
+
+    fn values<'k>(&'k self) -> Box<dyn Iterator<Item = &'k dyn ValueView> + 'k> {
+        ObjectView::values(self.contexts)
+    }
+
+    fn iter<'k>(&'k self) -> Box<dyn Iterator<Item = (KStringCow<'k>, &'k dyn ValueView)> + 'k> {
+        ObjectView::iter(self.contexts)
+    }
+
+    fn contains_key(&self, index: &str) -> bool {
+        self.note(index);
+        ObjectView::contains_key(self.contexts, index)
+    }
+
+    fn get<'s>(&'s self, index: &str) -> Option<&'s dyn ValueView> {
+        self.note(index);
+        ObjectView::get(self.contexts, index)
     }
 }
@@ -0,0 +1,94 @@
+use std::path::{Component, Path};
+
+/// Represent a path as a string separated by forward slashes, regardless of the platform.
+///
+/// # Arguments
+///
+/// * `path` - The path to represent.
+///
+/// # Returns
+///
+/// The path's components, joined with forward slashes.
+pub fn to_slash_string(path: impl AsRef<Path>) -> String {
+    let mut result = String::new();
+    for component in path.as_ref().components() {
+        match component {
+            Component::Prefix(prefix) => result.push_str(&prefix.as_os_str().to_string_lossy()),
+            Component::RootDir => result.push('/'),
+            component => {
+                if !result.is_empty() && !result.ends_with('/') {
+                    result.push('/');
+                }
+                result.push_str(&component.as_os_str().to_string_lossy());
+            }
+        }
+    }
+    result
+}
+
+/// Replace backslashes with forward slashes, such as in a URL or a layout's name.
+///
+/// # Arguments
+///
+/// * `value` - The text to normalise.
+///
+/// # Returns
+///
+/// The text, with every backslash replaced by a forward slash.
+pub fn normalise_separators(value: &str) -> String {
+    value.replace('\\', "/")
+}
+
+/// Obtain the path a page is output at from its URL.
+/// URLs ending with a slash are output as the `index.html` file of their directory.
+///
+/// # Arguments
+///
+/// * `url` - The URL of the page, relative to the site.
+///
+/// # Returns
+///
+/// The path the page is output at, relative to the site's directory.
+pub fn url_output_path(url: &str) -> String {
+    let url = url.trim_start_matches('/');
+    match url.is_empty() || url.ends_with('/') {
+        true => format!("output/{}index.html", url),
+        false => format!("output/{}", url),
+    }
+}
+
+/// Obtain the canonical form of a page's URL.
+///
+/// # Arguments
+///
+/// * `url` - The URL of the page, relative to the site.
+///
+/// * `site_url` - The URL of the site, if any.
+///
+/// # Returns
+///
+/// The URL of the page following the URL of the site, or the URL of the page relative to the site's root if there is no site URL.
+pub fn canonical_url(url: &str, site_url: Option<&str>) -> String {
+    format!(
+        "{}/{}",
+        site_url.unwrap_or_default().trim_end_matches('/'),
+        url.trim_start_matches('/')
+    )
+}
+
+/// Convert text into a form suitable for use in a path.
+///
+/// # Arguments
+///
+/// * `text` - The text to convert.
+///
+/// # Returns
+///
+/// The text in lowercase, with runs of non-alphanumeric characters replaced by hyphens.
+pub fn slugify(text: &str) -> String {
+    text.to_lowercase()
+        .split(|x: char| !x.is_alphanumeric())
+        .filter(|x| !x.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
@@ -0,0 +1,141 @@
+use crate::hooks::shell;
+use crate::page::Page;
+use miette::IntoDiagnostic;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tracing::{debug, warn};
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// The points in the build at which external plugins are given pages to transform.
+pub enum PluginStage {
+    /// After a page's frontmatter has been parsed, before it is rendered.
+    Frontmatter,
+    /// After a page has been rendered, before it is written.
+    Render,
+}
+
+impl PluginStage {
+    /// Get the name of the stage, as given to plugins.
+    ///
+    /// # Returns
+    ///
+    /// The name of the stage.
+    pub fn name(&self) -> &'static str {
+        match self {
+            PluginStage::Frontmatter => "frontmatter",
+            PluginStage::Render => "render",
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Debug, Default, Serialize, Deserialize)]
+/// A plugin extending how a site is built, defined by an entry of the `plugins` list in `global.toml`.
+pub struct PluginSettings {
+    /// The path to the plugin's WebAssembly module, relative to the site's directory.
+    #[serde(default)]
+    pub wasm: Option<PathBuf>,
+    /// The command running the plugin as an external process, which transforms pages given to it as JSON.
+    #[serde(default)]
+    pub command: Option<String>,
+    /// The stages at which the external process is given pages.
+    #[serde(default = "default_stages")]
+    pub stages: Vec<PluginStage>,
+}
+
+/// The default stages at which external plugins are given pages.
+fn default_stages() -> Vec<PluginStage> {
+    vec![PluginStage::Frontmatter, PluginStage::Render]
+}
+
+impl PluginSettings {
+    /// Determine if the plugin runs as an external process at a stage of the build.
+    ///
+    /// # Arguments
+    ///
+    /// * `stage` - The stage of the build.
+    ///
+    /// # Returns
+    ///
+    /// Whether or not the plugin is given pages at the stage.
+    pub fn runs_at(&self, stage: PluginStage) -> bool {
+        self.command.is_some() && self.stages.contains(&stage)
+    }
+}
+
+/// Transform a page with the external plugins running at a stage of the build.
+/// Each plugin is given the page as JSON on its standard input, along with the stage in the `VOX_PLUGIN_STAGE` environment variable, and writes the transformed page as JSON to its standard output.
+/// Plugins are run in order, each given the page transformed by the last.
+///
+/// # Arguments
+///
+/// * `plugins` - The site's plugins.
+///
+/// * `stage` - The stage of the build.
+///
+/// * `page` - The page to transform.
+///
+/// * `directory` - The directory to run the plugins in.
+///
+/// # Returns
+///
+/// The transformed page, or an error if a plugin could not be run, did not succeed, or did not output a page.
+pub fn run_plugins(
+    plugins: &[PluginSettings],
+    stage: PluginStage,
+    mut page: Page,
+    directory: &Path,
+) -> miette::Result<Page> {
+    for command in plugins
+        .iter()
+        .filter(|x| x.runs_at(stage))
+        .filter_map(|x| x.command.as_ref())
+    {
+        debug!(
+            "Running `{}` on `{}` ({}) … ",
+            command,
+            page.to_path_string(),
+            stage.name()
+        );
+        let input = serde_json::to_vec(&page).into_diagnostic()?;
+        let mut child = shell(command)
+            .current_dir(directory)
+            .env("VOX_PLUGIN_STAGE", stage.name())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .into_diagnostic()?;
+        // The page is written while the output is read, so that neither blocks the other.
+        let mut stdin = child
+            .stdin
+            .take()
+            .ok_or(miette::miette!("Unable to write to `{}`.", command))?;
+        let writing = std::thread::spawn(move || stdin.write_all(&input));
+        let output = child.wait_with_output().into_diagnostic()?;
+        // A plugin may exit without reading the whole page.
+        writing.join().ok();
+        for line in String::from_utf8_lossy(&output.stderr).lines() {
+            warn!("{}", line);
+        }
+        if !output.status.success() {
+            return Err(miette::miette!(
+                "The plugin `{}` exited with {} while transforming `{}`.",
+                command,
+                output.status,
+                page.to_path_string()
+            ));
+        }
+        page = serde_json::from_slice(&output.stdout).map_err(|err| {
+            miette::miette!(
+                "The plugin `{}` did not output a page when transforming `{}`: {}",
+                command,
+                page.to_path_string(),
+                err
+            )
+        })?;
+    }
+    Ok(page)
+}
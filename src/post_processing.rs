@@ -0,0 +1,494 @@
+use crate::page::Page;
+use crate::plugins::PluginSettings;
+#[cfg(feature = "wasm")]
+use crate::wasm_plugins::WasmPlugin;
+use path_clean::PathClean;
+use serde::{Deserialize, Serialize};
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// A function transforming a page's rendered HTML before it is written.
+/// The function is given the page and its rendered HTML, and returns the HTML to be written.
+pub type PostProcessor = Arc<dyn Fn(&Page, String) -> String + Send + Sync>;
+
+#[derive(PartialEq, Eq, Clone, Default, Debug)]
+/// Settings for processing rendered pages before they are written, defined in `global.toml`.
+pub struct PostProcessingSettings {
+    /// The settings for processing links to other sites, if they are to be processed.
+    pub external_links: Option<ExternalLinkSettings>,
+    /// The settings for processing images, if they are to be processed.
+    pub images: Option<ImageSettings>,
+    /// The URL of the site.
+    pub url: Option<String>,
+    /// The site's plugins; those running at the render stage are given each rendered page.
+    pub plugins: Vec<PluginSettings>,
+    /// The site's plugins compiled to WebAssembly; those exporting a post-processing function are given each rendered page.
+    #[cfg(feature = "wasm")]
+    pub wasm_plugins: Vec<WasmPlugin>,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+/// Settings for processing links to other sites, defined by the `external_links` table in `global.toml`.
+pub struct ExternalLinkSettings {
+    /// The `rel` attribute added to external links.
+    #[serde(default = "default_rel")]
+    pub rel: String,
+    /// Whether or not external links open in a new tab, with `target="_blank"`.
+    #[serde(default)]
+    pub new_tab: bool,
+    /// The domains whose links are not treated as external, including their subdomains.
+    /// The domain of the site's `url` is always allowed.
+    #[serde(default)]
+    pub allow: Vec<String>,
+}
+
+impl Default for ExternalLinkSettings {
+    fn default() -> Self {
+        Self {
+            rel: default_rel(),
+            new_tab: false,
+            allow: Vec::new(),
+        }
+    }
+}
+
+/// The default `rel` attribute of external links.
+fn default_rel() -> String {
+    "noopener noreferrer".to_string()
+}
+
+impl ExternalLinkSettings {
+    /// Determine if a link points to another site.
+    ///
+    /// # Arguments
+    ///
+    /// * `href` - The destination of the link.
+    ///
+    /// # Returns
+    ///
+    /// Whether or not the link is absolute and its domain is not allowed.
+    pub fn is_external(&self, href: &str) -> bool {
+        let Some(host) = url_host(href) else {
+            return false;
+        };
+        !self.allow.iter().any(|domain| {
+            let domain = domain.trim_start_matches('.').to_lowercase();
+            host == domain || host.ends_with(&format!(".{}", domain))
+        })
+    }
+
+    /// Add the `rel` and `target` attributes to the external links in HTML.
+    ///
+    /// # Arguments
+    ///
+    /// * `html` - The HTML.
+    ///
+    /// # Returns
+    ///
+    /// The HTML, with its external links modified.
+    pub fn apply(&self, html: &str) -> String {
+        map_tags(html, "a", |attributes| {
+            let Some(href) = get_attribute(attributes, "href") else {
+                return false;
+            };
+            if !self.is_external(&href) {
+                return false;
+            }
+            let mut rel: Vec<String> = get_attribute(attributes, "rel")
+                .map(|x| x.split_whitespace().map(|x| x.to_string()).collect())
+                .unwrap_or_default();
+            for value in self.rel.split_whitespace() {
+                if !rel.iter().any(|x| x.eq_ignore_ascii_case(value)) {
+                    rel.push(value.to_string());
+                }
+            }
+            if !rel.is_empty() {
+                set_attribute(attributes, "rel", rel.join(" "));
+            }
+            if self.new_tab && get_attribute(attributes, "target").is_none() {
+                set_attribute(attributes, "target", "_blank".to_string());
+            }
+            true
+        })
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+/// Settings for processing images, defined by the `images` table in `global.toml`.
+pub struct ImageSettings {
+    /// Whether or not images are loaded lazily, with `loading="lazy"`.
+    #[serde(default = "default_true")]
+    pub lazy: bool,
+    /// Whether or not images are decoded asynchronously, with `decoding="async"`.
+    #[serde(default = "default_true")]
+    pub async_decoding: bool,
+    /// Whether or not images are given `width` and `height` attributes, when their files are available.
+    #[serde(default = "default_true")]
+    pub dimensions: bool,
+}
+
+impl Default for ImageSettings {
+    fn default() -> Self {
+        Self {
+            lazy: true,
+            async_decoding: true,
+            dimensions: true,
+        }
+    }
+}
+
+/// Used for settings which are enabled by default.
+fn default_true() -> bool {
+    true
+}
+
+impl ImageSettings {
+    /// Add the `loading`, `decoding`, `width`, and `height` attributes to the images in HTML.
+    /// Attributes already present on an image are left as they were written.
+    ///
+    /// # Arguments
+    ///
+    /// * `html` - The HTML.
+    ///
+    /// * `get_dimensions` - A function obtaining the width and height of an image from its source.
+    ///
+    /// # Returns
+    ///
+    /// The HTML, with its images modified.
+    pub fn apply(
+        &self,
+        html: &str,
+        mut get_dimensions: impl FnMut(&str) -> Option<(u32, u32)>,
+    ) -> String {
+        map_tags(html, "img", |attributes| {
+            let mut modified = false;
+            let mut set_missing_attribute = |attributes: &mut Attributes, name, value| {
+                if get_attribute(attributes, name).is_none() {
+                    set_attribute(attributes, name, value);
+                    modified = true;
+                }
+            };
+            if self.lazy {
+                set_missing_attribute(attributes, "loading", "lazy".to_string());
+            }
+            if self.async_decoding {
+                set_missing_attribute(attributes, "decoding", "async".to_string());
+            }
+            let has_dimensions = get_attribute(attributes, "width").is_some()
+                || get_attribute(attributes, "height").is_some();
+            if self.dimensions && !has_dimensions {
+                if let Some((width, height)) =
+                    get_attribute(attributes, "src").and_then(|src| get_dimensions(&src))
+                {
+                    set_missing_attribute(attributes, "width", width.to_string());
+                    set_missing_attribute(attributes, "height", height.to_string());
+                }
+            }
+            modified
+        })
+    }
+}
+
+/// Obtain the paths an image may be found at.
+///
+/// # Arguments
+///
+/// * `src` - The source of the image.
+///
+/// * `output_path` - The output path of the page containing the image.
+///
+/// * `site_url` - The URL of the site, if any.
+///
+/// # Returns
+///
+/// The paths the image may be found at, in order of preference, or nothing if the image is on another site.
+pub fn image_paths(src: &str, output_path: &str, site_url: Option<&str>) -> Vec<PathBuf> {
+    let mut src = src.trim();
+    // Images within the site may be referred to with the site's URL.
+    if let Some(site_url) = site_url.map(|x| x.trim_end_matches('/')) {
+        if let Some(path) = src.strip_prefix(site_url).filter(|x| x.starts_with('/')) {
+            src = path;
+        }
+    }
+    if src.is_empty() || src.starts_with("data:") || src.contains("://") || src.starts_with("//") {
+        return Vec::new();
+    }
+    let src = src.split(['?', '#']).next().unwrap_or_default();
+    match src.strip_prefix('/') {
+        Some(path) => vec![
+            Path::new("output").join(path).clean(),
+            PathBuf::from(path).clean(),
+        ],
+        None => {
+            let output_path = Path::new(output_path)
+                .parent()
+                .unwrap_or(Path::new("output"))
+                .join(src)
+                .clean();
+            let source_path = output_path
+                .strip_prefix("output")
+                .map(|x| x.to_path_buf())
+                .unwrap_or_default();
+            vec![output_path, source_path]
+        }
+    }
+}
+
+/// Obtain the domain of an absolute URL.
+///
+/// # Arguments
+///
+/// * `url` - The URL.
+///
+/// # Returns
+///
+/// The URL's domain in lowercase, or `None` if the URL is not an absolute HTTP(S) URL.
+pub fn url_host(url: &str) -> Option<String> {
+    let url = url.trim();
+    let lowercase_url = url.to_ascii_lowercase();
+    let after_scheme = ["http://", "https://", "//"]
+        .iter()
+        .find(|scheme| lowercase_url.starts_with(**scheme))
+        .map(|scheme| &lowercase_url[scheme.len()..])?;
+    let authority = after_scheme
+        .split(['/', '?', '#'])
+        .next()
+        .unwrap_or_default();
+    let host = authority.rsplit('@').next().unwrap_or_default();
+    let host = host.split(':').next().unwrap_or_default();
+    (!host.is_empty()).then(|| host.to_string())
+}
+
+/// The attributes of an HTML tag, in order of appearance.
+/// Each attribute has a name in lowercase and, unless it is a boolean attribute, a value with its character references decoded.
+pub type Attributes = Vec<(String, Option<String>)>;
+
+/// Obtain the value of an attribute of an HTML tag.
+///
+/// # Arguments
+///
+/// * `attributes` - The attributes of the tag.
+///
+/// * `name` - The name of the attribute, in lowercase.
+///
+/// # Returns
+///
+/// The value of the attribute, if it is present.
+pub fn get_attribute(attributes: &Attributes, name: &str) -> Option<String> {
+    attributes
+        .iter()
+        .find(|(attribute, _)| attribute == name)
+        .map(|(_, value)| value.clone().unwrap_or_default())
+}
+
+/// Set the value of an attribute of an HTML tag, adding the attribute if it is not present.
+///
+/// # Arguments
+///
+/// * `attributes` - The attributes of the tag.
+///
+/// * `name` - The name of the attribute, in lowercase.
+///
+/// * `value` - The value of the attribute.
+pub fn set_attribute(attributes: &mut Attributes, name: &str, value: String) {
+    match attributes
+        .iter_mut()
+        .find(|(attribute, _)| attribute == name)
+    {
+        Some((_, existing_value)) => *existing_value = Some(value),
+        None => attributes.push((name.to_string(), Some(value))),
+    }
+}
+
+/// Parse the attributes of an HTML tag.
+///
+/// # Arguments
+///
+/// * `source` - The source of the tag following its name, excluding the closing `>`.
+///
+/// # Returns
+///
+/// The attributes of the tag.
+fn parse_attributes(source: &str) -> Attributes {
+    let mut attributes = Vec::new();
+    let mut characters = source.chars().peekable();
+    loop {
+        while characters
+            .next_if(|x| x.is_whitespace() || *x == '/')
+            .is_some()
+        {}
+        let mut name = String::new();
+        while let Some(character) =
+            characters.next_if(|x| !x.is_whitespace() && *x != '=' && *x != '/')
+        {
+            name.push(character);
+        }
+        if name.is_empty() {
+            break;
+        }
+        while characters.next_if(|x| x.is_whitespace()).is_some() {}
+        if characters.next_if_eq(&'=').is_none() {
+            attributes.push((name.to_lowercase(), None));
+            continue;
+        }
+        while characters.next_if(|x| x.is_whitespace()).is_some() {}
+        let mut value = String::new();
+        match characters.next_if(|x| *x == '"' || *x == '\'') {
+            Some(quote) => {
+                for character in characters.by_ref() {
+                    if character == quote {
+                        break;
+                    }
+                    value.push(character);
+                }
+            }
+            None => {
+                while let Some(character) = characters.next_if(|x| !x.is_whitespace()) {
+                    value.push(character);
+                }
+            }
+        }
+        attributes.push((
+            name.to_lowercase(),
+            Some(html_escape::decode_html_entities(&value).to_string()),
+        ));
+    }
+    attributes
+}
+
+/// Write an HTML tag.
+///
+/// # Arguments
+///
+/// * `name` - The name of the tag.
+///
+/// * `attributes` - The attributes of the tag.
+///
+/// * `self_closing` - Whether or not the tag is written as self-closing.
+///
+/// # Returns
+///
+/// The HTML tag.
+fn write_tag(name: &str, attributes: &Attributes, self_closing: bool) -> String {
+    let mut tag = format!("<{}", name);
+    for (attribute, value) in attributes {
+        match value {
+            Some(value) => tag.push_str(&format!(
+                " {}=\"{}\"",
+                attribute,
+                html_escape::encode_double_quoted_attribute(value)
+            )),
+            None => tag.push_str(&format!(" {}", attribute)),
+        }
+    }
+    tag.push_str(if self_closing { " />" } else { ">" });
+    tag
+}
+
+/// Find the end of an HTML tag, ignoring any `>` within quoted attribute values.
+///
+/// # Arguments
+///
+/// * `source` - The source of the tag, starting from its `<`.
+///
+/// # Returns
+///
+/// The position of the tag's closing `>`, if it is closed.
+fn find_tag_end(source: &str) -> Option<usize> {
+    let mut quote = None;
+    for (position, character) in source.char_indices() {
+        match (quote, character) {
+            (None, '"' | '\'') => quote = Some(character),
+            (Some(open_quote), _) if open_quote == character => quote = None,
+            (None, '>') => return Some(position),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// An occurrence of an HTML tag.
+pub struct Tag {
+    /// The position of the tag in the HTML, from its `<` to its `>` inclusive.
+    pub span: Range<usize>,
+    /// The name of the tag, as it is written.
+    pub name: String,
+    /// The attributes of the tag.
+    pub attributes: Attributes,
+    /// Whether or not the tag is written as self-closing.
+    pub self_closing: bool,
+}
+
+/// Find each occurrence of an HTML tag.
+///
+/// # Arguments
+///
+/// * `html` - The HTML.
+///
+/// * `name` - The name of the tag, in lowercase.
+///
+/// # Returns
+///
+/// The occurrences of the tag, in order of appearance.
+pub fn find_tags(html: &str, name: &str) -> Vec<Tag> {
+    let opening = format!("<{}", name);
+    let lowercase_html = html.to_ascii_lowercase();
+    let mut tags = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = lowercase_html[offset..].find(&opening).map(|x| x + offset) {
+        let name_end = start + opening.len();
+        let is_tag = lowercase_html[name_end..]
+            .chars()
+            .next()
+            .is_some_and(|x| x.is_whitespace() || x == '>' || x == '/');
+        let Some(end) = find_tag_end(&html[start..])
+            .filter(|_| is_tag)
+            .map(|x| x + start)
+        else {
+            offset = name_end;
+            continue;
+        };
+        let source = &html[name_end..end];
+        tags.push(Tag {
+            span: start..end + 1,
+            name: html[start + 1..name_end].to_string(),
+            attributes: parse_attributes(source),
+            self_closing: source.trim_end().ends_with('/'),
+        });
+        offset = end + 1;
+    }
+    tags
+}
+
+/// Modify the attributes of each occurrence of an HTML tag.
+/// Tags whose attributes are not modified are left as they were written.
+///
+/// # Arguments
+///
+/// * `html` - The HTML.
+///
+/// * `name` - The name of the tag, in lowercase.
+///
+/// * `modify` - A function modifying the attributes of a tag, returning whether or not it did so.
+///
+/// # Returns
+///
+/// The HTML, with the tags modified.
+pub fn map_tags(html: &str, name: &str, mut modify: impl FnMut(&mut Attributes) -> bool) -> String {
+    let mut processed = String::with_capacity(html.len());
+    let mut offset = 0;
+    for mut tag in find_tags(html, name) {
+        processed.push_str(&html[offset..tag.span.start]);
+        if modify(&mut tag.attributes) {
+            processed.push_str(&write_tag(&tag.name, &tag.attributes, tag.self_closing));
+        } else {
+            processed.push_str(&html[tag.span.clone()]);
+        }
+        offset = tag.span.end;
+    }
+    processed.push_str(&html[offset..]);
+    processed
+}
@@ -0,0 +1,135 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::io::{IsTerminal, Write};
+#[cfg(not(target_arch = "wasm32"))]
+use ticky::Stopwatch;
+#[cfg(not(target_arch = "wasm32"))]
+use tracing::info;
+
+/// How often progress is logged when it is not drawn as a bar, in seconds.
+#[cfg(not(target_arch = "wasm32"))]
+const LOG_INTERVAL: f64 = 5.0;
+
+/// How often a progress bar is redrawn, in seconds.
+#[cfg(not(target_arch = "wasm32"))]
+const DRAW_INTERVAL: f64 = 0.1;
+
+/// The width of a progress bar, in characters.
+#[cfg(not(target_arch = "wasm32"))]
+const BAR_WIDTH: usize = 30;
+
+/// Reports the progress of a stage of a build, such as rendering or writing pages.
+/// Progress is drawn as a bar on standard error when it is a terminal, and logged periodically otherwise.
+/// Nothing is reported if information is not being logged.
+pub struct Progress {
+    /// What is being done, such as `Rendering`.
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    action: &'static str,
+    /// The number of pages to be processed.
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    total: usize,
+    /// The number of pages processed so far.
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    completed: usize,
+    /// Whether or not progress is drawn as a bar.
+    #[cfg(not(target_arch = "wasm32"))]
+    bar: bool,
+    /// The time since the stage began.
+    #[cfg(not(target_arch = "wasm32"))]
+    timer: Stopwatch,
+    /// When progress was last reported, in seconds since the stage began.
+    #[cfg(not(target_arch = "wasm32"))]
+    last_reported: f64,
+}
+
+impl Progress {
+    /// Begin reporting the progress of a stage of a build.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - What is being done, such as `Rendering`.
+    ///
+    /// * `total` - The number of pages to be processed.
+    ///
+    /// # Returns
+    ///
+    /// A report of no pages having been processed.
+    pub fn new(action: &'static str, total: usize) -> Self {
+        Self {
+            action,
+            total,
+            completed: 0,
+            #[cfg(not(target_arch = "wasm32"))]
+            bar: std::io::stderr().is_terminal(),
+            #[cfg(not(target_arch = "wasm32"))]
+            timer: Stopwatch::start_new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            last_reported: 0.0,
+        }
+    }
+
+    /// Record that a page has been processed, reporting progress if it is due.
+    pub fn tick(&mut self) {
+        self.completed += 1;
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            if !tracing::enabled!(tracing::Level::INFO) {
+                return;
+            }
+            let elapsed = self.timer.elapsed().as_secs_f64();
+            let interval = match self.bar {
+                true => DRAW_INTERVAL,
+                false => LOG_INTERVAL,
+            };
+            if elapsed - self.last_reported < interval && self.completed < self.total {
+                return;
+            }
+            self.last_reported = elapsed;
+            if self.bar {
+                self.draw(elapsed);
+            } else if self.completed < self.total {
+                info!(
+                    "{}: {} of {} pages in {:.2} seconds … ",
+                    self.action, self.completed, self.total, elapsed
+                );
+            }
+        }
+    }
+
+    /// Draw the progress bar over the current line of standard error.
+    /// The cursor is returned to the start of the line, so that any log written afterwards replaces the bar.
+    ///
+    /// # Arguments
+    ///
+    /// * `elapsed` - The time since the stage began, in seconds.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn draw(&self, elapsed: f64) {
+        let filled = BAR_WIDTH * self.completed / self.total.max(1);
+        let mut bar = "=".repeat(filled);
+        if filled < BAR_WIDTH {
+            bar.push('>');
+        }
+        let mut stderr = std::io::stderr().lock();
+        write!(
+            stderr,
+            "\x1b[2K{} [{:<width$}] {}/{} pages, {:.1} s\r",
+            self.action,
+            bar,
+            self.completed,
+            self.total,
+            elapsed,
+            width = BAR_WIDTH
+        )
+        .ok();
+        stderr.flush().ok();
+    }
+
+    /// Stop reporting progress, clearing the progress bar.
+    pub fn finish(self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.bar && tracing::enabled!(tracing::Level::INFO) {
+            let mut stderr = std::io::stderr().lock();
+            write!(stderr, "\x1b[2K").ok();
+            stderr.flush().ok();
+        }
+    }
+}
@@ -1,10 +1,51 @@
-use crate::builds::EdgeType;
+use crate::admonitions::{AdmonitionBlock, ADMONITION_KINDS};
+use crate::builds::{CompiledTemplates, EdgeType};
+use crate::changes::Changes;
+use crate::content_api::{collection_index_path, json_output_path, PageEntry};
+use crate::content_block::ContentBlock;
 use crate::date::{self, Date};
+use crate::date_archives::{date_archives, DateArchiveSettings};
+use crate::details_block::DetailsBlock;
+use crate::error::LayoutCycle;
+#[cfg(not(feature = "wasm"))]
+use crate::error::WasmPluginUnsupported;
+use crate::exclusion::Exclusions;
+use crate::feeds::{atom_feed, FeedContent, FeedSettings};
+use crate::format_date_filter::FormatDate;
+use crate::git::{GitMetadata, RepositoryMetadata};
+use crate::hooks::{run_hooks, HookSettings, HookStage};
+use crate::images::image_dimensions;
+use crate::in_groups_of_filter::InGroupsOf;
+use crate::lint::{lint_pages, LintSettings, Linter};
+use crate::load_data_tag::{FetchedData, LoadDataTag};
 use crate::markdown_block::MarkdownBlock;
-use crate::math_block::MathBlock;
+use crate::math_block::{MathBlock, MathCache};
+use crate::paths::{normalise_separators, to_slash_string, url_output_path};
+use crate::plugins::{run_plugins, PluginSettings, PluginStage};
+use crate::post_processing::{
+    image_paths, url_host, ExternalLinkSettings, PostProcessingSettings, PostProcessor,
+};
+use crate::progress::Progress;
+use crate::random_filters::{Sample, Shuffle};
+use crate::related::{compute_related, RelatedSettings};
+use crate::remote_data::{load_remote_data, RemoteSource};
+use crate::schema::Schema;
+use crate::search::{SearchDocument, SearchSettings, OPENSEARCH_PATH};
+use crate::seo_tag::SeoTag;
+use crate::sitemap::{sitemap, SitemapSettings};
+use crate::sort_by_filter::SortBy;
+use crate::summary::{BuildSummary, StageTimer};
+use crate::syntax_highlighting::SyntaxHighlightingSettings;
+use crate::theme::{is_git_url, ThemeLock, THEMES_DIRECTORY};
+use crate::url_conflicts::UrlConflictStrategy;
+use crate::url_transforms::{UrlTransformSettings, UrlTransformer, UrlTransforms};
+#[cfg(feature = "wasm")]
+use crate::wasm_plugins::WasmPlugin;
+use crate::wikilinks::compute_links;
 use crate::{builds::Build, page::Page};
 use ahash::{AHashMap, AHashSet, HashSet, HashSetExt};
-use chrono::{Locale, Utc};
+use chrono::{Locale, SecondsFormat, Utc};
+use chrono_tz::Tz;
 use daggy::petgraph::algo::toposort;
 use daggy::petgraph::dot::{Config, Dot};
 use daggy::Walker;
@@ -13,13 +54,15 @@ use layout::backends::svg::SVGWriter;
 use layout::core::color::Color;
 use layout::gv::{DotParser, GraphBuilder};
 use layout::std_shapes::shapes::ShapeKind;
+use liquid::partials::PartialSource;
 use liquid::{object, Object};
 use miette::IntoDiagnostic;
 use path_clean::PathClean;
+use std::collections::BTreeMap;
 use std::path::Path;
 use std::path::PathBuf;
-use syntect::highlighting::ThemeSet;
-use syntect::html::css_for_theme_with_class_style;
+use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
 use ticky::Stopwatch;
 use toml::Table;
 use tracing::{debug, info, trace, warn};
@@ -27,8 +70,40 @@ use tracing::{debug, info, trace, warn};
 /// The Vox crate version number.
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
-/// An implementation of the Vox build process.
-pub trait VoxProvider {
+/// Get the name of the machine building the site.
+///
+/// # Returns
+///
+/// The machine's hostname, or `None` if unavailable.
+pub fn build_host() -> Option<String> {
+    std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .ok()
+        .or_else(|| {
+            ["/proc/sys/kernel/hostname", "/etc/hostname"]
+                .iter()
+                .find_map(|path| std::fs::read_to_string(path).ok())
+        })
+        .map(|x| x.trim().to_string())
+        .filter(|x| !x.is_empty())
+}
+
+/// A function customising the Liquid parser, such as by registering additional tags, filters, or blocks.
+/// The function is given the parser's builder after Vox's own tags, filters, and blocks have been registered, and returns the builder to use.
+pub type ParserCustomiser =
+    Arc<dyn Fn(liquid::ParserBuilder) -> liquid::ParserBuilder + Send + Sync>;
+
+/// A function generating pages which are not read from the site's files, such as index pages or pages of content from elsewhere.
+/// The function is given the pages read from the site's files, along with the site's locale and timezone, and returns the pages to add to the site.
+/// A generated page is named by its `directory` and `name`, which must not be those of another page; its `depends` and `collections` relate it to other pages as for any page.
+pub type PageGenerator =
+    Arc<dyn Fn(&[&Page], Locale, Tz) -> miette::Result<Vec<Page>> + Send + Sync>;
+
+/// A function reading a file's contents while pages are being rendered, given the file's path relative to the site's directory.
+pub type FileReader = Arc<dyn Fn(&Path) -> miette::Result<Vec<u8>> + Send + Sync>;
+
+/// The source of a site, which pages, snippets, and other files are read from.
+pub trait InputProvider {
     /// Read a file's contents as a string.
     ///
     /// # Arguments
@@ -40,6 +115,160 @@ pub trait VoxProvider {
     /// The file's contents as a string.
     fn read_to_string(&self, path: impl AsRef<std::path::Path>) -> miette::Result<String>;
 
+    /// Read a file's contents as bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the file.
+    ///
+    /// # Returns
+    ///
+    /// The file's contents as bytes.
+    fn read_bytes(&self, path: impl AsRef<std::path::Path>) -> miette::Result<Vec<u8>> {
+        self.read_to_string(path).map(|x| x.into_bytes())
+    }
+
+    /// List all Vox pages.
+    ///
+    /// # Returns
+    ///
+    /// A list of paths to Vox pages.
+    fn list_vox_files(&self) -> miette::Result<Vec<PathBuf>>;
+
+    /// List all Markdown files, which are pages if they begin with frontmatter.
+    ///
+    /// # Returns
+    ///
+    /// A list of paths to Markdown files, or an empty list if the provider does not support Markdown pages.
+    fn list_markdown_files(&self) -> miette::Result<Vec<PathBuf>> {
+        Ok(Vec::new())
+    }
+
+    /// List all HTML pages, being HTML files outside of the output & snippets folders.
+    ///
+    /// # Returns
+    ///
+    /// A list of paths to HTML pages, or an empty list if the provider does not support HTML pages.
+    fn list_html_files(&self) -> miette::Result<Vec<PathBuf>> {
+        Ok(Vec::new())
+    }
+
+    /// List all Vox snippets.
+    ///
+    /// # Returns
+    ///
+    /// A list of paths to Vox snippets.
+    fn list_snippets(&self) -> miette::Result<Vec<PathBuf>>;
+
+    /// List all files within a directory of the site & its subdirectories.
+    ///
+    /// # Arguments
+    ///
+    /// * `directory` - The directory, relative to the site's directory.
+    ///
+    /// # Returns
+    ///
+    /// A list of paths to the files, relative to the site's directory, or an empty list if the provider cannot list directories.
+    fn list_directory(&self, _directory: &Path) -> miette::Result<Vec<PathBuf>> {
+        Ok(Vec::new())
+    }
+
+    /// List the files copied into the output when the site is built, such as the assets of a theme.
+    /// Each file is output at its path within the `assets` folder.
+    ///
+    /// # Returns
+    ///
+    /// A list of paths to the files, relative to the site's directory.
+    fn list_assets(&self) -> miette::Result<Vec<PathBuf>> {
+        Ok(Vec::new())
+    }
+
+    /// Get a function reading files' contents while pages are being rendered.
+    /// Unlike the provider, the function can be kept by the Liquid parser.
+    ///
+    /// # Returns
+    ///
+    /// The function, or `None` if the provider cannot read files while pages are being rendered.
+    fn file_reader(&self) -> Option<FileReader> {
+        None
+    }
+
+    /// Get the cache of math rendered while pages are being rendered.
+    /// Like the function reading files, the cache can be kept by the Liquid parser.
+    ///
+    /// # Returns
+    ///
+    /// The cache, or `None` if the provider does not cache rendered math.
+    fn math_cache(&self) -> Option<MathCache> {
+        None
+    }
+
+    /// Read the site's version control history, from which the metadata of pages and of the repository is obtained.
+    /// This is done each time the DAG is constructed, rather than for each page.
+    fn refresh_git_history(&self) {}
+
+    /// Obtain a page's metadata from version control.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the page.
+    ///
+    /// * `locale` - The locale for date formatting.
+    ///
+    /// * `timezone` - The timezone for date formatting.
+    ///
+    /// # Returns
+    ///
+    /// The page's metadata, or `None` if unavailable.
+    fn get_git_metadata(
+        &self,
+        _path: &Path,
+        _locale: Locale,
+        _timezone: Tz,
+    ) -> Option<GitMetadata> {
+        None
+    }
+
+    /// Obtain the metadata of the repository containing the site, as of when its history was last read.
+    ///
+    /// # Returns
+    ///
+    /// The repository's metadata, or `None` if unavailable.
+    fn get_repository_metadata(&self) -> Option<RepositoryMetadata> {
+        None
+    }
+
+    /// Get the directory commands run for the site, such as hooks, are run in.
+    ///
+    /// # Returns
+    ///
+    /// The site's directory, or `None` if the provider cannot run commands.
+    fn working_directory(&self) -> Option<PathBuf> {
+        None
+    }
+
+    /// Get the environment the site is built for, such as `production`.
+    ///
+    /// # Returns
+    ///
+    /// The name of the environment, or `None` if none was given.
+    fn environment(&self) -> Option<String> {
+        None
+    }
+
+    /// Watch the site for changes to its sources.
+    /// Changes to the site's output are not included.
+    ///
+    /// # Returns
+    ///
+    /// A stream of changes to the site, or `None` if the provider cannot be watched.
+    fn watch(&self) -> miette::Result<Option<Changes>> {
+        Ok(None)
+    }
+}
+
+/// The destination of a site's output, which rendered pages and other generated files are written to.
+pub trait OutputProvider {
     /// Write data to a file.
     ///
     /// # Arguments
@@ -60,19 +289,57 @@ pub trait VoxProvider {
     /// * `path` - The path to the file.
     fn remove_file(&self, path: impl AsRef<Path>) -> miette::Result<()>;
 
-    /// List all Vox pages.
+    /// Begin staging output, so that files written to the output directory only replace it once the build is committed.
+    /// The staged output begins as a copy of the current output.
     ///
     /// # Returns
     ///
-    /// A list of paths to Vox pages.
-    fn list_vox_files(&self) -> miette::Result<Vec<PathBuf>>;
+    /// Whether or not output is being staged; providers which cannot stage output write to the output directory directly.
+    fn stage_output(&self) -> miette::Result<bool> {
+        Ok(false)
+    }
 
-    /// List all Vox snippets.
+    /// Replace the output directory with the staged output, and stop staging output.
+    ///
+    /// # Arguments
+    ///
+    /// * `remove_orphans` - Whether or not to remove files output by previous builds which were not output by this build.
     ///
     /// # Returns
     ///
-    /// A list of paths to Vox snippets.
-    fn list_snippets(&self) -> miette::Result<Vec<PathBuf>>;
+    /// The number of orphaned files removed.
+    fn commit_staged_output(&self, _remove_orphans: bool) -> miette::Result<usize> {
+        Ok(0)
+    }
+
+    /// Discard the staged output, leaving the output directory as it was, and stop staging output.
+    fn discard_staged_output(&self) -> miette::Result<()> {
+        Ok(())
+    }
+}
+
+/// An implementation of the Vox build process.
+/// Sources are read through the provider's [`InputProvider`] implementation, and output is written through its [`OutputProvider`] implementation.
+pub trait VoxProvider: InputProvider + OutputProvider {
+    /// Get the functions customising the Liquid parser.
+    /// These are applied in order, after Vox's own tags, filters, and blocks have been registered.
+    ///
+    /// # Returns
+    ///
+    /// The parser customisation functions.
+    fn get_parser_customisers(&self) -> Vec<ParserCustomiser> {
+        Vec::new()
+    }
+
+    /// Get the functions generating pages which are not read from the site's files.
+    /// These are called in order each time the DAG is constructed, after the site's own pages have been read.
+    ///
+    /// # Returns
+    ///
+    /// The page generation functions.
+    fn get_page_generators(&self) -> Vec<PageGenerator> {
+        Vec::new()
+    }
 
     /// Create a Liquid parser.
     ///
@@ -80,8 +347,41 @@ pub trait VoxProvider {
     ///
     /// A Liquid parser.
     fn create_liquid_parser(&self) -> miette::Result<liquid::Parser> {
-        liquid::ParserBuilder::with_stdlib()
+        self.create_liquid_parser_with_data(FetchedData::default())
+    }
+
+    /// Create a Liquid parser, sharing the data it fetches from URLs.
+    ///
+    /// # Arguments
+    ///
+    /// * `fetched` - Data fetched from URLs during the build, which is kept between parsers.
+    ///
+    /// # Returns
+    ///
+    /// A Liquid parser.
+    fn create_liquid_parser_with_data(
+        &self,
+        fetched: FetchedData,
+    ) -> miette::Result<liquid::Parser> {
+        let (locale, _) = self.get_locale_and_timezone()?;
+        let math_cache = self.math_cache();
+        if let Some(math_cache) = &math_cache {
+            math_cache.load(self)?;
+        }
+        // Plugins would register their own filters & tags, so a site cannot be rendered without them.
+        #[cfg(not(feature = "wasm"))]
+        if let Some(plugin) = self.get_plugins()?.into_iter().find_map(|x| x.wasm) {
+            return Err(WasmPluginUnsupported {
+                plugin: to_slash_string(plugin),
+            }
+            .into());
+        }
+        let mut parser_builder = liquid::ParserBuilder::with_stdlib()
             .tag(liquid_lib::jekyll::IncludeTag)
+            // Unlike `include`, `render` gives snippets an isolated scope, containing only the parameters passed to them.
+            .tag(liquid_lib::stdlib::RenderTag)
+            .tag(SeoTag)
+            .tag(LoadDataTag::new(self.file_reader(), fetched))
             .filter(liquid_lib::jekyll::ArrayToSentenceString)
             .filter(liquid_lib::jekyll::Pop)
             .filter(liquid_lib::jekyll::Push)
@@ -91,58 +391,662 @@ pub trait VoxProvider {
             .filter(liquid_lib::jekyll::Sort)
             .filter(liquid_lib::shopify::Pluralize)
             .filter(liquid_lib::extra::DateInTz)
-            .block(MathBlock)
-            .block(MarkdownBlock)
-            .partials(liquid::partials::EagerCompiler::new(self.partial_source()))
-            .build()
-            .into_diagnostic()
+            .filter(FormatDate::new(locale))
+            .filter(InGroupsOf)
+            .filter(Sample)
+            .filter(Shuffle)
+            .filter(SortBy)
+            .block(MathBlock::new(math_cache.clone()))
+            .block(MathBlock::inline(math_cache))
+            .block(MarkdownBlock::new(self.file_reader()))
+            .block(ContentBlock)
+            .block(DetailsBlock)
+            .partials(liquid::partials::EagerCompiler::new(self.partial_source()));
+        for (kind, _) in ADMONITION_KINDS {
+            parser_builder = parser_builder.block(AdmonitionBlock::new(kind));
+        }
+        #[cfg(feature = "wasm")]
+        for plugin in self.get_wasm_plugins()? {
+            for filter in plugin.filters() {
+                parser_builder = parser_builder.filter(filter);
+            }
+            for tag in plugin.tags() {
+                parser_builder = parser_builder.tag(tag);
+            }
+        }
+        for parser_customiser in self.get_parser_customisers() {
+            parser_builder = parser_customiser(parser_builder);
+        }
+        parser_builder.build().into_diagnostic()
+    }
+
+    /// Get the frontmatter defaults applying to a page.
+    /// Defaults are defined in `_defaults.toml` files, applying to pages in the same directory and its subdirectories; defaults in deeper directories take precedence.
+    /// Layouts are not given defaults, as a default layout would otherwise be applied to the layouts themselves.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the page.
+    ///
+    /// # Returns
+    ///
+    /// The frontmatter defaults of the page.
+    fn get_frontmatter_defaults(&self, path: &Path) -> miette::Result<Table> {
+        let mut defaults = Table::new();
+        if Page::is_layout_path(path) {
+            return Ok(defaults);
+        }
+        let directories = path.ancestors().skip(1).collect::<Vec<_>>();
+        for directory in directories.into_iter().rev() {
+            if let Ok(directory_defaults) = self.read_to_string(directory.join("_defaults.toml")) {
+                defaults = Page::merge_frontmatter(
+                    defaults,
+                    directory_defaults.parse::<Table>().into_diagnostic()?,
+                );
+            }
+        }
+        Ok(defaults)
+    }
+
+    /// Get the schema that page frontmatter must conform to.
+    /// The schema is defined by the `schema` table in `global.toml`.
+    ///
+    /// # Returns
+    ///
+    /// The frontmatter schema, or an empty schema if none is defined.
+    fn get_frontmatter_schema(&self) -> miette::Result<Schema> {
+        let Ok(global_file) = self.read_to_string("global.toml") else {
+            return Ok(Schema::default());
+        };
+        match global_file
+            .parse::<Table>()
+            .into_diagnostic()?
+            .get("schema")
+        {
+            Some(schema) => schema.clone().try_into().into_diagnostic(),
+            None => Ok(Schema::default()),
+        }
+    }
+
+    /// Get the settings for generating a search index.
+    /// The settings are defined by the `search` table in `global.toml`.
+    ///
+    /// # Returns
+    ///
+    /// The search index settings, or `None` if no search index is to be generated.
+    fn get_search_settings(&self) -> miette::Result<Option<SearchSettings>> {
+        let Ok(global_file) = self.read_to_string("global.toml") else {
+            return Ok(None);
+        };
+        match global_file
+            .parse::<Table>()
+            .into_diagnostic()?
+            .get("search")
+        {
+            Some(settings) => Ok(Some(settings.clone().try_into().into_diagnostic()?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get the settings for generating a sitemap.
+    /// The settings are defined by the `sitemap` table in `global.toml`.
+    ///
+    /// # Returns
+    ///
+    /// The sitemap settings, or `None` if no sitemap is to be generated.
+    fn get_sitemap_settings(&self) -> miette::Result<Option<SitemapSettings>> {
+        let Ok(global_file) = self.read_to_string("global.toml") else {
+            return Ok(None);
+        };
+        match global_file
+            .parse::<Table>()
+            .into_diagnostic()?
+            .get("sitemap")
+        {
+            Some(settings) => Ok(Some(settings.clone().try_into().into_diagnostic()?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get the paths excluded from the site.
+    /// The paths are defined by the `exclude` list of glob patterns in `global.toml`.
+    ///
+    /// # Returns
+    ///
+    /// The paths excluded from the site.
+    fn get_exclusions(&self) -> miette::Result<Exclusions> {
+        // Installed themes are not part of the site, aside from the theme the site is layered above.
+        let mut patterns = ThemeLock::read(self)?
+            .themes
+            .iter()
+            .map(|theme| {
+                format!(
+                    "{}/{}",
+                    THEMES_DIRECTORY,
+                    glob::Pattern::escape(&theme.name)
+                )
+            })
+            .collect::<Vec<_>>();
+        let Ok(global_file) = self.read_to_string("global.toml") else {
+            return Exclusions::new(&patterns);
+        };
+        let global = global_file.parse::<Table>().into_diagnostic()?;
+        // The site's own theme is excluded wherever it is resolved to; a directory within the site, or an installed theme, whether or not it is locked.
+        if let Some(theme) = global
+            .get("theme")
+            .and_then(|x| x.as_str())
+            .filter(|x| !is_git_url(x))
+        {
+            let directory = to_slash_string(PathBuf::from(theme).clean());
+            if !Path::new(&directory).is_absolute() && !directory.starts_with("..") {
+                patterns.push(glob::Pattern::escape(&directory));
+            }
+            patterns.push(format!(
+                "{}/{}",
+                THEMES_DIRECTORY,
+                glob::Pattern::escape(theme)
+            ));
+        }
+        if let Some(exclude) = global.get("exclude") {
+            let exclude: Vec<String> = exclude.clone().try_into().into_diagnostic()?;
+            patterns.extend(exclude);
+        }
+        Exclusions::new(&patterns)
+    }
+
+    /// Get the sources of remote data.
+    /// The sources are defined by the tables under `data.remote` in `global.toml`.
+    ///
+    /// # Returns
+    ///
+    /// The remote sources, by name.
+    fn get_remote_sources(&self) -> miette::Result<BTreeMap<String, RemoteSource>> {
+        let Ok(global_file) = self.read_to_string("global.toml") else {
+            return Ok(BTreeMap::new());
+        };
+        match global_file
+            .parse::<Table>()
+            .into_diagnostic()?
+            .get("data")
+            .and_then(|x| x.get("remote"))
+        {
+            Some(sources) => sources.clone().try_into().into_diagnostic(),
+            None => Ok(BTreeMap::new()),
+        }
+    }
+
+    /// Get the commands run while building the site.
+    /// The commands are defined by the `hooks` table in `global.toml`.
+    ///
+    /// # Returns
+    ///
+    /// The hook settings, or the default settings if none are defined.
+    fn get_hook_settings(&self) -> miette::Result<HookSettings> {
+        let Ok(global_file) = self.read_to_string("global.toml") else {
+            return Ok(HookSettings::default());
+        };
+        match global_file.parse::<Table>().into_diagnostic()?.get("hooks") {
+            Some(settings) => settings.clone().try_into().into_diagnostic(),
+            None => Ok(HookSettings::default()),
+        }
+    }
+
+    /// Run the hooks of a stage of the build.
+    /// Hooks are only run if the provider can run commands.
+    ///
+    /// # Arguments
+    ///
+    /// * `stage` - The stage of the build.
+    ///
+    /// * `rebuilding` - Whether or not a watched site is being rebuilt, in which case hooks with `watch = false` are not run.
+    fn run_hooks(&self, stage: HookStage, rebuilding: bool) -> miette::Result<()> {
+        let Some(directory) = self.working_directory() else {
+            return Ok(());
+        };
+        run_hooks(
+            &self.get_hook_settings()?.commands(stage, rebuilding),
+            &directory,
+        )
+    }
+
+    /// Get the plugins extending how the site is built.
+    /// The plugins are defined by the `plugins` list in `global.toml`.
+    ///
+    /// # Returns
+    ///
+    /// The plugins, in the order they are declared.
+    fn get_plugins(&self) -> miette::Result<Vec<PluginSettings>> {
+        let Ok(global_file) = self.read_to_string("global.toml") else {
+            return Ok(Vec::new());
+        };
+        match global_file
+            .parse::<Table>()
+            .into_diagnostic()?
+            .get("plugins")
+        {
+            Some(plugins) => plugins.clone().try_into().into_diagnostic(),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Load the plugins compiled to WebAssembly.
+    /// These are the plugins in the `plugins` list in `global.toml` with a `wasm` module.
+    ///
+    /// # Returns
+    ///
+    /// The compiled plugins, in the order they are declared.
+    #[cfg(feature = "wasm")]
+    fn get_wasm_plugins(&self) -> miette::Result<Vec<WasmPlugin>> {
+        self.get_plugins()?
+            .into_iter()
+            .filter_map(|x| x.wasm)
+            .map(|path| WasmPlugin::load(&path, &self.read_bytes(&path)?))
+            .collect()
+    }
+
+    /// Get the settings for the archive pages of dated collections.
+    /// The settings are defined by the `archives` list in `global.toml`.
+    ///
+    /// # Returns
+    ///
+    /// The settings of each archived collection, in the order they are declared.
+    fn get_archive_settings(&self) -> miette::Result<Vec<DateArchiveSettings>> {
+        let Ok(global_file) = self.read_to_string("global.toml") else {
+            return Ok(Vec::new());
+        };
+        match global_file
+            .parse::<Table>()
+            .into_diagnostic()?
+            .get("archives")
+        {
+            Some(archives) => archives.clone().try_into().into_diagnostic(),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Get the settings for the Atom feeds of collections.
+    /// The settings are defined by the `feeds` list in `global.toml`.
+    ///
+    /// # Returns
+    ///
+    /// The settings of each feed.
+    fn get_feed_settings(&self) -> miette::Result<Vec<FeedSettings>> {
+        let Ok(global_file) = self.read_to_string("global.toml") else {
+            return Ok(Vec::new());
+        };
+        match global_file.parse::<Table>().into_diagnostic()?.get("feeds") {
+            Some(feeds) => feeds.clone().try_into().into_diagnostic(),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Get the settings for computing related pages.
+    /// The settings are defined by the `related` table in `global.toml`.
+    ///
+    /// # Returns
+    ///
+    /// The related page settings, or `None` if related pages are not to be computed.
+    fn get_related_settings(&self) -> miette::Result<Option<RelatedSettings>> {
+        let Ok(global_file) = self.read_to_string("global.toml") else {
+            return Ok(None);
+        };
+        match global_file
+            .parse::<Table>()
+            .into_diagnostic()?
+            .get("related")
+        {
+            Some(settings) => Ok(Some(settings.clone().try_into().into_diagnostic()?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get the settings for highlighting code within Markdown.
+    /// The settings are defined by the `syntax_highlighting` table in `global.toml`.
+    ///
+    /// # Returns
+    ///
+    /// The syntax highlighting settings.
+    fn get_syntax_highlighting_settings(&self) -> miette::Result<SyntaxHighlightingSettings> {
+        let Ok(global_file) = self.read_to_string("global.toml") else {
+            return Ok(SyntaxHighlightingSettings::default());
+        };
+        match global_file
+            .parse::<Table>()
+            .into_diagnostic()?
+            .get("syntax_highlighting")
+        {
+            Some(settings) => Ok(settings.clone().try_into().into_diagnostic()?),
+            None => Ok(SyntaxHighlightingSettings::default()),
+        }
+    }
+
+    /// Save the math rendered while pages were being rendered, so that later builds need not render it again.
+    ///
+    /// # Arguments
+    ///
+    /// * `prune` - Whether or not to forget math which was not part of the pages rendered, such as after a full build.
+    fn save_math_cache(&self, prune: bool) -> miette::Result<()> {
+        match self.math_cache() {
+            Some(math_cache) => math_cache.save(self, prune),
+            None => Ok(()),
+        }
+    }
+
+    /// Get how pages sharing a URL are handled.
+    /// This is defined by `url_conflicts` in `global.toml`.
+    ///
+    /// # Returns
+    ///
+    /// The strategy for handling pages sharing a URL, which fails the build by default.
+    fn get_url_conflict_strategy(&self) -> miette::Result<UrlConflictStrategy> {
+        let Ok(global_file) = self.read_to_string("global.toml") else {
+            return Ok(UrlConflictStrategy::default());
+        };
+        match global_file
+            .parse::<Table>()
+            .into_diagnostic()?
+            .get("url_conflicts")
+        {
+            Some(strategy) => strategy.clone().try_into().into_diagnostic(),
+            None => Ok(UrlConflictStrategy::default()),
+        }
+    }
+
+    /// Get the functions transforming the URL of every page.
+    /// These are applied in order, after the transforms defined in `global.toml`.
+    ///
+    /// # Returns
+    ///
+    /// The URL transformation functions.
+    fn get_url_transformers(&self) -> Vec<UrlTransformer> {
+        Vec::new()
+    }
+
+    /// Get the transforms applied to the URL of every page.
+    /// The transforms are defined by the `url_transforms` table in `global.toml`, and are followed by the provider's URL transformation functions.
+    ///
+    /// # Returns
+    ///
+    /// The URL transforms.
+    fn get_url_transforms(&self) -> miette::Result<UrlTransforms> {
+        let settings = match self.read_to_string("global.toml") {
+            Ok(global_file) => match global_file
+                .parse::<Table>()
+                .into_diagnostic()?
+                .get("url_transforms")
+            {
+                Some(settings) => settings.clone().try_into().into_diagnostic()?,
+                None => UrlTransformSettings::default(),
+            },
+            Err(_) => UrlTransformSettings::default(),
+        };
+        Ok(UrlTransforms {
+            settings,
+            transformers: self.get_url_transformers(),
+        })
+    }
+
+    /// Get the settings for linting the content of pages.
+    /// The settings are defined by the `lint` table in `global.toml`.
+    ///
+    /// # Returns
+    ///
+    /// The lint settings, or `None` if pages are not to be linted.
+    fn get_lint_settings(&self) -> miette::Result<Option<LintSettings>> {
+        let Ok(global_file) = self.read_to_string("global.toml") else {
+            return Ok(None);
+        };
+        match global_file.parse::<Table>().into_diagnostic()?.get("lint") {
+            Some(settings) => Ok(Some(settings.clone().try_into().into_diagnostic()?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Lint the content of pages, if linting is enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `pages` - The pages to lint.
+    ///
+    /// # Returns
+    ///
+    /// An error if any problem found is an error.
+    fn lint<'a>(&self, pages: impl IntoIterator<Item = &'a Page>) -> miette::Result<()> {
+        match self.get_lint_settings()? {
+            Some(settings) => lint_pages(self, &Linter::new(settings)?, pages),
+            None => Ok(()),
+        }
+    }
+
+    /// Get the settings for processing rendered pages before they are written.
+    /// The settings are defined by the `external_links` and `images` tables in `global.toml`.
+    ///
+    /// # Returns
+    ///
+    /// The post-processing settings.
+    fn get_post_processing_settings(&self) -> miette::Result<PostProcessingSettings> {
+        let Ok(global_file) = self.read_to_string("global.toml") else {
+            return Ok(PostProcessingSettings::default());
+        };
+        let global = global_file.parse::<Table>().into_diagnostic()?;
+        let url = global
+            .get("url")
+            .and_then(|x| x.as_str())
+            .map(|x| x.to_string());
+        let external_links = match global.get("external_links") {
+            Some(settings) => {
+                let mut settings: ExternalLinkSettings =
+                    settings.clone().try_into().into_diagnostic()?;
+                // Links to the site itself are never external.
+                if let Some(site_host) = url.as_deref().and_then(url_host) {
+                    settings.allow.push(site_host);
+                }
+                Some(settings)
+            }
+            None => None,
+        };
+        let images = match global.get("images") {
+            Some(settings) => Some(settings.clone().try_into().into_diagnostic()?),
+            None => None,
+        };
+        Ok(PostProcessingSettings {
+            external_links,
+            images,
+            url,
+            plugins: self.get_plugins()?,
+            #[cfg(feature = "wasm")]
+            wasm_plugins: self.get_wasm_plugins()?,
+        })
+    }
+
+    /// Get the functions transforming each page's rendered HTML before it is written.
+    /// These are applied in order, after any post-processing configured in `global.toml`.
+    ///
+    /// # Returns
+    ///
+    /// The post-processing functions.
+    fn get_post_processors(&self) -> Vec<PostProcessor> {
+        Vec::new()
+    }
+
+    /// Copy the site's assets into the output.
+    /// Each asset is output at its path within the `assets` folder.
+    ///
+    /// # Returns
+    ///
+    /// The number of assets copied.
+    fn copy_assets(&self) -> miette::Result<usize> {
+        let assets = self.list_assets()?;
+        for asset in assets.iter() {
+            let output_path =
+                Path::new("output").join(asset.strip_prefix("assets").unwrap_or(asset));
+            debug!("Copying `{}` … ", to_slash_string(asset));
+            self.write_file(output_path, self.read_bytes(asset)?)?;
+        }
+        Ok(assets.len())
+    }
+
+    /// Process a rendered page before it is written.
+    ///
+    /// # Arguments
+    ///
+    /// * `page` - The page being written; for layouts, the page above the layouts.
+    ///
+    /// * `rendered` - The rendered page.
+    ///
+    /// * `output_path` - The output path of the page.
+    ///
+    /// * `settings` - The post-processing settings.
+    ///
+    /// # Returns
+    ///
+    /// The processed page, or an error if a plugin could not process it.
+    fn post_process(
+        &self,
+        page: &Page,
+        rendered: String,
+        output_path: &str,
+        settings: &PostProcessingSettings,
+    ) -> miette::Result<String> {
+        let mut processed = rendered;
+        if output_path.ends_with(".html") {
+            if let Some(external_link_settings) = &settings.external_links {
+                processed = external_link_settings.apply(&processed);
+            }
+            if let Some(image_settings) = &settings.images {
+                processed = image_settings.apply(&processed, |src| {
+                    image_paths(src, output_path, settings.url.as_deref())
+                        .into_iter()
+                        .find_map(|path| self.read_bytes(path).ok())
+                        .and_then(|bytes| image_dimensions(&bytes))
+                });
+            }
+        }
+        if let Some(directory) = self.working_directory().filter(|_| {
+            settings
+                .plugins
+                .iter()
+                .any(|x| x.runs_at(PluginStage::Render))
+        }) {
+            let mut plugin_page = page.clone();
+            plugin_page.rendered = processed.into();
+            processed = run_plugins(
+                &settings.plugins,
+                PluginStage::Render,
+                plugin_page,
+                &directory,
+            )?
+            .rendered
+            .to_string();
+        }
+        #[cfg(feature = "wasm")]
+        for plugin in settings
+            .wasm_plugins
+            .iter()
+            .filter(|x| x.has_post_processor())
+        {
+            processed = plugin.post_process(page, processed, output_path)?;
+        }
+        for post_processor in self.get_post_processors() {
+            processed = post_processor(page, processed);
+        }
+        Ok(processed)
     }
 
     /// Given a path and locale, get a page.
     ///
     /// # Arguments
     ///
-    /// * `path` - The path to the page.
+    /// * `path` - The path to the page, relative to the site's directory.
     ///
     /// * `locale` - The locale for date formatting.
     ///
+    /// * `timezone` - The timezone for date formatting.
+    ///
+    /// * `schema` - The schema the page's frontmatter is validated against.
+    ///
     /// # Returns
     ///
     /// A Vox page.
-    fn path_to_page(&self, path: PathBuf, locale: Locale) -> miette::Result<Page> {
-        Page::new(self.read_to_string(path.clone())?, path, locale)
+    fn path_to_page(
+        &self,
+        path: PathBuf,
+        locale: Locale,
+        timezone: Tz,
+        schema: &Schema,
+    ) -> miette::Result<Page> {
+        let mut page = Page::new(
+            self.read_to_string(path.clone())?,
+            &path,
+            locale,
+            timezone,
+            self.get_frontmatter_defaults(&path)?,
+            schema,
+        )?;
+        page.git = self.get_git_metadata(&path, locale, timezone);
+        if let Some(directory) = self.working_directory() {
+            page = run_plugins(
+                &self.get_plugins()?,
+                PluginStage::Frontmatter,
+                page,
+                &directory,
+            )?;
+        }
+        Ok(page)
     }
 
-    /// Get the global Liquid context.
+    /// Get the site's global settings, as configured in `global.toml`.
     ///
     /// # Returns
     ///
-    /// The global Liquid context and detected locale.
-    fn get_global_context(&self) -> miette::Result<(Object, Locale)> {
-        let global_context = match self.read_to_string("global.toml") {
-            Ok(global_file) => global_file.parse::<Table>().into_diagnostic()?,
+    /// The site's global settings.
+    fn get_global_settings(&self) -> miette::Result<Table> {
+        match self.read_to_string("global.toml") {
+            Ok(global_file) => global_file.parse::<Table>().into_diagnostic(),
             Err(_) => format!("locale = '{}'", date::default_locale_string())
                 .parse::<Table>()
-                .into_diagnostic()?,
-        };
-        let locale: String = global_context
-            .get("locale")
-            .unwrap_or(&toml::Value::String(date::default_locale_string()))
-            .as_str()
-            .unwrap_or(&date::default_locale_string())
-            .to_string();
-        let locale = date::locale_string_to_locale(locale.clone());
-        let current_date = Date::chrono_to_date(Utc::now(), locale);
+                .into_diagnostic(),
+        }
+    }
+
+    /// Get the locale and timezone of the site, without constructing the global Liquid context.
+    ///
+    /// # Returns
+    ///
+    /// The detected locale, and configured timezone.
+    fn get_locale_and_timezone(&self) -> miette::Result<(Locale, Tz)> {
+        Ok(date::settings_to_locale_and_timezone(
+            &self.get_global_settings()?,
+        ))
+    }
+
+    /// Get the global Liquid context.
+    /// The repository's metadata is as of when the DAG was last constructed.
+    ///
+    /// # Returns
+    ///
+    /// The global Liquid context, detected locale, and configured timezone.
+    fn get_global_context(&self) -> miette::Result<(Object, Locale, Tz)> {
+        let global_context = self.get_global_settings()?;
+        let (locale, timezone) = date::settings_to_locale_and_timezone(&global_context);
+        let current_date = Date::chrono_to_date(Utc::now(), locale, timezone);
+        // Remote data is read as it was cached when the build started.
+        let data = load_remote_data(self, &self.get_remote_sources()?)?;
         Ok((
             object!({
                 "global": global_context,
+                "data": data,
                 "meta": {
                     "builder": "Vox",
                     "version": VERSION,
                     "date": current_date,
+                    "host": build_host(),
+                    "environment": self.environment(),
+                    "git": self.get_repository_metadata(),
                 }
             }),
             locale,
+            timezone,
         ))
     }
 
@@ -154,6 +1058,8 @@ pub trait VoxProvider {
     ///
     /// * `layout_index` - The DAG index of the page's layout, if it has one.
     ///
+    /// * `synthesised` - The page, if it is not read from its path, such as an archive page.
+    ///
     /// * `dag` - The DAG to upsert the page into.
     ///
     /// * `pages` - Mapping of paths to DAG indices.
@@ -165,22 +1071,32 @@ pub trait VoxProvider {
     /// * `collection_members` - Mapping of collection names to a set of pages in said collection.
     ///
     /// * `locale` - The locale for date formatting.
+    ///
+    /// * `timezone` - The timezone for date formatting.
+    ///
+    /// * `schema` - The schema the frontmatter of pages is validated against.
     #[allow(clippy::too_many_arguments)]
     fn insert_or_update_page(
         &self,
         entry: PathBuf,
         layout_index: Option<NodeIndex>,
+        synthesised: Option<Page>,
         dag: &mut StableDag<Page, EdgeType>,
         pages: &mut AHashMap<PathBuf, NodeIndex>,
         layouts: &mut AHashMap<PathBuf, HashSet<NodeIndex>>,
         collection_dependents: &mut AHashMap<String, HashSet<NodeIndex>>,
         collection_members: &mut AHashMap<String, HashSet<NodeIndex>>,
         locale: Locale,
+        timezone: Tz,
+        schema: &Schema,
     ) -> miette::Result<()> {
         let entry = entry.clean();
         let (page, index) = if !Page::is_layout_path(&entry) {
             debug!("Inserting or updating page: {:?} … ", entry);
-            let page = self.path_to_page(entry.clone(), locale)?;
+            let page = match synthesised {
+                Some(page) => page,
+                None => self.path_to_page(entry.clone(), locale, timezone, schema)?,
+            };
             // If the page already exists in the DAG, update it. Otherwise, insert it.
             let index = if pages.contains_key(&entry) {
                 debug!("Updating page: {:?} … ", entry);
@@ -204,13 +1120,15 @@ pub trait VoxProvider {
 
         // A page's parents are pages in the collections it depends on. Its layout is a child.
         let layout = page.layout.clone();
+        let outer_layouts = page.outer_layouts.clone();
         let collections = page.collections.clone();
         let depends = page.depends.clone();
         debug!("Layout used: {:?} … ", layout);
         debug!("Collections used: {:?} … ", depends);
         if let Some(layout) = layout {
             // Layouts are inserted multiple times, once for each page that uses them.
-            let layout_path = PathBuf::from(format!("layouts/{}.vox", layout)).clean();
+            let layout_path =
+                PathBuf::from(format!("layouts/{}.vox", normalise_separators(&layout))).clean();
             let children = dag.children(index).iter(dag).collect::<Vec<_>>();
             // If this page is being updated, the old layout should be replaced with the current one in the DAG.
             let old_layout = children
@@ -220,8 +1138,31 @@ pub trait VoxProvider {
                 trace!("Removing old layout … ");
                 dag.remove_node(old_layout.1);
             }
+            // Layouts may use other layouts, but a layout using itself would be inserted endlessly.
+            let mut ancestor = Some(index);
+            while let Some(ancestor_index) = ancestor {
+                if PathBuf::from(dag.graph()[ancestor_index].to_path_string()).clean()
+                    == layout_path
+                {
+                    return Err(LayoutCycle {
+                        layout: to_slash_string(&layout_path),
+                    })
+                    .into_diagnostic();
+                }
+                ancestor = dag
+                    .parents(ancestor_index)
+                    .iter(dag)
+                    .find(|parent| *dag.edge_weight(parent.0).unwrap() == EdgeType::Layout)
+                    .map(|parent| parent.1);
+            }
             debug!("Inserting layout: {:?} … ", layout_path);
-            let layout_page = self.path_to_page(layout_path.clone(), locale)?;
+            let mut layout_page =
+                self.path_to_page(layout_path.clone(), locale, timezone, schema)?;
+            // The layouts listed after a page's layout wrap it in turn, in place of the layouts it would otherwise use.
+            if let Some((outer_layout, outer_layouts)) = outer_layouts.split_first() {
+                layout_page.layout = Some(outer_layout.clone());
+                layout_page.outer_layouts = outer_layouts.to_vec();
+            }
             let layout_index = dag.add_child(index, EdgeType::Layout, layout_page);
             if let Some(layouts) = layouts.get_mut(&layout_path) {
                 layouts.insert(layout_index.1);
@@ -320,36 +1261,203 @@ pub trait VoxProvider {
 
         if page.url.is_empty() {
             let layout_url = self.get_layout_url(page_index, &build.dag);
-            layout_url.map(|layout_url| format!("output/{}", layout_url))
+            layout_url.map(|layout_url| url_output_path(&layout_url))
         } else if !page.url.is_empty() {
-            Some(format!("output/{}", page.url))
+            Some(url_output_path(&page.url))
         } else {
             None
         }
     }
 
     /// Generate stylesheets for syntax highlighting.
+    /// No stylesheets are generated if code is styled inline.
     fn generate_syntax_stylesheets(&self) -> miette::Result<()> {
-        let css_path = PathBuf::from("output/css/");
-        let dark_css_path = css_path.join("dark-code.css");
-        let light_css_path = css_path.join("light-code.css");
-        let code_css_path = css_path.join("code.css");
-
-        let ts = ThemeSet::load_defaults();
-        let dark_theme = &ts.themes["base16-ocean.dark"];
-        let css_dark =
-            css_for_theme_with_class_style(dark_theme, syntect::html::ClassStyle::Spaced)
-                .into_diagnostic()?;
-        self.write_file(dark_css_path, css_dark)?;
-
-        let light_theme = &ts.themes["base16-ocean.light"];
-        let css_light =
-            css_for_theme_with_class_style(light_theme, syntect::html::ClassStyle::Spaced)
-                .into_diagnostic()?;
-        self.write_file(light_css_path, css_light)?;
-
-        let css = r#"@import url("light-code.css") (prefers-color-scheme: light);@import url("dark-code.css") (prefers-color-scheme: dark);"#;
-        self.write_file(code_css_path, css)?;
+        for (path, css) in self.get_syntax_highlighting_settings()?.stylesheets()? {
+            self.write_file(PathBuf::from("output").join(path), css)?;
+        }
+        Ok(())
+    }
+
+    /// Output JSON representations of rendered pages, and an index of every collection.
+    ///
+    /// # Arguments
+    ///
+    /// * `build` - A Vox build.
+    ///
+    /// * `updated_pages` - The indices of the pages rendered during the build.
+    fn generate_json(&self, build: &Build, updated_pages: &[NodeIndex]) -> miette::Result<()> {
+        for updated_page_index in updated_pages {
+            let updated_page = &build.dag.graph()[*updated_page_index];
+            if updated_page.is_layout || updated_page.url.is_empty() {
+                continue;
+            }
+            let Some(json_path) = json_output_path(&url_output_path(&updated_page.url)) else {
+                continue;
+            };
+            debug!(
+                "Writing `{}` to `{}` … ",
+                updated_page.to_path_string(),
+                json_path
+            );
+            self.write_file(
+                json_path,
+                serde_json::to_string(&PageEntry::from_page(updated_page, true))
+                    .into_diagnostic()?,
+            )?;
+        }
+        let mut collections: AHashMap<String, Vec<&Page>> = AHashMap::new();
+        for page in build.dag.graph().node_weights() {
+            if page.is_layout || page.url.is_empty() {
+                continue;
+            }
+            for collection in page.collections.iter().flatten() {
+                collections
+                    .entry(collection.clone())
+                    .or_default()
+                    .push(page);
+            }
+        }
+        for (collection, mut members) in collections {
+            members.sort_by(|lhs, rhs| Page::collection_order(lhs, rhs));
+            let entries = members
+                .into_iter()
+                .map(|page| PageEntry::from_page(page, false))
+                .collect::<Vec<_>>();
+            self.write_file(
+                collection_index_path(&collection),
+                serde_json::to_string(&entries).into_diagnostic()?,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Output a search index of a build's pages, if configured to.
+    /// If the site has a search results page, an OpenSearch description is also output.
+    ///
+    /// # Arguments
+    ///
+    /// * `build` - A Vox build.
+    fn generate_search_index(&self, build: &Build) -> miette::Result<()> {
+        let Some(settings) = self.get_search_settings()? else {
+            return Ok(());
+        };
+        let mut pages = build
+            .dag
+            .graph()
+            .node_weights()
+            .filter(|page| settings.includes(page))
+            .collect::<Vec<_>>();
+        pages.sort_by_key(|page| &page.url);
+        let documents = pages
+            .into_iter()
+            .enumerate()
+            .map(|(id, page)| SearchDocument::from_page(id, page))
+            .collect::<Vec<_>>();
+        let output_path = settings.output_path();
+        info!(
+            "Writing search index of {} pages to `{}` … ",
+            documents.len(),
+            output_path
+        );
+        self.write_file(
+            output_path,
+            serde_json::to_string(&documents).into_diagnostic()?,
+        )?;
+        let global = self
+            .read_to_string("global.toml")?
+            .parse::<Table>()
+            .into_diagnostic()?;
+        let get_global = |key| global.get(key).and_then(|x| x.as_str());
+        if let Some(description) =
+            settings.opensearch_description(get_global("title"), get_global("url"))
+        {
+            debug!("Writing OpenSearch description to `{}` … ", OPENSEARCH_PATH);
+            self.write_file(format!("output/{}", OPENSEARCH_PATH), description)?;
+        }
+        Ok(())
+    }
+
+    /// Output the Atom feeds of collections, if configured to.
+    ///
+    /// # Arguments
+    ///
+    /// * `build` - A Vox build.
+    fn generate_feeds(&self, build: &Build) -> miette::Result<()> {
+        let feeds = self.get_feed_settings()?;
+        if feeds.is_empty() {
+            return Ok(());
+        }
+        let global = self
+            .read_to_string("global.toml")?
+            .parse::<Table>()
+            .into_diagnostic()?;
+        let get_global = |key| global.get(key).and_then(|x| x.as_str());
+        let now = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+        for settings in feeds {
+            if settings.absolute_urls
+                && settings.content == FeedContent::Full
+                && get_global("url").is_none()
+            {
+                warn!(
+                    "The feed of `{}` cannot have absolute URLs, as `global.toml` has no `url` … ",
+                    settings.collection
+                );
+            }
+            let pages = build
+                .dag
+                .graph()
+                .node_weights()
+                .filter(|page| {
+                    !page.is_layout
+                        && url_output_path(&page.url).ends_with(".html")
+                        && page
+                            .collections
+                            .as_ref()
+                            .is_some_and(|x| x.contains(&settings.collection))
+                })
+                .collect::<Vec<_>>();
+            let output_path = settings.output_path();
+            info!(
+                "Writing feed of {} pages to `{}` … ",
+                settings.limit.unwrap_or(usize::MAX).min(pages.len()),
+                output_path
+            );
+            self.write_file(
+                output_path,
+                atom_feed(
+                    &settings,
+                    &pages,
+                    get_global("title"),
+                    get_global("author"),
+                    get_global("url"),
+                    &now,
+                ),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Output a sitemap of the site's pages, if configured to.
+    ///
+    /// # Arguments
+    ///
+    /// * `build` - A Vox build.
+    fn generate_sitemap(&self, build: &Build) -> miette::Result<()> {
+        let Some(settings) = self.get_sitemap_settings()? else {
+            return Ok(());
+        };
+        let global = self
+            .read_to_string("global.toml")?
+            .parse::<Table>()
+            .into_diagnostic()?;
+        let site_url = global.get("url").and_then(|x| x.as_str());
+        if site_url.is_none() {
+            warn!("The sitemap cannot have absolute URLs, as `global.toml` has no `url` … ");
+        }
+        let pages = build.dag.graph().node_weights().collect::<Vec<_>>();
+        let output_path = settings.output_path();
+        info!("Writing sitemap to `{}` … ", output_path);
+        self.write_file(output_path, sitemap(&settings, &pages, site_url)?)?;
         Ok(())
     }
 
@@ -360,13 +1468,25 @@ pub trait VoxProvider {
     /// * `build` - A Vox build.
     fn visualise_dag(&self, build: &Build) -> miette::Result<()> {
         let dag_graph = build.dag.graph();
+        // Nodes are coloured by whether they are layouts or in collections, as determined when their pages were created.
+        let mut node_colours = AHashMap::new();
+        for page in dag_graph.node_weights() {
+            let label = to_slash_string(PathBuf::from(page.to_path_string()).clean());
+            let colour = if page.is_layout {
+                "#FFDFBA"
+            } else if page.collections.is_some() {
+                "#DAFFBA"
+            } else {
+                "#BADAFF"
+            };
+            node_colours.insert(label, colour);
+        }
         let dag_graphviz = Dot::with_attr_getters(
             dag_graph,
             &[Config::NodeNoLabel, Config::EdgeNoLabel],
             &|_graph, edge| format!("label = \"{:?}\"", edge.weight()),
             &|_graph, node| {
-                let path = PathBuf::from(node.1.to_path_string()).clean();
-                let label = path.to_string_lossy().to_string();
+                let label = to_slash_string(PathBuf::from(node.1.to_path_string()).clean());
                 format!("label = \"{}\"", label)
             },
         );
@@ -382,17 +1502,8 @@ pub trait VoxProvider {
                 let old_shape = node.shape.clone();
                 if let ShapeKind::Circle(label) = old_shape {
                     node.shape = ShapeKind::Box(label.clone());
-                    if Page::is_layout_path(label.clone()) {
-                        node.look.fill_color = Some(Color::fast("#FFDFBA"));
-                    } else {
-                        match Page::get_collections_from_path(label)? {
-                            Some(_) => {
-                                node.look.fill_color = Some(Color::fast("#DAFFBA"));
-                            }
-                            None => {
-                                node.look.fill_color = Some(Color::fast("#BADAFF"));
-                            }
-                        }
+                    if let Some(colour) = node_colours.get(&label) {
+                        node.look.fill_color = Some(Color::fast(colour));
                     }
                 }
             }
@@ -405,6 +1516,58 @@ pub trait VoxProvider {
         Ok(())
     }
 
+    /// Render a Liquid template with the contexts of the site, without building it.
+    /// Given a page, the template is rendered with the contexts that page is rendered with, including its rendered content.
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - The Liquid template to render.
+    ///
+    /// * `page` - The path of a page, relative to the site's directory.
+    ///
+    /// # Returns
+    ///
+    /// The rendered template.
+    fn evaluate(&self, template: &str, page: Option<&Path>) -> miette::Result<String> {
+        let template_parser = self.create_liquid_parser()?;
+        // The DAG is constructed first, as doing so reads the repository's metadata.
+        let (dag, pages, _layouts) = self.generate_dag()?;
+        let (contexts, locale, timezone) = self.get_global_context()?;
+        let mut build = Build {
+            template_parser,
+            contexts,
+            locale,
+            timezone,
+            dag,
+            templates: CompiledTemplates::default(),
+            url_transforms: self.get_url_transforms()?,
+        };
+        build.resolve_url_conflicts(self.get_url_conflict_strategy()?)?;
+        build.insert_collections_context()?;
+        let contexts = match page {
+            Some(page) => {
+                let page = page.clean();
+                let index = pages
+                    .iter()
+                    .find(|(path, _)| path.clean() == page)
+                    .map(|(_, index)| *index)
+                    .ok_or(miette::miette!(
+                        "`{}` is not a page of the site.",
+                        to_slash_string(&page)
+                    ))?;
+                build.render_with_ancestors(index, &mut AHashSet::new())?;
+                build.get_page_contexts(index)?
+            }
+            None => build.contexts.clone(),
+        };
+        build
+            .template_parser
+            .parse(template)
+            .into_diagnostic()?
+            .render(&contexts)
+            .into_diagnostic()
+    }
+
     /// Perform an initial build of a Vox site.
     ///
     /// # Arguments
@@ -415,37 +1578,118 @@ pub trait VoxProvider {
     ///
     /// * `locale` - The locale for date formatting.
     ///
+    /// * `timezone` - The timezone for date formatting.
+    ///
     /// * `dag` - The DAG representing the structure of the site.
     ///
     /// * `visualise_dag` - Whether or not to output a visualisation of the DAG.
     ///
     /// * `generate_syntax_css` - Whether or not to output a stylesheet for syntax highlighting.
     ///
+    /// * `generate_json` - Whether or not to output JSON representations of pages and collections.
+    ///
     /// # Returns
     ///
     /// A list of rendered pages and the DAG of the finished Vox build.
+    #[allow(clippy::too_many_arguments)]
     fn generate_site(
         &self,
         template_parser: liquid::Parser,
         contexts: liquid::Object,
         locale: Locale,
+        timezone: Tz,
         dag: StableDag<Page, EdgeType>,
         visualise_dag: bool,
         generate_syntax_css: bool,
+        generate_json: bool,
     ) -> miette::Result<(Vec<NodeIndex>, StableDag<Page, EdgeType>)> {
+        self.generate_site_with_sink(
+            template_parser,
+            contexts,
+            locale,
+            timezone,
+            dag,
+            visualise_dag,
+            generate_syntax_css,
+            generate_json,
+            &mut BuildSummary::default(),
+            |_page, output_path, rendered| self.write_file(output_path, rendered),
+        )
+    }
+
+    /// Perform an initial build of a Vox site, passing each rendered page to a sink rather than writing it.
+    /// Other output, such as JSON representations of pages or search indices, is still written.
+    ///
+    /// # Arguments
+    ///
+    /// * `template_parser` - A Liquid parser.
+    ///
+    /// * `contexts` - The Liquid contexts to render with.
+    ///
+    /// * `locale` - The locale for date formatting.
+    ///
+    /// * `timezone` - The timezone for date formatting.
+    ///
+    /// * `dag` - The DAG representing the structure of the site.
+    ///
+    /// * `visualise_dag` - Whether or not to output a visualisation of the DAG.
+    ///
+    /// * `generate_syntax_css` - Whether or not to output a stylesheet for syntax highlighting.
+    ///
+    /// * `generate_json` - Whether or not to output JSON representations of pages and collections.
+    ///
+    /// * `summary` - The summary of the build, in which the pages rendered & written and the stages performed are recorded.
+    ///
+    /// * `sink` - Receives each rendered page as it is produced, with its output path and processed contents; for layouts, the page is the page above the layouts.
+    ///   Pages with layouts are received again for each layout, each superseding the last at the same output path.
+    ///
+    /// # Returns
+    ///
+    /// A list of rendered pages and the DAG of the finished Vox build.
+    #[allow(clippy::too_many_arguments)]
+    fn generate_site_with_sink(
+        &self,
+        template_parser: liquid::Parser,
+        contexts: liquid::Object,
+        locale: Locale,
+        timezone: Tz,
+        dag: StableDag<Page, EdgeType>,
+        visualise_dag: bool,
+        generate_syntax_css: bool,
+        generate_json: bool,
+        summary: &mut BuildSummary,
+        mut sink: impl FnMut(&Page, &str, &[u8]) -> miette::Result<()>,
+    ) -> miette::Result<(Vec<NodeIndex>, StableDag<Page, EdgeType>)> {
+        // Time is unavailable when compiled to WebAssembly.
+        #[cfg(not(target_arch = "wasm32"))]
         let mut timer = Stopwatch::start_new();
         let mut build = Build {
             template_parser,
             contexts,
             locale,
+            timezone,
             dag,
+            templates: CompiledTemplates::default(),
+            url_transforms: self.get_url_transforms()?,
         };
+        build.resolve_url_conflicts(self.get_url_conflict_strategy()?)?;
+        let stage = StageTimer::start();
+        self.lint(build.dag.graph().node_weights())?;
+        summary.record("lint", stage);
+        let stage = StageTimer::start();
         let updated_pages = build.render_all()?;
+        summary.record("render", stage);
+        summary.pages_rendered += updated_pages.len();
         if visualise_dag {
             self.visualise_dag(&build)?;
         }
         info!("{} pages were rendered … ", updated_pages.len());
+        let stage = StageTimer::start();
+        let post_processing_settings = self.get_post_processing_settings()?;
+        let mut written = AHashSet::new();
+        let mut progress = Progress::new("Writing", updated_pages.len());
         for updated_page_index in updated_pages.iter() {
+            progress.tick();
             let updated_page = &build.dag.graph()[*updated_page_index];
             // If a page has no URL, it may be a layout.
             // Layouts contain rendered content but must be written using their parent's URL.
@@ -453,27 +1697,54 @@ pub trait VoxProvider {
             match output_path {
                 None => {
                     warn!("Page has no URL: {:#?} … ", updated_page.to_path_string());
+                    summary.files_skipped += 1;
                     continue;
                 }
                 Some(output_path) => {
-                    info!(
+                    debug!(
                         "Writing `{}` to `{}` … ",
                         updated_page.to_path_string(),
                         output_path
                     );
-                    self.write_file(output_path, updated_page.rendered.clone())?;
+                    let page = &build.dag.graph()
+                        [Build::get_page_above_layouts(&build.dag, *updated_page_index)];
+                    let rendered = self.post_process(
+                        page,
+                        updated_page.rendered.to_string(),
+                        &output_path,
+                        &post_processing_settings,
+                    )?;
+                    sink(page, &output_path, rendered.as_bytes())?;
+                    written.insert(output_path);
                 }
             }
         }
+        progress.finish();
+        summary.files_written += written.len();
+        summary.record("write", stage);
+        let stage = StageTimer::start();
         if generate_syntax_css {
             self.generate_syntax_stylesheets()?;
         }
-        timer.stop();
-        info!(
-            "Generated {} pages in {:.2} seconds … ",
-            updated_pages.len(),
-            timer.elapsed_s()
-        );
+        if generate_json {
+            self.generate_json(&build, &updated_pages)?;
+        }
+        self.generate_search_index(&build)?;
+        self.generate_feeds(&build)?;
+        self.generate_sitemap(&build)?;
+        self.save_math_cache(true)?;
+        summary.record("supplementary", stage);
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            timer.stop();
+            info!(
+                "Generated {} pages in {:.2} seconds … ",
+                updated_pages.len(),
+                timer.elapsed_s()
+            );
+        }
+        #[cfg(target_arch = "wasm32")]
+        info!("Generated {} pages … ", updated_pages.len());
         Ok((updated_pages, build.dag))
     }
 
@@ -489,30 +1760,37 @@ pub trait VoxProvider {
     ///
     /// * `generate_syntax_css` - Whether or not to output a stylesheet for syntax highlighting.
     ///
+    /// * `generate_json` - Whether or not to output JSON representations of pages and collections.
+    ///
     /// * `old_dag` - The former DAG.
     ///
     /// * `old_pages` - Former mapping of paths to DAG indices.
     ///
     /// * `old_layouts` - Former mapping of paths to a set of DAG indices.
     ///
+    /// * `summary` - The summary of the rebuild, in which the pages rendered & written and the stages performed are recorded.
+    ///
     /// # Returns
     ///
     /// The DAG of the new finished Vox build, a new mapping of paths to DAG indices, and a new mapping of paths to a set of DAG indices.
-    #[allow(clippy::type_complexity)]
+    #[allow(clippy::type_complexity, clippy::too_many_arguments)]
     fn incremental_regeneration(
         &self,
         global_or_snippets_changed: bool,
         parser: liquid::Parser,
         visualise_dag: bool,
         generate_syntax_css: bool,
+        generate_json: bool,
         old_dag: StableDag<Page, crate::builds::EdgeType>,
         old_pages: AHashMap<PathBuf, NodeIndex>,
         old_layouts: AHashMap<PathBuf, HashSet<NodeIndex>>,
+        summary: &mut BuildSummary,
     ) -> miette::Result<(
         StableDag<Page, crate::builds::EdgeType>,
         AHashMap<PathBuf, NodeIndex<u32>>,
         AHashMap<PathBuf, HashSet<NodeIndex>>,
     )> {
+        let stage = StageTimer::start();
         let (mut new_dag, new_pages, new_layouts) = self.generate_dag()?;
         let (added_or_modified, removed, removed_output_paths) = self.get_dag_difference(
             &old_dag,
@@ -538,14 +1816,17 @@ pub trait VoxProvider {
             old_pages,
             &new_pages,
         )?;
+        summary.record("dag", stage);
         Ok((
             self.output_regenerated(
                 visualise_dag,
                 generate_syntax_css,
+                generate_json,
                 parser,
                 removed_output_paths,
                 new_dag,
                 pages_to_render,
+                summary,
             )?,
             new_pages,
             new_layouts,
@@ -562,6 +1843,8 @@ pub trait VoxProvider {
     ///
     /// * `generate_syntax_css` - Whether or not to output a stylesheet for syntax highlighting.
     ///
+    /// * `generate_json` - Whether or not to output JSON representations of pages and collections.
+    ///
     /// * `parser` - A Liquid parser.
     ///
     /// * `removed_output_paths` - A set of paths pointing to removed output files.
@@ -570,73 +1853,143 @@ pub trait VoxProvider {
     ///
     /// * `pages_to_render` - A set of pages needing to be rendered.
     ///
+    /// * `summary` - The summary of the rebuild, in which the pages rendered & written and the stages performed are recorded.
+    ///
     /// # Returns
     ///
     /// The DAG of the new finished Vox build.
+    #[allow(clippy::too_many_arguments)]
     fn output_regenerated(
         &self,
         visualise_dag: bool,
         generate_syntax_css: bool,
+        generate_json: bool,
         parser: liquid::Parser,
         removed_output_paths: AHashSet<PathBuf>,
         new_dag: StableDag<Page, crate::builds::EdgeType>,
-        pages_to_render: AHashSet<NodeIndex>,
+        mut pages_to_render: AHashSet<NodeIndex>,
+        summary: &mut BuildSummary,
     ) -> miette::Result<StableDag<Page, crate::builds::EdgeType>> {
         let global = self.get_global_context()?;
         info!("Rebuilding … ");
+        // Time is unavailable when compiled to WebAssembly.
+        #[cfg(not(target_arch = "wasm32"))]
         let mut timer = Stopwatch::start_new();
         let mut build = Build {
             template_parser: parser,
             contexts: global.0,
             locale: global.1,
+            timezone: global.2,
             dag: new_dag,
+            templates: CompiledTemplates::default(),
+            url_transforms: self.get_url_transforms()?,
         };
+        // Pages sharing a URL are rendered again, as which of them keeps the URL may have changed.
+        for resolved_page in build.resolve_url_conflicts(self.get_url_conflict_strategy()?)? {
+            pages_to_render.insert(resolved_page);
+            pages_to_render.extend(Build::get_descendants(&build.dag, resolved_page));
+        }
         if visualise_dag {
             self.visualise_dag(&build)?;
         }
 
         // Delete the output of removed pages.
+        let stage = StageTimer::start();
+        summary.files_removed += removed_output_paths.len();
         for removed_output_path in removed_output_paths {
             debug!("Removing {:?} … ", removed_output_path);
+            if generate_json {
+                if let Some(json_path) = json_output_path(&to_slash_string(&removed_output_path)) {
+                    // The JSON output may not exist if the page was last output without it.
+                    self.remove_file(json_path).ok();
+                }
+            }
             self.remove_file(removed_output_path)?;
         }
+        summary.record("remove", stage);
+
+        let stage = StageTimer::start();
+        self.lint(
+            pages_to_render
+                .iter()
+                .filter_map(|page| build.dag.graph().node_weight(*page)),
+        )?;
+        summary.record("lint", stage);
 
+        let stage = StageTimer::start();
+        build.insert_collections_context()?;
         let mut rendered_pages = Vec::new();
         let render_order = toposort(&build.dag.graph(), None).unwrap_or_default();
+        let mut progress = Progress::new("Rendering", pages_to_render.len());
         for page in render_order
             .iter()
             .filter(|page| pages_to_render.contains(page))
         {
             build.render_page(*page, false, &mut rendered_pages)?;
+            progress.tick();
         }
+        progress.finish();
+        summary.record("render", stage);
+        summary.pages_rendered += rendered_pages.len();
 
+        let stage = StageTimer::start();
+        let post_processing_settings = self.get_post_processing_settings()?;
+        let mut written = AHashSet::new();
+        let mut progress = Progress::new("Writing", rendered_pages.len());
         for updated_page_index in rendered_pages.iter() {
+            progress.tick();
             let updated_page = &build.dag.graph()[*updated_page_index];
             let output_path = self.get_output_path(updated_page, updated_page_index, &build);
             match output_path {
                 None => {
                     warn!("Page has no URL: {:#?} … ", updated_page.to_path_string());
+                    summary.files_skipped += 1;
                     continue;
                 }
                 Some(output_path) => {
-                    info!(
+                    debug!(
                         "Writing `{}` to `{}` … ",
                         updated_page.to_path_string(),
                         output_path
                     );
-                    self.write_file(output_path, updated_page.rendered.clone())?;
+                    let rendered = self.post_process(
+                        &build.dag.graph()
+                            [Build::get_page_above_layouts(&build.dag, *updated_page_index)],
+                        updated_page.rendered.to_string(),
+                        &output_path,
+                        &post_processing_settings,
+                    )?;
+                    self.write_file(&output_path, rendered)?;
+                    written.insert(output_path);
                 }
             }
         }
+        progress.finish();
+        summary.files_written += written.len();
+        summary.record("write", stage);
+        let stage = StageTimer::start();
         if generate_syntax_css {
             self.generate_syntax_stylesheets()?;
         }
-        timer.stop();
-        info!(
-            "Generated {} pages in {:.2} seconds … ",
-            rendered_pages.len(),
-            timer.elapsed_s()
-        );
+        if generate_json {
+            self.generate_json(&build, &rendered_pages)?;
+        }
+        self.generate_search_index(&build)?;
+        self.generate_feeds(&build)?;
+        self.generate_sitemap(&build)?;
+        self.save_math_cache(false)?;
+        summary.record("supplementary", stage);
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            timer.stop();
+            info!(
+                "Generated {} pages in {:.2} seconds … ",
+                rendered_pages.len(),
+                timer.elapsed_s()
+            );
+        }
+        #[cfg(target_arch = "wasm32")]
+        info!("Generated {} pages … ", rendered_pages.len());
         Ok(build.dag)
     }
 
@@ -671,6 +2024,13 @@ pub trait VoxProvider {
                     let new_page = new_dag.node_weight_mut(*page_index).unwrap();
                     new_page.url.clone_from(&old_page.url);
                     new_page.rendered.clone_from(&old_page.rendered);
+                    new_page.blocks.clone_from(&old_page.blocks);
+                    new_page.reads_collections = old_page.reads_collections;
+                    new_page.next.clone_from(&old_page.next);
+                    new_page.previous.clone_from(&old_page.previous);
+                    new_page.related.clone_from(&old_page.related);
+                    new_page.links.clone_from(&old_page.links);
+                    new_page.backlinks.clone_from(&old_page.backlinks);
                 }
             }
         }
@@ -706,6 +2066,7 @@ pub trait VoxProvider {
     /// # Returns
     ///
     /// A set of pages needing to be rendered.
+    #[allow(clippy::too_many_arguments)]
     fn pages_to_render(
         &self,
         old_dag: &StableDag<Page, crate::builds::EdgeType>,
@@ -722,6 +2083,32 @@ pub trait VoxProvider {
             pages_to_render.extend(new_pages.values());
             pages_to_render.extend(new_layouts.values().flatten());
         }
+        // The `collections` context changes whenever pages do, so pages that read it when last rendered need to be re-rendered.
+        // A layout reading it is re-rendered along with the page it wraps.
+        if !added_or_modified.is_empty() || !removed.is_empty() {
+            let new_page_indices: AHashMap<String, NodeIndex> = new_pages
+                .values()
+                .filter_map(|page_index| {
+                    new_dag
+                        .node_weight(*page_index)
+                        .map(|page| (page.to_path_string(), *page_index))
+                })
+                .collect();
+            for old_page_index in old_dag.graph().node_indices().filter(|page_index| {
+                old_dag
+                    .node_weight(*page_index)
+                    .is_some_and(|page| page.reads_collections)
+            }) {
+                let old_page_index = Build::get_page_above_layouts(old_dag, old_page_index);
+                if let Some(page_index) = old_dag
+                    .node_weight(old_page_index)
+                    .and_then(|page| new_page_indices.get(&page.to_path_string()))
+                {
+                    pages_to_render.insert(*page_index);
+                    pages_to_render.extend(Build::get_descendants(new_dag, *page_index));
+                }
+            }
+        }
         for page_index in added_or_modified.clone() {
             let descendants = Build::get_descendants(new_dag, page_index);
             for descendant in descendants {
@@ -884,9 +2271,9 @@ pub trait VoxProvider {
                 if let Some(old_page) = old_dag_pages.get(page_path) {
                     let output_path = if old_page.url.is_empty() {
                         let layout_url = self.get_layout_url(&old_pages[*page_path], old_dag);
-                        layout_url.map(|layout_url| format!("output/{}", layout_url))
+                        layout_url.map(|layout_url| url_output_path(&layout_url))
                     } else if !old_page.url.is_empty() {
-                        Some(format!("output/{}", old_page.url))
+                        Some(url_output_path(&old_page.url))
                     } else {
                         None
                     };
@@ -920,7 +2307,10 @@ pub trait VoxProvider {
         AHashMap<PathBuf, NodeIndex>,
         AHashMap<PathBuf, HashSet<NodeIndex>>,
     )> {
-        let global = self.get_global_context()?;
+        self.refresh_git_history();
+        let (locale, timezone) = self.get_locale_and_timezone()?;
+        // The schema is read once, rather than for every page.
+        let schema = self.get_frontmatter_schema()?;
         let mut dag = StableDag::new();
         let mut pages: AHashMap<PathBuf, NodeIndex> = AHashMap::new();
         let mut layouts: AHashMap<PathBuf, HashSet<NodeIndex>> = AHashMap::new();
@@ -930,37 +2320,244 @@ pub trait VoxProvider {
         // DAG construction.
         debug!("Constructing DAG … ");
         // In the event that a layout has collection parents, we do not want it duplicated, so we avoid inserting it at first.
-        for entry in self
+        let exclusions = self.get_exclusions()?;
+        let markdown_pages = self.list_markdown_files()?.into_iter().filter(|x| {
+            self.read_to_string(x)
+                .is_ok_and(|contents| Page::begins_with_frontmatter(&contents))
+        });
+        let page_paths = self
             .list_vox_files()?
             .into_iter()
+            .chain(markdown_pages)
+            .chain(self.list_html_files()?)
+            .collect();
+        for entry in exclusions
+            .filter(page_paths)
+            .into_iter()
             .filter(|x| !Page::is_layout_path(x))
         {
             self.insert_or_update_page(
                 entry,
                 None,
+                None,
                 &mut dag,
                 &mut pages,
                 &mut layouts,
                 &mut collection_dependents,
                 &mut collection_members,
-                global.1,
+                locale,
+                timezone,
+                &schema,
             )?;
         }
+        // We insert the pages generated in code, which may depend on the pages read from the site's files or belong to their collections.
+        let page_generators = self.get_page_generators();
+        if !page_generators.is_empty() {
+            trace!("Generating pages … ");
+            let mut generated = Vec::new();
+            {
+                let read_pages = pages
+                    .values()
+                    .map(|index| &dag.graph()[*index])
+                    .collect::<Vec<_>>();
+                for page_generator in page_generators {
+                    generated.extend(page_generator(&read_pages, locale, timezone)?);
+                }
+            }
+            for page in generated {
+                let entry = PathBuf::from(page.to_path_string()).clean();
+                if pages.contains_key(&entry) || Page::is_layout_path(&entry) {
+                    return Err(miette::miette!(
+                        "A generated page has the same path as another page or layout: `{}`.",
+                        to_slash_string(&entry)
+                    ));
+                }
+                self.insert_or_update_page(
+                    entry,
+                    None,
+                    Some(page),
+                    &mut dag,
+                    &mut pages,
+                    &mut layouts,
+                    &mut collection_dependents,
+                    &mut collection_members,
+                    locale,
+                    timezone,
+                    &schema,
+                )?;
+            }
+        }
+        // We synthesise the archive pages of dated collections, each depending on the pages dated within its period.
+        let mut archive_members = Vec::new();
+        for archive_settings in self.get_archive_settings()? {
+            let mut members = collection_members
+                .get(&archive_settings.collection)
+                .map(|members| members.iter().copied().collect::<Vec<_>>())
+                .unwrap_or_default();
+            members.sort();
+            let member_pages = members
+                .iter()
+                .map(|member| &dag.graph()[*member])
+                .collect::<Vec<_>>();
+            for archive in date_archives(&archive_settings, &member_pages, locale, timezone)? {
+                let entry = PathBuf::from(archive.page.to_path_string()).clean();
+                self.insert_or_update_page(
+                    entry.clone(),
+                    None,
+                    Some(archive.page),
+                    &mut dag,
+                    &mut pages,
+                    &mut layouts,
+                    &mut collection_dependents,
+                    &mut collection_members,
+                    locale,
+                    timezone,
+                    &schema,
+                )?;
+                archive_members.push((
+                    pages[&entry],
+                    archive
+                        .members
+                        .into_iter()
+                        .map(|position| members[position])
+                        .collect::<Vec<_>>(),
+                ));
+            }
+        }
         // We update the layouts with their parents and children once all other pages have been inserted.
-        for (layout_path, layout_indices) in layouts.clone() {
-            for layout_index in layout_indices {
+        // Layouts may use layouts of their own, so this continues until no more layouts are inserted.
+        let mut updated_layouts = HashSet::new();
+        loop {
+            let pending_layouts: Vec<_> = layouts
+                .iter()
+                .flat_map(|(path, indices)| indices.iter().map(move |index| (path.clone(), *index)))
+                .filter(|(_, index)| !updated_layouts.contains(index))
+                .collect();
+            if pending_layouts.is_empty() {
+                break;
+            }
+            for (layout_path, layout_index) in pending_layouts {
+                updated_layouts.insert(layout_index);
                 self.insert_or_update_page(
-                    layout_path.clone(),
+                    layout_path,
                     Some(layout_index),
+                    None,
                     &mut dag,
                     &mut pages,
                     &mut layouts,
                     &mut collection_dependents,
                     &mut collection_members,
-                    global.1,
+                    locale,
+                    timezone,
+                    &schema,
                 )?;
             }
         }
+        // We link each page to its neighbours in its collection.
+        trace!("Linking pages to their neighbours … ");
+        let mut neighbours = Vec::new();
+        for (collection, members) in collection_members.iter() {
+            let mut members = members
+                .iter()
+                .filter(|member| {
+                    dag.graph()[**member]
+                        .collections
+                        .as_ref()
+                        .and_then(|x| x.last())
+                        == Some(collection)
+                })
+                .collect::<Vec<_>>();
+            members.sort_by(|lhs, rhs| {
+                Page::collection_order(&dag.graph()[**lhs], &dag.graph()[**rhs])
+            });
+            for (position, member) in members.iter().enumerate() {
+                let previous = position
+                    .checked_sub(1)
+                    .map(|x| Box::new(dag.graph()[*members[x]].to_neighbour()));
+                let next = members
+                    .get(position + 1)
+                    .map(|x| Box::new(dag.graph()[**x].to_neighbour()));
+                neighbours.push((**member, next, previous));
+            }
+        }
+        for (member, next, previous) in neighbours {
+            let page = dag.node_weight_mut(member).unwrap();
+            page.next = next;
+            page.previous = previous;
+        }
+        // We relate each page to the pages most similar to it.
+        if let Some(related_settings) = self.get_related_settings()? {
+            trace!("Computing related pages … ");
+            let candidates = dag
+                .graph()
+                .node_indices()
+                .filter(|index| {
+                    let page = &dag.graph()[*index];
+                    !page.is_layout && !page.permalink.is_empty()
+                })
+                .collect::<Vec<_>>();
+            let candidate_pages = candidates
+                .iter()
+                .map(|index| &dag.graph()[*index])
+                .collect::<Vec<_>>();
+            let related = compute_related(&candidate_pages, &related_settings)
+                .into_iter()
+                .map(|positions| {
+                    positions
+                        .into_iter()
+                        .map(|position| candidate_pages[position].to_neighbour())
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+            for (index, related) in candidates.into_iter().zip(related) {
+                dag.node_weight_mut(index).unwrap().related = related;
+            }
+        }
+        // We link each page to the pages it links to with wikilinks, and vice versa.
+        trace!("Resolving wikilinks … ");
+        let sources = dag
+            .graph()
+            .node_indices()
+            .filter(|index| !dag.graph()[*index].is_layout)
+            .collect::<Vec<_>>();
+        let targets = sources
+            .iter()
+            .filter(|index| !dag.graph()[**index].permalink.is_empty())
+            .copied()
+            .collect::<Vec<_>>();
+        let source_pages = sources
+            .iter()
+            .map(|index| &dag.graph()[*index])
+            .collect::<Vec<_>>();
+        let target_pages = targets
+            .iter()
+            .map(|index| &dag.graph()[*index])
+            .collect::<Vec<_>>();
+        let links = compute_links(&source_pages, &target_pages);
+        let mut backlinks: AHashMap<NodeIndex, Vec<Page>> = AHashMap::new();
+        let mut outlinks = Vec::new();
+        for (source, positions) in sources.iter().zip(links) {
+            let source_page = dag.graph()[*source].to_neighbour();
+            outlinks.push((
+                *source,
+                positions
+                    .iter()
+                    .map(|position| target_pages[*position].to_neighbour())
+                    .collect::<Vec<_>>(),
+            ));
+            for position in positions {
+                backlinks
+                    .entry(targets[position])
+                    .or_default()
+                    .push(source_page.clone());
+            }
+        }
+        for (source, links) in outlinks {
+            dag.node_weight_mut(source).unwrap().links = links;
+        }
+        for (target, backlinks) in backlinks {
+            dag.node_weight_mut(target).unwrap().backlinks = backlinks;
+        }
         // We construct edges between collection members and dependents.
         for (collection, members) in collection_members {
             if let Some(dependents) = collection_dependents.get(&collection) {
@@ -972,6 +2569,12 @@ pub trait VoxProvider {
                 }
             }
         }
+        for (archive, members) in archive_members {
+            for member in members {
+                dag.add_edge(member, archive, EdgeType::Collection)
+                    .into_diagnostic()?;
+            }
+        }
         Ok((dag, pages, layouts))
     }
 
@@ -981,21 +2584,29 @@ pub trait VoxProvider {
     ///
     /// A source of Liquid partials.
     fn partial_source(&self) -> liquid::partials::InMemorySource {
-        let snippet_paths = self.list_snippets().unwrap_or_default();
+        let snippet_paths = self
+            .get_exclusions()
+            .unwrap_or_default()
+            .filter(self.list_snippets().unwrap_or_default());
         let snippets: Vec<_> = snippet_paths
-            .clone()
-            .into_iter()
-            .filter_map(|x| self.read_to_string(x).ok())
-            .collect();
-        let snippet_names: Vec<_> = snippet_paths
-            .clone()
             .into_iter()
-            .filter_map(|x| x.file_name().map(|y| y.to_os_string()))
-            .map(|x| x.to_string_lossy().to_string())
+            .filter_map(|x| self.read_to_string(&x).ok().map(|y| (x, y)))
             .collect();
         let mut partial_source = liquid::partials::InMemorySource::new();
-        for (name, snippet) in snippet_names.iter().zip(snippets.iter()) {
-            partial_source.add(name, snippet);
+        for (path, snippet) in snippets.iter() {
+            if let Some(name) = path.file_name() {
+                partial_source.add(name.to_string_lossy(), snippet);
+            }
+        }
+        // Snippets can also be referred to without their extension (eg: `{% render 'card' %}`), unless another snippet has that name.
+        for (path, snippet) in snippets.iter() {
+            if path.extension().is_some_and(|x| x == "voxs") {
+                if let Some(stem) = path.file_stem().map(|x| x.to_string_lossy()) {
+                    if !partial_source.contains(&stem) {
+                        partial_source.add(stem, snippet);
+                    }
+                }
+            }
         }
         partial_source
     }
@@ -1,70 +1,473 @@
-use crate::provider::VoxProvider;
+use crate::changes::{Change, Changes};
+use crate::math_block::MathCache;
+use crate::page::Page;
+use crate::post_processing::PostProcessor;
+use crate::provider::{
+    FileReader, InputProvider, OutputProvider, PageGenerator, ParserCustomiser, VoxProvider,
+};
+use crate::url_transforms::UrlTransformer;
+use chrono::{DateTime, Locale, Utc};
+use chrono_tz::Tz;
+use miette::IntoDiagnostic;
 use std::{
-    collections::HashMap,
-    sync::{Arc, Mutex},
+    collections::{BTreeSet, HashMap},
+    fmt,
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{channel, Sender},
+        Arc, Mutex, MutexGuard,
+    },
 };
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// A file held in memory.
+pub struct RamFile {
+    /// The file's contents.
+    pub contents: Vec<u8>,
+    /// When the file was last modified.
+    pub modified: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+/// The files held in memory by a provider at a point in time.
+pub struct Snapshot {
+    files: HashMap<PathBuf, RamFile>,
+}
+
+impl Snapshot {
+    /// Get the files in the snapshot.
+    ///
+    /// # Returns
+    ///
+    /// The files, keyed by their paths relative to the site's directory.
+    pub fn files(&self) -> &HashMap<PathBuf, RamFile> {
+        &self.files
+    }
+
+    /// Compare the snapshot with a later one.
+    ///
+    /// # Arguments
+    ///
+    /// * `later` - The later snapshot.
+    ///
+    /// # Returns
+    ///
+    /// The files added, modified, and removed between the snapshots.
+    pub fn diff(&self, later: &Snapshot) -> SnapshotDifference {
+        let mut difference = SnapshotDifference::default();
+        for (path, file) in &later.files {
+            match self.files.get(path) {
+                None => difference.added.push(path.clone()),
+                Some(earlier_file) if earlier_file.contents != file.contents => {
+                    difference.modified.push(path.clone())
+                }
+                Some(_) => {}
+            }
+        }
+        for path in self.files.keys() {
+            if !later.files.contains_key(path) {
+                difference.removed.push(path.clone());
+            }
+        }
+        difference.added.sort();
+        difference.modified.sort();
+        difference.removed.sort();
+        difference
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+/// The difference between two snapshots of the files held in memory by a provider.
+pub struct SnapshotDifference {
+    /// The files present in the later snapshot, but not the earlier one.
+    pub added: Vec<PathBuf>,
+    /// The files present in both snapshots, with different contents.
+    pub modified: Vec<PathBuf>,
+    /// The files present in the earlier snapshot, but not the later one.
+    pub removed: Vec<PathBuf>,
+}
+
+impl SnapshotDifference {
+    /// Determine if the snapshots are the same.
+    ///
+    /// # Returns
+    ///
+    /// Whether or not no files were added, modified, or removed.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.modified.is_empty() && self.removed.is_empty()
+    }
+
+    /// Describe the difference as a change to the site's sources.
+    ///
+    /// # Returns
+    ///
+    /// A change including every file added, modified, or removed, except those in the site's output.
+    pub fn to_change(&self) -> Change {
+        Change {
+            paths: self
+                .added
+                .iter()
+                .chain(&self.modified)
+                .chain(&self.removed)
+                .filter(|path| !path.starts_with("output"))
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Default)]
 /// A provider of the Vox build system that reads & writes from memory.
+/// The file system is never accessed, so sites can be built where there is no file system, such as in tests or sandboxes.
+/// With only the `ram_provider` feature enabled, Vox can be compiled to WebAssembly (eg: `cargo build --target wasm32-unknown-unknown --features ram_provider`).
 pub struct RamProvider {
-    files: Arc<Mutex<HashMap<std::path::PathBuf, String>>>,
+    files: Arc<Mutex<HashMap<PathBuf, RamFile>>>,
+    watchers: Arc<Mutex<Vec<Sender<Change>>>>,
+    post_processors: Vec<PostProcessor>,
+    parser_customisers: Vec<ParserCustomiser>,
+    page_generators: Vec<PageGenerator>,
+    url_transformers: Vec<UrlTransformer>,
+    math_cache: MathCache,
 }
-impl VoxProvider for RamProvider {
-    fn read_to_string(&self, path: impl AsRef<std::path::Path>) -> miette::Result<String> {
-        self.files
-            .try_lock()
-            .map_err(|e| miette::miette!("{}", e))?
-            .get(&path.as_ref().to_path_buf())
+impl fmt::Debug for RamProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RamProvider")
+            .field(
+                "files",
+                &self
+                    .files
+                    .try_lock()
+                    .map(|x| x.keys().cloned().collect::<BTreeSet<_>>())
+                    .unwrap_or_default(),
+            )
+            .field(
+                "watchers",
+                &self
+                    .watchers
+                    .try_lock()
+                    .map(|x| x.len())
+                    .unwrap_or_default(),
+            )
+            .field("post_processors", &self.post_processors.len())
+            .field("parser_customisers", &self.parser_customisers.len())
+            .field("page_generators", &self.page_generators.len())
+            .field("url_transformers", &self.url_transformers.len())
+            .finish()
+    }
+}
+impl InputProvider for RamProvider {
+    fn read_to_string(&self, path: impl AsRef<Path>) -> miette::Result<String> {
+        String::from_utf8(self.read_bytes(path)?).into_diagnostic()
+    }
+    fn read_bytes(&self, path: impl AsRef<Path>) -> miette::Result<Vec<u8>> {
+        self.lock_files()?
+            .get(path.as_ref())
+            .map(|file| file.contents.clone())
             .ok_or(miette::miette!("File not found … "))
+    }
+    fn list_vox_files(&self) -> miette::Result<Vec<PathBuf>> {
+        Ok(self
+            .lock_files()?
+            .keys()
+            .filter(|x| Some("vox") == x.extension().and_then(|y| y.to_str()))
             .cloned()
+            .collect())
     }
-    fn write_file(
-        &self,
-        path: impl AsRef<std::path::Path> + Clone,
-        contents: impl AsRef<[u8]>,
-    ) -> miette::Result<()> {
-        self.files
-            .try_lock()
-            .map_err(|e| miette::miette!("{}", e))?
-            .insert(
-                path.as_ref().to_path_buf(),
-                String::from_utf8_lossy(contents.as_ref()).to_string(),
-            );
-        Ok(())
+    fn list_markdown_files(&self) -> miette::Result<Vec<PathBuf>> {
+        Ok(self
+            .lock_files()?
+            .keys()
+            .filter(|x| Some("md") == x.extension().and_then(|y| y.to_str()))
+            .cloned()
+            .collect())
     }
-    fn remove_file(&self, path: impl AsRef<std::path::Path>) -> miette::Result<()> {
-        self.files
-            .try_lock()
-            .map_err(|e| miette::miette!("{}", e))?
-            .remove(&path.as_ref().to_path_buf());
-        Ok(())
+    fn list_html_files(&self) -> miette::Result<Vec<PathBuf>> {
+        Ok(self
+            .lock_files()?
+            .keys()
+            .filter(|x| Some("html") == x.extension().and_then(|y| y.to_str()))
+            .filter(|x| !x.starts_with("output") && !x.starts_with("snippets"))
+            .cloned()
+            .collect())
     }
-    fn list_vox_files(&self) -> miette::Result<Vec<std::path::PathBuf>> {
+    fn list_snippets(&self) -> miette::Result<Vec<PathBuf>> {
         Ok(self
-            .files
-            .try_lock()
-            .map_err(|e| miette::miette!("{}", e))?
-            .clone()
-            .into_keys()
-            .filter(|x| Some("vox") == x.extension().map(|y| y.to_str()).flatten())
+            .lock_files()?
+            .keys()
+            .filter(|x| x.starts_with("snippets/"))
+            .cloned()
             .collect())
     }
-    fn list_snippets(&self) -> miette::Result<Vec<std::path::PathBuf>> {
+    fn list_directory(&self, directory: &Path) -> miette::Result<Vec<PathBuf>> {
         Ok(self
-            .files
+            .lock_files()?
+            .keys()
+            .filter(|x| x.starts_with(directory))
+            .cloned()
+            .collect())
+    }
+    fn file_reader(&self) -> Option<FileReader> {
+        let files = self.files.clone();
+        Some(Arc::new(move |path| {
+            files
+                .lock()
+                .map_err(|e| miette::miette!("{}", e))?
+                .get(path)
+                .map(|file| file.contents.clone())
+                .ok_or(miette::miette!("File not found … "))
+        }))
+    }
+    fn math_cache(&self) -> Option<MathCache> {
+        Some(self.math_cache.clone())
+    }
+    fn watch(&self) -> miette::Result<Option<Changes>> {
+        let (sender, receiver) = channel();
+        self.watchers
             .try_lock()
             .map_err(|e| miette::miette!("{}", e))?
-            .clone()
-            .into_keys()
-            .filter(|x| x.starts_with("snippets/"))
-            .collect())
+            .push(sender);
+        Ok(Some(Box::new(receiver.into_iter())))
+    }
+}
+impl OutputProvider for RamProvider {
+    fn write_file(
+        &self,
+        path: impl AsRef<Path> + Clone,
+        contents: impl AsRef<[u8]>,
+    ) -> miette::Result<()> {
+        self.lock_files()?.insert(
+            path.as_ref().to_path_buf(),
+            RamFile {
+                contents: contents.as_ref().to_vec(),
+                modified: Utc::now(),
+            },
+        );
+        self.notify_watchers(path.as_ref())
+    }
+    fn remove_file(&self, path: impl AsRef<Path>) -> miette::Result<()> {
+        self.lock_files()?.remove(path.as_ref());
+        self.notify_watchers(path.as_ref())
+    }
+}
+impl VoxProvider for RamProvider {
+    fn get_post_processors(&self) -> Vec<PostProcessor> {
+        self.post_processors.clone()
+    }
+    fn get_parser_customisers(&self) -> Vec<ParserCustomiser> {
+        self.parser_customisers.clone()
+    }
+    fn get_page_generators(&self) -> Vec<PageGenerator> {
+        self.page_generators.clone()
+    }
+    fn get_url_transformers(&self) -> Vec<UrlTransformer> {
+        self.url_transformers.clone()
     }
 }
 impl RamProvider {
     /// Create a new Vox provider that reads & writes from memory.
-    pub fn new(initial_files: Option<HashMap<std::path::PathBuf, String>>) -> Self {
+    ///
+    /// # Arguments
+    ///
+    /// * `initial_files` - The contents of the site's files, keyed by their paths relative to the site's directory (eg: `global.toml` or `blog/hello.vox`).
+    ///
+    /// # Returns
+    ///
+    /// A provider whose site is made up of the given files.
+    pub fn new(initial_files: Option<HashMap<PathBuf, String>>) -> Self {
+        let modified = Utc::now();
+        let files = initial_files
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(path, contents)| {
+                (
+                    path,
+                    RamFile {
+                        contents: contents.into_bytes(),
+                        modified,
+                    },
+                )
+            })
+            .collect();
         Self {
-            files: Arc::new(Mutex::new(initial_files.unwrap_or_default())),
+            files: Arc::new(Mutex::new(files)),
+            watchers: Arc::new(Mutex::new(Vec::new())),
+            post_processors: Vec::new(),
+            parser_customisers: Vec::new(),
+            page_generators: Vec::new(),
+            url_transformers: Vec::new(),
+            math_cache: MathCache::new(),
+        }
+    }
+
+    /// Obtain exclusive access to the files held in memory.
+    ///
+    /// # Returns
+    ///
+    /// The files, keyed by their paths relative to the site's directory.
+    fn lock_files(&self) -> miette::Result<MutexGuard<'_, HashMap<PathBuf, RamFile>>> {
+        self.files.try_lock().map_err(|e| miette::miette!("{}", e))
+    }
+
+    /// Obtain when a file was last modified.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the file.
+    ///
+    /// # Returns
+    ///
+    /// The date & time the file was last written.
+    pub fn modified(&self, path: impl AsRef<Path>) -> miette::Result<DateTime<Utc>> {
+        self.lock_files()?
+            .get(path.as_ref())
+            .map(|file| file.modified)
+            .ok_or(miette::miette!("File not found … "))
+    }
+
+    /// List the files & directories immediately within a directory.
+    /// Directories exist only as long as they contain files.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the directory; an empty path is the site's directory.
+    ///
+    /// # Returns
+    ///
+    /// The paths of the directory's entries, relative to the site's directory, in order.
+    pub fn list_directory(&self, path: impl AsRef<Path>) -> miette::Result<Vec<PathBuf>> {
+        let directory = path.as_ref();
+        Ok(self
+            .lock_files()?
+            .keys()
+            .filter_map(|file_path| {
+                let entry = file_path
+                    .strip_prefix(directory)
+                    .ok()?
+                    .components()
+                    .next()?;
+                Some(directory.join(entry))
+            })
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect())
+    }
+
+    /// Take a snapshot of the files held in memory.
+    ///
+    /// # Returns
+    ///
+    /// A copy of the files as they currently are.
+    pub fn snapshot(&self) -> miette::Result<Snapshot> {
+        Ok(Snapshot {
+            files: self.lock_files()?.clone(),
+        })
+    }
+
+    /// Compare the files held in memory with an earlier snapshot of them.
+    ///
+    /// # Arguments
+    ///
+    /// * `snapshot` - The earlier snapshot.
+    ///
+    /// # Returns
+    ///
+    /// The files added, modified, and removed since the snapshot was taken.
+    pub fn diff(&self, snapshot: &Snapshot) -> miette::Result<SnapshotDifference> {
+        Ok(snapshot.diff(&self.snapshot()?))
+    }
+
+    /// Notify anything watching the site of a change to a file.
+    /// Changes to the site's output, and to files cached by builds, are not notified.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the changed file.
+    fn notify_watchers(&self, path: &Path) -> miette::Result<()> {
+        if path.starts_with("output") || path.starts_with(".vox") {
+            return Ok(());
         }
+        let change = Change {
+            paths: vec![path.to_path_buf()],
+        };
+        // Watchers which have stopped watching are forgotten.
+        self.watchers
+            .try_lock()
+            .map_err(|e| miette::miette!("{}", e))?
+            .retain(|watcher| watcher.send(change.clone()).is_ok());
+        Ok(())
+    }
+
+    /// Add a function transforming each page's rendered HTML before it is written.
+    ///
+    /// # Arguments
+    ///
+    /// * `post_processor` - A function given a page and its rendered HTML, returning the HTML to be written.
+    ///
+    /// # Returns
+    ///
+    /// The provider, with the function added after any previously added.
+    pub fn with_post_processor(
+        mut self,
+        post_processor: impl Fn(&Page, String) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.post_processors.push(Arc::new(post_processor));
+        self
+    }
+
+    /// Add a function customising the Liquid parser, such as by registering additional tags, filters, or blocks.
+    ///
+    /// # Arguments
+    ///
+    /// * `parser_customiser` - A function given the parser's builder, returning the builder to use.
+    ///
+    /// # Returns
+    ///
+    /// The provider, with the function added after any previously added.
+    pub fn with_parser_customiser(
+        mut self,
+        parser_customiser: impl Fn(liquid::ParserBuilder) -> liquid::ParserBuilder
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        self.parser_customisers.push(Arc::new(parser_customiser));
+        self
+    }
+
+    /// Add a function generating pages which are not read from the site's files, such as index pages or pages of content from elsewhere.
+    ///
+    /// # Arguments
+    ///
+    /// * `page_generator` - A function given the pages read from the site's files, with the site's locale and timezone, returning the pages to add.
+    ///
+    /// # Returns
+    ///
+    /// The provider, with the function added after any previously added.
+    pub fn with_page_generator(
+        mut self,
+        page_generator: impl Fn(&[&Page], Locale, Tz) -> miette::Result<Vec<Page>>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        self.page_generators.push(Arc::new(page_generator));
+        self
+    }
+
+    /// Add a function transforming the URL of every page, such as to enforce a convention for URLs.
+    ///
+    /// # Arguments
+    ///
+    /// * `url_transformer` - A function given a page and its URL, returning the URL to use; it may be given a URL it has already transformed, which it should leave as it is.
+    ///
+    /// # Returns
+    ///
+    /// The provider, with the function added after any previously added.
+    pub fn with_url_transformer(
+        mut self,
+        url_transformer: impl Fn(&Page, String) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.url_transformers.push(Arc::new(url_transformer));
+        self
     }
 }
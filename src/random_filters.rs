@@ -0,0 +1,172 @@
+use liquid_core::Expression;
+use liquid_core::Result;
+use liquid_core::Runtime;
+use liquid_core::{
+    Display_filter, Filter, FilterParameters, FilterReflection, FromFilterParameters, ParseFilter,
+};
+use liquid_core::{Value, ValueView};
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+
+/// A source of random numbers, which is the same for every build when seeded with the same value.
+struct Shuffler {
+    /// The stream of random bytes, derived from the seed.
+    reader: blake3::OutputReader,
+}
+
+impl Shuffler {
+    /// Create a source of random numbers.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - The value the random numbers are derived from; if omitted, they differ with every use.
+    fn new(seed: Option<&dyn ValueView>) -> Self {
+        let mut hasher = blake3::Hasher::new();
+        match seed {
+            Some(seed) => hasher.update(seed.to_kstr().as_bytes()),
+            None => hasher.update(&RandomState::new().hash_one(()).to_le_bytes()),
+        };
+        Self {
+            reader: hasher.finalize_xof(),
+        }
+    }
+
+    /// Pick a random number below a bound.
+    ///
+    /// # Arguments
+    ///
+    /// * `bound` - The number the random number is below.
+    ///
+    /// # Returns
+    ///
+    /// A random number from zero up to, but not including, the bound.
+    fn below(&mut self, bound: usize) -> usize {
+        let mut bytes = [0; 8];
+        self.reader.fill(&mut bytes);
+        ((u64::from_le_bytes(bytes) as u128 * bound as u128) >> 64) as usize
+    }
+
+    /// Shuffle the start of a list of values, so that it is a random sample of the list.
+    ///
+    /// # Arguments
+    ///
+    /// * `values` - The values to shuffle.
+    ///
+    /// * `count` - The number of values at the start of the list to shuffle.
+    fn shuffle(&mut self, values: &mut [Value], count: usize) {
+        for index in 0..count.min(values.len()) {
+            let other = index + self.below(values.len() - index);
+            values.swap(index, other);
+        }
+    }
+}
+
+/// Obtain the values of an array.
+///
+/// # Arguments
+///
+/// * `input` - A Liquid value.
+///
+/// # Returns
+///
+/// The values of the array, or no values if the value is nil.
+fn input_to_values(input: &dyn ValueView) -> Result<Vec<Value>> {
+    match input.as_array() {
+        Some(array) => Ok(array.values().map(|x| x.to_value()).collect()),
+        None if input.is_nil() => Ok(Vec::new()),
+        None => Err(liquid_core::Error::with_msg("Array expected")),
+    }
+}
+
+#[derive(Debug, FilterParameters)]
+struct ShuffleArgs {
+    #[parameter(
+        description = "The value the order is derived from, so that it is the same for every build.",
+        arg_type = "any"
+    )]
+    seed: Option<Expression>,
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "shuffle",
+    description = "Shuffles the items of an array, optionally deriving their order from a seed.",
+    parameters(ShuffleArgs),
+    parsed(ShuffleFilter)
+)]
+/// A Liquid filter shuffling the items of an array.
+/// Given a seed, the items are shuffled the same way for every build, eg: `{{ collections.blog | shuffle: page.url }}`.
+pub struct Shuffle;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "shuffle"]
+struct ShuffleFilter {
+    #[parameters]
+    args: ShuffleArgs,
+}
+
+impl Filter for ShuffleFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let mut values = input_to_values(input)?;
+        let count = values.len();
+        Shuffler::new(args.seed.as_ref().map(|x| x.as_view())).shuffle(&mut values, count);
+        Ok(Value::array(values))
+    }
+}
+
+#[derive(Debug, FilterParameters)]
+struct SampleArgs {
+    #[parameter(
+        description = "The number of items to pick; if omitted, a single item is picked.",
+        arg_type = "integer"
+    )]
+    count: Option<Expression>,
+    #[parameter(
+        description = "The value the items picked are derived from, so that they are the same for every build.",
+        arg_type = "any"
+    )]
+    seed: Option<Expression>,
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "sample",
+    description = "Picks random items from an array, optionally deriving them from a seed.",
+    parameters(SampleArgs),
+    parsed(SampleFilter)
+)]
+/// A Liquid filter picking random items from an array.
+/// Without a count, a single item is picked; with one, an array of that many items is picked.
+/// Given a seed, the same items are picked for every build, eg: `{{ collections.blog | sample: 3, page.url }}`.
+pub struct Sample;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "sample"]
+struct SampleFilter {
+    #[parameters]
+    args: SampleArgs,
+}
+
+impl Filter for SampleFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let count = match args.count {
+            Some(count) => Some(
+                usize::try_from(count)
+                    .map_err(|_| liquid_core::Error::with_msg("Count must not be negative"))?,
+            ),
+            None => None,
+        };
+        let mut values = input_to_values(input)?;
+        Shuffler::new(args.seed.as_ref().map(|x| x.as_view()))
+            .shuffle(&mut values, count.unwrap_or(1));
+        match count {
+            Some(count) => {
+                values.truncate(count);
+                Ok(Value::array(values))
+            }
+            None => Ok(values.into_iter().next().unwrap_or(Value::Nil)),
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use crate::page::Page;
+use crate::search::html_to_text;
+use ahash::{AHashMap, AHashSet};
+use serde::{Deserialize, Serialize};
+
+#[derive(PartialEq, Eq, Clone, Copy, Default, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// The method used to determine how related two pages are.
+pub enum RelatedStrategy {
+    /// Pages are related by the tags, categories, and collections they share.
+    Taxonomy,
+    /// Pages are related by the similarity of their content.
+    Content,
+    /// Pages are related by both their shared taxonomy and the similarity of their content.
+    #[default]
+    Hybrid,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+/// Settings for computing related pages, defined by the `related` table in `global.toml`.
+pub struct RelatedSettings {
+    /// The maximum number of related pages of each page.
+    #[serde(default = "default_count")]
+    pub count: usize,
+    /// The method used to determine how related two pages are.
+    #[serde(default)]
+    pub strategy: RelatedStrategy,
+}
+
+impl Default for RelatedSettings {
+    fn default() -> Self {
+        Self {
+            count: default_count(),
+            strategy: RelatedStrategy::default(),
+        }
+    }
+}
+
+/// The default maximum number of related pages of each page.
+fn default_count() -> usize {
+    5
+}
+
+/// Obtain the terms classifying a page; its tags, categories, and collections.
+///
+/// # Arguments
+///
+/// * `page` - The page.
+///
+/// # Returns
+///
+/// The terms classifying the page, each prefixed with its kind.
+fn taxonomy_terms(page: &Page) -> AHashSet<String> {
+    let mut terms = AHashSet::new();
+    for kind in ["tags", "categories"] {
+        for term in page
+            .data
+            .get(kind)
+            .and_then(|x| x.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|x| x.as_str())
+        {
+            terms.insert(format!("{}:{}", kind, term.to_lowercase()));
+        }
+    }
+    for collection in page.collections.iter().flatten() {
+        terms.insert(format!("collections:{}", collection));
+    }
+    terms
+}
+
+/// Obtain the frequency of each word in a page's content.
+///
+/// # Arguments
+///
+/// * `page` - The page.
+///
+/// # Returns
+///
+/// The number of times each word of at least three letters appears in the page's content.
+fn term_frequencies(page: &Page) -> AHashMap<String, f64> {
+    let mut frequencies = AHashMap::new();
+    for word in html_to_text(&page.content)
+        .to_lowercase()
+        .split(|x: char| !x.is_alphanumeric())
+        .filter(|x| x.chars().count() >= 3)
+    {
+        *frequencies.entry(word.to_string()).or_insert(0.0) += 1.0;
+    }
+    frequencies
+}
+
+/// Compute the related pages of each page.
+///
+/// # Arguments
+///
+/// * `pages` - The pages to relate to one another.
+///
+/// * `settings` - The settings for computing related pages.
+///
+/// # Returns
+///
+/// For each page, the positions of its related pages in `pages`, from most to least related.
+pub fn compute_related(pages: &[&Page], settings: &RelatedSettings) -> Vec<Vec<usize>> {
+    let use_taxonomy = settings.strategy != RelatedStrategy::Content;
+    let use_content = settings.strategy != RelatedStrategy::Taxonomy;
+    let taxonomies = pages
+        .iter()
+        .map(|page| taxonomy_terms(page))
+        .collect::<Vec<_>>();
+
+    // Content is compared by the cosine similarity of TF-IDF vectors.
+    let mut vectors = Vec::new();
+    if use_content {
+        let frequencies = pages
+            .iter()
+            .map(|page| term_frequencies(page))
+            .collect::<Vec<_>>();
+        let mut document_frequencies: AHashMap<&str, f64> = AHashMap::new();
+        for page_frequencies in frequencies.iter() {
+            for term in page_frequencies.keys() {
+                *document_frequencies.entry(term.as_str()).or_insert(0.0) += 1.0;
+            }
+        }
+        let page_count = pages.len() as f64;
+        for page_frequencies in frequencies.iter() {
+            let mut vector = AHashMap::new();
+            for (term, frequency) in page_frequencies {
+                let weight = frequency * (page_count / document_frequencies[term.as_str()]).ln();
+                if weight > 0.0 {
+                    vector.insert(term.clone(), weight);
+                }
+            }
+            let magnitude = vector.values().map(|x| x * x).sum::<f64>().sqrt();
+            for weight in vector.values_mut() {
+                *weight /= magnitude;
+            }
+            vectors.push(vector);
+        }
+    }
+
+    let mut related = Vec::new();
+    for lhs in 0..pages.len() {
+        let mut scores = Vec::new();
+        for rhs in (0..pages.len()).filter(|rhs| *rhs != lhs) {
+            let mut score = 0.0;
+            if use_taxonomy {
+                let union = taxonomies[lhs].union(&taxonomies[rhs]).count();
+                if union > 0 {
+                    score += taxonomies[lhs].intersection(&taxonomies[rhs]).count() as f64
+                        / union as f64;
+                }
+            }
+            if use_content {
+                score += vectors[lhs]
+                    .iter()
+                    .filter_map(|(term, weight)| vectors[rhs].get(term).map(|x| x * weight))
+                    .sum::<f64>();
+            }
+            if score > 0.0 {
+                scores.push((rhs, score));
+            }
+        }
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        related.push(
+            scores
+                .into_iter()
+                .take(settings.count)
+                .map(|(rhs, _)| rhs)
+                .collect(),
+        );
+    }
+    related
+}
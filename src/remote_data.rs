@@ -0,0 +1,338 @@
+use crate::provider::InputProvider;
+#[cfg(feature = "remote_data")]
+use crate::provider::VoxProvider;
+use chrono::{DateTime, Utc};
+use liquid::Object;
+use miette::IntoDiagnostic;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+#[cfg(feature = "remote_data")]
+use tracing::{info, warn};
+
+/// The directory remote data is cached in, relative to the site's directory.
+pub const CACHE_DIRECTORY: &str = ".vox/data";
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// The format of data read into a Liquid context.
+pub enum DataFormat {
+    /// JavaScript Object Notation.
+    Json,
+    /// Tom's Obvious, Minimal Language.
+    Toml,
+    /// Comma-separated values, with a header row naming the fields of each subsequent row.
+    Csv,
+}
+
+impl DataFormat {
+    /// Determine the format of data from its path or URL.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path or URL of the data.
+    ///
+    /// # Returns
+    ///
+    /// The format indicated by the extension, or `None` if the extension does not indicate a format.
+    pub fn from_path(path: &str) -> Option<Self> {
+        let path = path.split(['?', '#']).next().unwrap_or_default();
+        match path
+            .rsplit_once('.')
+            .map(|(_, x)| x.to_lowercase())
+            .as_deref()
+        {
+            Some("json") => Some(Self::Json),
+            Some("toml") => Some(Self::Toml),
+            Some("csv") => Some(Self::Csv),
+            _ => None,
+        }
+    }
+
+    /// Parse data of this format.
+    ///
+    /// # Arguments
+    ///
+    /// * `body` - The data.
+    ///
+    /// # Returns
+    ///
+    /// The data as a Liquid value; CSV data is a list of objects, one per row.
+    pub fn parse(&self, body: &str) -> miette::Result<liquid::model::Value> {
+        match self {
+            DataFormat::Json => liquid::model::to_value(
+                &serde_json::from_str::<serde_json::Value>(body).into_diagnostic()?,
+            )
+            .into_diagnostic(),
+            DataFormat::Toml => {
+                liquid::model::to_value(&body.parse::<toml::Table>().into_diagnostic()?)
+                    .into_diagnostic()
+            }
+            DataFormat::Csv => liquid::model::to_value(&parse_csv(body)?).into_diagnostic(),
+        }
+    }
+}
+
+/// Parse CSV data.
+/// Fields may be quoted, in which case they may contain commas, line breaks, and quotation marks written twice.
+///
+/// # Arguments
+///
+/// * `body` - The data, beginning with a header row.
+///
+/// # Returns
+///
+/// Each row after the header, mapping the header's fields to the row's.
+fn parse_csv(body: &str) -> miette::Result<Vec<BTreeMap<String, String>>> {
+    let mut records: Vec<Vec<String>> = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut characters = body.chars().peekable();
+    while let Some(character) = characters.next() {
+        match (quoted, character) {
+            (true, '"') if characters.peek() == Some(&'"') => {
+                characters.next();
+                field.push('"');
+            }
+            (true, '"') => quoted = false,
+            (true, character) => field.push(character),
+            (false, '"') if field.is_empty() => quoted = true,
+            (false, ',') => record.push(std::mem::take(&mut field)),
+            (false, '\r') if characters.peek() == Some(&'\n') => {}
+            (false, '\n') => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            (false, character) => field.push(character),
+        }
+    }
+    if quoted {
+        return Err(miette::miette!("Unterminated quoted field in CSV data."));
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    // Blank lines are not rows.
+    records.retain(|x| !(x.len() == 1 && x[0].is_empty()));
+    let mut records = records.into_iter();
+    let header = records.next().unwrap_or_default();
+    Ok(records
+        .map(|record| {
+            header
+                .iter()
+                .cloned()
+                .zip(record.into_iter().chain(std::iter::repeat(String::new())))
+                .collect()
+        })
+        .collect())
+}
+
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+/// A source of remote data, defined by a table under `data.remote` in `global.toml`.
+pub struct RemoteSource {
+    /// The URL the data is fetched from.
+    pub url: String,
+    /// The format of the data; if unspecified, it is inferred from the URL or the response.
+    #[serde(default)]
+    pub format: Option<DataFormat>,
+    /// The number of seconds fetched data is used for before it is fetched again.
+    #[serde(default = "default_max_age")]
+    pub max_age: i64,
+}
+
+/// The default number of seconds fetched data is used for before it is fetched again.
+fn default_max_age() -> i64 {
+    3600
+}
+
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+/// Remote data, as cached after it was last fetched.
+pub struct CachedData {
+    /// The URL the data was fetched from.
+    pub url: String,
+    /// When the data was fetched.
+    pub fetched: DateTime<Utc>,
+    /// The format of the data.
+    pub format: DataFormat,
+    /// The data, as fetched.
+    pub body: String,
+}
+
+impl CachedData {
+    /// Determine if cached data can be used in place of fetching it again.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The source of the data.
+    ///
+    /// # Returns
+    ///
+    /// Whether or not the data was fetched from the source within its maximum age.
+    pub fn is_fresh(&self, source: &RemoteSource) -> bool {
+        self.url == source.url && (Utc::now() - self.fetched).num_seconds() < source.max_age
+    }
+
+    /// Parse the cached data.
+    ///
+    /// # Returns
+    ///
+    /// The data as a Liquid value.
+    pub fn parse(&self) -> miette::Result<liquid::model::Value> {
+        self.format.parse(&self.body)
+    }
+}
+
+/// Get the path remote data is cached at.
+///
+/// # Arguments
+///
+/// * `name` - The name of the data's source.
+///
+/// # Returns
+///
+/// The path of the cached data, relative to the site's directory.
+pub fn cache_path(name: &str) -> PathBuf {
+    PathBuf::from(CACHE_DIRECTORY).join(format!("{}.json", name))
+}
+
+/// Read the cached data of a remote source.
+///
+/// # Arguments
+///
+/// * `provider` - The provider the site is read from.
+///
+/// * `name` - The name of the data's source.
+///
+/// # Returns
+///
+/// The cached data, or `None` if the source's data has not been fetched.
+pub fn read_cache(provider: &(impl InputProvider + ?Sized), name: &str) -> Option<CachedData> {
+    provider
+        .read_to_string(cache_path(name))
+        .ok()
+        .and_then(|x| serde_json::from_str(&x).ok())
+}
+
+/// Load the cached data of remote sources.
+/// Sources which have not been fetched are left out.
+///
+/// # Arguments
+///
+/// * `provider` - The provider the site is read from.
+///
+/// * `sources` - The remote sources, by name.
+///
+/// # Returns
+///
+/// The data of each source, by name.
+pub fn load_remote_data(
+    provider: &(impl InputProvider + ?Sized),
+    sources: &BTreeMap<String, RemoteSource>,
+) -> miette::Result<Object> {
+    let mut data = Object::new();
+    for name in sources.keys() {
+        if let Some(cached) = read_cache(provider, name) {
+            let value = cached.parse().map_err(|err| {
+                miette::miette!("Unable to parse the data of `{}`: {}", name, err)
+            })?;
+            data.insert(name.clone().into(), value);
+        }
+    }
+    Ok(data)
+}
+
+/// Infer the format of remote data.
+///
+/// # Arguments
+///
+/// * `source` - The source of the data.
+///
+/// * `content_type` - The media type the data was served as.
+///
+/// # Returns
+///
+/// The format specified by the source, otherwise the format indicated by the URL or media type, otherwise JSON.
+#[cfg(feature = "remote_data")]
+fn infer_format(source: &RemoteSource, content_type: &str) -> DataFormat {
+    if let Some(format) = source.format {
+        return format;
+    }
+    if let Some(format) = DataFormat::from_path(&source.url) {
+        return format;
+    }
+    if content_type.contains("toml") {
+        DataFormat::Toml
+    } else if content_type.contains("csv") {
+        DataFormat::Csv
+    } else {
+        DataFormat::Json
+    }
+}
+
+/// Fetch the data of remote sources, caching it.
+/// Data is only fetched if its cached copy is older than the source's maximum age.
+/// If data cannot be fetched, its cached copy is used instead.
+///
+/// # Arguments
+///
+/// * `provider` - The provider the site is built with.
+///
+/// * `offline` - Whether to only use cached data, rather than fetching any.
+#[cfg(feature = "remote_data")]
+pub fn refresh_remote_data(provider: &impl VoxProvider, offline: bool) -> miette::Result<()> {
+    for (name, source) in provider.get_remote_sources()? {
+        let cached = read_cache(provider, &name);
+        if offline {
+            if cached.is_none() {
+                warn!(
+                    "Working offline, and the data of `{}` has not been fetched … ",
+                    name
+                );
+            }
+            continue;
+        }
+        if cached.as_ref().is_some_and(|x| x.is_fresh(&source)) {
+            continue;
+        }
+        info!("Fetching the data of `{}` … ", name);
+        let fetched = ureq::get(&source.url)
+            .call()
+            .into_diagnostic()
+            .and_then(|response| {
+                let format = infer_format(&source, response.content_type());
+                Ok(CachedData {
+                    url: source.url.clone(),
+                    fetched: Utc::now(),
+                    format,
+                    body: response.into_string().into_diagnostic()?,
+                })
+            });
+        match (fetched, cached) {
+            (Ok(fetched), _) => {
+                // Data which cannot be parsed is not cached.
+                fetched.parse().map_err(|err| {
+                    miette::miette!("Unable to parse the data of `{}`: {}", name, err)
+                })?;
+                provider.write_file(
+                    cache_path(&name),
+                    serde_json::to_string(&fetched).into_diagnostic()?,
+                )?;
+            }
+            (Err(err), Some(_)) => {
+                warn!(
+                    "Unable to fetch the data of `{}`; using the data last fetched … \n{:?}",
+                    name, err
+                );
+            }
+            (Err(err), None) => {
+                return Err(miette::miette!(
+                    "Unable to fetch the data of `{}`: {}",
+                    name,
+                    err
+                ));
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,237 @@
+use crate::error::{InvalidFrontmatterKey, MissingFrontmatterKey, UnknownFrontmatterKey};
+use ahash::AHashMap;
+use miette::{NamedSource, SourceSpan};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use toml::Table;
+
+/// Frontmatter keys with meaning to Vox, which are permitted even by strict schemas.
+pub const BUILTIN_KEYS: [&str; 21] = [
+    "date",
+    "updated",
+    "layout",
+    "permalink",
+    "depends",
+    "collections",
+    "weight",
+    "order",
+    "timezone",
+    "excerpt",
+    "excerpt_separator",
+    "summary",
+    "title",
+    "description",
+    "author",
+    "image",
+    "tags",
+    "categories",
+    "lint",
+    "sitemap",
+    "slug",
+];
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// The type of a frontmatter value.
+pub enum ValueType {
+    /// A string.
+    String,
+    /// An integer.
+    Integer,
+    /// A floating-point number.
+    Float,
+    /// A boolean.
+    Boolean,
+    /// A date-time.
+    Datetime,
+    /// An array.
+    Array,
+    /// A table.
+    Table,
+}
+
+impl ValueType {
+    /// Determine if a value is of this type.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to check.
+    ///
+    /// # Returns
+    ///
+    /// Whether or not the value is of this type.
+    pub fn matches(&self, value: &toml::Value) -> bool {
+        matches!(
+            (self, value),
+            (ValueType::String, toml::Value::String(_))
+                | (ValueType::Integer, toml::Value::Integer(_))
+                | (ValueType::Float, toml::Value::Float(_))
+                | (ValueType::Boolean, toml::Value::Boolean(_))
+                | (ValueType::Datetime, toml::Value::Datetime(_))
+                | (ValueType::Array, toml::Value::Array(_))
+                | (ValueType::Table, toml::Value::Table(_))
+        )
+    }
+}
+
+#[derive(PartialEq, Clone, Default, Debug, Serialize, Deserialize)]
+/// The constraints on a frontmatter key.
+pub struct KeySchema {
+    /// Whether or not pages must specify this key.
+    #[serde(default)]
+    pub required: bool,
+    /// The type of this key's value.
+    #[serde(rename = "type")]
+    pub value_type: Option<ValueType>,
+    /// The values this key is permitted to have.
+    pub values: Option<Vec<toml::Value>>,
+}
+
+#[derive(PartialEq, Clone, Default, Debug, Serialize, Deserialize)]
+/// A schema that page frontmatter must conform to.
+/// This is defined by the `schema` table in `global.toml`.
+pub struct Schema {
+    /// Whether or not keys not declared in the schema are rejected.
+    #[serde(default)]
+    pub strict: bool,
+    /// The constraints on each frontmatter key.
+    #[serde(default)]
+    pub keys: AHashMap<String, KeySchema>,
+    /// Schemas applying only to pages in particular collections, in addition to this schema.
+    #[serde(default)]
+    pub collections: AHashMap<String, Schema>,
+}
+
+impl Schema {
+    /// Obtain the schema applying to pages in the given collections.
+    ///
+    /// # Arguments
+    ///
+    /// * `collections` - The collections a page belongs to.
+    ///
+    /// # Returns
+    ///
+    /// This schema, with the schemas of the given collections applied.
+    pub fn for_collections(&self, collections: &[String]) -> Schema {
+        let mut schema = Schema {
+            strict: self.strict,
+            keys: self.keys.clone(),
+            collections: AHashMap::new(),
+        };
+        for collection_schema in collections
+            .iter()
+            .filter_map(|collection| self.collections.get(collection))
+        {
+            schema.strict |= collection_schema.strict;
+            schema.keys.extend(collection_schema.keys.clone());
+        }
+        schema
+    }
+
+    /// Find the location of a top-level key in a page's frontmatter.
+    ///
+    /// # Arguments
+    ///
+    /// * `frontmatter` - The frontmatter of a page.
+    ///
+    /// * `key` - The key to find.
+    ///
+    /// # Returns
+    ///
+    /// The location of the key, if it appears in the frontmatter.
+    pub fn find_key_span(frontmatter: &str, key: &str) -> Option<SourceSpan> {
+        let mut offset = 0;
+        for line in frontmatter.split_inclusive('\n') {
+            let trimmed_line = line.trim_start();
+            // Keys after a table header are not top-level keys.
+            if trimmed_line.starts_with('[') {
+                return None;
+            }
+            let key_offset = offset + (line.len() - trimmed_line.len());
+            for candidate in [
+                key.to_string(),
+                format!("\"{}\"", key),
+                format!("'{}'", key),
+            ] {
+                if let Some(remainder) = trimmed_line.strip_prefix(&candidate) {
+                    if remainder.trim_start().starts_with('=') {
+                        return Some((key_offset, candidate.len()).into());
+                    }
+                }
+            }
+            offset += line.len();
+        }
+        None
+    }
+
+    /// Validate a page's frontmatter against this schema.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The parsed frontmatter of the page, including any defaults.
+    ///
+    /// * `frontmatter` - The frontmatter of the page, as written.
+    ///
+    /// * `path` - The path to the page.
+    pub fn validate(&self, data: &Table, frontmatter: &str, path: &Path) -> miette::Result<()> {
+        let src = || NamedSource::new(path.to_string_lossy(), frontmatter.to_string());
+        let mut keys = self.keys.iter().collect::<Vec<_>>();
+        keys.sort_by_key(|(key, _)| *key);
+        for (key, key_schema) in keys {
+            let Some(value) = data.get(key) else {
+                if key_schema.required {
+                    return Err(MissingFrontmatterKey {
+                        src: src(),
+                        key: key.clone(),
+                        span: (0, frontmatter.len()).into(),
+                    }
+                    .into());
+                }
+                continue;
+            };
+            if let Some(value_type) = key_schema.value_type {
+                if !value_type.matches(value) {
+                    return Err(InvalidFrontmatterKey {
+                        src: src(),
+                        key: key.clone(),
+                        reason: format!("expected a value of type `{:?}`", value_type)
+                            .to_lowercase(),
+                        span: Self::find_key_span(frontmatter, key),
+                    }
+                    .into());
+                }
+            }
+            if let Some(values) = &key_schema.values {
+                if !values.contains(value) {
+                    return Err(InvalidFrontmatterKey {
+                        src: src(),
+                        key: key.clone(),
+                        reason: format!(
+                            "expected one of {}",
+                            values
+                                .iter()
+                                .map(|x| x.to_string())
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ),
+                        span: Self::find_key_span(frontmatter, key),
+                    }
+                    .into());
+                }
+            }
+        }
+        if self.strict {
+            for key in data.keys() {
+                if !self.keys.contains_key(key) && !BUILTIN_KEYS.contains(&key.as_str()) {
+                    return Err(UnknownFrontmatterKey {
+                        src: src(),
+                        key: key.clone(),
+                        span: Self::find_key_span(frontmatter, key),
+                    }
+                    .into());
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,354 @@
+use crate::page::Page;
+use serde::{Deserialize, Serialize};
+
+/// The output path of a site's OpenSearch description, relative to the output directory.
+pub const OPENSEARCH_PATH: &str = "opensearch.xml";
+
+/// The longest short name permitted in an OpenSearch description, in characters.
+const OPENSEARCH_SHORT_NAME_LENGTH: usize = 16;
+
+/// HTML elements which do not separate the words of surrounding text.
+const INLINE_ELEMENTS: [&str; 22] = [
+    "a", "abbr", "b", "bdi", "bdo", "cite", "code", "data", "dfn", "em", "i", "kbd", "mark", "q",
+    "s", "samp", "small", "span", "strong", "sub", "sup", "u",
+];
+
+#[derive(PartialEq, Eq, Clone, Default, Debug, Serialize, Deserialize)]
+/// Settings for generating a search index, defined by the `search` table in `global.toml`.
+pub struct SearchSettings {
+    /// The collections whose pages are indexed; if omitted, all pages are indexed.
+    pub include: Option<Vec<String>>,
+    /// The collections whose pages are not indexed.
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// The output path of the search index, relative to the output directory.
+    pub output: Option<String>,
+    /// The path of the page displaying search results, which is given the query as its `q` parameter.
+    /// If set, an OpenSearch description is output, so that browsers can search the site.
+    pub page: Option<String>,
+}
+
+impl SearchSettings {
+    /// Determine if a page is indexed.
+    ///
+    /// # Arguments
+    ///
+    /// * `page` - The page.
+    ///
+    /// # Returns
+    ///
+    /// Whether or not the page is indexed.
+    pub fn includes(&self, page: &Page) -> bool {
+        if page.is_layout || page.url.is_empty() {
+            return false;
+        }
+        let collections = page.collections.clone().unwrap_or_default();
+        if collections.iter().any(|x| self.exclude.contains(x)) {
+            return false;
+        }
+        match &self.include {
+            Some(include) => collections.iter().any(|x| include.contains(x)),
+            None => true,
+        }
+    }
+
+    /// Obtain the output path of the search index.
+    ///
+    /// # Returns
+    ///
+    /// The path of the search index, defaulting to `output/search.json`.
+    pub fn output_path(&self) -> String {
+        format!(
+            "output/{}",
+            self.output
+                .as_deref()
+                .unwrap_or("search.json")
+                .trim_start_matches('/')
+        )
+    }
+}
+
+impl SearchSettings {
+    /// Describe how to search a site, per the OpenSearch specification.
+    ///
+    /// # Arguments
+    ///
+    /// * `title` - The title of the site, if any.
+    ///
+    /// * `url` - The URL of the site, if any; the URL of the search results page is made absolute with it.
+    ///
+    /// # Returns
+    ///
+    /// The OpenSearch description as XML, or `None` if the site has no search results page.
+    pub fn opensearch_description(&self, title: Option<&str>, url: Option<&str>) -> Option<String> {
+        let page = self.page.as_deref()?.trim_start_matches('/');
+        let title = title.unwrap_or("Search");
+        let template = match url {
+            Some(url) => format!("{}/{}", url.trim_end_matches('/'), page),
+            None => format!("/{}", page),
+        };
+        let separator = match template.contains('?') {
+            true => '&',
+            false => '?',
+        };
+        let short_name = title
+            .chars()
+            .take(OPENSEARCH_SHORT_NAME_LENGTH)
+            .collect::<String>();
+        Some(format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<OpenSearchDescription xmlns=\"http://a9.com/-/spec/opensearch/1.1/\">\n  <ShortName>{}</ShortName>\n  <Description>{}</Description>\n  <InputEncoding>UTF-8</InputEncoding>\n  <Url type=\"text/html\" method=\"get\" template=\"{}\"/>\n</OpenSearchDescription>\n",
+            html_escape::encode_text(short_name.trim()),
+            html_escape::encode_text(&format!("Search {}", title)),
+            html_escape::encode_double_quoted_attribute(&format!(
+                "{}{}q={{searchTerms}}",
+                template, separator
+            ))
+        ))
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Default, Debug, Serialize, Deserialize)]
+/// A page as represented in the search index.
+/// The index is a list of these documents, suitable for indexing with client-side libraries such as Fuse.js or elasticlunr.
+pub struct SearchDocument {
+    /// The position of the document in the index.
+    pub id: usize,
+    /// The title of the page.
+    pub title: String,
+    /// The output path of the page.
+    pub url: String,
+    /// The text of the page's headings.
+    pub headings: Vec<String>,
+    /// The text of the page's rendered content.
+    pub body: String,
+}
+
+impl SearchDocument {
+    /// Represent a page in the search index.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The position of the document in the index.
+    ///
+    /// * `page` - The page, after rendering.
+    ///
+    /// # Returns
+    ///
+    /// The page's representation in the search index.
+    pub fn from_page(id: usize, page: &Page) -> Self {
+        let headings = extract_headings(&page.rendered);
+        let title = page
+            .data
+            .get("title")
+            .and_then(|x| x.as_str())
+            .map(|x| x.to_string())
+            .or(headings.first().cloned())
+            .unwrap_or(page.name.clone());
+        Self {
+            id,
+            title,
+            url: page.url.clone(),
+            headings,
+            body: html_to_text(&page.rendered),
+        }
+    }
+}
+
+/// Obtain the text of HTML, omitting scripts and stylesheets.
+///
+/// # Arguments
+///
+/// * `html` - The HTML.
+///
+/// # Returns
+///
+/// The text of the HTML, with runs of whitespace collapsed.
+pub fn html_to_text(html: &str) -> String {
+    let mut text = String::new();
+    let mut remainder = html;
+    while let Some(start) = remainder.find('<') {
+        text.push_str(&remainder[..start]);
+        let tag = &remainder[start..];
+        let Some(end) = tag.find('>') else {
+            remainder = "";
+            break;
+        };
+        let tag_name = tag[1..end]
+            .trim_start_matches('/')
+            .split(|x: char| x.is_whitespace() || x == '/')
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        remainder = &tag[end + 1..];
+        // The contents of scripts and stylesheets are not text.
+        let is_closing_tag = tag[1..].starts_with('/');
+        if !is_closing_tag && (tag_name == "script" || tag_name == "style") {
+            let closing_tag = format!("</{}", tag_name);
+            remainder = match remainder.to_ascii_lowercase().find(&closing_tag) {
+                Some(closing_start) => {
+                    let after_closing = &remainder[closing_start..];
+                    &after_closing[after_closing.find('>').map(|x| x + 1).unwrap_or(0)..]
+                }
+                None => "",
+            };
+        }
+        // Tags other than inline elements separate words.
+        if !INLINE_ELEMENTS.contains(&tag_name.as_str()) {
+            text.push(' ');
+        }
+    }
+    text.push_str(remainder);
+    html_escape::decode_html_entities(&text)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Obtain the text of the headings in HTML.
+///
+/// # Arguments
+///
+/// * `html` - The HTML.
+///
+/// # Returns
+///
+/// The text of each heading, in order of appearance.
+pub fn extract_headings(html: &str) -> Vec<String> {
+    let mut headings = Vec::new();
+    let lowercase_html = html.to_ascii_lowercase();
+    let mut offset = 0;
+    while let Some(start) = lowercase_html[offset..].find("<h") {
+        let start = offset + start;
+        offset = start + 2;
+        let Some(level) = lowercase_html[offset..]
+            .chars()
+            .next()
+            .filter(|x| ('1'..='6').contains(x))
+        else {
+            continue;
+        };
+        let Some(end) = lowercase_html[offset..].find(&format!("</h{}", level)) else {
+            continue;
+        };
+        let heading = html_to_text(&format!("<h{}", &html[offset..offset + end]));
+        if !heading.is_empty() {
+            headings.push(heading);
+        }
+        offset += end;
+    }
+    headings
+}
+
+#[derive(PartialEq, Clone, Default, Debug, Serialize, Deserialize)]
+/// A page matching a search query.
+pub struct SearchResult {
+    /// The title of the page.
+    pub title: String,
+    /// The output path of the page.
+    pub url: String,
+    /// The relevance of the page to the query; higher is more relevant.
+    pub score: f64,
+    /// An extract of the page's text surrounding the first match of the query.
+    pub snippet: String,
+}
+
+/// Count the occurrences of a term in text.
+///
+/// # Arguments
+///
+/// * `text` - The text, in lowercase.
+///
+/// * `term` - The term, in lowercase.
+///
+/// # Returns
+///
+/// The number of times the term appears in the text.
+fn count_term(text: &str, term: &str) -> usize {
+    text.matches(term).count()
+}
+
+/// Obtain an extract of text surrounding the first occurrence of a term.
+///
+/// # Arguments
+///
+/// * `text` - The text.
+///
+/// * `term` - The term, in lowercase.
+///
+/// # Returns
+///
+/// Up to ten words before and after the first occurrence of the term, or the start of the text if the term does not appear.
+fn snippet(text: &str, term: &str) -> String {
+    let words = text.split_whitespace().collect::<Vec<_>>();
+    let position = words
+        .iter()
+        .position(|word| word.to_lowercase().contains(term))
+        .unwrap_or_default();
+    let start = position.saturating_sub(10);
+    let end = (position + 11).min(words.len());
+    let mut extract = words[start..end].join(" ");
+    if start > 0 {
+        extract = format!("… {}", extract);
+    }
+    if end < words.len() {
+        extract = format!("{} …", extract);
+    }
+    extract
+}
+
+/// Query a search index.
+/// Pages must contain every term of the query; matches in titles are weighted above matches in headings, which are weighted above matches in the body.
+///
+/// # Arguments
+///
+/// * `documents` - The search index.
+///
+/// * `query` - The search query.
+///
+/// * `limit` - The maximum number of results.
+///
+/// # Returns
+///
+/// The pages matching the query, from most to least relevant.
+pub fn query_index(documents: &[SearchDocument], query: &str, limit: usize) -> Vec<SearchResult> {
+    let terms = query
+        .to_lowercase()
+        .split_whitespace()
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>();
+    if terms.is_empty() {
+        return Vec::new();
+    }
+    let mut results = Vec::new();
+    for document in documents {
+        let title = document.title.to_lowercase();
+        let headings = document.headings.join(" ").to_lowercase();
+        let body = document.body.to_lowercase();
+        let mut score = 0.0;
+        let mut matches_all_terms = true;
+        for term in terms.iter() {
+            let term_score = 10.0 * count_term(&title, term) as f64
+                + 5.0 * count_term(&headings, term) as f64
+                + count_term(&body, term) as f64;
+            if term_score == 0.0 {
+                matches_all_terms = false;
+                break;
+            }
+            score += term_score;
+        }
+        if !matches_all_terms {
+            continue;
+        }
+        // Longer pages are more likely to mention a term in passing.
+        let length = document.body.split_whitespace().count().max(1) as f64;
+        results.push(SearchResult {
+            title: document.title.clone(),
+            url: document.url.clone(),
+            score: score / length.sqrt(),
+            snippet: snippet(&document.body, &terms[0]),
+        });
+    }
+    results.sort_by(|lhs, rhs| rhs.score.total_cmp(&lhs.score));
+    results.truncate(limit);
+    results
+}
@@ -0,0 +1,181 @@
+use crate::search::OPENSEARCH_PATH;
+use liquid_core::error::ResultLiquidReplaceExt;
+use liquid_core::model::ScalarCow;
+use liquid_core::Language;
+use liquid_core::Renderable;
+use liquid_core::Result;
+use liquid_core::Runtime;
+use liquid_core::ValueView;
+use liquid_core::{ParseTag, TagReflection, TagTokenIter};
+use std::io::Write;
+
+#[derive(Copy, Clone, Debug, Default)]
+/// A Liquid template tag outputting metadata for search engines and social media.
+/// The tag is written as `{% seo %}`, and belongs in the `<head>` of a layout.
+pub struct SeoTag;
+
+impl SeoTag {
+    /// Provides a new instance of the SEO tag parser.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl TagReflection for SeoTag {
+    fn tag(&self) -> &'static str {
+        "seo"
+    }
+
+    fn description(&self) -> &'static str {
+        ""
+    }
+}
+
+impl ParseTag for SeoTag {
+    fn parse(
+        &self,
+        mut arguments: TagTokenIter<'_>,
+        _options: &Language,
+    ) -> Result<Box<dyn Renderable>> {
+        arguments.expect_nothing()?;
+        Ok(Box::new(Seo))
+    }
+
+    fn reflection(&self) -> &dyn TagReflection {
+        self
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Seo;
+
+/// Obtain a string from the contexts being rendered with.
+///
+/// # Arguments
+///
+/// * `runtime` - The Liquid runtime.
+///
+/// * `path` - The path to the value, eg: `["page", "data", "title"]`.
+///
+/// # Returns
+///
+/// The value as a string, if it exists and is not empty.
+fn get_string(runtime: &dyn Runtime, path: &[&str]) -> Option<String> {
+    let path = path
+        .iter()
+        .map(|x| ScalarCow::new(x.to_string()))
+        .collect::<Vec<_>>();
+    runtime
+        .try_get(&path)
+        .filter(|value| value.is_scalar())
+        .map(|value| value.to_kstr().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Obtain a value from a page's frontmatter, falling back to the global context.
+///
+/// # Arguments
+///
+/// * `runtime` - The Liquid runtime.
+///
+/// * `key` - The name of the value.
+///
+/// # Returns
+///
+/// The value as a string, if it exists in either the page's frontmatter or the global context.
+fn get_page_or_global_string(runtime: &dyn Runtime, key: &str) -> Option<String> {
+    get_string(runtime, &["page", "data", key]).or(get_string(runtime, &["global", key]))
+}
+
+impl Renderable for Seo {
+    fn render_to(&self, writer: &mut dyn Write, runtime: &dyn Runtime) -> Result<()> {
+        let site_title = get_string(runtime, &["global", "title"]);
+        let page_title = get_string(runtime, &["page", "data", "title"]);
+        let site_url = get_string(runtime, &["global", "url"])
+            .map(|url| url.trim_end_matches('/').to_string());
+        // Relative URLs are made absolute using the site's URL.
+        let absolute_url = |url: String| match &site_url {
+            Some(site_url) if !url.contains("://") => {
+                format!("{}/{}", site_url, url.trim_start_matches('/'))
+            }
+            _ => url,
+        };
+        let title = match (&page_title, &site_title) {
+            (Some(page_title), Some(site_title)) if page_title != site_title => {
+                Some(format!("{} | {}", page_title, site_title))
+            }
+            _ => page_title.clone().or(site_title.clone()),
+        };
+        let description = get_page_or_global_string(runtime, "description");
+        let author = get_page_or_global_string(runtime, "author");
+        let image = get_page_or_global_string(runtime, "image").map(absolute_url);
+        let canonical_url = get_string(runtime, &["page", "canonical_url"]).or(get_string(
+            runtime,
+            &["page", "url"],
+        )
+        .map(absolute_url));
+        let published = get_string(runtime, &["page", "date", "rfc_3339"]);
+        let updated = get_string(runtime, &["page", "updated", "rfc_3339"]);
+        let locale = get_string(runtime, &["global", "locale"]);
+        let twitter = get_string(runtime, &["global", "twitter"]);
+        let page_type = match published {
+            Some(_) => "article".to_string(),
+            None => "website".to_string(),
+        };
+        let card_type = match image {
+            Some(_) => "summary_large_image".to_string(),
+            None => "summary".to_string(),
+        };
+
+        let mut tags = Vec::new();
+        let mut meta = |attribute: &str, name: &str, content: &Option<String>| {
+            if let Some(content) = content {
+                tags.push(format!(
+                    "<meta {}=\"{}\" content=\"{}\">",
+                    attribute,
+                    name,
+                    html_escape::encode_double_quoted_attribute(content)
+                ));
+            }
+        };
+        meta("name", "description", &description);
+        meta("name", "author", &author);
+        meta(
+            "property",
+            "og:title",
+            &page_title.clone().or(site_title.clone()),
+        );
+        meta("property", "og:description", &description);
+        meta("property", "og:url", &canonical_url);
+        meta("property", "og:site_name", &site_title);
+        meta("property", "og:locale", &locale);
+        meta("property", "og:image", &image);
+        meta("property", "og:type", &Some(page_type));
+        meta("property", "article:published_time", &published);
+        meta("property", "article:modified_time", &updated);
+        meta("name", "twitter:card", &Some(card_type));
+        meta("name", "twitter:site", &twitter);
+        if let Some(title) = title {
+            tags.insert(
+                0,
+                format!("<title>{}</title>", html_escape::encode_text(&title)),
+            );
+        }
+        // Browsers can search the site if it has an OpenSearch description.
+        if get_string(runtime, &["global", "search", "page"]).is_some() {
+            tags.push(format!(
+                "<link rel=\"search\" type=\"application/opensearchdescription+xml\" title=\"{}\" href=\"{}\">",
+                html_escape::encode_double_quoted_attribute(site_title.as_deref().unwrap_or("Search")),
+                html_escape::encode_double_quoted_attribute(&absolute_url(format!("/{}", OPENSEARCH_PATH)))
+            ));
+        }
+        if let Some(canonical_url) = canonical_url {
+            tags.push(format!(
+                "<link rel=\"canonical\" href=\"{}\">",
+                html_escape::encode_double_quoted_attribute(&canonical_url)
+            ));
+        }
+        write!(writer, "{}", tags.join("\n")).replace("Failed to render")?;
+        Ok(())
+    }
+}
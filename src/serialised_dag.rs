@@ -0,0 +1,164 @@
+use crate::builds::EdgeType;
+use crate::page::Page;
+use crate::paths::to_slash_string;
+use daggy::{
+    petgraph::visit::{EdgeRef, IntoEdgeReferences},
+    stable_dag::StableDag,
+    NodeIndex,
+};
+use miette::IntoDiagnostic;
+use path_clean::PathClean;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+    path::PathBuf,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+/// A page in a serialised DAG.
+pub struct SerialisedNode {
+    /// The path to the page, relative to the site's directory.
+    pub path: PathBuf,
+    /// The hash of the page's frontmatter and contents; see [`Page::hash_source`].
+    pub hash: String,
+    /// The output path of the page, if its URL has been rendered.
+    pub url: String,
+    /// Whether or not the page is a layout.
+    pub is_layout: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+/// An edge in a serialised DAG.
+pub struct SerialisedEdge {
+    /// The position of the parent node in the DAG's list of nodes.
+    pub parent: usize,
+    /// The position of the child node in the DAG's list of nodes.
+    pub child: usize,
+    /// The type of edge.
+    pub edge_type: EdgeType,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+/// A representation of a site's DAG that can be written to & read from storage.
+/// Pages are represented by their paths & the hashes of their sources, rather than their full contents.
+pub struct SerialisedDag {
+    /// The pages in the DAG.
+    /// Layouts appear once for each page using them.
+    pub nodes: Vec<SerialisedNode>,
+    /// The edges between pages in the DAG.
+    pub edges: Vec<SerialisedEdge>,
+}
+
+impl SerialisedDag {
+    /// Create a serialisable representation of a DAG.
+    ///
+    /// # Arguments
+    ///
+    /// * `dag` - The DAG to represent.
+    ///
+    /// # Returns
+    ///
+    /// A representation of the DAG, with nodes in order of their indices.
+    pub fn new(dag: &StableDag<Page, EdgeType>) -> Self {
+        let mut positions = HashMap::new();
+        let mut nodes = Vec::new();
+        for index in dag.graph().node_indices() {
+            let page = &dag.graph()[index];
+            positions.insert(index, nodes.len());
+            nodes.push(SerialisedNode {
+                path: PathBuf::from(to_slash_string(
+                    PathBuf::from(page.to_path_string().trim_start_matches('/')).clean(),
+                )),
+                hash: page.hash.clone(),
+                url: page.url.clone(),
+                is_layout: page.is_layout,
+            });
+        }
+        let edges = dag
+            .graph()
+            .edge_references()
+            .map(|edge| SerialisedEdge {
+                parent: positions[&edge.source()],
+                child: positions[&edge.target()],
+                edge_type: *edge.weight(),
+            })
+            .collect();
+        Self { nodes, edges }
+    }
+
+    /// Write the DAG as JSON.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Where to write the DAG.
+    pub fn to_writer(&self, writer: impl Write) -> miette::Result<()> {
+        serde_json::to_writer(writer, self).into_diagnostic()
+    }
+
+    /// Read a DAG previously written as JSON.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - Where to read the DAG from.
+    ///
+    /// # Returns
+    ///
+    /// The DAG read.
+    pub fn from_reader(reader: impl Read) -> miette::Result<Self> {
+        let dag: Self = serde_json::from_reader(reader).into_diagnostic()?;
+        if let Some(edge) = dag
+            .edges
+            .iter()
+            .find(|edge| edge.parent >= dag.nodes.len() || edge.child >= dag.nodes.len())
+        {
+            return Err(miette::miette!(
+                "Edge from node {} to node {} refers to a node not in the DAG.",
+                edge.parent,
+                edge.child
+            ));
+        }
+        Ok(dag)
+    }
+
+    /// Reconstruct the DAG's structure for analysis.
+    ///
+    /// # Returns
+    ///
+    /// A DAG of the serialised nodes, with node indices matching their positions in the list of nodes.
+    pub fn to_dag(&self) -> miette::Result<StableDag<SerialisedNode, EdgeType>> {
+        let mut dag = StableDag::new();
+        for node in &self.nodes {
+            dag.add_node(node.clone());
+        }
+        for edge in &self.edges {
+            dag.add_edge(
+                NodeIndex::new(edge.parent),
+                NodeIndex::new(edge.child),
+                edge.edge_type,
+            )
+            .into_diagnostic()?;
+        }
+        Ok(dag)
+    }
+
+    /// Find the nodes representing a page.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the page, relative to the site's directory.
+    ///
+    /// # Returns
+    ///
+    /// The positions of the nodes representing the page; layouts may be represented by several nodes.
+    pub fn find(&self, path: impl Into<PathBuf>) -> Vec<usize> {
+        let path = path.into().clean();
+        let path = path.strip_prefix("/").unwrap_or(&path);
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.path == path)
+            .map(|(position, _)| position)
+            .collect()
+    }
+}
@@ -0,0 +1,417 @@
+use crate::builds::{Build, CompiledTemplates};
+use crate::changes::{Change, SourceHashes};
+use crate::paths::to_slash_string;
+use crate::provider::VoxProvider;
+use crate::template_parser::TemplateParser;
+use actix_files::NamedFile;
+use actix_web::{web, HttpRequest, HttpResponse, Scope};
+use ahash::{AHashMap, AHashSet, HashSet};
+use daggy::petgraph::algo::toposort;
+use daggy::NodeIndex;
+use path_clean::PathClean;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tracing::{debug, error, info};
+
+/// The state of a site being rendered on request.
+struct RenderState {
+    /// The build the site's pages are rendered with.
+    build: Build,
+    /// A mapping of paths to DAG indices.
+    pages: AHashMap<PathBuf, NodeIndex>,
+    /// A mapping of layout paths to a set of DAG indices.
+    layouts: AHashMap<PathBuf, HashSet<NodeIndex>>,
+    /// A mapping of output paths to the index of the page output there.
+    outputs: AHashMap<PathBuf, NodeIndex>,
+    /// The pages which have been rendered.
+    rendered: AHashSet<NodeIndex>,
+    /// The processed contents of pages which have been output, by output path.
+    responses: AHashMap<PathBuf, String>,
+    /// The hashes of the files every page depends upon, as they were when the site was prepared.
+    sources: SourceHashes,
+}
+
+/// A service rendering the pages of a site on request, rather than serving pre-built files.
+/// The service can be mounted inside another Actix Web application to serve a Vox-powered section of it.
+pub struct VoxService<P: VoxProvider> {
+    /// The provider the site is rendered with.
+    provider: Arc<P>,
+    /// The state of the site, if rendered pages are cached.
+    cache: Option<Arc<Mutex<Option<RenderState>>>>,
+    /// The Liquid parser the site is rendered with, kept while rendered pages are cached.
+    parser: TemplateParser,
+    /// A directory of files to serve when no page is output at a requested path.
+    static_files: Option<PathBuf>,
+    /// Whether or not to refuse to fetch data from URLs while rendering.
+    offline: bool,
+}
+
+impl<P: VoxProvider> Clone for VoxService<P> {
+    fn clone(&self) -> Self {
+        Self {
+            provider: self.provider.clone(),
+            cache: self.cache.clone(),
+            parser: self.parser.clone(),
+            static_files: self.static_files.clone(),
+            offline: self.offline,
+        }
+    }
+}
+
+impl<P: VoxProvider + Send + Sync + 'static> VoxService<P> {
+    /// Create a service rendering the pages of a site on request.
+    ///
+    /// # Arguments
+    ///
+    /// * `provider` - The provider the site is rendered with.
+    ///
+    /// # Returns
+    ///
+    /// A service rendering pages with the given provider.
+    pub fn new(provider: P) -> Self {
+        Self {
+            provider: Arc::new(provider),
+            cache: None,
+            parser: TemplateParser::new(),
+            static_files: None,
+            offline: false,
+        }
+    }
+
+    /// Cache rendered pages, only rendering a page on the first request for it.
+    /// Once cached, pages are only rendered again after [`VoxService::invalidate`] is called and their inputs have changed.
+    ///
+    /// # Returns
+    ///
+    /// The service, caching rendered pages.
+    pub fn with_caching(mut self) -> Self {
+        self.cache = Some(Arc::new(Mutex::new(None)));
+        self
+    }
+
+    /// Serve files from a directory when no page is output at a requested path.
+    ///
+    /// # Arguments
+    ///
+    /// * `directory` - The directory of files to serve.
+    ///
+    /// # Returns
+    ///
+    /// The service, serving files from the given directory.
+    pub fn with_static_files(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.static_files = Some(directory.into());
+        self
+    }
+
+    /// Refuse to fetch data from URLs while rendering.
+    ///
+    /// # Returns
+    ///
+    /// The service, working offline.
+    pub fn offline(mut self) -> Self {
+        self.offline = true;
+        self
+    }
+
+    /// Get the provider the site is rendered with.
+    ///
+    /// # Returns
+    ///
+    /// The service's provider.
+    pub fn provider(&self) -> &P {
+        &self.provider
+    }
+
+    /// Obtain the output paths a request path may correspond to.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path requested, relative to where the service is mounted.
+    ///
+    /// # Returns
+    ///
+    /// The candidate output paths, in order of preference.
+    fn candidate_output_paths(path: &str) -> Vec<PathBuf> {
+        let path = path.trim_start_matches('/');
+        let output = PathBuf::from("output");
+        if path.is_empty() || path.ends_with('/') {
+            return vec![output.join(path).join("index.html").clean()];
+        }
+        let mut candidates = vec![output.join(path).clean()];
+        if PathBuf::from(path).extension().is_none() {
+            candidates.push(output.join(path).join("index.html").clean());
+            candidates.push(output.join(format!("{}.html", path)).clean());
+        }
+        candidates
+    }
+
+    /// Prepare to render the site, without rendering any pages.
+    ///
+    /// # Returns
+    ///
+    /// The state of the site, with the URLs of its pages rendered.
+    fn prepare(&self) -> miette::Result<RenderState> {
+        // Without caching, the site is prepared afresh for every request.
+        let sources = SourceHashes::from_provider(self.provider.as_ref())?;
+        // Data fetched from URLs may have changed since the site was last prepared.
+        self.parser.begin_build(self.offline)?;
+        let template_parser = match &self.cache {
+            Some(_) => self.parser.get(self.provider.as_ref())?,
+            None => {
+                self.parser.invalidate()?;
+                self.parser.get(self.provider.as_ref())?
+            }
+        };
+        // The DAG is constructed first, as doing so reads the repository's metadata.
+        let (dag, pages, layouts) = self.provider.generate_dag()?;
+        let (contexts, locale, timezone) = self.provider.get_global_context()?;
+        let mut build = Build {
+            template_parser,
+            contexts,
+            locale,
+            timezone,
+            dag,
+            templates: CompiledTemplates::default(),
+            url_transforms: self.provider.get_url_transforms()?,
+        };
+        build.resolve_url_conflicts(self.provider.get_url_conflict_strategy()?)?;
+        build.insert_collections_context()?;
+        // Layouts are output using the URL of the page above them, so the last page in topological order is the one output.
+        let mut outputs = AHashMap::new();
+        for index in toposort(&build.dag.graph(), None).unwrap_or_default() {
+            if let Some(output_path) =
+                self.provider
+                    .get_output_path(&build.dag.graph()[index], &index, &build)
+            {
+                outputs.insert(PathBuf::from(output_path).clean(), index);
+            }
+        }
+        Ok(RenderState {
+            build,
+            pages,
+            layouts,
+            outputs,
+            rendered: AHashSet::new(),
+            responses: AHashMap::new(),
+            sources,
+        })
+    }
+
+    /// Render the page output at a given path.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The state of the site.
+    ///
+    /// * `path` - The path requested, relative to where the service is mounted.
+    ///
+    /// # Returns
+    ///
+    /// The output path of the page and its processed contents, or `None` if no page is output at the given path.
+    fn render_with_state(
+        &self,
+        state: &mut RenderState,
+        path: &str,
+    ) -> miette::Result<Option<(String, String)>> {
+        let output = Self::candidate_output_paths(path)
+            .into_iter()
+            .find_map(|candidate| state.outputs.get(&candidate).map(|x| (candidate, *x)));
+        let Some((output_path, index)) = output else {
+            debug!("No page is output at `{}` … ", path);
+            return Ok(None);
+        };
+        let output_path_string = to_slash_string(&output_path);
+        if let Some(response) = state.responses.get(&output_path) {
+            debug!("Using cached `{}` … ", output_path_string);
+            return Ok(Some((output_path_string, response.clone())));
+        }
+        state
+            .build
+            .render_with_ancestors(index, &mut state.rendered)?;
+        let post_processing_settings = self.provider.get_post_processing_settings()?;
+        let rendered = self.provider.post_process(
+            &state.build.dag.graph()[Build::get_page_above_layouts(&state.build.dag, index)],
+            state.build.dag.graph()[index].rendered.to_string(),
+            &output_path_string,
+            &post_processing_settings,
+        )?;
+        state.responses.insert(output_path, rendered.clone());
+        Ok(Some((output_path_string, rendered)))
+    }
+
+    /// Render the page output at a given path.
+    /// Only the page and the pages it depends upon are rendered, unless they are already cached.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path requested, relative to where the service is mounted.
+    ///
+    /// # Returns
+    ///
+    /// The output path of the page and its processed contents, or `None` if no page is output at the given path.
+    pub fn render(&self, path: &str) -> miette::Result<Option<(String, String)>> {
+        match &self.cache {
+            Some(cache) => {
+                let mut cache = cache.lock().map_err(|e| miette::miette!("{}", e))?;
+                if cache.is_none() {
+                    *cache = Some(self.prepare()?);
+                }
+                self.render_with_state(cache.as_mut().unwrap(), path)
+            }
+            None => self.render_with_state(&mut self.prepare()?, path),
+        }
+    }
+
+    /// Discard cached pages whose inputs have changed.
+    ///
+    /// # Arguments
+    ///
+    /// * `change` - The change to the site's sources; if the global context or any snippets have changed, every page is discarded.
+    pub fn invalidate(&self, change: &Change) -> miette::Result<()> {
+        let Some(cache) = &self.cache else {
+            return Ok(());
+        };
+        let mut cache = cache.lock().map_err(|e| miette::miette!("{}", e))?;
+        let Some(old_state) = cache.take() else {
+            // The parser is only created again if the change affects it.
+            return self.parser.update(self.provider.as_ref(), change);
+        };
+        // Files touched without being modified are not considered changed.
+        let sources = SourceHashes::from_provider(self.provider.as_ref())?;
+        let change = change
+            .clone()
+            .without_unchanged(&old_state.sources, &sources);
+        self.parser.update(self.provider.as_ref(), &change)?;
+        if change.global_or_snippets_changed() {
+            info!("Discarding all cached pages … ");
+            return Ok(());
+        }
+        let mut new_state = self.prepare()?;
+        let (added_or_modified, removed, _removed_output_paths) =
+            self.provider.get_dag_difference(
+                &old_state.build.dag,
+                &old_state.pages,
+                &old_state.layouts,
+                &new_state.build.dag,
+                &new_state.pages,
+                &new_state.layouts,
+            )?;
+        let mut stale = self.provider.pages_to_render(
+            &old_state.build.dag,
+            &new_state.build.dag,
+            &new_state.pages,
+            &new_state.layouts,
+            false,
+            added_or_modified,
+            removed,
+        )?;
+        for index in stale.clone() {
+            stale.extend(Build::get_descendants(&new_state.build.dag, index));
+        }
+        info!("Discarding {} cached pages … ", stale.len());
+        // Pages whose inputs are unchanged keep their rendered content.
+        for (page_path, index) in &new_state.pages {
+            if stale.contains(index) {
+                continue;
+            }
+            if let Some(old_index) = old_state.pages.get(page_path) {
+                if old_state.rendered.contains(old_index) {
+                    new_state.rendered.insert(*index);
+                }
+            }
+        }
+        self.provider.merge_dags(
+            &stale,
+            old_state.build.dag,
+            &mut new_state.build.dag,
+            old_state.pages,
+            &new_state.pages,
+        )?;
+        for (output_path, response) in old_state.responses {
+            if let Some(index) = new_state.outputs.get(&output_path) {
+                if !stale.contains(index) {
+                    new_state.responses.insert(output_path, response);
+                }
+            }
+        }
+        *cache = Some(new_state);
+        Ok(())
+    }
+
+    /// Create an Actix Web scope serving the site.
+    /// Requests for paths where no page is output are answered with a static file, if one is configured, or otherwise the site's `404.html` page, if it has one.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path the site is mounted at (eg: `/docs`).
+    ///
+    /// # Returns
+    ///
+    /// A scope which can be registered as a service of an Actix Web application.
+    pub fn scope(self, path: &str) -> Scope {
+        web::scope(path)
+            .app_data(web::Data::new(self))
+            .default_service(web::to(respond::<P>))
+    }
+}
+
+/// Respond to a request for a page.
+///
+/// # Arguments
+///
+/// * `request` - The request.
+///
+/// * `service` - The service rendering the site.
+///
+/// # Returns
+///
+/// The rendered page, a static file, or an error page.
+async fn respond<P: VoxProvider + Send + Sync + 'static>(
+    request: HttpRequest,
+    service: web::Data<VoxService<P>>,
+) -> HttpResponse {
+    let path = request.match_info().unprocessed().to_string();
+    let rendering_service = service.get_ref().clone();
+    let rendering_path = path.clone();
+    let rendering = web::block(move || rendering_service.render(&rendering_path)).await;
+    let not_found = match rendering {
+        Ok(Ok(Some((output_path, rendered)))) => {
+            let content_type = mime_guess::from_path(&output_path).first_or_octet_stream();
+            return HttpResponse::Ok()
+                .content_type(content_type.as_ref())
+                .body(rendered);
+        }
+        Ok(Ok(None)) => {
+            if let Some(static_files) = &service.static_files {
+                let static_files = static_files.clean();
+                let file_path = static_files.join(path.trim_start_matches('/')).clean();
+                if file_path.starts_with(&static_files) && file_path.is_file() {
+                    if let Ok(file) = NamedFile::open(file_path) {
+                        return file.into_response(&request);
+                    }
+                }
+            }
+            let rendering_service = service.get_ref().clone();
+            web::block(move || rendering_service.render("404.html")).await
+        }
+        Ok(Err(err)) => Ok(Err(err)),
+        Err(err) => Err(err),
+    };
+    match not_found {
+        Ok(Ok(Some((output_path, rendered)))) => {
+            let content_type = mime_guess::from_path(&output_path).first_or_octet_stream();
+            HttpResponse::NotFound()
+                .content_type(content_type.as_ref())
+                .body(rendered)
+        }
+        Ok(Ok(None)) => HttpResponse::NotFound().body("Not found."),
+        Ok(Err(err)) => {
+            error!("Unable to render `{}`: {:#?}", request.path(), err);
+            HttpResponse::InternalServerError().body("Unable to render the page.")
+        }
+        Err(err) => {
+            error!("Unable to render `{}`: {:#?}", request.path(), err);
+            HttpResponse::InternalServerError().body("Unable to render the page.")
+        }
+    }
+}
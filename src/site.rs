@@ -0,0 +1,446 @@
+use crate::audit::audit_site_accessibility;
+use crate::builds::EdgeType;
+use crate::changes::{Change, SourceHashes};
+use crate::hooks::HookStage;
+use crate::page::Page;
+use crate::provider::VoxProvider;
+#[cfg(feature = "remote_data")]
+use crate::remote_data::refresh_remote_data;
+use crate::summary::{BuildSummary, StageTimer};
+use crate::template_parser::TemplateParser;
+#[cfg(feature = "validate")]
+use crate::validation::validate_site_html;
+use ahash::{AHashMap, HashSet};
+use daggy::{stable_dag::StableDag, NodeIndex};
+use std::path::PathBuf;
+use tracing::{debug, info, warn};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+/// Options controlling what is output when building a site, aside from its pages.
+pub struct BuildOptions {
+    /// Whether or not to output a visualisation of the DAG.
+    pub visualise_dag: bool,
+    /// Whether or not to output a stylesheet for syntax highlighting.
+    pub generate_syntax_css: bool,
+    /// Whether or not to output JSON representations of pages and collections.
+    pub generate_json: bool,
+    /// Whether or not to remove files output by previous builds which were not output by this build.
+    /// This is only possible if the provider can stage output.
+    pub remove_orphans: bool,
+    /// Whether or not to only use cached remote data, rather than fetching any.
+    pub offline: bool,
+    /// Whether or not to check the pages output for common accessibility problems once built.
+    pub audit_accessibility: bool,
+    /// Whether or not to check the HTML of the pages output for structural errors once built.
+    /// This requires Vox to be built with the `validate` feature.
+    pub validate_html: bool,
+    /// Whether or not to skip the site's hooks.
+    pub skip_hooks: bool,
+}
+
+/// The state of a site after it has been built, used to rebuild it incrementally.
+struct BuiltSite {
+    /// The DAG of the site.
+    dag: StableDag<Page, EdgeType>,
+    /// A mapping of paths to DAG indices.
+    pages: AHashMap<PathBuf, NodeIndex>,
+    /// A mapping of layout paths to a set of DAG indices.
+    layouts: AHashMap<PathBuf, HashSet<NodeIndex>>,
+    /// The hashes of the files every page depends upon, as they were when the site was built.
+    sources: SourceHashes,
+}
+
+/// A site built by a Vox provider.
+/// The site owns its provider, and remembers the result of its last build so that it can be rebuilt incrementally.
+pub struct Site<P: VoxProvider> {
+    /// The provider the site is built with.
+    provider: P,
+    /// Options controlling what is output when building the site.
+    options: BuildOptions,
+    /// The Liquid parser the site is built with, kept between builds.
+    parser: TemplateParser,
+    /// The state of the site after its last build, if it has been built.
+    built: Option<BuiltSite>,
+    /// The summary of the site's last build or rebuild, if it has been built.
+    summary: Option<BuildSummary>,
+}
+
+impl<P: VoxProvider> Site<P> {
+    /// Create a site built by a Vox provider.
+    ///
+    /// # Arguments
+    ///
+    /// * `provider` - The provider the site is built with.
+    ///
+    /// # Returns
+    ///
+    /// A site which has not yet been built.
+    pub fn new(provider: P) -> Self {
+        Self {
+            provider,
+            options: BuildOptions::default(),
+            parser: TemplateParser::new(),
+            built: None,
+            summary: None,
+        }
+    }
+
+    /// Set the options controlling what is output when building the site.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - The build options.
+    ///
+    /// # Returns
+    ///
+    /// The site, with the given build options.
+    pub fn with_options(mut self, options: BuildOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Get the provider the site is built with.
+    ///
+    /// # Returns
+    ///
+    /// The site's provider.
+    pub fn provider(&self) -> &P {
+        &self.provider
+    }
+
+    /// Get the options controlling what is output when building the site.
+    ///
+    /// # Returns
+    ///
+    /// The site's build options.
+    pub fn options(&self) -> BuildOptions {
+        self.options
+    }
+
+    /// Get the DAG of the site as of its last build.
+    ///
+    /// # Returns
+    ///
+    /// The site's DAG, or `None` if the site has not been built.
+    pub fn dag(&self) -> Option<&StableDag<Page, EdgeType>> {
+        self.built.as_ref().map(|built| &built.dag)
+    }
+
+    /// Get the summary of the site's last build or rebuild.
+    ///
+    /// # Returns
+    ///
+    /// The summary of the last build, or `None` if the site has not been built, or nothing was rebuilt as nothing changed.
+    pub fn summary(&self) -> Option<&BuildSummary> {
+        self.summary.as_ref()
+    }
+
+    /// Build the entire site, regardless of any previous build.
+    ///
+    /// # Returns
+    ///
+    /// The indices of the pages rendered.
+    pub fn build(&mut self) -> miette::Result<Vec<NodeIndex>> {
+        let provider = &self.provider;
+        let mut summary = BuildSummary::default();
+        let (rendered_pages, built) =
+            self.build_site(true, &mut summary, |_page, output_path, rendered| {
+                provider.write_file(output_path, rendered)
+            })?;
+        self.audit(&built.dag, &mut summary);
+        self.built = Some(built);
+        self.summary = Some(summary);
+        Ok(rendered_pages)
+    }
+
+    /// Build the entire site, regardless of any previous build, passing each rendered page to a sink rather than writing it.
+    ///
+    /// # Arguments
+    ///
+    /// * `sink` - Receives each rendered page as it is produced, with its output path and processed contents; for layouts, the page is the page above the layouts.
+    ///   Pages with layouts are received again for each layout, each superseding the last at the same output path.
+    ///
+    /// # Returns
+    ///
+    /// The indices of the pages rendered.
+    pub fn build_with_sink(
+        &mut self,
+        sink: impl FnMut(&Page, &str, &[u8]) -> miette::Result<()>,
+    ) -> miette::Result<Vec<NodeIndex>> {
+        let mut summary = BuildSummary::default();
+        let (rendered_pages, built) = self.build_site(false, &mut summary, sink)?;
+        self.built = Some(built);
+        self.summary = Some(summary);
+        Ok(rendered_pages)
+    }
+
+    /// Build the entire site.
+    /// If the provider can stage output, the output directory is only replaced once the build succeeds.
+    ///
+    /// # Arguments
+    ///
+    /// * `copy_assets` - Whether or not to copy the site's assets into the output.
+    ///
+    /// * `summary` - The summary of the build, in which the stages of the build are recorded.
+    ///
+    /// * `sink` - Receives each rendered page as it is produced.
+    ///
+    /// # Returns
+    ///
+    /// The indices of the pages rendered, and the state of the built site.
+    fn build_site(
+        &self,
+        copy_assets: bool,
+        summary: &mut BuildSummary,
+        sink: impl FnMut(&Page, &str, &[u8]) -> miette::Result<()>,
+    ) -> miette::Result<(Vec<NodeIndex>, BuiltSite)> {
+        self.run_hooks(HookStage::PreBuild, false, summary)?;
+        // A full build does not rely on any previous build, including the parser it created.
+        self.parser.invalidate()?;
+        self.parser.begin_build(self.options.offline)?;
+        #[cfg(feature = "remote_data")]
+        {
+            let stage = StageTimer::start();
+            refresh_remote_data(&self.provider, self.options.offline)?;
+            summary.record("remote_data", stage);
+        }
+        if !self.provider.stage_output()? {
+            if self.options.remove_orphans {
+                warn!("The site's provider cannot stage output, so orphaned output will not be removed … ");
+            }
+            let built = self.build_site_unstaged(copy_assets, summary, sink)?;
+            self.run_hooks(HookStage::PostBuild, false, summary)?;
+            return Ok(built);
+        }
+        match self.build_site_unstaged(copy_assets, summary, sink) {
+            Ok(built) => {
+                let stage = StageTimer::start();
+                summary.files_removed += self
+                    .provider
+                    .commit_staged_output(self.options.remove_orphans)?;
+                summary.record("commit", stage);
+                self.run_hooks(HookStage::PostBuild, false, summary)?;
+                Ok(built)
+            }
+            Err(err) => {
+                warn!("Build failed; leaving the previous output in place … ");
+                self.provider.discard_staged_output()?;
+                Err(err)
+            }
+        }
+    }
+
+    /// Build the entire site, writing output directly.
+    ///
+    /// # Arguments
+    ///
+    /// * `copy_assets` - Whether or not to copy the site's assets into the output.
+    ///
+    /// * `summary` - The summary of the build, in which the stages of the build are recorded.
+    ///
+    /// * `sink` - Receives each rendered page as it is produced.
+    ///
+    /// # Returns
+    ///
+    /// The indices of the pages rendered, and the state of the built site.
+    fn build_site_unstaged(
+        &self,
+        copy_assets: bool,
+        summary: &mut BuildSummary,
+        sink: impl FnMut(&Page, &str, &[u8]) -> miette::Result<()>,
+    ) -> miette::Result<(Vec<NodeIndex>, BuiltSite)> {
+        // Assets are copied first, so that pages output at the same paths take precedence.
+        if copy_assets {
+            let stage = StageTimer::start();
+            summary.files_written += self.provider.copy_assets()?;
+            summary.record("assets", stage);
+        }
+        let stage = StageTimer::start();
+        let sources = SourceHashes::from_provider(&self.provider)?;
+        let parser = self.parser.get(&self.provider)?;
+        // The DAG is constructed first, as doing so reads the repository's metadata.
+        let (dag, pages, layouts) = self.provider.generate_dag()?;
+        let (contexts, locale, timezone) = self.provider.get_global_context()?;
+        summary.record("dag", stage);
+        let (rendered_pages, dag) = self.provider.generate_site_with_sink(
+            parser,
+            contexts,
+            locale,
+            timezone,
+            dag,
+            self.options.visualise_dag,
+            self.options.generate_syntax_css,
+            self.options.generate_json,
+            summary,
+            sink,
+        )?;
+        Ok((
+            rendered_pages,
+            BuiltSite {
+                dag,
+                pages,
+                layouts,
+                sources,
+            },
+        ))
+    }
+
+    /// Rebuild the site, only rendering the pages affected by changes since its last build.
+    /// If the site has not been built, it is built entirely.
+    /// If the rebuild fails, the state of the last successful build is kept, so that the site can be rebuilt from it once the problem is fixed.
+    ///
+    /// # Arguments
+    ///
+    /// * `change` - The change to the site's sources since its last build.
+    pub fn rebuild(&mut self, change: &Change) -> miette::Result<()> {
+        // The state of the last build is kept until the rebuild succeeds, so that a failed rebuild can be retried.
+        let Some(built) = self.built.as_ref() else {
+            self.build()?;
+            return Ok(());
+        };
+        // Files touched without being modified are not considered changed.
+        let sources = SourceHashes::from_provider(&self.provider)?;
+        let change = change.clone().without_unchanged(&built.sources, &sources);
+        // Neither hooks nor any stage of the build are run if nothing changed.
+        if change.paths.is_empty() {
+            debug!("No files have changed; nothing to rebuild … ");
+            self.summary = None;
+            return Ok(());
+        }
+        let global_or_snippets_changed = change.global_or_snippets_changed();
+        let mut summary = BuildSummary::default();
+        self.run_hooks(HookStage::PreBuild, true, &mut summary)?;
+        // The sources of remote data may have changed.
+        #[cfg(feature = "remote_data")]
+        if global_or_snippets_changed {
+            let stage = StageTimer::start();
+            refresh_remote_data(&self.provider, self.options.offline)?;
+            summary.record("remote_data", stage);
+        }
+        // The parser depends upon the site's locale and snippets, and is only created again if they have changed.
+        self.parser.update(&self.provider, &change)?;
+        self.parser.begin_build(self.options.offline)?;
+        let parser = self.parser.get(&self.provider)?;
+        let (dag, pages, layouts) = self.provider.incremental_regeneration(
+            global_or_snippets_changed,
+            parser,
+            self.options.visualise_dag,
+            self.options.generate_syntax_css,
+            self.options.generate_json,
+            built.dag.clone(),
+            built.pages.clone(),
+            built.layouts.clone(),
+            &mut summary,
+        )?;
+        self.run_hooks(HookStage::PostBuild, true, &mut summary)?;
+        self.audit(&dag, &mut summary);
+        self.built = Some(BuiltSite {
+            dag,
+            pages,
+            layouts,
+            sources,
+        });
+        self.summary = Some(summary);
+        Ok(())
+    }
+
+    /// Run the site's hooks for a stage of a build, recording the time they take.
+    ///
+    /// # Arguments
+    ///
+    /// * `stage` - The stage of the build.
+    ///
+    /// * `rebuilding` - Whether or not the site is being rebuilt, in which case hooks with `watch = false` are not run.
+    ///
+    /// * `summary` - The summary of the build.
+    fn run_hooks(
+        &self,
+        stage: HookStage,
+        rebuilding: bool,
+        summary: &mut BuildSummary,
+    ) -> miette::Result<()> {
+        if self.options.skip_hooks {
+            return Ok(());
+        }
+        let timer = StageTimer::start();
+        self.provider.run_hooks(stage, rebuilding)?;
+        summary.record(
+            match stage {
+                HookStage::PreBuild => "pre_build_hooks",
+                HookStage::PostBuild => "post_build_hooks",
+            },
+            timer,
+        );
+        Ok(())
+    }
+
+    /// Audit the pages output by a build, as enabled by the build options, logging the problems found.
+    ///
+    /// # Arguments
+    ///
+    /// * `dag` - The DAG of the built site.
+    ///
+    /// * `summary` - The summary of the build.
+    fn audit(&self, dag: &StableDag<Page, EdgeType>, summary: &mut BuildSummary) {
+        if !self.options.audit_accessibility && !self.options.validate_html {
+            return;
+        }
+        let stage = StageTimer::start();
+        if self.options.audit_accessibility {
+            info!("Auditing accessibility … ");
+            let problems = audit_site_accessibility(&self.provider, dag);
+            info!("Found {} accessibility problems … ", problems);
+        }
+        if self.options.validate_html {
+            #[cfg(feature = "validate")]
+            {
+                info!("Validating HTML … ");
+                let errors = validate_site_html(&self.provider, dag);
+                info!("Found {} HTML errors … ", errors);
+            }
+            #[cfg(not(feature = "validate"))]
+            warn!("Validating HTML requires Vox to be built with the `validate` feature … ");
+        }
+        summary.record("audit", stage);
+    }
+
+    /// Build the site, then rebuild it each time its provider notifies it of a change.
+    /// The site is watched until the provider stops notifying it of changes.
+    pub fn watch(&mut self) -> miette::Result<()> {
+        self.watch_with_summaries(|_summary| ())
+    }
+
+    /// Build the site, then rebuild it each time its provider notifies it of a change, passing the summary of each rebuild to a callback.
+    /// The site is watched until the provider stops notifying it of changes.
+    ///
+    /// # Arguments
+    ///
+    /// * `on_rebuild` - Receives the summary of each build or rebuild performed while watching.
+    pub fn watch_with_summaries(
+        &mut self,
+        mut on_rebuild: impl FnMut(&BuildSummary),
+    ) -> miette::Result<()> {
+        let changes = self.provider.watch()?.ok_or(miette::miette!(
+            "The site's provider cannot be watched for changes."
+        ))?;
+        if self.built.is_none() {
+            info!("Performing initial build … ");
+            self.build()?;
+            if let Some(summary) = &self.summary {
+                on_rebuild(summary);
+            }
+        }
+        for change in changes {
+            // The exclusions may themselves have changed.
+            let change = change.without_excluded(&self.provider.get_exclusions()?);
+            if change.paths.is_empty() {
+                continue;
+            }
+            self.rebuild(&change)?;
+            if let Some(summary) = &self.summary {
+                on_rebuild(summary);
+            }
+        }
+        Ok(())
+    }
+}
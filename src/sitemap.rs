@@ -0,0 +1,189 @@
+use crate::error::InvalidSitemapProperty;
+use crate::page::Page;
+use crate::paths::{canonical_url, url_output_path};
+use miette::IntoDiagnostic;
+use serde::{Deserialize, Serialize};
+use toml::Value;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// How often a page is likely to change, as suggested to search engines.
+pub enum ChangeFrequency {
+    /// The page changes each time it is accessed.
+    Always,
+    /// The page changes about once an hour.
+    Hourly,
+    /// The page changes about once a day.
+    Daily,
+    /// The page changes about once a week.
+    Weekly,
+    /// The page changes about once a month.
+    Monthly,
+    /// The page changes about once a year.
+    Yearly,
+    /// The page is archived, and does not change.
+    Never,
+}
+
+impl ChangeFrequency {
+    /// Get the name of the change frequency, as written in a sitemap.
+    ///
+    /// # Returns
+    ///
+    /// The name of the change frequency.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Always => "always",
+            Self::Hourly => "hourly",
+            Self::Daily => "daily",
+            Self::Weekly => "weekly",
+            Self::Monthly => "monthly",
+            Self::Yearly => "yearly",
+            Self::Never => "never",
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Default, Debug, Serialize, Deserialize)]
+/// Settings for generating a sitemap, defined by the `sitemap` table in `global.toml`.
+pub struct SitemapSettings {
+    /// The output path of the sitemap, relative to the output directory.
+    pub output: Option<String>,
+    /// The collections whose pages are not listed.
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+impl SitemapSettings {
+    /// Obtain the output path of the sitemap.
+    ///
+    /// # Returns
+    ///
+    /// The path of the sitemap, defaulting to `output/sitemap.xml`.
+    pub fn output_path(&self) -> String {
+        format!(
+            "output/{}",
+            self.output
+                .as_deref()
+                .unwrap_or("sitemap.xml")
+                .trim_start_matches('/')
+        )
+    }
+}
+
+#[derive(PartialEq, Clone, Default, Debug, Serialize, Deserialize)]
+/// A page's entry in the sitemap, defined by the `sitemap` table in the page's frontmatter.
+pub struct SitemapEntrySettings {
+    /// How important the page is relative to the site's other pages, from `0.0` to `1.0`.
+    pub priority: Option<f64>,
+    /// How often the page is likely to change.
+    pub changefreq: Option<ChangeFrequency>,
+}
+
+impl SitemapEntrySettings {
+    /// Read a page's entry in the sitemap from its frontmatter.
+    ///
+    /// # Arguments
+    ///
+    /// * `page` - The page.
+    ///
+    /// # Returns
+    ///
+    /// The settings of the page's entry, or `None` if the page has `sitemap = false` in its frontmatter.
+    pub fn from_page(page: &Page) -> miette::Result<Option<Self>> {
+        let invalid = |reason: String| InvalidSitemapProperty {
+            page: page.to_path_string().trim_start_matches('/').to_string(),
+            reason,
+        };
+        let settings = match page.data.get("sitemap") {
+            None => Self::default(),
+            Some(Value::Boolean(included)) => match included {
+                true => Self::default(),
+                false => return Ok(None),
+            },
+            Some(settings) => settings
+                .clone()
+                .try_into::<Self>()
+                .map_err(|err| invalid(err.message().trim().to_string()))
+                .into_diagnostic()?,
+        };
+        if let Some(priority) = settings.priority.filter(|x| !(0.0..=1.0).contains(x)) {
+            return Err(invalid(format!(
+                "the priority {} is not from 0.0 to 1.0",
+                priority
+            )))
+            .into_diagnostic();
+        }
+        Ok(Some(settings))
+    }
+}
+
+/// Create a sitemap of a site's pages, per the Sitemaps protocol.
+/// Layouts, pages which are not output as HTML, pages in excluded collections, and pages with `sitemap = false` in their frontmatter are not listed.
+///
+/// # Arguments
+///
+/// * `settings` - The settings of the sitemap.
+///
+/// * `pages` - The pages of the site.
+///
+/// * `site_url` - The URL of the site, if any; without it, pages are listed by their URLs relative to the site's root.
+///
+/// # Returns
+///
+/// The sitemap as XML, with its entries ordered by URL.
+pub fn sitemap(
+    settings: &SitemapSettings,
+    pages: &[&Page],
+    site_url: Option<&str>,
+) -> miette::Result<String> {
+    let mut entries = Vec::new();
+    for page in pages {
+        if page.is_layout
+            || page.url.is_empty()
+            || !url_output_path(&page.url).ends_with(".html")
+            || page
+                .collections
+                .iter()
+                .flatten()
+                .any(|x| settings.exclude.contains(x))
+        {
+            continue;
+        }
+        if let Some(entry) = SitemapEntrySettings::from_page(page)? {
+            entries.push((page, entry));
+        }
+    }
+    entries.sort_by(|(lhs, _), (rhs, _)| lhs.url.cmp(&rhs.url));
+
+    let mut sitemap = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n");
+    for (page, entry) in entries {
+        let location = match page.canonical_url.is_empty() {
+            true => canonical_url(&page.url, site_url),
+            false => page.canonical_url.clone(),
+        };
+        sitemap.push_str("  <url>\n");
+        sitemap.push_str(&format!(
+            "    <loc>{}</loc>\n",
+            html_escape::encode_text(&location)
+        ));
+        if let Some(last_modified) = page.updated.as_ref().or(page.date.as_ref()) {
+            sitemap.push_str(&format!(
+                "    <lastmod>{}</lastmod>\n",
+                last_modified.rfc_3339
+            ));
+        }
+        if let Some(changefreq) = entry.changefreq {
+            sitemap.push_str(&format!(
+                "    <changefreq>{}</changefreq>\n",
+                changefreq.as_str()
+            ));
+        }
+        if let Some(priority) = entry.priority {
+            sitemap.push_str(&format!("    <priority>{}</priority>\n", priority));
+        }
+        sitemap.push_str("  </url>\n");
+    }
+    sitemap.push_str("</urlset>\n");
+    Ok(sitemap)
+}
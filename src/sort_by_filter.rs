@@ -0,0 +1,229 @@
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime};
+use liquid_core::Expression;
+use liquid_core::Result;
+use liquid_core::Runtime;
+use liquid_core::{
+    Display_filter, Filter, FilterParameters, FilterReflection, FromFilterParameters, ParseFilter,
+};
+use liquid_core::{Value, ValueView};
+use std::cmp::Ordering;
+
+/// The key TOML date-times are held under when frontmatter is given to templates.
+const TOML_DATETIME_KEY: &str = "$__toml_private_datetime";
+
+#[derive(Debug, FilterParameters)]
+struct SortByArgs {
+    #[parameter(
+        description = "The dotted path of the value to sort by, eg: `data.event.start`.",
+        arg_type = "str"
+    )]
+    path: Expression,
+    #[parameter(
+        description = "The direction to sort in, either `asc` or `desc`; defaults to `asc`.",
+        arg_type = "str"
+    )]
+    direction: Option<Expression>,
+    #[parameter(
+        description = "Whether items without the value appear `first` or `last`; defaults to `last`.",
+        arg_type = "str"
+    )]
+    nils: Option<Expression>,
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "sort_by",
+    description = "Sorts an array of objects by a dotted path into them, in either direction.",
+    parameters(SortByArgs),
+    parsed(SortByFilter)
+)]
+/// A Liquid filter sorting an array of objects, such as pages, by a value nested within them.
+/// Dates, including those of pages and those written in frontmatter, are sorted chronologically, eg: `{{ collections.events | sort_by: "data.event.start", "desc" }}`.
+pub struct SortBy;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "sort_by"]
+struct SortByFilter {
+    #[parameters]
+    args: SortByArgs,
+}
+
+/// Find a value nested within another.
+///
+/// # Arguments
+///
+/// * `value` - The value to search within.
+///
+/// * `path` - The keys leading to the nested value, separated by periods; arrays are indexed by number.
+///
+/// # Returns
+///
+/// The nested value, or `None` if there is no value at the path.
+fn find_nested<'v>(value: &'v dyn ValueView, path: &str) -> Option<&'v dyn ValueView> {
+    path.split('.')
+        .filter(|x| !x.is_empty())
+        .try_fold(value, |value, key| match value.as_object() {
+            Some(object) => object.get(key),
+            None => value.as_array()?.get(key.parse().ok()?),
+        })
+        .filter(|x| !x.is_nil())
+}
+
+/// The value an item is sorted by.
+/// Keys are ordered first by their kind, with dates before numbers, numbers before text, and text before anything else, then within their kind; this is a total order, whichever values a collection mixes.
+#[derive(Debug)]
+enum SortKey {
+    /// A date-time, compared chronologically.
+    Date(DateTime<FixedOffset>),
+    /// A whole or fractional number, compared numerically.
+    Number(f64),
+    /// Text, compared lexically.
+    Text(String),
+    /// Any other value, such as a boolean or a list, compared by its rendered text.
+    Other(String),
+}
+
+impl SortKey {
+    /// Obtain the key to sort an item by.
+    /// Dates, whether the dates of pages, date-times written in frontmatter, or text written as a date-time, are keyed by their date-times.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value at the path being sorted by.
+    ///
+    /// # Returns
+    ///
+    /// The key to sort by.
+    fn new(value: &dyn ValueView) -> Self {
+        let value = value
+            .as_object()
+            .and_then(|x| x.get("rfc_3339").or(x.get(TOML_DATETIME_KEY)))
+            .unwrap_or(value);
+        let text = value.to_kstr().to_string();
+        if let Some(datetime) = value.as_scalar().and_then(|_| parse_datetime(&text)) {
+            return Self::Date(datetime);
+        }
+        match value.type_name() {
+            "whole number" | "fractional number" => value
+                .as_scalar()
+                .and_then(|x| x.to_float())
+                .map(Self::Number)
+                .unwrap_or(Self::Other(text)),
+            "string" => Self::Text(text),
+            _ => Self::Other(text),
+        }
+    }
+
+    /// The position of the key's kind among the kinds of keys.
+    fn rank(&self) -> u8 {
+        match self {
+            Self::Date(_) => 0,
+            Self::Number(_) => 1,
+            Self::Text(_) => 2,
+            Self::Other(_) => 3,
+        }
+    }
+}
+
+impl Ord for SortKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Date(lhs), Self::Date(rhs)) => lhs.cmp(rhs),
+            (Self::Number(lhs), Self::Number(rhs)) => lhs.total_cmp(rhs),
+            (Self::Text(lhs), Self::Text(rhs)) | (Self::Other(lhs), Self::Other(rhs)) => {
+                lhs.cmp(rhs)
+            }
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
+impl PartialOrd for SortKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for SortKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for SortKey {}
+
+/// Read text as a date-time.
+/// Date-times are read per RFC 3339, as written by Liquid, or as a date alone, which is taken to be midnight in UTC.
+///
+/// # Arguments
+///
+/// * `text` - The text to read.
+///
+/// # Returns
+///
+/// The date-time, if the text is one.
+fn parse_datetime(text: &str) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(text)
+        .or_else(|_| DateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S %z"))
+        .ok()
+        .or_else(|| {
+            NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f")
+                .or_else(|_| {
+                    NaiveDate::parse_from_str(text, "%Y-%m-%d").map(|x| x.and_time(NaiveTime::MIN))
+                })
+                .ok()
+                .map(|x| x.and_utc().fixed_offset())
+        })
+}
+
+impl Filter for SortByFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let descending = match args.direction.as_deref() {
+            None | Some("asc") => false,
+            Some("desc") => true,
+            Some(_) => {
+                return Err(liquid_core::Error::with_msg(
+                    "Invalid direction. Must be \"asc\" or \"desc\".",
+                ))
+            }
+        };
+        let nils_first = match args.nils.as_deref() {
+            None | Some("last") => false,
+            Some("first") => true,
+            Some(_) => {
+                return Err(liquid_core::Error::with_msg(
+                    "Invalid nils order. Must be \"first\" or \"last\".",
+                ))
+            }
+        };
+        let items = match input.as_array() {
+            Some(array) => array.values().collect::<Vec<_>>(),
+            None if input.is_nil() => Vec::new(),
+            None => return Err(liquid_core::Error::with_msg("Array expected")),
+        };
+        if !items.iter().all(|x| x.is_object()) {
+            return Err(liquid_core::Error::with_msg("Array of objects expected"));
+        }
+        let mut keyed = items
+            .into_iter()
+            .map(|item| {
+                (
+                    find_nested(item, args.path.as_str()).map(SortKey::new),
+                    item.to_value(),
+                )
+            })
+            .collect::<Vec<_>>();
+        // Items without the value are kept together at one end, whichever the direction.
+        keyed.sort_by(|(lhs, _), (rhs, _)| match (lhs, rhs) {
+            (None, None) => Ordering::Equal,
+            (None, Some(_)) if nils_first => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(_), None) if nils_first => Ordering::Greater,
+            (Some(_), None) => Ordering::Less,
+            (Some(lhs), Some(rhs)) if descending => rhs.cmp(lhs),
+            (Some(lhs), Some(rhs)) => lhs.cmp(rhs),
+        });
+        Ok(Value::array(keyed.into_iter().map(|(_, item)| item)))
+    }
+}
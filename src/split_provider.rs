@@ -0,0 +1,242 @@
+use crate::changes::Changes;
+use crate::git::{GitMetadata, RepositoryMetadata};
+use crate::math_block::MathCache;
+use crate::page::Page;
+use crate::post_processing::PostProcessor;
+use crate::provider::{
+    FileReader, InputProvider, OutputProvider, PageGenerator, ParserCustomiser, VoxProvider,
+};
+use crate::url_transforms::UrlTransformer;
+use chrono::Locale;
+use chrono_tz::Tz;
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+#[derive(Clone)]
+/// A provider of the Vox build system that reads from one provider & writes through another.
+/// For example, a site can be read from the file system and its output kept in memory.
+pub struct SplitProvider<I: InputProvider, O: OutputProvider> {
+    input: I,
+    output: O,
+    post_processors: Vec<PostProcessor>,
+    parser_customisers: Vec<ParserCustomiser>,
+    page_generators: Vec<PageGenerator>,
+    url_transformers: Vec<UrlTransformer>,
+}
+impl<I: InputProvider + fmt::Debug, O: OutputProvider + fmt::Debug> fmt::Debug
+    for SplitProvider<I, O>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SplitProvider")
+            .field("input", &self.input)
+            .field("output", &self.output)
+            .field("post_processors", &self.post_processors.len())
+            .field("parser_customisers", &self.parser_customisers.len())
+            .field("page_generators", &self.page_generators.len())
+            .field("url_transformers", &self.url_transformers.len())
+            .finish()
+    }
+}
+impl<I: InputProvider, O: OutputProvider> InputProvider for SplitProvider<I, O> {
+    fn read_to_string(&self, path: impl AsRef<Path>) -> miette::Result<String> {
+        self.input.read_to_string(path)
+    }
+    fn read_bytes(&self, path: impl AsRef<Path>) -> miette::Result<Vec<u8>> {
+        self.input.read_bytes(path)
+    }
+    fn list_vox_files(&self) -> miette::Result<Vec<PathBuf>> {
+        self.input.list_vox_files()
+    }
+    fn list_markdown_files(&self) -> miette::Result<Vec<PathBuf>> {
+        self.input.list_markdown_files()
+    }
+    fn list_html_files(&self) -> miette::Result<Vec<PathBuf>> {
+        self.input.list_html_files()
+    }
+    fn list_snippets(&self) -> miette::Result<Vec<PathBuf>> {
+        self.input.list_snippets()
+    }
+    fn list_directory(&self, directory: &Path) -> miette::Result<Vec<PathBuf>> {
+        self.input.list_directory(directory)
+    }
+    fn list_assets(&self) -> miette::Result<Vec<PathBuf>> {
+        self.input.list_assets()
+    }
+    fn file_reader(&self) -> Option<FileReader> {
+        self.input.file_reader()
+    }
+    fn math_cache(&self) -> Option<MathCache> {
+        self.input.math_cache()
+    }
+    fn refresh_git_history(&self) {
+        self.input.refresh_git_history()
+    }
+    fn get_git_metadata(&self, path: &Path, locale: Locale, timezone: Tz) -> Option<GitMetadata> {
+        self.input.get_git_metadata(path, locale, timezone)
+    }
+    fn get_repository_metadata(&self) -> Option<RepositoryMetadata> {
+        self.input.get_repository_metadata()
+    }
+    fn working_directory(&self) -> Option<PathBuf> {
+        self.input.working_directory()
+    }
+    fn environment(&self) -> Option<String> {
+        self.input.environment()
+    }
+    fn watch(&self) -> miette::Result<Option<Changes>> {
+        self.input.watch()
+    }
+}
+impl<I: InputProvider, O: OutputProvider> OutputProvider for SplitProvider<I, O> {
+    fn write_file(
+        &self,
+        path: impl AsRef<Path> + Clone,
+        contents: impl AsRef<[u8]>,
+    ) -> miette::Result<()> {
+        self.output.write_file(path, contents)
+    }
+    fn remove_file(&self, path: impl AsRef<Path>) -> miette::Result<()> {
+        self.output.remove_file(path)
+    }
+    fn stage_output(&self) -> miette::Result<bool> {
+        self.output.stage_output()
+    }
+    fn commit_staged_output(&self, remove_orphans: bool) -> miette::Result<usize> {
+        self.output.commit_staged_output(remove_orphans)
+    }
+    fn discard_staged_output(&self) -> miette::Result<()> {
+        self.output.discard_staged_output()
+    }
+}
+impl<I: InputProvider, O: OutputProvider> VoxProvider for SplitProvider<I, O> {
+    fn get_post_processors(&self) -> Vec<PostProcessor> {
+        self.post_processors.clone()
+    }
+    fn get_parser_customisers(&self) -> Vec<ParserCustomiser> {
+        self.parser_customisers.clone()
+    }
+    fn get_page_generators(&self) -> Vec<PageGenerator> {
+        self.page_generators.clone()
+    }
+    fn get_url_transformers(&self) -> Vec<UrlTransformer> {
+        self.url_transformers.clone()
+    }
+}
+impl<I: InputProvider, O: OutputProvider> SplitProvider<I, O> {
+    /// Create a new Vox provider that reads from one provider & writes through another.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The provider the site is read from.
+    ///
+    /// * `output` - The provider the site's output is written through.
+    ///
+    /// # Returns
+    ///
+    /// A provider reading from `input` & writing through `output`.
+    pub fn new(input: I, output: O) -> Self {
+        Self {
+            input,
+            output,
+            post_processors: Vec::new(),
+            parser_customisers: Vec::new(),
+            page_generators: Vec::new(),
+            url_transformers: Vec::new(),
+        }
+    }
+
+    /// Get the provider the site is read from.
+    ///
+    /// # Returns
+    ///
+    /// The input provider.
+    pub fn input(&self) -> &I {
+        &self.input
+    }
+
+    /// Get the provider the site's output is written through.
+    ///
+    /// # Returns
+    ///
+    /// The output provider.
+    pub fn output(&self) -> &O {
+        &self.output
+    }
+
+    /// Add a function transforming each page's rendered HTML before it is written.
+    ///
+    /// # Arguments
+    ///
+    /// * `post_processor` - A function given a page and its rendered HTML, returning the HTML to be written.
+    ///
+    /// # Returns
+    ///
+    /// The provider, with the function added after any previously added.
+    pub fn with_post_processor(
+        mut self,
+        post_processor: impl Fn(&Page, String) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.post_processors.push(Arc::new(post_processor));
+        self
+    }
+
+    /// Add a function customising the Liquid parser, such as by registering additional tags, filters, or blocks.
+    ///
+    /// # Arguments
+    ///
+    /// * `parser_customiser` - A function given the parser's builder, returning the builder to use.
+    ///
+    /// # Returns
+    ///
+    /// The provider, with the function added after any previously added.
+    pub fn with_parser_customiser(
+        mut self,
+        parser_customiser: impl Fn(liquid::ParserBuilder) -> liquid::ParserBuilder
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        self.parser_customisers.push(Arc::new(parser_customiser));
+        self
+    }
+
+    /// Add a function generating pages which are not read from the site's files, such as index pages or pages of content from elsewhere.
+    ///
+    /// # Arguments
+    ///
+    /// * `page_generator` - A function given the pages read from the site's files, with the site's locale and timezone, returning the pages to add.
+    ///
+    /// # Returns
+    ///
+    /// The provider, with the function added after any previously added.
+    pub fn with_page_generator(
+        mut self,
+        page_generator: impl Fn(&[&Page], Locale, Tz) -> miette::Result<Vec<Page>>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        self.page_generators.push(Arc::new(page_generator));
+        self
+    }
+
+    /// Add a function transforming the URL of every page, such as to enforce a convention for URLs.
+    ///
+    /// # Arguments
+    ///
+    /// * `url_transformer` - A function given a page and its URL, returning the URL to use; it may be given a URL it has already transformed, which it should leave as it is.
+    ///
+    /// # Returns
+    ///
+    /// The provider, with the function added after any previously added.
+    pub fn with_url_transformer(
+        mut self,
+        url_transformer: impl Fn(&Page, String) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.url_transformers.push(Arc::new(url_transformer));
+        self
+    }
+}
@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+#[cfg(not(target_arch = "wasm32"))]
+use ticky::Stopwatch;
+
+#[derive(PartialEq, Clone, Debug, Default, Serialize, Deserialize)]
+/// The time taken by a stage of a build.
+pub struct StageSummary {
+    /// The name of the stage, such as `render`.
+    pub name: String,
+    /// The time taken by the stage, in seconds.
+    pub seconds: f64,
+}
+
+#[derive(PartialEq, Clone, Debug, Default, Serialize, Deserialize)]
+/// A summary of a build or rebuild of a site, for use by tools wrapping Vox.
+pub struct BuildSummary {
+    /// The number of pages rendered.
+    pub pages_rendered: usize,
+    /// The number of files written, being pages and assets.
+    /// Pages output through several layouts are only counted once.
+    pub files_written: usize,
+    /// The number of files removed, being the output of removed pages and, if removed, orphaned output.
+    pub files_removed: usize,
+    /// The number of rendered pages which were not written, as they have no URL.
+    pub files_skipped: usize,
+    /// The time taken by each stage of the build, in the order they were performed.
+    pub stages: Vec<StageSummary>,
+    /// The time taken by all stages of the build, in seconds.
+    pub seconds: f64,
+}
+
+impl BuildSummary {
+    /// Record the time taken by a stage of a build.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the stage.
+    ///
+    /// * `timer` - The timer started when the stage began.
+    pub fn record(&mut self, name: &str, timer: StageTimer) {
+        let seconds = timer.elapsed();
+        self.seconds += seconds;
+        self.stages.push(StageSummary {
+            name: name.to_string(),
+            seconds,
+        });
+    }
+}
+
+/// Measures the time taken by a stage of a build.
+/// Time is unavailable when compiled to WebAssembly, so stages are measured as taking no time.
+pub struct StageTimer {
+    /// The time since the stage began.
+    #[cfg(not(target_arch = "wasm32"))]
+    timer: Stopwatch,
+}
+
+impl StageTimer {
+    /// Begin measuring the time taken by a stage of a build.
+    ///
+    /// # Returns
+    ///
+    /// A timer, started at the beginning of the stage.
+    pub fn start() -> Self {
+        Self {
+            #[cfg(not(target_arch = "wasm32"))]
+            timer: Stopwatch::start_new(),
+        }
+    }
+
+    /// Get the time since the stage began.
+    ///
+    /// # Returns
+    ///
+    /// The time since the stage began, in seconds.
+    pub fn elapsed(&self) -> f64 {
+        #[cfg(not(target_arch = "wasm32"))]
+        return self.timer.elapsed().as_secs_f64();
+        #[cfg(target_arch = "wasm32")]
+        0.0
+    }
+}
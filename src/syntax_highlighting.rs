@@ -0,0 +1,168 @@
+use comrak::plugins::syntect::{SyntectAdapter, SyntectAdapterBuilder};
+use liquid_core::Result;
+use liquid_core::Runtime;
+use liquid_core::ValueView;
+use serde::{Deserialize, Serialize};
+use syntect::highlighting::ThemeSet;
+use syntect::html::{css_for_theme_with_class_style, ClassStyle};
+
+#[derive(PartialEq, Eq, Clone, Copy, Default, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// How highlighted code is styled.
+pub enum HighlightingMode {
+    /// Code is marked up with classes, styled by the stylesheets output with the `-s` option.
+    #[default]
+    Classes,
+    /// Code is styled inline with a single theme, and no stylesheets are output.
+    Inline,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+/// Settings for highlighting code within Markdown, defined by the `syntax_highlighting` table in `global.toml`.
+pub struct SyntaxHighlightingSettings {
+    /// How highlighted code is styled.
+    #[serde(default)]
+    pub mode: HighlightingMode,
+    /// The theme of code styled inline.
+    #[serde(default = "default_dark_theme")]
+    pub theme: String,
+    /// The theme of the stylesheet used when the reader prefers a light colour scheme.
+    #[serde(default = "default_light_theme")]
+    pub light_theme: String,
+    /// The theme of the stylesheet used when the reader prefers a dark colour scheme.
+    #[serde(default = "default_dark_theme")]
+    pub dark_theme: String,
+}
+
+/// By default, code is highlighted with the light variant of the Base16 Ocean theme when a light theme is needed.
+fn default_light_theme() -> String {
+    "base16-ocean.light".to_string()
+}
+
+/// By default, code is highlighted with the dark variant of the Base16 Ocean theme when a dark theme is needed.
+fn default_dark_theme() -> String {
+    "base16-ocean.dark".to_string()
+}
+
+impl Default for SyntaxHighlightingSettings {
+    fn default() -> Self {
+        Self {
+            mode: HighlightingMode::default(),
+            theme: default_dark_theme(),
+            light_theme: default_light_theme(),
+            dark_theme: default_dark_theme(),
+        }
+    }
+}
+
+impl SyntaxHighlightingSettings {
+    /// Read the settings from the `global` context.
+    ///
+    /// # Arguments
+    ///
+    /// * `global` - The `global` context, if any.
+    ///
+    /// # Returns
+    ///
+    /// The settings in the `syntax_highlighting` table of the `global` context, or the default settings if there is no such table.
+    pub fn from_global(global: Option<&dyn ValueView>) -> Result<Self> {
+        let Some(settings) = global
+            .and_then(|x| x.as_object())
+            .and_then(|x| x.get("syntax_highlighting"))
+        else {
+            return Ok(Self::default());
+        };
+        serde_json::to_value(settings.to_value())
+            .map_err(|err| err.to_string())
+            .and_then(|x| serde_json::from_value::<Self>(x).map_err(|err| err.to_string()))
+            .and_then(|x| x.check_themes(&ThemeSet::load_defaults()).map(|_| x))
+            .map_err(|err| {
+                liquid::Error::with_msg(format!("Invalid `syntax_highlighting` table: {}", err))
+            })
+    }
+
+    /// Read the settings from the `global` context of a Liquid runtime.
+    ///
+    /// # Arguments
+    ///
+    /// * `runtime` - The Liquid runtime.
+    ///
+    /// # Returns
+    ///
+    /// The settings in the `syntax_highlighting` table of the `global` context, or the default settings if there is no such table.
+    pub fn from_runtime(runtime: &dyn Runtime) -> Result<Self> {
+        let global = runtime.get(&["global".into()]).ok();
+        Self::from_global(global.as_ref().map(|x| x.as_view()))
+    }
+
+    /// Check that the themes used by these settings exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `themes` - The available themes.
+    ///
+    /// # Returns
+    ///
+    /// A description of the problem if a theme does not exist.
+    pub fn check_themes(&self, themes: &ThemeSet) -> std::result::Result<(), String> {
+        let used_themes = match self.mode {
+            HighlightingMode::Classes => vec![&self.light_theme, &self.dark_theme],
+            HighlightingMode::Inline => vec![&self.theme],
+        };
+        match used_themes
+            .into_iter()
+            .find(|x| !themes.themes.contains_key(x.as_str()))
+        {
+            Some(theme) => Err(format!(
+                "there is no theme named `{}`; the available themes are: {}.",
+                theme,
+                themes
+                    .themes
+                    .keys()
+                    .map(|x| format!("`{}`", x))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Create the adapter highlighting code when rendering Markdown.
+    ///
+    /// # Returns
+    ///
+    /// A syntax highlighter using classes or inline styles, per the highlighting mode.
+    pub fn adapter(&self) -> SyntectAdapter {
+        match self.mode {
+            HighlightingMode::Classes => SyntectAdapter::new(None),
+            HighlightingMode::Inline => SyntectAdapterBuilder::new().theme(&self.theme).build(),
+        }
+    }
+
+    /// Create the stylesheets for highlighted code.
+    /// `code.css` imports `light-code.css` or `dark-code.css`, depending on the reader's preferred colour scheme.
+    ///
+    /// # Returns
+    ///
+    /// The path of each stylesheet relative to the output directory, with its contents; there are none when code is styled inline.
+    pub fn stylesheets(&self) -> miette::Result<Vec<(&'static str, String)>> {
+        if self.mode == HighlightingMode::Inline {
+            return Ok(Vec::new());
+        }
+        let ts = ThemeSet::load_defaults();
+        self.check_themes(&ts)
+            .map_err(|err| miette::miette!("Invalid `syntax_highlighting` table: {}", err))?;
+        let css_dark =
+            css_for_theme_with_class_style(&ts.themes[&self.dark_theme], ClassStyle::Spaced)
+                .map_err(|err| miette::miette!("{}", err))?;
+        let css_light =
+            css_for_theme_with_class_style(&ts.themes[&self.light_theme], ClassStyle::Spaced)
+                .map_err(|err| miette::miette!("{}", err))?;
+        let css = r#"@import url("light-code.css") (prefers-color-scheme: light);@import url("dark-code.css") (prefers-color-scheme: dark);"#;
+        Ok(vec![
+            ("css/dark-code.css", css_dark),
+            ("css/light-code.css", css_light),
+            ("css/code.css", css.to_string()),
+        ])
+    }
+}
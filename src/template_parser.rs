@@ -0,0 +1,108 @@
+use crate::changes::Change;
+use crate::load_data_tag::FetchedData;
+use crate::plugins::PluginSettings;
+use crate::provider::VoxProvider;
+use chrono::Locale;
+use std::sync::{Arc, Mutex};
+use tracing::debug;
+
+/// A Liquid parser, with the locale & plugins it was created with.
+type CreatedParser = (liquid::Parser, Locale, Vec<PluginSettings>);
+
+#[derive(Clone, Default)]
+/// The Liquid parser of a site, kept between builds so that it is only created again when the site's snippets, locale, or plugins change.
+/// Clones of the parser share it, so that a change seen by one is seen by all.
+pub struct TemplateParser {
+    /// The parser, with the locale & plugins it was created with, if it has been created.
+    state: Arc<Mutex<Option<CreatedParser>>>,
+    /// Data fetched from URLs by the parser during the current build.
+    fetched: FetchedData,
+}
+
+impl std::fmt::Debug for TemplateParser {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TemplateParser")
+            .field(
+                "locale",
+                &self
+                    .state
+                    .try_lock()
+                    .ok()
+                    .and_then(|x| x.as_ref().map(|(_, locale, _)| *locale)),
+            )
+            .finish()
+    }
+}
+
+impl TemplateParser {
+    /// Create a parser which has not yet been created from a site.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the parser, creating it if it has not been created since it was last discarded.
+    ///
+    /// # Arguments
+    ///
+    /// * `provider` - The provider the site is read from.
+    ///
+    /// # Returns
+    ///
+    /// The site's Liquid parser.
+    pub fn get(&self, provider: &(impl VoxProvider + ?Sized)) -> miette::Result<liquid::Parser> {
+        let mut state = self.state.lock().map_err(|e| miette::miette!("{}", e))?;
+        if let Some((parser, _, _)) = state.as_ref() {
+            return Ok(parser.clone());
+        }
+        debug!("Creating the Liquid parser … ");
+        let (locale, _) = provider.get_locale_and_timezone()?;
+        let plugins = provider.get_plugins()?;
+        let parser = provider.create_liquid_parser_with_data(self.fetched.clone())?;
+        *state = Some((parser.clone(), locale, plugins));
+        Ok(parser)
+    }
+
+    /// Prepare the parser for a build, discarding the data it fetched from URLs during the previous build.
+    ///
+    /// # Arguments
+    ///
+    /// * `offline` - Whether to refuse to fetch data from URLs during the build.
+    pub fn begin_build(&self, offline: bool) -> miette::Result<()> {
+        self.fetched.begin_build(offline)
+    }
+
+    /// Discard the parser, so that it is created again when next needed.
+    pub fn invalidate(&self) -> miette::Result<()> {
+        *self.state.lock().map_err(|e| miette::miette!("{}", e))? = None;
+        Ok(())
+    }
+
+    /// Discard the parser if a change to the site affects it.
+    /// Snippets are compiled into the parser, the parser formats dates with the site's locale, and plugins may define filters & tags; no other change affects it.
+    ///
+    /// # Arguments
+    ///
+    /// * `provider` - The provider the site is read from.
+    ///
+    /// * `change` - The change to the site.
+    pub fn update(
+        &self,
+        provider: &(impl VoxProvider + ?Sized),
+        change: &Change,
+    ) -> miette::Result<()> {
+        if change.snippets_changed() || change.wasm_changed() {
+            return self.invalidate();
+        }
+        if !change.global_changed() {
+            return Ok(());
+        }
+        let mut state = self.state.lock().map_err(|e| miette::miette!("{}", e))?;
+        if let Some((_, locale, plugins)) = state.as_ref() {
+            let (new_locale, _) = provider.get_locale_and_timezone()?;
+            if *locale != new_locale || *plugins != provider.get_plugins()? {
+                *state = None;
+            }
+        }
+        Ok(())
+    }
+}
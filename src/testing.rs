@@ -0,0 +1,320 @@
+use crate::provider::InputProvider;
+use crate::ram_provider::RamProvider;
+use crate::site::{BuildOptions, Site};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+pub use insta;
+
+/// Assert that an output file of a test build matches its stored snapshot.
+/// Snapshots are stored alongside the calling test, and are reviewed with `cargo insta review`.
+///
+/// # Arguments
+///
+/// * `build` - The test build.
+///
+/// * `path` - The path of the output file, relative to the output directory (eg: `index.html`).
+#[macro_export]
+macro_rules! assert_output_snapshot {
+    ($build:expr, $path:expr) => {{
+        let path: &str = $path;
+        let output = $build.expect_output(path).to_string();
+        let name = path.replace(|c: char| !c.is_alphanumeric(), "_");
+        $crate::testing::insta::assert_snapshot!(name, output);
+    }};
+}
+
+#[derive(Clone, Debug, Default)]
+/// A site declared in memory, for testing builds.
+///
+/// ```
+/// use vox::assert_output_snapshot;
+/// use vox::testing::TestSite;
+///
+/// let build = TestSite::new()
+///     .global("title = \"Example\"")
+///     .layout("default", "---\n---\n<h1>{{ global.title }}</h1>{{ page.rendered }}")
+///     .page(
+///         "index.vox",
+///         "---\nlayout = \"default\"\npermalink = \"index.html\"\n---\nHello, world!",
+///     )
+///     .build()
+///     .unwrap();
+/// build.assert_output_contains("index.html", "Hello, world!");
+/// assert_output_snapshot!(build, "index.html");
+/// ```
+pub struct TestSite {
+    files: HashMap<PathBuf, String>,
+    options: BuildOptions,
+}
+
+impl TestSite {
+    /// Declare an empty site.
+    ///
+    /// # Returns
+    ///
+    /// A site with no files.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a file to the site.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the file, relative to the site's directory.
+    ///
+    /// * `contents` - The contents of the file.
+    ///
+    /// # Returns
+    ///
+    /// The site, with the file added.
+    pub fn file(mut self, path: impl Into<PathBuf>, contents: impl Into<String>) -> Self {
+        self.files.insert(path.into(), contents.into());
+        self
+    }
+
+    /// Set the site's `global.toml` file.
+    ///
+    /// # Arguments
+    ///
+    /// * `contents` - The contents of `global.toml`.
+    ///
+    /// # Returns
+    ///
+    /// The site, with the global context set.
+    pub fn global(self, contents: impl Into<String>) -> Self {
+        self.file("global.toml", contents)
+    }
+
+    /// Add a page to the site.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the page, relative to the site's directory (eg: `blog/hello.vox`).
+    ///
+    /// * `contents` - The page's frontmatter and body.
+    ///
+    /// # Returns
+    ///
+    /// The site, with the page added.
+    pub fn page(self, path: impl Into<PathBuf>, contents: impl Into<String>) -> Self {
+        self.file(path, contents)
+    }
+
+    /// Add a layout to the site.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the layout, as pages refer to it (eg: `default`).
+    ///
+    /// * `contents` - The layout's frontmatter and body.
+    ///
+    /// # Returns
+    ///
+    /// The site, with the layout added.
+    pub fn layout(self, name: &str, contents: impl Into<String>) -> Self {
+        self.file(format!("layouts/{}.vox", name), contents)
+    }
+
+    /// Add a snippet to the site.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The filename of the snippet, as pages include it (eg: `image.voxs`).
+    ///
+    /// * `contents` - The snippet's contents.
+    ///
+    /// # Returns
+    ///
+    /// The site, with the snippet added.
+    pub fn snippet(self, name: &str, contents: impl Into<String>) -> Self {
+        self.file(format!("snippets/{}", name), contents)
+    }
+
+    /// Set the options controlling what is output when building the site.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - The build options.
+    ///
+    /// # Returns
+    ///
+    /// The site, with the given build options.
+    pub fn with_options(mut self, options: BuildOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Create a provider holding the site's files in memory.
+    ///
+    /// # Returns
+    ///
+    /// A provider whose site is made up of the declared files.
+    pub fn provider(&self) -> RamProvider {
+        RamProvider::new(Some(self.files.clone()))
+    }
+
+    /// Build the site.
+    ///
+    /// # Returns
+    ///
+    /// The built site and its output.
+    pub fn build(&self) -> miette::Result<TestBuild> {
+        TestBuild::new(Site::new(self.provider()).with_options(self.options))
+    }
+}
+
+/// The output of building a site declared in memory.
+pub struct TestBuild {
+    site: Site<RamProvider>,
+    outputs: BTreeMap<PathBuf, String>,
+}
+
+impl TestBuild {
+    /// Build a site held in memory.
+    ///
+    /// # Arguments
+    ///
+    /// * `site` - The site to build.
+    ///
+    /// # Returns
+    ///
+    /// The built site and its output.
+    pub fn new(mut site: Site<RamProvider>) -> miette::Result<Self> {
+        site.build()?;
+        let mut outputs = BTreeMap::new();
+        for (path, file) in site.provider().snapshot()?.files() {
+            if let Ok(output_path) = path.strip_prefix("output") {
+                outputs.insert(
+                    output_path.to_path_buf(),
+                    String::from_utf8_lossy(&file.contents).to_string(),
+                );
+            }
+        }
+        Ok(Self { site, outputs })
+    }
+
+    /// Get the built site.
+    ///
+    /// # Returns
+    ///
+    /// The site, including its DAG and provider.
+    pub fn site(&self) -> &Site<RamProvider> {
+        &self.site
+    }
+
+    /// List the files output by the build.
+    ///
+    /// # Returns
+    ///
+    /// The paths of the output files, relative to the output directory, in order.
+    pub fn output_paths(&self) -> Vec<&Path> {
+        self.outputs.keys().map(|x| x.as_path()).collect()
+    }
+
+    /// Get the contents of an output file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the output file, relative to the output directory (eg: `index.html`).
+    ///
+    /// # Returns
+    ///
+    /// The contents of the file, or `None` if it was not output.
+    pub fn output(&self, path: impl AsRef<Path>) -> Option<&str> {
+        self.outputs.get(path.as_ref()).map(|x| x.as_str())
+    }
+
+    /// Get the contents of an output file, panicking if it was not output.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the output file, relative to the output directory (eg: `index.html`).
+    ///
+    /// # Returns
+    ///
+    /// The contents of the file.
+    pub fn expect_output(&self, path: impl AsRef<Path>) -> &str {
+        let path = path.as_ref();
+        self.output(path).unwrap_or_else(|| {
+            panic!(
+                "`{}` was not output; the output files are: {:#?}",
+                path.to_string_lossy(),
+                self.output_paths()
+            )
+        })
+    }
+
+    /// Assert that a file was output.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the output file, relative to the output directory (eg: `index.html`).
+    pub fn assert_output_exists(&self, path: impl AsRef<Path>) {
+        self.expect_output(path);
+    }
+
+    /// Assert that a file was not output.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file, relative to the output directory (eg: `index.html`).
+    pub fn assert_no_output(&self, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+        assert!(
+            self.output(path).is_none(),
+            "`{}` was output, but should not have been",
+            path.to_string_lossy()
+        );
+    }
+
+    /// Assert that an output file has exactly the expected contents, ignoring surrounding whitespace.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the output file, relative to the output directory (eg: `index.html`).
+    ///
+    /// * `expected` - The expected contents.
+    pub fn assert_output_eq(&self, path: impl AsRef<Path>, expected: &str) {
+        let path = path.as_ref();
+        assert_eq!(
+            self.expect_output(path).trim(),
+            expected.trim(),
+            "`{}` does not have the expected contents",
+            path.to_string_lossy()
+        );
+    }
+
+    /// Assert that an output file contains the expected text.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the output file, relative to the output directory (eg: `index.html`).
+    ///
+    /// * `expected` - The text expected within the file.
+    pub fn assert_output_contains(&self, path: impl AsRef<Path>, expected: &str) {
+        let path = path.as_ref();
+        let output = self.expect_output(path);
+        assert!(
+            output.contains(expected),
+            "`{}` does not contain {:?}; its contents are:\n{}",
+            path.to_string_lossy(),
+            expected,
+            output
+        );
+    }
+
+    /// Read a file from the built site, including its sources.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the file, relative to the site's directory.
+    ///
+    /// # Returns
+    ///
+    /// The file's contents.
+    pub fn read(&self, path: impl AsRef<Path>) -> miette::Result<String> {
+        self.site.provider().read_to_string(path)
+    }
+}
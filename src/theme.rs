@@ -0,0 +1,288 @@
+#[cfg(feature = "fs_provider")]
+use crate::fs_provider::FsProvider;
+#[cfg(feature = "fs_provider")]
+use crate::overlay_provider::OverlayProvider;
+use crate::provider::InputProvider;
+#[cfg(feature = "fs_provider")]
+use crate::split_provider::SplitProvider;
+use miette::IntoDiagnostic;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "fs_provider")]
+use std::path::{Path, PathBuf};
+#[cfg(feature = "fs_provider")]
+use std::process::Command;
+use toml::Table;
+#[cfg(feature = "fs_provider")]
+use tracing::info;
+
+/// The directory themes fetched from Git repositories are kept in, relative to the site's directory.
+pub const THEME_CACHE_DIRECTORY: &str = ".vox/themes";
+
+/// The directory installed themes are kept in, relative to the site's directory.
+pub const THEMES_DIRECTORY: &str = "themes";
+
+/// The file recording the revisions of installed themes, relative to the site's directory.
+pub const LOCKFILE: &str = "themes.lock";
+
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+/// A theme installed into a site.
+pub struct LockedTheme {
+    /// The name of the theme, being the name of its folder within the `themes` folder.
+    pub name: String,
+    /// The URL of the theme's Git repository.
+    pub url: String,
+    /// The hash of the commit the theme was installed from.
+    pub revision: String,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug, Default, Serialize, Deserialize)]
+/// A record of the themes installed into a site, kept in `themes.lock`.
+pub struct ThemeLock {
+    /// The installed themes, in order of name.
+    #[serde(default)]
+    pub themes: Vec<LockedTheme>,
+}
+
+impl ThemeLock {
+    /// Read the record of a site's installed themes.
+    ///
+    /// # Arguments
+    ///
+    /// * `provider` - The provider the site is read from.
+    ///
+    /// # Returns
+    ///
+    /// The record of installed themes, or an empty record if no themes are installed.
+    pub fn read(provider: &(impl InputProvider + ?Sized)) -> miette::Result<Self> {
+        match provider.read_to_string(LOCKFILE) {
+            Ok(lockfile) => toml::from_str(&lockfile).into_diagnostic(),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    /// Write the record of installed themes as TOML.
+    ///
+    /// # Returns
+    ///
+    /// The contents of `themes.lock`.
+    pub fn to_toml(&self) -> miette::Result<String> {
+        toml::to_string_pretty(self).into_diagnostic()
+    }
+
+    /// Find an installed theme.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the theme.
+    ///
+    /// # Returns
+    ///
+    /// The installed theme, or `None` if no theme of that name is installed.
+    pub fn get(&self, name: &str) -> Option<&LockedTheme> {
+        self.themes.iter().find(|theme| theme.name == name)
+    }
+
+    /// Record an installed theme, replacing any theme of the same name.
+    ///
+    /// # Arguments
+    ///
+    /// * `theme` - The installed theme.
+    pub fn insert(&mut self, theme: LockedTheme) {
+        self.themes.retain(|x| x.name != theme.name);
+        self.themes.push(theme);
+        self.themes.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+}
+
+/// Name a theme after its Git repository.
+///
+/// # Arguments
+///
+/// * `url` - The URL of the theme's Git repository.
+///
+/// # Returns
+///
+/// The last component of the URL, without any `.git` extension.
+pub fn theme_name(url: &str) -> String {
+    let url = url.trim_end_matches('/');
+    let name = url.rsplit(['/', ':']).next().unwrap_or(url);
+    name.strip_suffix(".git").unwrap_or(name).to_string()
+}
+
+#[cfg(feature = "fs_provider")]
+/// A provider reading a site from the file system above its theme, and writing the site's output to the file system.
+pub type ThemedProvider = SplitProvider<OverlayProvider<FsProvider, FsProvider>, FsProvider>;
+
+/// Determine if a theme refers to a Git repository, rather than a directory.
+///
+/// # Arguments
+///
+/// * `theme` - The `theme` setting of a site.
+///
+/// # Returns
+///
+/// Whether or not the theme is the URL of a Git repository.
+pub fn is_git_url(theme: &str) -> bool {
+    ["http://", "https://", "ssh://", "git://", "git@"]
+        .iter()
+        .any(|prefix| theme.starts_with(prefix))
+        || theme.ends_with(".git")
+}
+
+/// Get a site's theme.
+/// The theme is defined by the `theme` field in `global.toml`.
+///
+/// # Arguments
+///
+/// * `provider` - The provider the site is read from.
+///
+/// # Returns
+///
+/// The path to the theme's directory relative to the site's directory, or the URL of its Git repository; `None` if the site has no theme.
+pub fn get_theme(provider: &impl InputProvider) -> miette::Result<Option<String>> {
+    let Ok(global_file) = provider.read_to_string("global.toml") else {
+        return Ok(None);
+    };
+    match global_file.parse::<Table>().into_diagnostic()?.get("theme") {
+        Some(theme) => Ok(Some(theme.clone().try_into().into_diagnostic()?)),
+        None => Ok(None),
+    }
+}
+
+/// Find the directory of a site's theme, cloning its Git repository if it has not been cloned.
+/// A theme named neither by a path nor a URL is the installed theme of that name.
+///
+/// # Arguments
+///
+/// * `provider` - The provider the site is read from.
+///
+/// # Returns
+///
+/// The path to the theme's directory, or `None` if the site has no theme.
+#[cfg(feature = "fs_provider")]
+pub fn theme_directory(provider: &FsProvider) -> miette::Result<Option<PathBuf>> {
+    let Some(theme) = get_theme(provider)? else {
+        return Ok(None);
+    };
+    if !is_git_url(&theme) {
+        let directory = provider.root().join(&theme);
+        let installed = provider.root().join(THEMES_DIRECTORY).join(&theme);
+        if !directory.is_dir() && installed.is_dir() {
+            return Ok(Some(installed));
+        }
+        if !directory.is_dir() {
+            return Err(miette::miette!(
+                "The theme `{}` is not a directory, nor the URL of a Git repository.",
+                theme
+            ));
+        }
+        return Ok(Some(directory));
+    }
+    // Each repository is cloned into a directory named after its URL, so changing the URL clones the new repository.
+    let directory = provider
+        .root()
+        .join(THEME_CACHE_DIRECTORY)
+        .join(&blake3::hash(theme.as_bytes()).to_hex()[..16]);
+    if !directory.is_dir() {
+        info!("Cloning the theme `{}` … ", theme);
+        clone(&theme, &directory)?;
+    }
+    Ok(Some(directory))
+}
+
+/// Layer a site above its theme.
+///
+/// # Arguments
+///
+/// * `provider` - The provider the site is read from & written to.
+///
+/// # Returns
+///
+/// A provider reading the site above its theme, or `None` if the site has no theme.
+#[cfg(feature = "fs_provider")]
+pub fn themed_provider(provider: FsProvider) -> miette::Result<Option<ThemedProvider>> {
+    let Some(directory) = theme_directory(&provider)? else {
+        return Ok(None);
+    };
+    let theme = FsProvider::new().with_root(directory);
+    Ok(Some(SplitProvider::new(
+        OverlayProvider::new(provider.clone(), theme),
+        provider,
+    )))
+}
+
+/// Clone the latest commit of a Git repository.
+///
+/// # Arguments
+///
+/// * `url` - The URL of the repository.
+///
+/// * `directory` - The directory to clone the repository into.
+#[cfg(feature = "fs_provider")]
+fn clone(url: &str, directory: &Path) -> miette::Result<()> {
+    let status = Command::new("git")
+        .args(["clone", "--quiet", "--depth", "1", url])
+        .arg(directory)
+        .status()
+        .into_diagnostic()?;
+    if !status.success() {
+        std::fs::remove_dir_all(directory).ok();
+        return Err(miette::miette!(
+            "Cloning the theme `{}` failed: git exited with {}.",
+            url,
+            status
+        ));
+    }
+    Ok(())
+}
+
+/// Install the latest version of a theme into a site's `themes` folder, replacing any version already installed.
+/// The theme is copied without its Git history, so that it can be committed along with the site.
+///
+/// # Arguments
+///
+/// * `provider` - The provider the site is read from.
+///
+/// * `name` - The name of the theme.
+///
+/// * `url` - The URL of the theme's Git repository.
+///
+/// # Returns
+///
+/// The installed theme, to be recorded in `themes.lock`.
+#[cfg(feature = "fs_provider")]
+pub fn install_theme(provider: &FsProvider, name: &str, url: &str) -> miette::Result<LockedTheme> {
+    let fetching = provider
+        .root()
+        .join(THEME_CACHE_DIRECTORY)
+        .join(format!("installing-{}", name));
+    if fetching.exists() {
+        std::fs::remove_dir_all(&fetching).into_diagnostic()?;
+    }
+    info!("Cloning the theme `{}` … ", url);
+    clone(url, &fetching)?;
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(&fetching)
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .into_diagnostic()?;
+    if !output.status.success() {
+        return Err(miette::miette!(
+            "Unable to determine the revision of the theme `{}`.",
+            url
+        ));
+    }
+    std::fs::remove_dir_all(fetching.join(".git")).into_diagnostic()?;
+    let destination = provider.root().join(THEMES_DIRECTORY).join(name);
+    if destination.exists() {
+        std::fs::remove_dir_all(&destination).into_diagnostic()?;
+    }
+    std::fs::create_dir_all(provider.root().join(THEMES_DIRECTORY)).into_diagnostic()?;
+    std::fs::rename(&fetching, &destination).into_diagnostic()?;
+    Ok(LockedTheme {
+        name: name.to_string(),
+        url: url.to_string(),
+        revision: String::from_utf8_lossy(&output.stdout).trim().to_string(),
+    })
+}
@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(PartialEq, Eq, Clone, Copy, Default, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// How pages sharing a URL are handled, defined by `url_conflicts` in `global.toml`.
+/// Among pages sharing a URL, the page whose path comes first keeps it.
+pub enum UrlConflictStrategy {
+    /// The build fails.
+    #[default]
+    Error,
+    /// Only the first page is output, with a warning for each of the others.
+    First,
+    /// The URLs of the other pages are suffixed with a number, with a warning for each of them.
+    Suffix,
+}
+
+/// Suffix a URL with a number, so that it no longer conflicts with another.
+/// Pages output as `index.html` have their directory suffixed instead, so that their URLs stay pretty.
+///
+/// # Arguments
+///
+/// * `url` - The URL of a page, relative to the output directory.
+///
+/// * `number` - The number to suffix the URL with.
+///
+/// # Returns
+///
+/// The suffixed URL; for example, `blog/post.html` becomes `blog/post-2.html`, `blog/post/index.html` becomes `blog/post-2/index.html`, and `blog/post/` becomes `blog/post-2/`.
+pub fn suffix_url(url: &str, number: usize) -> String {
+    if let Some(directory) = url.strip_suffix('/') {
+        return match directory.trim_start_matches('/').is_empty() {
+            true => format!("{}index-{}.html", directory, number),
+            false => format!("{}-{}/", directory, number),
+        };
+    }
+    let (directory, file) = match url.rsplit_once('/') {
+        Some((directory, file)) => (Some(directory), file),
+        None => (None, url),
+    };
+    match directory {
+        Some(directory) if file == "index.html" && !directory.is_empty() => {
+            format!("{}-{}/{}", directory, number, file)
+        }
+        _ => {
+            let file = match file.rsplit_once('.') {
+                Some((stem, extension)) if !stem.is_empty() => {
+                    format!("{}-{}.{}", stem, number, extension)
+                }
+                _ => format!("{}-{}", file, number),
+            };
+            match directory {
+                Some(directory) => format!("{}/{}", directory, file),
+                None => file,
+            }
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use crate::paths::url_output_path;
+use liquid_core::ValueView;
+use serde::{Deserialize, Serialize};
+
+#[derive(PartialEq, Eq, Clone, Copy, Default, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// How the URLs of pages output as HTML are written, defined by `url_style` in `global.toml`.
+pub enum UrlStyle {
+    /// URLs are as given by permalinks.
+    #[default]
+    Permalink,
+    /// Pages are output as the `index.html` file of a directory named after them, and their URLs end with a slash; `blog/post.html` becomes `blog/post/`.
+    Pretty,
+    /// Pages are output as HTML files named after them, and their URLs end with `.html`; `blog/post/index.html` and `blog/post/` become `blog/post.html`.
+    Html,
+}
+
+impl UrlStyle {
+    /// Read the URL style from the `global` context.
+    ///
+    /// # Arguments
+    ///
+    /// * `global` - The `global` context, if any.
+    ///
+    /// # Returns
+    ///
+    /// The `url_style` of the `global` context, or the default style if it has none.
+    pub fn from_global(global: Option<&dyn ValueView>) -> liquid_core::Result<Self> {
+        let Some(style) = global
+            .and_then(|x| x.as_object())
+            .and_then(|x| x.get("url_style"))
+        else {
+            return Ok(Self::default());
+        };
+        serde_json::to_value(style.to_value())
+            .and_then(serde_json::from_value)
+            .map_err(|err| liquid::Error::with_msg(format!("Invalid `url_style`: {}", err)))
+    }
+
+    /// Write a URL in this style.
+    /// URLs of files other than HTML files are left as they are, as is the URL of the site's root.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of a page, as rendered from its permalink.
+    ///
+    /// # Returns
+    ///
+    /// The URL, written in this style.
+    pub fn apply(&self, url: &str) -> String {
+        if url.is_empty() {
+            return String::new();
+        }
+        let root = match url.starts_with('/') {
+            true => "/",
+            false => "",
+        };
+        let output_path = url_output_path(url);
+        let path = output_path.strip_prefix("output/").unwrap_or(&output_path);
+        match self {
+            UrlStyle::Permalink => url.to_string(),
+            UrlStyle::Pretty => {
+                let directory = path
+                    .strip_suffix("index.html")
+                    .filter(|x| x.is_empty() || x.ends_with('/'));
+                match (directory, path.strip_suffix(".html")) {
+                    (Some(""), _) => "/".to_string(),
+                    (Some(directory), _) => format!("{}{}", root, directory),
+                    (None, Some(stem)) => format!("{}{}/", root, stem),
+                    (None, None) => url.to_string(),
+                }
+            }
+            UrlStyle::Html => match path.strip_suffix("/index.html") {
+                Some(directory) => format!("{}{}.html", root, directory),
+                None => format!("{}{}", root, path),
+            },
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use crate::page::Page;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// A function transforming a page's URL once it has been rendered from its permalink.
+/// The function is given the page and its URL, and returns the URL to use.
+/// As a page's URL is rendered more than once during a build, a function may be given a URL it has already transformed, which it should leave as it is.
+pub type UrlTransformer = Arc<dyn Fn(&Page, String) -> String + Send + Sync>;
+
+#[derive(PartialEq, Eq, Clone, Default, Debug, Serialize, Deserialize)]
+/// Transforms applied to the URL of every page, defined by the `url_transforms` table in `global.toml`.
+pub struct UrlTransformSettings {
+    /// Whether or not URLs are made lowercase.
+    #[serde(default)]
+    pub lowercase: bool,
+    /// Whether or not dates at the start of each part of a URL, such as in `blog/2024-05-01-post.html`, are removed.
+    #[serde(default)]
+    pub strip_date_prefixes: bool,
+    /// The text spaces within URLs are replaced with, if they are to be replaced.
+    #[serde(default)]
+    pub replace_spaces: Option<String>,
+}
+
+impl UrlTransformSettings {
+    /// Transform a URL.
+    /// Dates are removed first, then spaces are replaced, then the URL is made lowercase.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of a page.
+    ///
+    /// # Returns
+    ///
+    /// The transformed URL.
+    pub fn apply(&self, url: String) -> String {
+        let mut url = url;
+        if self.strip_date_prefixes {
+            url = url
+                .split('/')
+                .map(strip_date_prefix)
+                .collect::<Vec<_>>()
+                .join("/");
+        }
+        if let Some(replacement) = &self.replace_spaces {
+            url = url.replace(' ', replacement);
+        }
+        if self.lowercase {
+            url = url.to_lowercase();
+        }
+        url
+    }
+}
+
+/// Remove a date from the start of a part of a URL.
+/// Dates are written as `YYYY-MM-DD-`, as in the names of posts in Jekyll sites.
+///
+/// # Arguments
+///
+/// * `segment` - The part of a URL between two slashes.
+///
+/// # Returns
+///
+/// The part of the URL without its date, or as it is if it does not begin with a date or is only a date.
+fn strip_date_prefix(segment: &str) -> &str {
+    let bytes = segment.as_bytes();
+    let is_date = bytes.len() > 11
+        && bytes[..11]
+            .iter()
+            .enumerate()
+            .all(|(position, byte)| match position {
+                4 | 7 | 10 => *byte == b'-',
+                _ => byte.is_ascii_digit(),
+            });
+    match is_date {
+        true => &segment[11..],
+        false => segment,
+    }
+}
+
+#[derive(Clone, Default)]
+/// The transforms applied to the URL of every page; those defined in `global.toml`, followed by any added by the provider.
+pub struct UrlTransforms {
+    /// The transforms defined in `global.toml`.
+    pub settings: UrlTransformSettings,
+    /// The functions transforming URLs, applied in order.
+    pub transformers: Vec<UrlTransformer>,
+}
+
+impl UrlTransforms {
+    /// Transform the URL of a page.
+    ///
+    /// # Arguments
+    ///
+    /// * `page` - The page.
+    ///
+    /// * `url` - The page's URL, as rendered from its permalink.
+    ///
+    /// # Returns
+    ///
+    /// The transformed URL.
+    pub fn apply(&self, page: &Page, url: String) -> String {
+        let mut url = self.settings.apply(url);
+        for transformer in &self.transformers {
+            url = transformer(page, url);
+        }
+        url
+    }
+}
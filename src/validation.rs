@@ -0,0 +1,457 @@
+use crate::builds::EdgeType;
+use crate::error::InvalidHtml;
+use crate::page::Page;
+use crate::paths::url_output_path;
+use crate::provider::VoxProvider;
+use ahash::AHashMap;
+use daggy::stable_dag::StableDag;
+use daggy::{NodeIndex, Walker};
+use html5ever::tendril::StrTendril;
+use html5ever::tokenizer::states::RawKind;
+use html5ever::tokenizer::{
+    BufferQueue, EndTag, ParseError, StartTag, Tag, TagToken, Token, TokenSink, TokenSinkResult,
+    Tokenizer, TokenizerOpts,
+};
+use miette::NamedSource;
+use tracing::{debug, warn};
+
+/// Elements which have no contents, and so are never closed.
+const VOID_ELEMENTS: [&str; 13] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// Elements whose end tags may be omitted.
+const OPTIONAL_END_ELEMENTS: [&str; 19] = [
+    "html", "head", "body", "p", "li", "dt", "dd", "option", "optgroup", "rb", "rt", "rtc", "rp",
+    "tr", "td", "th", "thead", "tbody", "tfoot",
+];
+
+/// Elements which close an open paragraph, as they cannot be within one.
+const PARAGRAPH_CLOSING_ELEMENTS: [&str; 32] = [
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "details",
+    "dialog",
+    "div",
+    "dl",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hgroup",
+    "hr",
+    "main",
+    "menu",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "ul",
+    "search",
+];
+
+/// Interactive elements, which cannot be within one another.
+const INTERACTIVE_ELEMENTS: [&str; 5] = ["a", "button", "input", "select", "textarea"];
+
+/// The elements each element must be directly within.
+const REQUIRED_PARENTS: [(&str, &[&str]); 6] = [
+    ("li", &["ul", "ol", "menu"]),
+    ("dt", &["dl", "div"]),
+    ("dd", &["dl", "div"]),
+    ("tr", &["table", "thead", "tbody", "tfoot"]),
+    ("td", &["tr"]),
+    ("th", &["tr"]),
+];
+
+/// The elements each element closes if they are open, as their end tags may be omitted.
+const IMPLIED_END_TAGS: [(&str, &[&str]); 11] = [
+    ("li", &["li"]),
+    ("dt", &["dt", "dd"]),
+    ("dd", &["dt", "dd"]),
+    ("option", &["option"]),
+    ("optgroup", &["option", "optgroup"]),
+    ("tr", &["td", "th", "tr"]),
+    ("td", &["td", "th"]),
+    ("th", &["td", "th"]),
+    ("thead", &["td", "th", "tr", "thead", "tbody", "tfoot"]),
+    ("tbody", &["td", "th", "tr", "thead", "tbody", "tfoot"]),
+    ("tfoot", &["td", "th", "tr", "thead", "tbody", "tfoot"]),
+];
+
+/// Elements whose contents are not parsed as HTML.
+const RAW_TEXT_ELEMENTS: [(&str, RawKind); 8] = [
+    ("script", RawKind::ScriptData),
+    ("style", RawKind::Rawtext),
+    ("xmp", RawKind::Rawtext),
+    ("iframe", RawKind::Rawtext),
+    ("noembed", RawKind::Rawtext),
+    ("noframes", RawKind::Rawtext),
+    ("title", RawKind::Rcdata),
+    ("textarea", RawKind::Rcdata),
+];
+
+/// Elements containing foreign content, within which tags may close themselves.
+const FOREIGN_ELEMENTS: [&str; 2] = ["svg", "math"];
+
+/// A structural error found in a rendered page.
+pub struct HtmlError {
+    /// The line the error is on, counting from one.
+    pub line: u64,
+    /// The error found.
+    pub problem: String,
+}
+
+/// Receives the tokens of a rendered page, tracking its open elements & identifiers to find structural errors.
+#[derive(Default)]
+struct ValidityChecker {
+    /// The open elements, outermost first, with the lines they were opened on.
+    open: Vec<(String, u64)>,
+    /// The identifiers of elements, with the lines they were first used on.
+    ids: AHashMap<String, u64>,
+    /// The paragraphs closed by elements which cannot be within them, with the closing element and the line it is on.
+    closed_paragraphs: Vec<(String, u64)>,
+    /// The errors found.
+    errors: Vec<HtmlError>,
+}
+
+impl ValidityChecker {
+    /// Record an error.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - The line the error is on.
+    ///
+    /// * `problem` - The error found.
+    fn error(&mut self, line: u64, problem: String) {
+        self.errors.push(HtmlError { line, problem });
+    }
+
+    /// Determine if an element is open.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the element.
+    ///
+    /// # Returns
+    ///
+    /// The position of the innermost open element of that name, if any.
+    fn open_position(&self, name: &str) -> Option<usize> {
+        self.open.iter().rposition(|(open, _)| open == name)
+    }
+
+    /// Close the elements from a position onward, reporting those which must be closed explicitly.
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - The position of the outermost element to close.
+    ///
+    /// * `explicitly_closed` - Whether or not the outermost element was closed by its end tag.
+    fn close_from(&mut self, position: usize, explicitly_closed: bool) {
+        let closed = self.open.split_off(position);
+        for (index, (name, line)) in closed.into_iter().enumerate() {
+            if (index == 0 && explicitly_closed) || OPTIONAL_END_ELEMENTS.contains(&name.as_str()) {
+                continue;
+            }
+            self.error(line, format!("`<{}>` is not closed", name));
+        }
+    }
+
+    /// Process a start tag.
+    ///
+    /// # Arguments
+    ///
+    /// * `tag` - The start tag.
+    ///
+    /// * `line` - The line the tag is on.
+    ///
+    /// # Returns
+    ///
+    /// How the tokenizer should treat the tag's contents.
+    fn start_tag(&mut self, tag: Tag, line: u64) -> TokenSinkResult<()> {
+        let name = tag.name.to_string();
+        if let Some(id) = tag
+            .attrs
+            .iter()
+            .find(|attribute| &*attribute.name.local == "id")
+            .map(|attribute| attribute.value.to_string())
+            .filter(|id| !id.is_empty())
+        {
+            match self.ids.get(&id) {
+                Some(first_line) => {
+                    let problem = format!("The ID `{}` is already used on line {}", id, first_line);
+                    self.error(line, problem);
+                }
+                None => {
+                    self.ids.insert(id, line);
+                }
+            }
+        }
+        let in_foreign_content = self
+            .open
+            .iter()
+            .any(|(open, _)| FOREIGN_ELEMENTS.contains(&open.as_str()));
+        if in_foreign_content {
+            if !tag.self_closing {
+                self.open.push((name, line));
+            }
+            return TokenSinkResult::Continue;
+        }
+
+        if let Some((_, implied)) = IMPLIED_END_TAGS.iter().find(|(x, _)| *x == name) {
+            while self
+                .open
+                .last()
+                .is_some_and(|(open, _)| implied.contains(&open.as_str()))
+            {
+                self.open.pop();
+            }
+        }
+        if PARAGRAPH_CLOSING_ELEMENTS.contains(&name.as_str()) {
+            if let Some(position) = self.open_position("p") {
+                // Closing a paragraph this way is permitted, unless the paragraph's end tag follows.
+                if name != "p" {
+                    self.closed_paragraphs.push((name.clone(), line));
+                }
+                self.close_from(position, true);
+            }
+        }
+        if INTERACTIVE_ELEMENTS.contains(&name.as_str()) {
+            if let Some((outer, _)) = self
+                .open
+                .iter()
+                .rev()
+                .find(|(open, _)| *open == "a" || *open == "button")
+            {
+                let problem = format!("`<{}>` cannot be within `<{}>`", name, outer);
+                self.error(line, problem);
+            }
+        }
+        if name == "form" && self.open_position("form").is_some() {
+            self.error(line, "`<form>` cannot be within `<form>`".to_string());
+        }
+        if let Some((_, parents)) = REQUIRED_PARENTS.iter().find(|(x, _)| *x == name) {
+            let parent = self.open.last().map(|(open, _)| open.as_str());
+            if !parent.is_some_and(|parent| parents.contains(&parent)) {
+                let problem = format!(
+                    "`<{}>` must be within {}",
+                    name,
+                    parents
+                        .iter()
+                        .map(|parent| format!("`<{}>`", parent))
+                        .collect::<Vec<_>>()
+                        .join(" or ")
+                );
+                self.error(line, problem);
+            }
+        }
+
+        if VOID_ELEMENTS.contains(&name.as_str()) {
+            return TokenSinkResult::Continue;
+        }
+        let is_foreign = FOREIGN_ELEMENTS.contains(&name.as_str());
+        if tag.self_closing && is_foreign {
+            return TokenSinkResult::Continue;
+        }
+        // Other elements are opened regardless of a trailing slash.
+        if tag.self_closing {
+            let problem = format!(
+                "`<{} />` does not close itself, as `<{}>` is not a void element",
+                name, name
+            );
+            self.error(line, problem);
+        }
+        let raw_kind = RAW_TEXT_ELEMENTS
+            .iter()
+            .find(|(x, _)| *x == name)
+            .map(|(_, kind)| *kind);
+        self.open.push((name, line));
+        match raw_kind {
+            Some(kind) => TokenSinkResult::RawData(kind),
+            None => TokenSinkResult::Continue,
+        }
+    }
+
+    /// Process an end tag.
+    ///
+    /// # Arguments
+    ///
+    /// * `tag` - The end tag.
+    ///
+    /// * `line` - The line the tag is on.
+    fn end_tag(&mut self, tag: Tag, line: u64) {
+        let name = tag.name.to_string();
+        if let Some(position) = self.open_position(&name) {
+            self.close_from(position, true);
+            return;
+        }
+        // The document's outermost elements are implied if they are not opened.
+        if ["html", "head", "body"].contains(&name.as_str()) {
+            return;
+        }
+        if name == "p" {
+            if let Some((closing, closing_line)) = self.closed_paragraphs.pop() {
+                let problem = format!(
+                    "`<{}>` on line {} cannot be within `<p>`, so the paragraph was closed before it",
+                    closing, closing_line
+                );
+                self.error(line, problem);
+                return;
+            }
+        }
+        self.error(line, format!("`</{}>` closes no open element", name));
+    }
+}
+
+impl TokenSink for ValidityChecker {
+    type Handle = ();
+
+    fn process_token(&mut self, token: Token, line_number: u64) -> TokenSinkResult<()> {
+        match token {
+            TagToken(tag) => match tag.kind {
+                StartTag => return self.start_tag(tag, line_number),
+                EndTag => self.end_tag(tag, line_number),
+            },
+            ParseError(error) => self.error(line_number, format!("Malformed markup: {}", error)),
+            _ => {}
+        }
+        TokenSinkResult::Continue
+    }
+
+    fn end(&mut self) {
+        if !self.open.is_empty() {
+            self.close_from(0, false);
+        }
+    }
+}
+
+/// Parse a rendered page with an HTML5 tokenizer, finding structural errors; unclosed elements, duplicate IDs, and invalid nesting.
+///
+/// # Arguments
+///
+/// * `html` - The rendered page.
+///
+/// # Returns
+///
+/// The errors found, in order of line.
+pub fn validate_html(html: &str) -> Vec<HtmlError> {
+    let mut input = BufferQueue::default();
+    input.push_back(StrTendril::from_slice(html));
+    let mut tokenizer = Tokenizer::new(ValidityChecker::default(), TokenizerOpts::default());
+    let _ = tokenizer.feed(&mut input);
+    tokenizer.end();
+    let mut errors = tokenizer.sink.errors;
+    errors.sort_by_key(|error| error.line);
+    errors
+}
+
+/// Find the source a line of a page's output was rendered from; the page itself, or one of its layouts.
+///
+/// # Arguments
+///
+/// * `line` - The line of output.
+///
+/// * `page_index` - The index of the page in the DAG.
+///
+/// * `dag` - The DAG of the built site.
+///
+/// # Returns
+///
+/// The path of the innermost page or layout whose rendered contents include the line, or of the page if none do.
+/// A line joining a page's contents to those of its layout is considered to be from the page.
+fn line_source(line: &str, page_index: NodeIndex, dag: &StableDag<Page, EdgeType>) -> String {
+    let mut index = page_index;
+    loop {
+        let page = &dag.graph()[index];
+        let rendered = page.rendered.trim();
+        let first_line = rendered.lines().next().unwrap_or_default().trim();
+        let last_line = rendered.lines().last().unwrap_or_default().trim();
+        if rendered.contains(line)
+            || (!first_line.is_empty() && line.contains(first_line))
+            || (!last_line.is_empty() && line.contains(last_line))
+        {
+            return page.to_path_string();
+        }
+        match dag
+            .children(index)
+            .iter(dag)
+            .find(|child| *dag.edge_weight(child.0).unwrap() == EdgeType::Layout)
+        {
+            Some(layout) => index = layout.1,
+            None => return dag.graph()[page_index].to_path_string(),
+        }
+    }
+}
+
+/// Check the HTML of each page output by a build for structural errors, logging the errors found.
+/// Each error names the page or layout that the erroneous markup was rendered from.
+///
+/// # Arguments
+///
+/// * `provider` - The provider the site was built with.
+///
+/// * `dag` - The DAG of the built site.
+///
+/// # Returns
+///
+/// The number of errors found.
+pub fn validate_site_html(provider: &impl VoxProvider, dag: &StableDag<Page, EdgeType>) -> usize {
+    let mut pages = dag
+        .graph()
+        .node_indices()
+        .filter(|index| {
+            let page = &dag.graph()[*index];
+            !page.is_layout && url_output_path(&page.url).ends_with(".html")
+        })
+        .collect::<Vec<_>>();
+    pages.sort_by_key(|index| dag.graph()[*index].to_path_string());
+    let mut errors = 0;
+    for page_index in pages {
+        let page = &dag.graph()[page_index];
+        let output_path = url_output_path(&page.url);
+        debug!("Validating {:?} … ", output_path);
+        let Ok(html) = provider.read_to_string(&output_path) else {
+            continue;
+        };
+        let line_starts = std::iter::once(0)
+            .chain(html.match_indices('\n').map(|(position, _)| position + 1))
+            .collect::<Vec<_>>();
+        for error in validate_html(&html) {
+            errors += 1;
+            let start = line_starts
+                .get(error.line.saturating_sub(1) as usize)
+                .copied()
+                .unwrap_or(html.len());
+            let end = html[start..]
+                .find('\n')
+                .map(|x| x + start)
+                .unwrap_or(html.len());
+            let source = match html[start..end].trim() {
+                "" => page.to_path_string(),
+                line => line_source(line, page_index, dag),
+            };
+            warn!(
+                "{:?}",
+                miette::Report::new(InvalidHtml {
+                    src: NamedSource::new(&output_path, html.clone()),
+                    source_path: source.trim_start_matches('/').to_string(),
+                    problem: error.problem,
+                    span: (start..end).into(),
+                })
+            );
+        }
+    }
+    errors
+}
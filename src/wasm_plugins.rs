@@ -0,0 +1,465 @@
+use crate::error::WasmPluginError;
+use crate::page::Page;
+use crate::paths::to_slash_string;
+use liquid_core::error::ResultLiquidReplaceExt;
+use liquid_core::parser::{FilterArguments, ParameterReflection};
+use liquid_core::Expression;
+use liquid_core::Language;
+use liquid_core::Renderable;
+use liquid_core::Result;
+use liquid_core::Runtime;
+use liquid_core::{Filter, FilterReflection, ParseFilter};
+use liquid_core::{ParseTag, TagReflection, TagTokenIter};
+use liquid_core::{Value, ValueView};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+use wasmi::{Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+/// The prefix of the names of functions a plugin exports as Liquid filters.
+pub const FILTER_PREFIX: &str = "vox_filter_";
+
+/// The prefix of the names of functions a plugin exports as Liquid tags.
+pub const TAG_PREFIX: &str = "vox_tag_";
+
+/// The name of the function a plugin exports to process rendered pages.
+pub const POST_PROCESSOR: &str = "vox_post_process";
+
+/// The name of the function a plugin exports to allocate memory for its input.
+const ALLOCATOR: &str = "vox_alloc";
+
+/// The name of the memory a plugin exports.
+const MEMORY: &str = "memory";
+
+/// The fuel given to each call into a plugin, bounding how long it can run.
+const FUEL: u64 = 1_000_000_000;
+
+/// The most memory, in bytes, a plugin can use in each call.
+const MEMORY_LIMIT: usize = 256 * 1024 * 1024;
+
+#[derive(Clone)]
+/// A plugin compiled to WebAssembly, run in a sandbox.
+/// Plugins are given no imports, and so can only compute; each call is given a fresh instance of the plugin, with limited memory & fuel.
+pub struct WasmPlugin {
+    /// The path to the plugin's module, relative to the site's directory.
+    path: String,
+    /// The hash of the plugin's module.
+    hash: blake3::Hash,
+    /// The engine the plugin's module was compiled with.
+    engine: Engine,
+    /// The plugin's compiled module.
+    module: Arc<Module>,
+}
+
+impl fmt::Debug for WasmPlugin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WasmPlugin")
+            .field("path", &self.path)
+            .field("hash", &self.hash)
+            .finish()
+    }
+}
+
+impl PartialEq for WasmPlugin {
+    fn eq(&self, other: &Self) -> bool {
+        self.path == other.path && self.hash == other.hash
+    }
+}
+
+impl Eq for WasmPlugin {}
+
+impl WasmPlugin {
+    /// Compile a plugin.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the plugin's module, relative to the site's directory.
+    ///
+    /// * `bytes` - The plugin's module.
+    ///
+    /// # Returns
+    ///
+    /// The plugin, or an error if the module is invalid, imports anything, or does not export its memory & allocator.
+    pub fn load(path: impl AsRef<Path>, bytes: &[u8]) -> miette::Result<Self> {
+        let path = to_slash_string(path);
+        let error = |reason: String| WasmPluginError {
+            plugin: path.clone(),
+            reason,
+        };
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, bytes).map_err(|err| error(err.to_string()))?;
+        if let Some(import) = module.imports().next() {
+            return Err(error(format!(
+                "it imports `{}.{}`, but plugins are given no imports",
+                import.module(),
+                import.name()
+            ))
+            .into());
+        }
+        for export in [MEMORY, ALLOCATOR] {
+            if module.get_export(export).is_none() {
+                return Err(error(format!("it does not export `{}`", export)).into());
+            }
+        }
+        Ok(Self {
+            hash: blake3::hash(bytes),
+            path,
+            engine,
+            module: Arc::new(module),
+        })
+    }
+
+    /// The path to the plugin's module, relative to the site's directory.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Get the names given to the functions the plugin exports with a prefix.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - The prefix of the functions' names.
+    ///
+    /// # Returns
+    ///
+    /// The names of the functions, without the prefix.
+    fn exported_names(&self, prefix: &str) -> Vec<String> {
+        self.module
+            .exports()
+            .filter(|x| x.ty().func().is_some())
+            .filter_map(|x| x.name().strip_prefix(prefix))
+            .filter(|x| !x.is_empty())
+            .map(|x| x.to_string())
+            .collect()
+    }
+
+    /// Get the Liquid filters the plugin defines.
+    ///
+    /// # Returns
+    ///
+    /// A filter for each function exported with the filter prefix.
+    pub fn filters(&self) -> Vec<WasmFilter> {
+        self.exported_names(FILTER_PREFIX)
+            .into_iter()
+            .map(|name| WasmFilter::new(self.clone(), name))
+            .collect()
+    }
+
+    /// Get the Liquid tags the plugin defines.
+    ///
+    /// # Returns
+    ///
+    /// A tag for each function exported with the tag prefix.
+    pub fn tags(&self) -> Vec<WasmTag> {
+        self.exported_names(TAG_PREFIX)
+            .into_iter()
+            .map(|name| WasmTag::new(self.clone(), name))
+            .collect()
+    }
+
+    /// Determine if the plugin processes rendered pages.
+    ///
+    /// # Returns
+    ///
+    /// Whether or not the plugin exports a post-processing function.
+    pub fn has_post_processor(&self) -> bool {
+        self.module
+            .get_export(POST_PROCESSOR)
+            .is_some_and(|x| x.func().is_some())
+    }
+
+    /// Call a function exported by the plugin.
+    /// The input is written to memory obtained from the plugin's allocator, and the function is given its address & length.
+    /// The function returns the address of its output in its upper 32 bits, and the output's length in its lower 32 bits.
+    ///
+    /// # Arguments
+    ///
+    /// * `function` - The name of the function.
+    ///
+    /// * `input` - The input to the function.
+    ///
+    /// # Returns
+    ///
+    /// The output of the function, or an error if the plugin failed.
+    fn call(&self, function: &str, input: &[u8]) -> std::result::Result<Vec<u8>, WasmPluginError> {
+        let error = |reason: String| WasmPluginError {
+            plugin: self.path.clone(),
+            reason: format!("`{}` failed, as {}", function, reason),
+        };
+        let limits = StoreLimitsBuilder::new().memory_size(MEMORY_LIMIT).build();
+        let mut store = Store::new(&self.engine, limits);
+        store.limiter(|limits: &mut StoreLimits| limits);
+        store.set_fuel(FUEL).map_err(|err| error(err.to_string()))?;
+        let instance = Linker::new(&self.engine)
+            .instantiate(&mut store, &self.module)
+            .and_then(|x| x.start(&mut store))
+            .map_err(|err| error(err.to_string()))?;
+        let memory = instance
+            .get_memory(&store, MEMORY)
+            .ok_or_else(|| error(format!("it does not export `{}`", MEMORY)))?;
+        let length =
+            i32::try_from(input.len()).map_err(|_| error("its input is too large".to_string()))?;
+        let address = instance
+            .get_typed_func::<i32, i32>(&store, ALLOCATOR)
+            .and_then(|x| x.call(&mut store, length))
+            .map_err(|err| error(err.to_string()))?;
+        memory
+            .write(&mut store, address as u32 as usize, input)
+            .map_err(|err| error(err.to_string()))?;
+        let output = instance
+            .get_typed_func::<(i32, i32), i64>(&store, function)
+            .and_then(|x| x.call(&mut store, (address, length)))
+            .map_err(|err| error(err.to_string()))? as u64;
+        let mut buffer = vec![0; (output & u32::MAX as u64) as usize];
+        memory
+            .read(&store, (output >> 32) as usize, &mut buffer)
+            .map_err(|err| error(err.to_string()))?;
+        Ok(buffer)
+    }
+
+    /// Process a rendered page with the plugin.
+    /// The plugin is given the page as JSON, as `{"page": …, "output_path": …}`, with the page's `rendered` field holding its processed HTML so far, and outputs the processed HTML.
+    ///
+    /// # Arguments
+    ///
+    /// * `page` - The page being written.
+    ///
+    /// * `rendered` - The rendered page.
+    ///
+    /// * `output_path` - The output path of the page.
+    ///
+    /// # Returns
+    ///
+    /// The processed page, or an error if the plugin failed.
+    pub fn post_process(
+        &self,
+        page: &Page,
+        rendered: String,
+        output_path: &str,
+    ) -> miette::Result<String> {
+        let mut page = page.clone();
+        page.rendered = rendered.into();
+        let input = json!({ "page": page, "output_path": output_path });
+        let output = self.call(POST_PROCESSOR, input.to_string().as_bytes())?;
+        String::from_utf8(output).map_err(|_| {
+            WasmPluginError {
+                plugin: self.path.clone(),
+                reason: format!("`{}` did not output UTF-8", POST_PROCESSOR),
+            }
+            .into()
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+/// A Liquid filter defined by a WebAssembly plugin.
+/// The plugin is given the filter's input & arguments as JSON, as `{"input": …, "arguments": […], "keywords": {…}}`, and outputs the filtered value as JSON.
+pub struct WasmFilter {
+    /// The plugin defining the filter.
+    plugin: WasmPlugin,
+    /// The name of the filter.
+    name: String,
+    /// A description of the filter.
+    description: String,
+}
+
+impl WasmFilter {
+    /// Provides a new instance of a plugin's filter parser.
+    ///
+    /// # Arguments
+    ///
+    /// * `plugin` - The plugin defining the filter.
+    ///
+    /// * `name` - The name of the filter.
+    pub fn new(plugin: WasmPlugin, name: String) -> Self {
+        Self {
+            description: format!("A filter defined by the plugin `{}`.", plugin.path()),
+            plugin,
+            name,
+        }
+    }
+}
+
+impl FilterReflection for WasmFilter {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn positional_parameters(&self) -> &'static [ParameterReflection] {
+        &[]
+    }
+
+    fn keyword_parameters(&self) -> &'static [ParameterReflection] {
+        &[]
+    }
+}
+
+impl ParseFilter for WasmFilter {
+    fn parse(&self, arguments: FilterArguments) -> Result<Box<dyn Filter>> {
+        Ok(Box::new(WasmFilterCall {
+            plugin: self.plugin.clone(),
+            name: self.name.clone(),
+            positional: arguments.positional.collect(),
+            keyword: arguments
+                .keyword
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        }))
+    }
+
+    fn reflection(&self) -> &dyn FilterReflection {
+        self
+    }
+}
+
+#[derive(Debug)]
+struct WasmFilterCall {
+    plugin: WasmPlugin,
+    name: String,
+    positional: Vec<Expression>,
+    keyword: Vec<(String, Expression)>,
+}
+
+impl fmt::Display for WasmFilterCall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let arguments = self
+            .positional
+            .iter()
+            .map(|x| x.to_string())
+            .chain(
+                self.keyword
+                    .iter()
+                    .map(|(key, value)| format!("{}: {}", key, value)),
+            )
+            .collect::<Vec<_>>();
+        match arguments.is_empty() {
+            true => write!(f, "{}", self.name),
+            false => write!(f, "{}: {}", self.name, arguments.join(", ")),
+        }
+    }
+}
+
+impl Filter for WasmFilterCall {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let arguments = self
+            .positional
+            .iter()
+            .map(|x| Ok(x.evaluate(runtime)?.to_value()))
+            .collect::<Result<Vec<_>>>()?;
+        let keywords = self
+            .keyword
+            .iter()
+            .map(|(key, value)| Ok((key.clone(), value.evaluate(runtime)?.to_value())))
+            .collect::<Result<BTreeMap<_, _>>>()?;
+        let input = json!({
+            "input": input.to_value(),
+            "arguments": arguments,
+            "keywords": keywords,
+        });
+        let output = self
+            .plugin
+            .call(
+                &format!("{}{}", FILTER_PREFIX, self.name),
+                input.to_string().as_bytes(),
+            )
+            .map_err(|err| liquid_core::Error::with_msg(err.to_string()))?;
+        serde_json::from_slice(&output).map_err(|err| {
+            liquid_core::Error::with_msg(format!("The plugin did not output JSON: {}", err))
+                .context("plugin", self.plugin.path().to_string())
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+/// A Liquid tag defined by a WebAssembly plugin.
+/// The tag is written as `{% name argument … %}`; the plugin is given its arguments as JSON, as `{"arguments": […]}`, and outputs the text the tag renders to.
+pub struct WasmTag {
+    /// The plugin defining the tag.
+    plugin: WasmPlugin,
+    /// The name of the tag.
+    name: String,
+    /// A description of the tag.
+    description: String,
+}
+
+impl WasmTag {
+    /// Provides a new instance of a plugin's tag parser.
+    ///
+    /// # Arguments
+    ///
+    /// * `plugin` - The plugin defining the tag.
+    ///
+    /// * `name` - The name of the tag.
+    pub fn new(plugin: WasmPlugin, name: String) -> Self {
+        Self {
+            description: format!("A tag defined by the plugin `{}`.", plugin.path()),
+            plugin,
+            name,
+        }
+    }
+}
+
+impl TagReflection for WasmTag {
+    fn tag(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+impl ParseTag for WasmTag {
+    fn parse(
+        &self,
+        arguments: TagTokenIter<'_>,
+        _options: &Language,
+    ) -> Result<Box<dyn Renderable>> {
+        Ok(Box::new(WasmTagCall {
+            plugin: self.plugin.clone(),
+            name: self.name.clone(),
+            arguments: arguments
+                .map(|x| x.expect_value().into_result())
+                .collect::<Result<_>>()?,
+        }))
+    }
+
+    fn reflection(&self) -> &dyn TagReflection {
+        self
+    }
+}
+
+#[derive(Debug)]
+struct WasmTagCall {
+    plugin: WasmPlugin,
+    name: String,
+    arguments: Vec<Expression>,
+}
+
+impl Renderable for WasmTagCall {
+    fn render_to(&self, writer: &mut dyn Write, runtime: &dyn Runtime) -> Result<()> {
+        let arguments = self
+            .arguments
+            .iter()
+            .map(|x| Ok(x.evaluate(runtime)?.to_value()))
+            .collect::<Result<Vec<_>>>()?;
+        let output = self
+            .plugin
+            .call(
+                &format!("{}{}", TAG_PREFIX, self.name),
+                json!({ "arguments": arguments }).to_string().as_bytes(),
+            )
+            .map_err(|err| liquid_core::Error::with_msg(err.to_string()))?;
+        writer.write_all(&output).replace("Failed to render")?;
+        Ok(())
+    }
+}
@@ -0,0 +1,178 @@
+use crate::page::Page;
+use ahash::AHashSet;
+use tracing::warn;
+
+/// The attribute marking links rendered from wikilinks.
+const WIKILINK_ATTRIBUTE: &str = "data-wikilink=\"true\"";
+
+/// Obtain the targets of the wikilinks in a page's content.
+/// Wikilinks are written as `[[Target]]`, or as `[[Target|Text]]` to link with different text.
+///
+/// # Arguments
+///
+/// * `content` - The page's content.
+///
+/// # Returns
+///
+/// The target of each wikilink, in order of appearance.
+pub fn extract_wikilinks(content: &str) -> Vec<String> {
+    let mut targets = Vec::new();
+    let mut remainder = content;
+    while let Some(start) = remainder.find("[[") {
+        remainder = &remainder[start + 2..];
+        let Some(end) = remainder.find("]]") else {
+            break;
+        };
+        let link = &remainder[..end];
+        remainder = &remainder[end + 2..];
+        if link.contains('\n') || link.contains("[[") {
+            continue;
+        }
+        let target = link.split('|').next().unwrap_or_default().trim();
+        if !target.is_empty() {
+            targets.push(target.to_string());
+        }
+    }
+    targets
+}
+
+/// Determine if a page is the target of a wikilink.
+/// A wikilink targets a page by its title, its path, or its filename, ignoring case.
+///
+/// # Arguments
+///
+/// * `page` - The page.
+///
+/// * `target` - The target of the wikilink.
+///
+/// # Returns
+///
+/// Whether or not the wikilink targets the page.
+pub fn is_target(page: &Page, target: &str) -> bool {
+    let target = target.trim().trim_start_matches('/').to_lowercase();
+    let target = target
+        .strip_suffix(".vox")
+        .or(target.strip_suffix(".md"))
+        .or(target.strip_suffix(".html"))
+        .unwrap_or(&target);
+    let title_matches = page
+        .data
+        .get("title")
+        .and_then(|x| x.as_str())
+        .is_some_and(|x| x.to_lowercase() == target);
+    let path = page.to_path_string().to_lowercase();
+    let extension = format!(".{}", page.format.extension());
+    let path = path.trim_start_matches('/').trim_end_matches(&extension);
+    title_matches || path == target || page.name.to_lowercase() == target
+}
+
+/// Compute the pages each page links to with wikilinks.
+///
+/// # Arguments
+///
+/// * `sources` - The pages whose wikilinks are resolved.
+///
+/// * `targets` - The pages which may be linked to.
+///
+/// # Returns
+///
+/// For each source page, the positions of the pages it links to in `targets`, in order of appearance.
+pub fn compute_links(sources: &[&Page], targets: &[&Page]) -> Vec<Vec<usize>> {
+    let mut links = Vec::new();
+    for source in sources {
+        let mut positions = Vec::new();
+        let mut seen = AHashSet::new();
+        for target in extract_wikilinks(&source.content) {
+            match targets.iter().position(|page| is_target(page, &target)) {
+                Some(position) => {
+                    if seen.insert(position) {
+                        positions.push(position);
+                    }
+                }
+                None => warn!(
+                    "Unable to resolve wikilink `[[{}]]` in {} … ",
+                    target,
+                    source.to_path_string()
+                ),
+            }
+        }
+        links.push(positions);
+    }
+    links
+}
+
+/// Decode the percent-encoded characters of a URL.
+///
+/// # Arguments
+///
+/// * `url` - The URL.
+///
+/// # Returns
+///
+/// The URL with its percent-encoded characters decoded.
+fn percent_decode(url: &str) -> String {
+    let bytes = url.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut position = 0;
+    while position < bytes.len() {
+        let encoded_byte = (bytes[position] == b'%')
+            .then(|| url.get(position + 1..position + 3))
+            .flatten()
+            .and_then(|x| u8::from_str_radix(x, 16).ok());
+        match encoded_byte {
+            Some(byte) => {
+                decoded.push(byte);
+                position += 3;
+            }
+            None => {
+                decoded.push(bytes[position]);
+                position += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// Point the wikilinks in rendered HTML to the pages they target.
+///
+/// # Arguments
+///
+/// * `html` - The rendered HTML.
+///
+/// * `links` - The pages linked to, with their URLs rendered.
+///
+/// # Returns
+///
+/// The HTML, with each resolvable wikilink pointing to the URL of its target.
+pub fn resolve_wikilinks(html: &str, links: &[Page]) -> String {
+    let mut resolved = String::with_capacity(html.len());
+    let mut remainder = html;
+    while let Some(attribute_start) = remainder.find(WIKILINK_ATTRIBUTE) {
+        let attribute_end = attribute_start + WIKILINK_ATTRIBUTE.len();
+        let before = &remainder[..attribute_start];
+        // Wikilinks are rendered as `<a href="…" data-wikilink="true">`.
+        let href = before.rfind("href=\"").and_then(|start| {
+            let start = start + 6;
+            before[start..].find('"').map(|end| (start, start + end))
+        });
+        match href {
+            Some((href_start, href_end)) => {
+                let target = percent_decode(&html_escape::decode_html_entities(
+                    &before[href_start..href_end],
+                ));
+                resolved.push_str(&before[..href_start]);
+                match links.iter().find(|page| is_target(page, &target)) {
+                    Some(page) => resolved.push_str(&html_escape::encode_double_quoted_attribute(
+                        &format!("/{}", page.url.trim_start_matches('/')),
+                    )),
+                    None => resolved.push_str(&before[href_start..href_end]),
+                }
+                resolved.push_str(&remainder[href_end..attribute_end]);
+            }
+            None => resolved.push_str(&remainder[..attribute_end]),
+        }
+        remainder = &remainder[attribute_end..];
+    }
+    resolved.push_str(remainder);
+    resolved
+}